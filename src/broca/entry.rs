@@ -8,6 +8,9 @@ use std::{fmt, fs};
 
 use super::BrocaError;
 
+/// Confidence of entries that do not state one.
+pub const DEFAULT_CONFIDENCE: f64 = 0.8;
+
 /// The type of a memory entry.
 #[derive(Debug, Clone, PartialEq, Serialize)]
 pub enum EntryType {
@@ -131,7 +134,7 @@ impl Entry {
 
         let confidence = extract_field(frontmatter, "confidence")
             .and_then(|c| c.parse::<f64>().ok())
-            .unwrap_or(0.8);
+            .unwrap_or(DEFAULT_CONFIDENCE);
 
         let tags = extract_tags(frontmatter);
         let created = extract_field(frontmatter, "created").unwrap_or_default();
@@ -525,10 +525,11 @@ mod tests {
         let dir = tempfile::tempdir().unwrap();
 
         // Entry ~200 days old with confidence 0.6 — not flagged with default (365) but flagged with aggressive (180)
+        let created = (Utc::now() - chrono::Duration::days(200)).format("%Y%m%d");
         create_entry(
             dir.path(),
             "old.md",
-            "type: fact\ntitle: \"Old\"\nconfidence: 0.6\ncreated: 20250901",
+            &format!("type: fact\ntitle: \"Old\"\nconfidence: 0.6\ncreated: {created}"),
             "content",
         );
 
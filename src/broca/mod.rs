@@ -11,7 +11,7 @@ pub mod gc;
 pub mod relations;
 mod search;
 
-pub use entry::{Entry, EntryType};
+pub use entry::{Entry, EntryType, DEFAULT_CONFIDENCE};
 pub use search::ScoredEntry;
 
 use chrono::Utc;
@@ -67,15 +67,68 @@ pub fn remember_with_validity(
     ttl_days: Option<u32>,
     valid_until: Option<&str>,
 ) -> Result<PathBuf, BrocaError> {
+    let entry = NewEntry {
+        entry_type: entry_type.to_string(),
+        title: title.to_string(),
+        content: content.to_string(),
+        tags: tags.to_vec(),
+        ttl_days,
+        valid_until: valid_until.map(String::from),
+        confidence: None,
+    };
+    remember_entry(memory_dir, &entry)
+}
+
+/// A memory entry to store with [`remember_entry`].
+#[derive(Debug, Clone, Default)]
+pub struct NewEntry {
+    pub entry_type: String,
+    pub title: String,
+    pub content: String,
+    pub tags: Vec<String>,
+    pub ttl_days: Option<u32>,
+    /// `YYYYMMDD` or `YYYY-MM-DD`, as for [`remember_with_validity`].
+    pub valid_until: Option<String>,
+    /// [`DEFAULT_CONFIDENCE`] when unset.
+    pub confidence: Option<f64>,
+}
+
+/// All knowledge entries, oldest first.
+pub fn entries(memory_dir: &Path) -> Result<Vec<Entry>, BrocaError> {
+    entry::load_all(&memory_dir.join("knowledge"))
+}
+
+/// Store a new memory entry.
+pub fn remember_entry(memory_dir: &Path, new: &NewEntry) -> Result<PathBuf, BrocaError> {
+    let NewEntry {
+        entry_type,
+        title,
+        content,
+        tags,
+        ttl_days,
+        valid_until,
+        confidence,
+    } = new;
     let entry_type: EntryType = entry_type.parse().map_err(BrocaError::Parse)?;
+    let confidence = confidence.unwrap_or(DEFAULT_CONFIDENCE);
+    if !(0.0..=1.0).contains(&confidence) {
+        return Err(BrocaError::Parse(format!(
+            "confidence must be between 0 and 1, got {confidence}"
+        )));
+    }
 
     let knowledge_dir = memory_dir.join("knowledge");
     fs::create_dir_all(&knowledge_dir)?;
 
     let timestamp = Utc::now().format("%Y%m%d-%H%M%S").to_string();
     let slug = slugify(title);
-    let filename = format!("{timestamp}-{slug}.md");
-    let path = knowledge_dir.join(&filename);
+    // Two entries with one title in the same second get distinct files.
+    let mut path = knowledge_dir.join(format!("{timestamp}-{slug}.md"));
+    let mut copy = 1;
+    while path.exists() {
+        copy += 1;
+        path = knowledge_dir.join(format!("{timestamp}-{slug}-{copy}.md"));
+    }
 
     let tags_str = if tags.is_empty() {
         String::new()
@@ -104,7 +157,7 @@ pub fn remember_with_validity(
          title: \"{title}\"\n\
          created: {timestamp}\n\
          {validity_str}\
-         confidence: 0.8\n\
+         confidence: {confidence}\n\
          {tags_str}\
          {ttl_str}\
          ---\n\n\
@@ -124,6 +177,11 @@ pub fn recall(
     search::recall(memory_dir, query, limit)
 }
 
+/// Search memory with relevance ranking, without recording access.
+pub fn rank(memory_dir: &Path, query: &str, limit: usize) -> Result<Vec<ScoredEntry>, BrocaError> {
+    search::rank(memory_dir, query, limit)
+}
+
 /// Show a specific memory entry's content (without frontmatter).
 /// Also records an access event for the entry.
 pub fn show(memory_dir: &Path, entry_name: &str) -> Result<String, BrocaError> {
@@ -157,6 +215,12 @@ pub fn search_tag(memory_dir: &Path, tag: &str) -> Result<Vec<Entry>, BrocaError
         .collect())
 }
 
+/// Today's journal file, which `journal` appends to.
+pub fn journal_path(memory_dir: &Path) -> PathBuf {
+    let date = Utc::now().format("%Y-%m-%d").to_string();
+    memory_dir.join("journal").join(format!("{date}.md"))
+}
+
 /// Add a journal entry (timestamped, informal).
 pub fn journal(memory_dir: &Path, content: &str) -> Result<PathBuf, BrocaError> {
     let journal_dir = memory_dir.join("journal");
@@ -165,7 +229,7 @@ pub fn journal(memory_dir: &Path, content: &str) -> Result<PathBuf, BrocaError>
     let now = Utc::now();
     let date = now.format("%Y-%m-%d").to_string();
     let time = now.format("%H:%M").to_string();
-    let path = journal_dir.join(format!("{date}.md"));
+    let path = journal_path(memory_dir);
 
     let entry = if path.exists() {
         let existing = fs::read_to_string(&path)?;
@@ -263,6 +327,77 @@ pub fn build_index(memory_dir: &Path) -> Result<usize, BrocaError> {
     Ok(entries.len())
 }
 
+/// Differences between `INDEX.md` and the entries in `knowledge/`.
+#[derive(Debug, Default, PartialEq)]
+pub struct IndexCheck {
+    /// Whether `INDEX.md` exists at all.
+    pub has_index: bool,
+    /// Entries on disk that the index does not list.
+    pub missing: Vec<String>,
+    /// Index lines whose entry file no longer exists.
+    pub stale: Vec<String>,
+    /// Markdown files in `knowledge/` that fail to parse as entries.
+    pub unparseable: Vec<String>,
+}
+
+impl IndexCheck {
+    /// An empty knowledge base without an index counts as consistent.
+    pub fn is_consistent(&self) -> bool {
+        self.missing.is_empty() && self.stale.is_empty() && self.unparseable.is_empty()
+    }
+}
+
+/// Compare `INDEX.md` against the knowledge directory without rewriting it.
+pub fn check_index(memory_dir: &Path) -> Result<IndexCheck, BrocaError> {
+    let knowledge_dir = memory_dir.join("knowledge");
+    let mut check = IndexCheck::default();
+
+    let mut on_disk = Vec::new();
+    if knowledge_dir.exists() {
+        for dir_entry in fs::read_dir(&knowledge_dir)? {
+            let path = dir_entry?.path();
+            if path.extension().is_some_and(|ext| ext == "md") {
+                let filename = path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                if Entry::from_file(&path).is_ok() {
+                    on_disk.push(filename);
+                } else {
+                    check.unparseable.push(filename);
+                }
+            }
+        }
+    }
+    on_disk.sort();
+    check.unparseable.sort();
+
+    let index_path = memory_dir.join("INDEX.md");
+    let indexed: Vec<String> = match fs::read_to_string(&index_path) {
+        Ok(content) => {
+            check.has_index = true;
+            content
+                .lines()
+                .filter(|line| line.starts_with("- **"))
+                .filter_map(|line| line.rsplit_once(" — ").map(|(_, f)| f.trim().to_string()))
+                .collect()
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(e.into()),
+    };
+
+    check.missing = on_disk
+        .iter()
+        .filter(|f| !indexed.contains(f))
+        .cloned()
+        .collect();
+    check.stale = indexed
+        .into_iter()
+        .filter(|f| !on_disk.contains(f) && !check.unparseable.contains(f))
+        .collect();
+    Ok(check)
+}
+
 /// Update the confidence score of a memory entry.
 pub fn update_confidence(
     memory_dir: &Path,
@@ -479,6 +614,16 @@ mod tests {
         assert!(content.contains("This is test content."));
     }
 
+    #[test]
+    fn test_remember_same_title_twice_keeps_both() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = remember(dir.path(), "fact", "Same", "one", &[], None).unwrap();
+        let second = remember(dir.path(), "fact", "Same", "two", &[], None).unwrap();
+        assert_ne!(first, second);
+        assert!(fs::read_to_string(first).unwrap().contains("one"));
+        assert!(fs::read_to_string(second).unwrap().contains("two"));
+    }
+
     #[test]
     fn test_remember_invalid_type() {
         let dir = tempfile::tempdir().unwrap();
@@ -583,6 +728,27 @@ mod tests {
         assert!(index.contains("Beta"));
     }
 
+    #[test]
+    fn test_check_index() {
+        let dir = tempfile::tempdir().unwrap();
+        let memory_dir = dir.path();
+
+        let first = remember(memory_dir, "fact", "Alpha", "Content A", &[], None).unwrap();
+        let check = check_index(memory_dir).unwrap();
+        assert!(!check.has_index);
+        assert_eq!(check.missing.len(), 1);
+
+        build_index(memory_dir).unwrap();
+        assert!(check_index(memory_dir).unwrap().is_consistent());
+
+        fs::remove_file(&first).unwrap();
+        fs::write(memory_dir.join("knowledge/broken.md"), "no frontmatter").unwrap();
+        let check = check_index(memory_dir).unwrap();
+        assert_eq!(check.stale.len(), 1);
+        assert_eq!(check.unparseable, vec!["broken.md".to_string()]);
+        assert!(!check.is_consistent());
+    }
+
     #[test]
     fn test_search_tag() {
         let dir = tempfile::tempdir().unwrap();
@@ -159,11 +159,26 @@ fn access_boost(count: u64) -> f64 {
 /// 5. Temporal decay — recent entries score higher
 /// 6. Access frequency boost — frequently recalled entries score higher
 /// 7. Superseded entries penalized (×0.3)
+///
+/// Returned entries are recorded as accessed; see `rank` for a lookup that
+/// leaves the access log alone.
 pub fn recall(
     memory_dir: &Path,
     query: &str,
     limit: usize,
 ) -> Result<Vec<ScoredEntry>, BrocaError> {
+    let scored = rank(memory_dir, query, limit)?;
+
+    // Record access for returned results (non-blocking best-effort)
+    let accessed_files: Vec<&str> = scored.iter().map(|e| e.filename.as_str()).collect();
+    let _ = access::record_access(memory_dir, &accessed_files);
+
+    Ok(scored)
+}
+
+/// Rank memory like `recall` without recording access, for lookups the
+/// agent didn't ask for (they would otherwise reinforce their own results).
+pub fn rank(memory_dir: &Path, query: &str, limit: usize) -> Result<Vec<ScoredEntry>, BrocaError> {
     let knowledge_dir = memory_dir.join("knowledge");
     let entries = entry::load_all(&knowledge_dir)?;
 
@@ -318,10 +333,6 @@ pub fn recall(
 
     scored.truncate(limit);
 
-    // Record access for returned results (non-blocking best-effort)
-    let accessed_files: Vec<&str> = scored.iter().map(|e| e.filename.as_str()).collect();
-    let _ = access::record_access(memory_dir, &accessed_files);
-
     Ok(scored)
 }
 
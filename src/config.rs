@@ -2,13 +2,36 @@
 //!
 //! Reads boucle.toml and provides typed access to all settings.
 
-use serde::Deserialize;
+pub mod edit;
+pub mod migrate;
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 use std::{fmt, fs, io};
 
+/// Built-in context sections, in their default order.
+pub const CONTEXT_SECTIONS: &[&str] = &[
+    "goals", "state", "recall", "actions", "files", "plugins", "status", "last_log",
+];
+
+/// Built-in probes of the `status` context section, in their default order.
+pub const STATUS_PROBES: &[&str] = &[
+    "timestamp",
+    "disk",
+    "iterations",
+    "git_changes",
+    "last_commit",
+];
+
 /// Top-level configuration from boucle.toml.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct Config {
+    /// Layout version of the file (see [`migrate`]); 0 when absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<u32>,
+
     pub agent: AgentConfig,
 
     #[serde(default)]
@@ -25,9 +48,88 @@ pub struct Config {
 
     #[serde(default)]
     pub mcp: McpConfig,
+
+    #[serde(default)]
+    pub context: ContextConfig,
+
+    #[serde(default)]
+    pub hooks: HooksConfig,
+
+    /// Named run profiles, selected with `boucle --profile <name>` or
+    /// `BOUCLE_PROFILE`. `[profile.<name>]` is accepted too, but not
+    /// alongside `[profiles.<name>]`.
+    #[serde(default, alias = "profile")]
+    pub profiles: BTreeMap<String, RunProfile>,
+
+    /// Settings for command plugins in `plugins/`, passed to each as
+    /// `BOUCLE_PLUGIN_*` variables.
+    #[serde(default)]
+    pub plugins: BTreeMap<String, toml::Table>,
+
+    #[serde(default)]
+    pub plugin_limits: PluginLimitsConfig,
+
+    #[serde(default)]
+    pub metrics: MetricsConfig,
+
+    #[serde(default)]
+    pub telemetry: TelemetryConfig,
+
+    #[serde(default)]
+    pub notify: NotifyConfig,
+
+    /// Named secrets, referred to as `secret:<name>` by settings that take
+    /// credentials and resolved only when one of them is used.
+    #[serde(default)]
+    pub secrets: BTreeMap<String, SecretSource>,
 }
 
-#[derive(Debug, Deserialize)]
+/// Where a `[secrets]` value comes from: `{ env = "LINEAR_TOKEN" }`,
+/// `{ file = "secrets/linear" }` (relative to the agent root), or
+/// `{ command = "op read op://agent/linear/token" }` (its trimmed stdout).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields, rename_all = "lowercase")]
+pub enum SecretSource {
+    Env(String),
+    File(String),
+    Command(String),
+}
+
+/// Overrides applied by `boucle --profile <name>`.
+///
+/// Besides the shorthands below, `[profiles.<name>.agent]`, `.loop`,
+/// `.schedule`, and `.context` tables are merged over the top-level ones
+/// when the config is loaded (see [`load_profile`]). Each profile also gets
+/// its own lock file and a log subdirectory (see `Config::apply_profile`),
+/// so profiles can run side by side.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct RunProfile {
+    #[serde(default)]
+    pub model: Option<String>,
+
+    #[serde(default)]
+    pub goals_file: Option<String>,
+
+    #[serde(default)]
+    pub context_dir: Option<String>,
+
+    #[serde(default)]
+    pub interval: Option<String>,
+
+    #[serde(default)]
+    pub agent: toml::Table,
+
+    #[serde(default, rename = "loop")]
+    pub loop_config: toml::Table,
+
+    #[serde(default)]
+    pub schedule: toml::Table,
+
+    #[serde(default)]
+    pub context: toml::Table,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
 pub struct AgentConfig {
     pub name: String,
 
@@ -47,18 +149,74 @@ pub struct AgentConfig {
 
     #[serde(default)]
     pub allowed_tools: Option<String>,
+
+    /// Extra variables set on the LLM subprocess. Values support `${VAR}`
+    /// expansion and `file:<path>` indirection (see `resolve_env_value`).
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+
+    /// Host variables stripped from the LLM subprocess. A trailing `*`
+    /// matches by prefix (e.g. `AWS_*`).
+    #[serde(default)]
+    pub env_deny: Vec<String>,
+
+    /// LLM CLI to run in place of `claude` or `codex`, e.g. a wrapper
+    /// script or a pinned version; relative paths are from the agent root.
+    #[serde(default)]
+    pub cli: Option<String>,
+
+    /// Extra arguments for the LLM CLI, after the ones boucle passes.
+    #[serde(default)]
+    pub cli_args: Vec<String>,
 }
 
-#[derive(Debug, Deserialize)]
+impl AgentConfig {
+    /// Whether the model runs on the codex CLI (`gpt-*`) rather than claude.
+    pub fn uses_codex(&self) -> bool {
+        self.model.starts_with("gpt-")
+    }
+
+    /// The LLM CLI to run: `cli` if set, else `codex` or `claude` for the
+    /// model. A `cli` with a path separator is resolved against `root`.
+    pub fn cli_program(&self, root: &Path) -> PathBuf {
+        match self.cli.as_deref() {
+            Some(cli) if cli.contains(['/', '\\']) => root.join(cli),
+            Some(cli) => PathBuf::from(cli),
+            None if self.uses_codex() => PathBuf::from("codex"),
+            None => PathBuf::from("claude"),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
 pub struct MemoryConfig {
     #[serde(default = "default_memory_dir")]
     pub dir: String,
 
     #[serde(default = "default_state_file")]
     pub state_file: String,
+
+    /// Defaults for new entries by type, from `[memory.defaults.<type>]`.
+    #[serde(default)]
+    pub defaults: BTreeMap<String, EntryDefaults>,
 }
 
-#[derive(Debug, Deserialize)]
+/// Settings a new memory entry of one type gets unless it sets its own;
+/// the tags are added to the entry's.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct EntryDefaults {
+    #[serde(default)]
+    pub confidence: Option<f64>,
+
+    #[serde(default)]
+    pub tags: Vec<String>,
+
+    #[serde(default)]
+    pub ttl_days: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
 pub struct LoopConfig {
     #[serde(default)]
     pub context_dir: Option<String>,
@@ -69,165 +227,1816 @@ pub struct LoopConfig {
     #[serde(default)]
     pub log_dir: Option<String>,
 
+    #[serde(default)]
+    pub goals_file: Option<String>,
+
     #[serde(default = "default_max_tokens")]
     pub max_tokens: usize,
 
     #[serde(default = "default_llm_timeout_seconds")]
     pub llm_timeout_seconds: u64,
+
+    #[serde(default)]
+    pub auto_journal: bool,
+
+    #[serde(default = "default_rate_limit_cooldown")]
+    pub rate_limit_cooldown: String,
+
+    /// Let `memory.dir`, `context_dir`, `hooks_dir`, and `log_dir` point
+    /// outside the agent root (an absolute path or one through `..`).
+    #[serde(default)]
+    pub allow_external_dirs: bool,
+
+    #[serde(default)]
+    pub context: LoopContextConfig,
 }
 
-#[derive(Debug, Deserialize)]
+/// `[loop.context]`: which built-in context sections are included, in order.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct LoopContextConfig {
+    #[serde(default = "default_context_sections")]
+    pub sections: Vec<String>,
+
+    /// `[loop.context.when.<section>]`: conditions a section is gated on.
+    #[serde(default)]
+    pub when: BTreeMap<String, ContextCondition>,
+
+    /// Project files for the `files` section, as globs relative to the
+    /// agent root.
+    #[serde(default)]
+    pub include: Vec<ContextInclude>,
+
+    /// Bytes each `include` entry may add unless it sets `max_bytes`.
+    #[serde(default = "default_include_max_bytes")]
+    pub include_max_bytes: usize,
+}
+
+/// One `[loop.context] include` entry: `"notes/*.md"` or
+/// `{ glob = "notes/*.md", max_bytes = 4096 }`.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum ContextInclude {
+    Glob(String),
+    Capped { glob: String, max_bytes: usize },
+}
+
+impl ContextInclude {
+    pub fn glob(&self) -> &str {
+        match self {
+            ContextInclude::Glob(glob) | ContextInclude::Capped { glob, .. } => glob,
+        }
+    }
+
+    pub fn max_bytes(&self) -> Option<usize> {
+        match self {
+            ContextInclude::Glob(_) => None,
+            ContextInclude::Capped { max_bytes, .. } => Some(*max_bytes),
+        }
+    }
+}
+
+/// When a context section or plugin is included. Every condition that is
+/// set must hold.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct ContextCondition {
+    /// Only every Nth iteration (the 1st, the N+1th, …).
+    #[serde(default)]
+    pub every: Option<u32>,
+
+    /// Only during these local hours, e.g. "9-17" or "22-6".
+    #[serde(default)]
+    pub hours: Option<String>,
+
+    /// Only while this path (relative to the agent root) exists.
+    #[serde(default)]
+    pub if_exists: Option<String>,
+
+    /// Only when an earlier context plugin printed `boucle-flag: <name>`.
+    #[serde(default)]
+    pub if_flag: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
 pub struct ScheduleConfig {
     #[serde(default = "default_interval")]
     pub interval: String,
 
+    /// Random delay of up to this long (e.g. `5m`) before each scheduled
+    /// run, so agents on the same schedule don't all start at once.
+    #[serde(default)]
+    pub jitter: Option<String>,
+
+    /// Local time of day scheduled runs are confined to, e.g.
+    /// `"08:00-22:00"`; may wrap past midnight.
+    #[serde(default)]
+    pub active_hours: Option<String>,
+
+    /// Weekdays scheduled runs are confined to, in cron syntax such as
+    /// `"mon-fri"`.
+    #[serde(default)]
+    pub active_days: Option<String>,
+
+    /// What `boucle run --scheduled` does after runs were missed.
+    #[serde(default)]
+    pub catch_up: CatchUp,
+
     #[serde(default)]
     #[allow(dead_code)]
     pub method: Option<String>,
+
+    /// `[schedule.<name>]` tables: more cadences for particular tasks,
+    /// each scheduled alongside the others by `boucle schedule`.
+    #[serde(flatten)]
+    pub named: BTreeMap<String, NamedSchedule>,
+
+    /// The named schedule that started this run, from `boucle run
+    /// --schedule`. Not read from boucle.toml.
+    #[serde(skip)]
+    pub current: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
-pub struct GitConfig {
-    #[serde(default = "default_commit_name")]
-    pub commit_name: String,
+impl ScheduleConfig {
+    /// Keys of `[schedule]` itself; any other key names a schedule.
+    const FIELDS: &'static [&'static str] = &[
+        "interval",
+        "jitter",
+        "active_hours",
+        "active_days",
+        "catch_up",
+        "method",
+    ];
 
-    #[serde(default = "default_commit_email")]
-    pub commit_email: String,
+    /// The task of the schedule that started this run: its `task`, or
+    /// its name.
+    pub fn task(&self) -> Option<&str> {
+        let name = self.current.as_deref()?;
+        let task = self.named.get(name).and_then(|s| s.task.as_deref());
+        Some(task.unwrap_or(name))
+    }
+
+    /// `active_hours` and `active_days`, or `None` when neither is set.
+    pub fn active_window(&self) -> Result<Option<ActiveWindow>, String> {
+        if self.active_hours.is_none() && self.active_days.is_none() {
+            return Ok(None);
+        }
+        let (start, end) = match self.active_hours.as_deref() {
+            Some(hours) => parse_active_hours(hours)?,
+            None => (0, 0),
+        };
+        let weekdays = match self.active_days.as_deref() {
+            Some(days) => parse_cron_field(days.trim(), 0, 7, WEEKDAY_NAMES)
+                .map_err(|e| format!("Invalid schedule.active_days '{days}': {e}"))?
+                .map(|days| {
+                    let mut days: Vec<u32> = days.into_iter().map(|d| d % 7).collect();
+                    days.sort_unstable();
+                    days.dedup();
+                    days
+                }),
+            None => None,
+        };
+        Ok(Some(ActiveWindow {
+            start,
+            end,
+            weekdays,
+        }))
+    }
 }
 
-#[derive(Debug, Deserialize)]
-pub struct McpConfig {
-    #[serde(default = "default_enable_mcp")]
-    pub enable: bool,
+/// `schedule.catch_up`: `"once"`, `"skip"`, or a number of iterations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(untagged, try_from = "toml::Value")]
+pub enum CatchUp {
+    /// One iteration per run that came due, up to this many.
+    Iterations(u32),
+    Policy(CatchUpPolicy),
 }
 
-impl Default for GitConfig {
-    fn default() -> Self {
-        Self {
-            commit_name: default_commit_name(),
-            commit_email: default_commit_email(),
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CatchUpPolicy {
+    /// Run one iteration, however many runs were missed.
+    Once,
+    /// Run only when on time; a late run waits for the next one.
+    Skip,
+}
+
+impl TryFrom<toml::Value> for CatchUp {
+    type Error = String;
+
+    fn try_from(value: toml::Value) -> Result<Self, String> {
+        match value {
+            toml::Value::String(ref s) if s == "once" => Ok(CatchUp::Policy(CatchUpPolicy::Once)),
+            toml::Value::String(ref s) if s == "skip" => Ok(CatchUp::Policy(CatchUpPolicy::Skip)),
+            toml::Value::Integer(n) if n > 0 => u32::try_from(n)
+                .map(CatchUp::Iterations)
+                .map_err(|e| e.to_string()),
+            other => Err(format!(
+                "schedule.catch_up must be \"once\", \"skip\", or a number of iterations above 0, not {other}"
+            )),
         }
     }
 }
 
-impl Default for McpConfig {
+impl Default for CatchUp {
     fn default() -> Self {
-        Self {
-            enable: default_enable_mcp(),
-        }
+        CatchUp::Policy(CatchUpPolicy::Once)
     }
 }
 
-// Default value functions
-fn default_model() -> String {
-    "gpt-5.4".to_string()
-}
-fn default_system_prompt() -> String {
-    "system-prompt.md".to_string()
+/// One `[schedule.<name>]` table.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct NamedSchedule {
+    pub interval: String,
+
+    /// What the runs are for, shown in the context and passed to hooks and
+    /// plugins as `BOUCLE_TASK`; the schedule's name if unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task: Option<String>,
+
+    /// Overrides `schedule.jitter`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jitter: Option<String>,
 }
-fn default_memory_dir() -> String {
-    "memory".to_string()
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GitConfig {
+    /// Use git at all: status in the context, snapshots, run branches, and
+    /// commits. Unset means on when the agent root is in a git repository.
+    #[serde(default)]
+    pub enabled: Option<bool>,
+
+    #[serde(default = "default_commit_name")]
+    pub commit_name: String,
+
+    #[serde(default = "default_commit_email")]
+    pub commit_email: String,
+
+    #[serde(default)]
+    pub rollback_on_failure: bool,
+
+    #[serde(default)]
+    pub run_branch: Option<String>,
+
+    #[serde(default = "default_merge_run_branch")]
+    pub merge_run_branch: bool,
+
+    /// Branches neither the runner nor the model may commit or push to:
+    /// names, or globs like `release/*`.
+    #[serde(default)]
+    pub protected_branches: Vec<String>,
+
+    /// Commit the agent root's changes after each iteration.
+    #[serde(default = "default_auto_commit")]
+    pub auto_commit: bool,
+
+    /// Message for those commits; `{agent}`, `{iteration}`, `{timestamp}`,
+    /// and `{summary}` are filled in.
+    #[serde(default = "default_commit_message")]
+    pub commit_message: String,
+
+    /// Push to `remote` at the end of each iteration.
+    #[serde(default)]
+    pub push: bool,
+
+    #[serde(default = "default_remote")]
+    pub remote: String,
+
+    /// Branch on the remote to push to; the checked-out branch's name if unset.
+    #[serde(default)]
+    pub branch: Option<String>,
+
+    /// Git command run before each iteration to bring in upstream work:
+    /// `fetch`, or `pull` with `--rebase`, `--no-rebase`, `--ff-only`,
+    /// `--autostash`, and a remote and branch, e.g. `pull --rebase`.
+    #[serde(default)]
+    pub sync: Option<String>,
+
+    /// Files and directories, under the agent root, that iteration commits
+    /// take changes from; all of them if empty. `.boucleignore` narrows it.
+    #[serde(default)]
+    pub paths: Vec<String>,
+
+    /// Push the run branch and open a pull request for it instead of merging.
+    #[serde(default)]
+    pub pull_request: bool,
+
+    /// Sign the runner's commits and merges.
+    #[serde(default)]
+    pub sign: Option<SignFormat>,
+
+    /// `user.signingkey` for those signatures: an SSH key file (or
+    /// `key::<public key>`), or a GPG key id. Git's own setting if unset.
+    #[serde(default)]
+    pub signing_key: Option<String>,
 }
-fn default_state_file() -> String {
-    "STATE.md".to_string()
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SignFormat {
+    Ssh,
+    Gpg,
 }
-fn default_max_tokens() -> usize {
-    200_000
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ContextConfig {
+    /// Token budget for the assembled context; `loop.max_tokens` if unset.
+    #[serde(default)]
+    pub budget_tokens: Option<usize>,
+
+    /// Context sections from most to least important. When the context is
+    /// over budget, sections are trimmed starting from the end of the list.
+    #[serde(default = "default_context_priorities")]
+    pub priorities: Vec<String>,
+
+    /// Timeout for each `context.d/` script unless overridden per plugin.
+    #[serde(default = "default_plugin_timeout")]
+    pub plugin_timeout: String,
+
+    /// How many memory entries the `recall` section injects.
+    #[serde(default = "default_recall_limit")]
+    pub recall_limit: usize,
+
+    /// Per-script settings, keyed by file name (or name without extension).
+    #[serde(default)]
+    pub plugins: BTreeMap<String, ContextPluginConfig>,
+
+    #[serde(default)]
+    pub summarize: SummarizeConfig,
+
+    #[serde(default)]
+    pub dedupe: DedupeConfig,
+
+    #[serde(default)]
+    pub status: StatusConfig,
+
+    /// URLs fetched into the plugins section, keyed by name.
+    #[serde(default)]
+    pub remote: BTreeMap<String, RemoteSourceConfig>,
+
+    #[serde(default)]
+    pub redact: RedactConfig,
+
+    #[serde(default)]
+    pub github: GithubContextConfig,
 }
-fn default_llm_timeout_seconds() -> u64 {
-    7_200
+
+/// `[context.github]` — the built-in plugin listing GitHub issues assigned
+/// to the agent's account and pull requests waiting for its review.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GithubContextConfig {
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Only these repositories ("owner/name"); every one the account can
+    /// see when empty.
+    #[serde(default)]
+    pub repos: Vec<String>,
+
+    /// Variable holding an API token. When it is unset, the `gh` CLI's own
+    /// login is used.
+    #[serde(default = "default_github_token_env")]
+    pub token_env: String,
+
+    /// Most issues, and most review requests, listed.
+    #[serde(default = "default_github_limit")]
+    pub limit: usize,
 }
-fn default_interval() -> String {
-    "1h".to_string()
+
+/// `[context.redact]` — replace secrets in the assembled context before it
+/// is logged or sent to the LLM.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RedactConfig {
+    #[serde(default = "default_redact_enabled")]
+    pub enabled: bool,
+
+    /// Regular expressions matched in addition to the built-in token formats.
+    #[serde(default)]
+    pub patterns: Vec<String>,
+
+    /// `KEY=VALUE` files, relative to the agent root, whose values are
+    /// redacted wherever they appear.
+    #[serde(default = "default_redact_env_files")]
+    pub env_files: Vec<String>,
 }
-fn default_commit_name() -> String {
-    "Boucle".to_string()
+
+/// `[context.remote.<name>]` — a URL whose body is added to the context.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RemoteSourceConfig {
+    pub url: String,
+
+    /// Request headers, resolved like `[agent.env]`.
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+
+    /// Reuse the last response this long before asking the server again.
+    #[serde(default = "default_remote_ttl")]
+    pub ttl: String,
+
+    /// Request timeout; `context.plugin_timeout` if unset.
+    #[serde(default)]
+    pub timeout: Option<String>,
+
+    /// Longest body included; the rest is cut.
+    #[serde(default = "default_remote_max_bytes")]
+    pub max_bytes: usize,
 }
-fn default_commit_email() -> String {
-    "boucle@agent".to_string()
+
+/// `[context.status]` — what the System Status section reports.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct StatusConfig {
+    /// Built-in probes to run, in order; all of `STATUS_PROBES` by default.
+    #[serde(default = "default_status_probes")]
+    pub probes: Vec<String>,
+
+    /// Extra probes: label → shell command whose output is reported.
+    #[serde(default)]
+    pub commands: BTreeMap<String, String>,
+
+    /// Timeout for each extra probe command.
+    #[serde(default = "default_status_command_timeout")]
+    pub command_timeout: String,
 }
-fn default_enable_mcp() -> bool {
-    false
+
+/// `[context.dedupe]` — replace sections that did not change since the
+/// previous iteration with a short marker.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct DedupeConfig {
+    /// Sections that may be elided; none by default.
+    #[serde(default)]
+    pub sections: Vec<String>,
+
+    /// Send an unchanged section in full again after this many iterations.
+    #[serde(default = "default_dedupe_refresh_every")]
+    pub refresh_every: u32,
 }
 
-impl Default for MemoryConfig {
-    fn default() -> Self {
-        Self {
-            dir: default_memory_dir(),
-            state_file: default_state_file(),
-        }
-    }
+/// `[context.summarize]` — replace oversized sections with an LLM summary.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SummarizeConfig {
+    /// Summarize a section once it exceeds this many tokens; off if unset.
+    #[serde(default)]
+    pub over_tokens: Option<usize>,
+
+    /// Model for the summarization call; `agent.model` if unset.
+    #[serde(default)]
+    pub model: Option<String>,
+
+    /// Sections that may be summarized.
+    #[serde(default = "default_summarize_sections")]
+    pub sections: Vec<String>,
+
+    #[serde(default = "default_summarize_timeout")]
+    pub timeout: String,
 }
 
-impl Default for LoopConfig {
+/// `[context.plugins.<name>]`
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct ContextPluginConfig {
+    #[serde(default)]
+    pub timeout: Option<String>,
+
+    /// Abort the iteration when this script fails instead of noting the
+    /// failure in the context.
+    #[serde(default)]
+    pub required: bool,
+
+    /// Reuse the script's last successful output for this long.
+    #[serde(default)]
+    pub cache_ttl: Option<String>,
+
+    /// Extra arguments passed to the script.
+    #[serde(default)]
+    pub args: Vec<String>,
+
+    /// Extra variables set for the script, resolved like `[agent.env]`.
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+
+    /// Run the script only when these conditions hold.
+    #[serde(default)]
+    pub when: ContextCondition,
+
+    /// Order among context plugins; lower runs and is placed first.
+    /// Defaults to 100, like built-in plugins without a priority.
+    #[serde(default)]
+    pub priority: Option<i32>,
+
+    /// Honor `"trusted": true` in the script's JSON output. Off by default
+    /// so a script relaying fetched JSON can't promote it.
+    #[serde(default)]
+    pub allow_trusted: bool,
+
+    /// Run the script in a sandbox; overrides one in its manifest.
+    #[serde(default)]
+    pub sandbox: Option<SandboxConfig>,
+}
+
+impl ContextConfig {
+    /// Settings for a context script, matched by file name, then by stem.
+    pub fn plugin(&self, file_name: &str) -> Option<&ContextPluginConfig> {
+        self.plugins.get(file_name).or_else(|| {
+            let stem = Path::new(file_name).file_stem()?.to_str()?;
+            self.plugins.get(stem)
+        })
+    }
+}
+
+/// `[hooks]`: how lifecycle hooks run.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct HooksConfig {
+    /// Longest a hook script may run before it is killed and counted as
+    /// failed.
+    #[serde(default = "default_hook_timeout")]
+    pub timeout: String,
+
+    /// HTTP POST notifications for run events, by name.
+    #[serde(default)]
+    pub webhooks: BTreeMap<String, WebhookConfig>,
+
+    /// Per-hook settings, e.g. `[hooks.post-llm] required = false`.
+    #[serde(flatten)]
+    pub points: BTreeMap<String, HookPointConfig>,
+
+    /// Hooks allowed to run, from `boucle run --only-hooks` / `--skip-hooks`
+    /// (an empty list); all of them if unset. Not read from boucle.toml.
+    #[serde(skip)]
+    pub only: Option<Vec<String>>,
+}
+
+/// `[hooks.webhooks.<name>]` — a URL that is POSTed to on run events.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct WebhookConfig {
+    pub url: String,
+
+    /// Events to send, e.g. `["run-failed", "committed"]`; all if empty.
+    #[serde(default)]
+    pub events: Vec<String>,
+
+    /// JSON body template with `{event}`, `{agent}`, `{reason}`, ...
+    /// placeholders; the full event payload if unset.
+    #[serde(default)]
+    pub body: Option<String>,
+
+    /// Request headers, resolved like `[agent.env]`.
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+
+    /// Extra attempts after a network error, 429, or 5xx response.
+    #[serde(default = "default_webhook_retries")]
+    pub retries: u32,
+
+    /// Timeout for each attempt.
+    #[serde(default = "default_webhook_timeout")]
+    pub timeout: String,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct HookPointConfig {
+    /// Whether a failure stops the run (the default). A failing optional
+    /// hook is logged and the iteration carries on.
+    #[serde(default)]
+    pub required: Option<bool>,
+
+    /// Overrides `[hooks] timeout` for this hook.
+    #[serde(default)]
+    pub timeout: Option<String>,
+
+    /// Run the hook's scripts in the background, like a `# boucle: async`
+    /// header comment.
+    #[serde(default, rename = "async")]
+    pub background: bool,
+
+    /// Run the hook's scripts in a sandbox.
+    #[serde(default)]
+    pub sandbox: Option<SandboxConfig>,
+}
+
+/// A `sandbox` table for a hook, context script, or plugin. Its presence
+/// turns the sandbox on: the script gets a clean environment and can only
+/// write to the memory directory under the agent root.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct SandboxConfig {
+    /// Host variables passed through besides PATH, HOME, the locale, and
+    /// the ones boucle sets (exact names or `PREFIX*`).
+    #[serde(default)]
+    pub env: Vec<String>,
+
+    /// Mount the agent root read-only, except the memory directory.
+    #[serde(default = "default_sandbox_read_only")]
+    pub read_only: bool,
+
+    /// Allow network access.
+    #[serde(default = "default_sandbox_network")]
+    pub network: bool,
+}
+
+impl HooksConfig {
+    pub fn enabled(&self, hook: &str) -> bool {
+        self.only
+            .as_ref()
+            .is_none_or(|only| only.iter().any(|name| name == hook))
+    }
+
+    pub fn required(&self, hook: &str) -> bool {
+        self.points
+            .get(hook)
+            .and_then(|point| point.required)
+            .unwrap_or(true)
+    }
+
+    pub fn background(&self, hook: &str) -> bool {
+        self.points.get(hook).is_some_and(|point| point.background)
+    }
+
+    pub fn sandbox(&self, hook: &str) -> Option<&SandboxConfig> {
+        self.points.get(hook)?.sandbox.as_ref()
+    }
+
+    pub fn timeout(&self, hook: &str) -> &str {
+        self.points
+            .get(hook)
+            .and_then(|point| point.timeout.as_deref())
+            .unwrap_or(&self.timeout)
+    }
+}
+
+/// Limits on command plugins run as `boucle <name>` or `plugin_*` tools.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PluginLimitsConfig {
+    /// Kill a plugin after this long unless its manifest sets `timeout`.
+    #[serde(default = "default_plugin_limit_timeout")]
+    pub timeout: String,
+
+    /// Stop a captured plugin once it prints more than this many bytes to
+    /// stdout or stderr.
+    #[serde(default = "default_max_output_bytes")]
+    pub max_output_bytes: usize,
+
+    /// Plugins allowed to run at once in this agent; 0 for no limit.
+    #[serde(default = "default_max_concurrent")]
+    pub max_concurrent: usize,
+}
+
+/// Prometheus metrics for the agent, as served by `boucle metrics`.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct MetricsConfig {
+    /// File (relative to the agent root) rewritten with the metrics after
+    /// each `boucle run`, for node_exporter's textfile collector, e.g.
+    /// `"/var/lib/node_exporter/textfile/boucle-myagent.prom"`.
+    #[serde(default)]
+    pub textfile: Option<String>,
+}
+
+/// `[notify]` — messages to operators about how runs turn out.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct NotifyConfig {
+    /// Spending in US dollars on one UTC day, from the run records, past
+    /// which `budget-exceeded` is sent.
+    #[serde(default)]
+    pub daily_budget_usd: Option<f64>,
+
+    /// Where messages go, by name.
+    #[serde(default)]
+    pub channels: BTreeMap<String, NotifyChannel>,
+}
+
+/// `[notify.channels.<name>]`.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct NotifyChannel {
+    #[serde(rename = "type")]
+    pub kind: NotifyKind,
+
+    /// Incoming webhook for `slack`, `discord`, and `webhook` channels,
+    /// resolved like `[agent.env]` (`secret:<name>`, `file:<path>`, `${VAR}`).
+    #[serde(default)]
+    pub url: Option<String>,
+
+    /// Recipient of an `email` channel.
+    #[serde(default)]
+    pub to: Option<String>,
+
+    /// Command an `email` channel pipes the message to.
+    #[serde(default = "default_sendmail")]
+    pub sendmail: String,
+
+    /// Events to send, e.g. `["failure", "daily-summary"]`; failure,
+    /// budget-exceeded and stopped if empty.
+    #[serde(default)]
+    pub events: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NotifyKind {
+    Slack,
+    Discord,
+    Webhook,
+    Email,
+}
+
+/// OpenTelemetry export of each iteration as a trace.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct TelemetryConfig {
+    /// OTLP/HTTP collector, e.g. `"http://localhost:4318"`. Traces go to
+    /// `<endpoint>/v1/traces`. `OTEL_EXPORTER_OTLP_ENDPOINT` takes precedence
+    /// when set.
+    #[serde(default)]
+    pub otlp_endpoint: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct McpConfig {
+    #[serde(default = "default_enable_mcp")]
+    pub enable: bool,
+}
+
+impl Default for GitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: None,
+            commit_name: default_commit_name(),
+            commit_email: default_commit_email(),
+            rollback_on_failure: false,
+            run_branch: None,
+            merge_run_branch: default_merge_run_branch(),
+            protected_branches: Vec::new(),
+            auto_commit: default_auto_commit(),
+            commit_message: default_commit_message(),
+            push: false,
+            remote: default_remote(),
+            branch: None,
+            sync: None,
+            paths: Vec::new(),
+            pull_request: false,
+            sign: None,
+            signing_key: None,
+        }
+    }
+}
+
+impl Default for PluginLimitsConfig {
+    fn default() -> Self {
+        Self {
+            timeout: default_plugin_limit_timeout(),
+            max_output_bytes: default_max_output_bytes(),
+            max_concurrent: default_max_concurrent(),
+        }
+    }
+}
+
+impl Default for ContextConfig {
+    fn default() -> Self {
+        Self {
+            budget_tokens: None,
+            priorities: default_context_priorities(),
+            plugin_timeout: default_plugin_timeout(),
+            recall_limit: default_recall_limit(),
+            plugins: BTreeMap::new(),
+            summarize: SummarizeConfig::default(),
+            dedupe: DedupeConfig::default(),
+            status: StatusConfig::default(),
+            remote: BTreeMap::new(),
+            redact: RedactConfig::default(),
+            github: GithubContextConfig::default(),
+        }
+    }
+}
+
+impl Default for GithubContextConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            repos: Vec::new(),
+            token_env: default_github_token_env(),
+            limit: default_github_limit(),
+        }
+    }
+}
+
+impl Default for RedactConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_redact_enabled(),
+            patterns: Vec::new(),
+            env_files: default_redact_env_files(),
+        }
+    }
+}
+
+impl Default for StatusConfig {
+    fn default() -> Self {
+        Self {
+            probes: default_status_probes(),
+            commands: BTreeMap::new(),
+            command_timeout: default_status_command_timeout(),
+        }
+    }
+}
+
+impl Default for DedupeConfig {
+    fn default() -> Self {
+        Self {
+            sections: Vec::new(),
+            refresh_every: default_dedupe_refresh_every(),
+        }
+    }
+}
+
+impl Default for SummarizeConfig {
+    fn default() -> Self {
+        Self {
+            over_tokens: None,
+            model: None,
+            sections: default_summarize_sections(),
+            timeout: default_summarize_timeout(),
+        }
+    }
+}
+
+impl Default for HooksConfig {
+    fn default() -> Self {
+        Self {
+            timeout: default_hook_timeout(),
+            webhooks: BTreeMap::new(),
+            points: BTreeMap::new(),
+            only: None,
+        }
+    }
+}
+
+impl Default for McpConfig {
+    fn default() -> Self {
+        Self {
+            enable: default_enable_mcp(),
+        }
+    }
+}
+
+// Default value functions
+fn default_model() -> String {
+    "gpt-5.4".to_string()
+}
+fn default_system_prompt() -> String {
+    "system-prompt.md".to_string()
+}
+fn default_memory_dir() -> String {
+    "memory".to_string()
+}
+fn default_state_file() -> String {
+    "STATE.md".to_string()
+}
+fn default_max_tokens() -> usize {
+    200_000
+}
+fn default_llm_timeout_seconds() -> u64 {
+    7_200
+}
+fn default_rate_limit_cooldown() -> String {
+    "15m".to_string()
+}
+fn default_interval() -> String {
+    "1h".to_string()
+}
+fn default_commit_name() -> String {
+    "Boucle".to_string()
+}
+fn default_commit_email() -> String {
+    "boucle@agent".to_string()
+}
+fn default_merge_run_branch() -> bool {
+    true
+}
+fn default_auto_commit() -> bool {
+    true
+}
+fn default_commit_message() -> String {
+    "Loop iteration: {timestamp}".to_string()
+}
+fn default_remote() -> String {
+    "origin".to_string()
+}
+fn default_enable_mcp() -> bool {
+    false
+}
+fn default_sandbox_read_only() -> bool {
+    true
+}
+fn default_sandbox_network() -> bool {
+    true
+}
+fn default_hook_timeout() -> String {
+    "5m".to_string()
+}
+fn default_webhook_retries() -> u32 {
+    2
+}
+fn default_webhook_timeout() -> String {
+    "10s".to_string()
+}
+fn default_sendmail() -> String {
+    "sendmail -t".to_string()
+}
+fn default_plugin_timeout() -> String {
+    "60s".to_string()
+}
+fn default_github_token_env() -> String {
+    "GITHUB_TOKEN".to_string()
+}
+fn default_github_limit() -> usize {
+    20
+}
+fn default_plugin_limit_timeout() -> String {
+    "10m".to_string()
+}
+fn default_max_output_bytes() -> usize {
+    1024 * 1024
+}
+fn default_max_concurrent() -> usize {
+    4
+}
+fn default_summarize_sections() -> Vec<String> {
+    vec!["plugins".to_string(), "last_log".to_string()]
+}
+fn default_summarize_timeout() -> String {
+    "120s".to_string()
+}
+fn default_dedupe_refresh_every() -> u32 {
+    5
+}
+fn default_redact_enabled() -> bool {
+    true
+}
+fn default_redact_env_files() -> Vec<String> {
+    vec![".env".to_string()]
+}
+fn default_remote_ttl() -> String {
+    "15m".to_string()
+}
+fn default_remote_max_bytes() -> usize {
+    16 * 1024
+}
+fn default_include_max_bytes() -> usize {
+    16 * 1024
+}
+fn default_status_probes() -> Vec<String> {
+    STATUS_PROBES
+        .iter()
+        .map(|probe| probe.to_string())
+        .collect()
+}
+fn default_status_command_timeout() -> String {
+    "10s".to_string()
+}
+fn default_recall_limit() -> usize {
+    5
+}
+fn default_context_sections() -> Vec<String> {
+    CONTEXT_SECTIONS.iter().map(|s| s.to_string()).collect()
+}
+fn default_context_priorities() -> Vec<String> {
+    CONTEXT_SECTIONS.iter().map(|s| s.to_string()).collect()
+}
+
+impl Default for MemoryConfig {
+    fn default() -> Self {
+        Self {
+            dir: default_memory_dir(),
+            state_file: default_state_file(),
+            defaults: BTreeMap::new(),
+        }
+    }
+}
+
+impl Default for LoopConfig {
     fn default() -> Self {
         Self {
             context_dir: None,
             hooks_dir: None,
             log_dir: None,
+            goals_file: None,
             max_tokens: default_max_tokens(),
             llm_timeout_seconds: default_llm_timeout_seconds(),
+            auto_journal: false,
+            rate_limit_cooldown: default_rate_limit_cooldown(),
+            allow_external_dirs: false,
+            context: LoopContextConfig::default(),
+        }
+    }
+}
+
+impl Default for LoopContextConfig {
+    fn default() -> Self {
+        Self {
+            sections: default_context_sections(),
+            when: BTreeMap::new(),
+            include: Vec::new(),
+            include_max_bytes: default_include_max_bytes(),
+        }
+    }
+}
+
+impl Default for ScheduleConfig {
+    fn default() -> Self {
+        Self {
+            interval: default_interval(),
+            jitter: None,
+            active_hours: None,
+            active_days: None,
+            catch_up: CatchUp::default(),
+            method: None,
+            named: BTreeMap::new(),
+            current: None,
+        }
+    }
+}
+
+/// Errors that can occur during configuration.
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    Parse(toml::de::Error),
+    NotFound,
+    Invalid(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(e) => write!(f, "IO error: {e}"),
+            ConfigError::Parse(e) => write!(f, "Parse error: {e}"),
+            ConfigError::NotFound => write!(f, "boucle.toml not found"),
+            ConfigError::Invalid(msg) => write!(f, "Invalid value: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl From<io::Error> for ConfigError {
+    fn from(e: io::Error) -> Self {
+        ConfigError::Io(e)
+    }
+}
+
+impl From<toml::de::Error> for ConfigError {
+    fn from(e: toml::de::Error) -> Self {
+        ConfigError::Parse(e)
+    }
+}
+
+impl RunProfile {
+    /// The section tables the profile overrides, by section name.
+    fn sections(&self) -> [(&'static str, &toml::Table); 4] {
+        [
+            ("agent", &self.agent),
+            ("loop", &self.loop_config),
+            ("schedule", &self.schedule),
+            ("context", &self.context),
+        ]
+    }
+}
+
+impl Config {
+    /// Apply a named run profile in place.
+    ///
+    /// Logs move to `<log_dir>/<name>/` so each profile keeps its own
+    /// history (and its own "last log entry" in the next context).
+    pub fn apply_profile(&mut self, name: &str) -> Result<(), ConfigError> {
+        if !is_valid_profile_name(name) {
+            return Err(ConfigError::Invalid(format!(
+                "profile name '{name}' may only contain letters, digits, '-' and '_'"
+            )));
+        }
+        let profile = self.profiles.get(name).ok_or_else(|| {
+            let known: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+            ConfigError::Invalid(format!(
+                "unknown profile '{name}' (defined: {})",
+                if known.is_empty() {
+                    "none".to_string()
+                } else {
+                    known.join(", ")
+                }
+            ))
+        })?;
+
+        if let Some(ref model) = profile.model {
+            self.agent.model = model.clone();
+        }
+        if let Some(ref goals_file) = profile.goals_file {
+            self.loop_config.goals_file = Some(goals_file.clone());
+        }
+        if let Some(ref context_dir) = profile.context_dir {
+            self.loop_config.context_dir = Some(context_dir.clone());
+        }
+        if let Some(ref interval) = profile.interval {
+            self.schedule.interval = interval.clone();
+        }
+        let base_log_dir = self.loop_config.log_dir.as_deref().unwrap_or("logs");
+        self.loop_config.log_dir = Some(format!("{base_log_dir}/{name}"));
+        Ok(())
+    }
+
+    /// The configured directories by key; `None` when one is unset.
+    fn dirs_mut(&mut self) -> [(&'static str, Option<&mut String>); 4] {
+        let loop_config = &mut self.loop_config;
+        [
+            ("memory.dir", Some(&mut self.memory.dir)),
+            ("loop.context_dir", loop_config.context_dir.as_mut()),
+            ("loop.hooks_dir", loop_config.hooks_dir.as_mut()),
+            ("loop.log_dir", loop_config.log_dir.as_mut()),
+        ]
+    }
+
+    /// Normalize the configured directories against `root`: `./memory/`
+    /// becomes `memory`, and an absolute path inside the root becomes
+    /// relative. A directory outside the root is an error unless
+    /// `loop.allow_external_dirs` is set, as is one that exists but is
+    /// not a directory.
+    pub fn resolve_dirs(&mut self, root: &Path) -> Result<(), ConfigError> {
+        let allow_external = self.loop_config.allow_external_dirs;
+        for (key, value) in self.dirs_mut() {
+            let Some(value) = value else {
+                continue;
+            };
+            if value.trim().is_empty() {
+                return Err(ConfigError::Invalid(format!("{key} is empty")));
+            }
+            let resolved = match resolve_dir(root, value) {
+                Some(resolved) => resolved,
+                None if allow_external => normalize_path(Path::new(value)),
+                None => {
+                    return Err(ConfigError::Invalid(format!(
+                        "{key} '{value}' is outside the agent root (set loop.allow_external_dirs = true to allow it)"
+                    )))
+                }
+            };
+            let path = root.join(&resolved);
+            if path.exists() && !path.is_dir() {
+                return Err(ConfigError::Invalid(format!(
+                    "{key} '{value}' is not a directory"
+                )));
+            }
+            *value = resolved.to_string_lossy().into_owned();
+        }
+        Ok(())
+    }
+
+    /// Create the directories the runner writes to, `memory.dir` and
+    /// `loop.log_dir`, naming the setting when that fails.
+    pub fn create_dirs(&self, root: &Path) -> Result<(), ConfigError> {
+        let log_dir = self.loop_config.log_dir.as_deref().unwrap_or("logs");
+        for (key, dir) in [
+            ("memory.dir", self.memory.dir.as_str()),
+            ("loop.log_dir", log_dir),
+        ] {
+            fs::create_dir_all(root.join(dir)).map_err(|e| {
+                ConfigError::Invalid(format!("{key} '{dir}': cannot create directory: {e}"))
+            })?;
+        }
+        Ok(())
+    }
+}
+
+/// `value` relative to `root` with `.` and `..` folded away, or `None`
+/// when it leads outside `root`.
+fn resolve_dir(root: &Path, value: &str) -> Option<PathBuf> {
+    let path = normalize_path(Path::new(value));
+    if !path.has_root() {
+        let escapes = path.components().next() == Some(std::path::Component::ParentDir);
+        return (!escapes).then_some(path);
+    }
+    let absolute_root = std::path::absolute(root).ok().map(|r| normalize_path(&r));
+    let canonical_root = root.canonicalize().ok();
+    [absolute_root, canonical_root]
+        .into_iter()
+        .flatten()
+        .find_map(|root| path.strip_prefix(root).ok().map(Path::to_path_buf))
+        .map(|relative| {
+            if relative.as_os_str().is_empty() {
+                PathBuf::from(".")
+            } else {
+                relative
+            }
+        })
+}
+
+/// Fold `.` and `..` components out of `path` without touching the disk.
+fn normalize_path(path: &Path) -> PathBuf {
+    use std::path::Component;
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match normalized.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    normalized.pop();
+                }
+                Some(Component::RootDir | Component::Prefix(_)) => {}
+                _ => normalized.push(".."),
+            },
+            other => normalized.push(other),
+        }
+    }
+    if normalized.as_os_str().is_empty() {
+        normalized.push(".");
+    }
+    normalized
+}
+
+pub(crate) fn is_valid_profile_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Load configuration from boucle.toml in the given directory.
+pub fn load(root: &Path) -> Result<Config, ConfigError> {
+    load_profile(root, None)
+}
+
+/// Load configuration with run profile `profile` applied, if given: the
+/// workspace `[defaults]` go under boucle.toml, the profile's section
+/// tables are merged over it, then `BOUCLE_*` overrides are layered on top,
+/// then [`Config::apply_profile`] runs.
+pub fn load_profile(root: &Path, profile: Option<&str>) -> Result<Config, ConfigError> {
+    let config_path = root.join("boucle.toml");
+    if !config_path.exists() {
+        return Err(ConfigError::NotFound);
+    }
+    let content = fs::read_to_string(&config_path)?;
+    let mut table: toml::Table = toml::from_str(&content)?;
+    check_profile_tables(&table)?;
+    let defaults = workspace_defaults(root)?.filter(|defaults| !defaults.is_empty());
+    if let Some(mut base) = defaults.clone() {
+        merge_table(&mut base, &table);
+        table = base;
+    }
+    let merged = profile.is_some_and(|name| merge_profile(&mut table, name));
+    let applied = apply_env_overrides(&mut table, std::env::vars())?;
+    check_schedule_keys(&table)?;
+    let mut config: Config = if defaults.is_none() && !merged && applied.is_empty() {
+        toml::from_str(&content)?
+    } else {
+        toml::Value::Table(table).try_into().map_err(|e| {
+            let mut sources = Vec::new();
+            if defaults.is_some() {
+                sources.push(format!("{WORKSPACE_FILE} defaults"));
+            }
+            if let Some(name) = profile.filter(|_| merged) {
+                sources.push(format!("profile '{name}'"));
+            }
+            if !applied.is_empty() {
+                sources.push(applied.join(", "));
+            }
+            ConfigError::Invalid(format!(
+                "boucle.toml with {} applied: {e}",
+                sources.join(" and ")
+            ))
+        })?
+    };
+    if let Some(version) = config.version.filter(|v| *v > migrate::CURRENT_VERSION) {
+        return Err(migrate::newer_than_supported(version));
+    }
+    if let Some(name) = profile {
+        config.apply_profile(name)?;
+    }
+    config.resolve_dirs(root)?;
+    Ok(config)
+}
+
+/// `[profile]` is the old name of `[profiles]`. A file with both would fail
+/// as a duplicate field; say which tables clash instead.
+fn check_profile_tables(table: &toml::Table) -> Result<(), ConfigError> {
+    if table.contains_key("profile") && table.contains_key("profiles") {
+        return Err(ConfigError::Invalid(
+            "boucle.toml has both [profiles.<name>] and [profile.<name>] tables — [profile] is the old name; move those into [profiles]".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Every key in `[schedule]` other than its own settings is taken as a
+/// named schedule, so a typo would otherwise fail with a confusing type
+/// error.
+fn check_schedule_keys(table: &toml::Table) -> Result<(), ConfigError> {
+    let Some(schedule) = table.get("schedule").and_then(|s| s.as_table()) else {
+        return Ok(());
+    };
+    for (key, value) in schedule {
+        if ScheduleConfig::FIELDS.contains(&key.as_str()) {
+            continue;
         }
+        if !value.is_table() {
+            return Err(ConfigError::Invalid(format!(
+                "unknown key 'schedule.{key}' — [schedule] takes {}, or [schedule.<name>] tables",
+                ScheduleConfig::FIELDS.join(", ")
+            )));
+        }
+        if !is_valid_profile_name(key) {
+            return Err(ConfigError::Invalid(format!(
+                "schedule name '{key}' may only contain letters, digits, '-' and '_'"
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// A config for long-running processes such as `boucle mcp` that reloads
+/// itself when boucle.toml changes.
+#[derive(Debug)]
+pub struct LiveConfig {
+    root: PathBuf,
+    profile: Option<String>,
+    stamp: Option<(SystemTime, u64)>,
+    config: Config,
+}
+
+impl LiveConfig {
+    pub fn load(root: &Path, profile: Option<&str>) -> Result<LiveConfig, ConfigError> {
+        let stamp = config_stamp(root);
+        Ok(LiveConfig {
+            root: root.to_path_buf(),
+            profile: profile.map(str::to_string),
+            stamp,
+            config: load_profile(root, profile)?,
+        })
+    }
+
+    pub fn get(&self) -> &Config {
+        &self.config
+    }
+
+    /// Reload if boucle.toml changed since the last look. A new version
+    /// that fails to load is returned as the error, once, and the previous
+    /// config stays in use. Returns whether the config was replaced.
+    pub fn refresh(&mut self) -> Result<bool, ConfigError> {
+        let stamp = config_stamp(&self.root);
+        if stamp == self.stamp {
+            return Ok(false);
+        }
+        self.stamp = stamp;
+        self.config = load_profile(&self.root, self.profile.as_deref())?;
+        Ok(true)
+    }
+}
+
+/// Modification time and size of boucle.toml, to notice edits.
+fn config_stamp(root: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = fs::metadata(root.join("boucle.toml")).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// Merge the section tables of profile `name` over the top-level ones.
+/// Returns whether the profile has any.
+pub(crate) fn merge_profile(table: &mut toml::Table, name: &str) -> bool {
+    let profile: Option<RunProfile> = ["profiles", "profile"]
+        .iter()
+        .find_map(|key| table.get(*key)?.get(name))
+        .and_then(|profile| profile.clone().try_into().ok());
+    let Some(profile) = profile else {
+        return false;
+    };
+    let mut merged = false;
+    for (section, overlay) in profile.sections() {
+        if overlay.is_empty() {
+            continue;
+        }
+        let base = table
+            .entry(section)
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+        if let toml::Value::Table(base) = base {
+            merge_table(base, overlay);
+            merged = true;
+        }
+    }
+    merged
+}
+
+/// Merge `overlay` into `base`, recursing into tables both have.
+pub(crate) fn merge_table(base: &mut toml::Table, overlay: &toml::Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(overlay)) => {
+                merge_table(base, overlay)
+            }
+            _ => {
+                base.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
+/// Top-level tables of boucle.toml.
+pub const SECTIONS: &[&str] = &[
+    "agent",
+    "memory",
+    "loop",
+    "schedule",
+    "git",
+    "mcp",
+    "context",
+    "hooks",
+    "profiles",
+    "plugins",
+    "plugin_limits",
+    "metrics",
+    "telemetry",
+    "notify",
+    "secrets",
+];
+
+/// Sections whose settings `BOUCLE_<SECTION>_<KEY>` variables override.
+const ENV_OVERRIDE_SECTIONS: &[&str] = &[
+    "agent",
+    "memory",
+    "loop",
+    "schedule",
+    "git",
+    "mcp",
+    "context",
+    "hooks",
+    "plugin_limits",
+    "metrics",
+    "telemetry",
+    "notify",
+];
+
+/// Variables boucle sets for the scripts it runs, which would otherwise
+/// look like overrides to a `boucle` started from one of them.
+const ENV_OVERRIDE_RESERVED: &[&str] = &["BOUCLE_AGENT_NAME", "BOUCLE_MEMORY_ENTRY"];
+
+/// Layer `BOUCLE_<SECTION>_<KEY>` variables from `vars` over a parsed
+/// boucle.toml: `BOUCLE_LOOP_LLM_TIMEOUT_SECONDS=600` sets
+/// `[loop] llm_timeout_seconds`. Values are read as TOML values (numbers,
+/// booleans, `["a", "b"]` arrays) and as plain strings when that does not
+/// fit the setting. Returns the names of the variables applied.
+pub(crate) fn apply_env_overrides(
+    table: &mut toml::Table,
+    vars: impl IntoIterator<Item = (String, String)>,
+) -> Result<Vec<String>, ConfigError> {
+    let mut vars: Vec<_> = vars.into_iter().collect();
+    vars.sort();
+    let mut applied = Vec::new();
+    for (name, value) in vars {
+        let Some(rest) = name.strip_prefix("BOUCLE_") else {
+            continue;
+        };
+        if ENV_OVERRIDE_RESERVED.contains(&name.as_str()) {
+            continue;
+        }
+        let Some((section, key)) = ENV_OVERRIDE_SECTIONS.iter().find_map(|section| {
+            let key = rest
+                .strip_prefix(&section.to_uppercase())?
+                .strip_prefix('_')?;
+            (!key.is_empty()).then(|| (*section, key.to_lowercase()))
+        }) else {
+            continue;
+        };
+
+        let typed = toml::Value::deserialize(toml::de::ValueDeserializer::new(&value))
+            .ok()
+            .filter(|typed| !typed.is_str());
+        let is_typed = typed.is_some();
+        let setting = typed.unwrap_or_else(|| toml::Value::String(value.clone()));
+        set_override(table, &name, section, key.clone(), setting)?;
+        if is_typed
+            && toml::Value::Table(table.clone())
+                .try_into::<Config>()
+                .is_err()
+        {
+            set_override(table, &name, section, key, toml::Value::String(value))?;
+        }
+        applied.push(name);
+    }
+    Ok(applied)
+}
+
+/// Dotted paths of the keys in `table` that no setting reads, such as
+/// `loop.max_tokns`; serde skips them silently when loading. Empty when the
+/// table does not load at all, since [`load`] reports that.
+pub fn unknown_keys(table: &toml::Table) -> Vec<String> {
+    let mut unknown = Vec::new();
+    let loaded: Result<Config, _> =
+        serde_ignored::deserialize(toml::Value::Table(table.clone()), |path| {
+            let path = path.to_string();
+            let segments: Vec<&str> = path.split('.').filter(|s| *s != "?").collect();
+            unknown.push(segments.join("."));
+        });
+    if loaded.is_err() {
+        return Vec::new();
+    }
+    unknown
+}
+
+/// The configuration the runner will use, as `boucle config show`
+/// prints it: defaults filled in, and for each setting where it came from.
+#[derive(Debug)]
+pub struct EffectiveConfig {
+    pub table: toml::Table,
+    /// Source of each value by dotted path: `default`,
+    /// `boucle-workspace.toml`, `boucle.toml`, `profile <name>`, or the
+    /// `BOUCLE_*` variable that set it.
+    pub sources: BTreeMap<String, String>,
+}
+
+/// Load the configuration like [`load_profile`] and work out where each
+/// resulting setting came from by comparing the file, the file with the
+/// profile's tables merged, the `BOUCLE_*` overrides, and the final config.
+pub fn effective(root: &Path, profile: Option<&str>) -> Result<EffectiveConfig, ConfigError> {
+    let config = load_profile(root, profile)?;
+    let mut file: toml::Table = toml::from_str(&fs::read_to_string(root.join("boucle.toml"))?)?;
+    if let Some(profiles) = file
+        .remove("profile")
+        .filter(|_| !file.contains_key("profiles"))
+    {
+        file.insert("profiles".to_string(), profiles);
+    }
+    let mut layered = workspace_defaults(root)?.unwrap_or_default();
+    merge_table(&mut layered, &file);
+    let mut merged = layered.clone();
+    if let Some(name) = profile {
+        merge_profile(&mut merged, name);
+    }
+    let mut overridden = merged.clone();
+    let applied = apply_env_overrides(&mut overridden, std::env::vars())?;
+    let before_profile: Config = toml::Value::Table(overridden.clone()).try_into()?;
+
+    let table = to_table(&config)?;
+    let [file, layered, merged, overridden, before_profile, effective] = [
+        &file,
+        &layered,
+        &merged,
+        &overridden,
+        &to_table(&before_profile)?,
+        &table,
+    ]
+    .map(|table| {
+        let mut leaves = BTreeMap::new();
+        flatten_leaves(table, "", &mut leaves);
+        leaves
+    });
+    let sources = effective
+        .iter()
+        .map(|(path, value)| {
+            let var = format!("BOUCLE_{}", path.replace('.', "_").to_uppercase());
+            let source = if overridden.get(path) != merged.get(path) && applied.contains(&var) {
+                var
+            } else if let Some(name) = profile.filter(|_| {
+                merged.get(path) != layered.get(path) || before_profile.get(path) != Some(value)
+            }) {
+                format!("profile {name}")
+            } else if file.contains_key(path) {
+                "boucle.toml".to_string()
+            } else if layered.contains_key(path) {
+                WORKSPACE_FILE.to_string()
+            } else {
+                "default".to_string()
+            };
+            (path.clone(), source)
+        })
+        .collect();
+    Ok(EffectiveConfig { table, sources })
+}
+
+impl EffectiveConfig {
+    /// The settings as TOML, each followed by a `# <source>` comment.
+    pub fn to_annotated_toml(&self) -> String {
+        let mut out = String::new();
+        self.write_table(&mut out, &self.table, &mut Vec::new());
+        out.trim_start().to_string()
     }
-}
 
-impl Default for ScheduleConfig {
-    fn default() -> Self {
-        Self {
-            interval: default_interval(),
-            method: None,
+    fn write_table(&self, out: &mut String, table: &toml::Table, path: &mut Vec<String>) {
+        let (tables, values): (Vec<_>, Vec<_>) =
+            table.iter().partition(|(_, value)| value.is_table());
+        if !path.is_empty() && (!values.is_empty() || (path.len() == 1 && tables.is_empty())) {
+            let header: Vec<String> = path.iter().map(|key| toml_key(key)).collect();
+            out.push_str(&format!("\n[{}]\n", header.join(".")));
+        }
+        for (key, value) in values {
+            path.push(key.clone());
+            let source = self
+                .sources
+                .get(&path.join("."))
+                .map_or("default", String::as_str);
+            out.push_str(&format!("{} = {value}  # {source}\n", toml_key(key)));
+            path.pop();
+        }
+        for (key, value) in tables {
+            if let toml::Value::Table(table) = value {
+                path.push(key.clone());
+                self.write_table(out, table, path);
+                path.pop();
+            }
         }
     }
+
+    /// `{"config": {...}, "sources": {"loop.max_tokens": "default", ...}}`.
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({"config": self.table, "sources": self.sources})
+    }
 }
 
-/// Errors that can occur during configuration.
-#[derive(Debug)]
-pub enum ConfigError {
-    Io(io::Error),
-    Parse(toml::de::Error),
-    NotFound,
+fn to_table(config: &Config) -> Result<toml::Table, ConfigError> {
+    toml::Table::try_from(config).map_err(|e| ConfigError::Invalid(e.to_string()))
 }
 
-impl fmt::Display for ConfigError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            ConfigError::Io(e) => write!(f, "IO error: {e}"),
-            ConfigError::Parse(e) => write!(f, "Parse error: {e}"),
-            ConfigError::NotFound => write!(f, "boucle.toml not found"),
+/// Collect the non-table values of `table` by dotted path. Arrays count
+/// as single values.
+fn flatten_leaves(table: &toml::Table, prefix: &str, leaves: &mut BTreeMap<String, toml::Value>) {
+    for (key, value) in table {
+        let path = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{prefix}.{key}")
+        };
+        match value {
+            toml::Value::Table(table) => flatten_leaves(table, &path, leaves),
+            value => {
+                leaves.insert(path, value.clone());
+            }
         }
     }
 }
 
-impl std::error::Error for ConfigError {}
+/// `key` as written in TOML: bare when it can be, quoted otherwise.
+fn toml_key(key: &str) -> String {
+    let bare = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if bare {
+        key.to_string()
+    } else {
+        toml::Value::String(key.to_string()).to_string()
+    }
+}
 
-impl From<io::Error> for ConfigError {
-    fn from(e: io::Error) -> Self {
-        ConfigError::Io(e)
+fn set_override(
+    table: &mut toml::Table,
+    var: &str,
+    section: &str,
+    key: String,
+    value: toml::Value,
+) -> Result<(), ConfigError> {
+    table
+        .entry(section)
+        .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+        .as_table_mut()
+        .ok_or_else(|| ConfigError::Invalid(format!("{var}: [{section}] is not a table")))?
+        .insert(key, value);
+    Ok(())
+}
+
+/// Resolve an `[agent.env]` or `[context.plugins.<name>.env]` value;
+/// `setting` is the dotted key used in error messages.
+///
+/// `file:<path>` reads the value from a file (relative to the agent root,
+/// trailing newline trimmed) so secrets can stay out of boucle.toml, and
+/// `secret:<name>` resolves the entry `name` of `secrets` (the config's
+/// `[secrets]`). Otherwise `${VAR}` references are expanded from the host
+/// environment; an unset variable is an error rather than a silent empty
+/// string.
+pub fn resolve_env_value(
+    root: &Path,
+    secrets: &BTreeMap<String, SecretSource>,
+    setting: &str,
+    value: &str,
+) -> Result<String, ConfigError> {
+    if let Some(name) = value.strip_prefix("secret:") {
+        return resolve_secret(root, secrets, setting, name.trim());
     }
+    if let Some(path) = value.strip_prefix("file:") {
+        let content = fs::read_to_string(root.join(path.trim()))
+            .map_err(|e| ConfigError::Invalid(format!("{setting}: cannot read '{path}': {e}")))?;
+        return Ok(content.trim_end_matches(['\n', '\r']).to_string());
+    }
+    expand_vars(setting, value, |name| std::env::var(name).ok())
 }
 
-impl From<toml::de::Error> for ConfigError {
-    fn from(e: toml::de::Error) -> Self {
-        ConfigError::Parse(e)
+/// Expand the `${VAR}` references in `value`, looking each one up with
+/// `var`.
+fn expand_vars(
+    setting: &str,
+    value: &str,
+    var: impl Fn(&str) -> Option<String>,
+) -> Result<String, ConfigError> {
+    let mut out = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let end = after.find('}').ok_or_else(|| {
+            ConfigError::Invalid(format!("{setting}: unterminated '${{' in '{value}'"))
+        })?;
+        let name = &after[..end];
+        let resolved = var(name).ok_or_else(|| {
+            ConfigError::Invalid(format!("{setting}: host variable '{name}' is not set"))
+        })?;
+        out.push_str(&resolved);
+        rest = &after[end + 1..];
     }
+    out.push_str(rest);
+    Ok(out)
 }
 
-/// Load configuration from boucle.toml in the given directory.
-pub fn load(root: &Path) -> Result<Config, ConfigError> {
-    let config_path = root.join("boucle.toml");
-    if !config_path.exists() {
-        return Err(ConfigError::NotFound);
+/// Values of `command` secrets already run by this process, by agent root
+/// and secret name, so a password manager is asked once per process.
+static COMMAND_SECRETS: std::sync::Mutex<BTreeMap<(PathBuf, String), String>> =
+    std::sync::Mutex::new(BTreeMap::new());
+
+/// The value of secret `name` from `secrets`; `setting` is the dotted key
+/// that asked for it, for error messages. Errors never include the value.
+pub fn resolve_secret(
+    root: &Path,
+    secrets: &BTreeMap<String, SecretSource>,
+    setting: &str,
+    name: &str,
+) -> Result<String, ConfigError> {
+    let invalid =
+        |detail: String| ConfigError::Invalid(format!("{setting}: secret '{name}' {detail}"));
+    let source = secrets
+        .get(name)
+        .ok_or_else(|| invalid("is not defined in [secrets]".to_string()))?;
+    let value = match source {
+        SecretSource::Env(var) => std::env::var(var)
+            .map_err(|_| invalid(format!("needs host variable '{var}', which is not set")))?,
+        SecretSource::File(path) => fs::read_to_string(root.join(path))
+            .map_err(|e| invalid(format!("cannot read '{path}': {e}")))?,
+        SecretSource::Command(command) => {
+            let key = (root.to_path_buf(), name.to_string());
+            let mut cache = COMMAND_SECRETS.lock().unwrap_or_else(|e| e.into_inner());
+            if let Some(value) = cache.get(&key) {
+                return Ok(value.clone());
+            }
+            let output = secret_command(command)
+                .current_dir(root)
+                .stdin(std::process::Stdio::null())
+                .stderr(std::process::Stdio::null())
+                .output()
+                .map_err(|e| invalid(format!("command could not start: {e}")))?;
+            if !output.status.success() {
+                return Err(invalid(format!("command failed ({})", output.status)));
+            }
+            let value = String::from_utf8_lossy(&output.stdout).trim().to_string();
+            if !value.is_empty() {
+                cache.insert(key, value.clone());
+            }
+            value
+        }
+    };
+    let value = value.trim_end_matches(['\n', '\r']).to_string();
+    if value.is_empty() {
+        return Err(invalid("is empty".to_string()));
     }
-    let content = fs::read_to_string(&config_path)?;
-    let config: Config = toml::from_str(&content)?;
-    Ok(config)
+    Ok(value)
+}
+
+#[cfg(unix)]
+fn secret_command(command: &str) -> std::process::Command {
+    let mut cmd = std::process::Command::new("sh");
+    cmd.arg("-c").arg(command);
+    cmd
+}
+
+#[cfg(not(unix))]
+fn secret_command(command: &str) -> std::process::Command {
+    let mut cmd = std::process::Command::new("cmd");
+    cmd.arg("/C").arg(command);
+    cmd
+}
+
+/// Whether a variable name matches one of `patterns`: exact names or
+/// `PREFIX*`, as in `env_deny`.
+pub fn env_matches(name: &str, patterns: &[String]) -> bool {
+    patterns
+        .iter()
+        .any(|pattern| match pattern.strip_suffix('*') {
+            Some(prefix) => name.starts_with(prefix),
+            None => name == pattern,
+        })
 }
 
 /// Find the agent root by searching upward for boucle.toml.
 pub fn find_agent_root(start: &Path) -> Option<PathBuf> {
+    find_ancestor_with(start, "boucle.toml")
+}
+
+/// Find the workspace an agent root or directory belongs to by searching
+/// upward for boucle-workspace.toml.
+pub fn find_workspace(start: &Path) -> Result<Option<Workspace>, ConfigError> {
+    find_ancestor_with(start, WORKSPACE_FILE)
+        .map(|root| Workspace::load(&root))
+        .transpose()
+}
+
+fn find_ancestor_with(start: &Path, file_name: &str) -> Option<PathBuf> {
     let mut dir = start.to_path_buf();
     loop {
-        if dir.join("boucle.toml").exists() {
+        if dir.join(file_name).exists() {
             return Some(dir);
         }
         if !dir.pop() {
@@ -236,33 +2045,416 @@ pub fn find_agent_root(start: &Path) -> Option<PathBuf> {
     }
 }
 
-/// Parse an interval string like "1h", "30m", "5s" into seconds.
+/// File that groups agent roots into a workspace.
+pub const WORKSPACE_FILE: &str = "boucle-workspace.toml";
+
+/// boucle-workspace.toml: agents managed together and the settings they
+/// share.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WorkspaceConfig {
+    /// Agent roots relative to the workspace file. Globs such as
+    /// `"agents/*"` match the directories that have a boucle.toml.
+    pub members: Vec<String>,
+
+    /// Tables merged under each member's boucle.toml, e.g.
+    /// `[defaults.git]`; the member's own settings win.
+    #[serde(default)]
+    pub defaults: toml::Table,
+}
+
+/// A loaded boucle-workspace.toml and the directory it is in.
+#[derive(Debug)]
+pub struct Workspace {
+    pub root: PathBuf,
+    pub config: WorkspaceConfig,
+}
+
+impl Workspace {
+    pub fn load(root: &Path) -> Result<Workspace, ConfigError> {
+        let content = fs::read_to_string(root.join(WORKSPACE_FILE))?;
+        let config = toml::from_str(&content)
+            .map_err(|e| ConfigError::Invalid(format!("{WORKSPACE_FILE}: {e}")))?;
+        Ok(Workspace {
+            root: root.to_path_buf(),
+            config,
+        })
+    }
+
+    /// Member agent roots, in the order listed (glob matches sorted).
+    pub fn members(&self) -> Result<Vec<PathBuf>, ConfigError> {
+        let mut members: Vec<PathBuf> = Vec::new();
+        for member in &self.config.members {
+            let path = self.root.join(member);
+            let matched = if member.contains(['*', '?', '[']) {
+                let pattern = path.to_string_lossy().into_owned();
+                let matches = glob::glob(&pattern).map_err(|e| {
+                    ConfigError::Invalid(format!("{WORKSPACE_FILE}: member '{member}': {e}"))
+                })?;
+                let mut matched: Vec<PathBuf> = matches
+                    .flatten()
+                    .filter(|dir| dir.join("boucle.toml").is_file())
+                    .collect();
+                matched.sort();
+                matched
+            } else if path.join("boucle.toml").is_file() {
+                vec![path]
+            } else {
+                return Err(ConfigError::Invalid(format!(
+                    "{WORKSPACE_FILE}: member '{member}' has no boucle.toml"
+                )));
+            };
+            for dir in matched {
+                if !members.contains(&dir) {
+                    members.push(dir);
+                }
+            }
+        }
+        Ok(members)
+    }
+
+    /// A member's path relative to the workspace, for labelling output.
+    pub fn member_label(&self, member: &Path) -> String {
+        member
+            .strip_prefix(&self.root)
+            .unwrap_or(member)
+            .display()
+            .to_string()
+    }
+
+    /// Whether `root` is one of the members.
+    fn has_member(&self, root: &Path) -> Result<bool, ConfigError> {
+        let Ok(root) = root.canonicalize() else {
+            return Ok(false);
+        };
+        Ok(self
+            .members()?
+            .iter()
+            .any(|member| member.canonicalize().is_ok_and(|member| member == root)))
+    }
+}
+
+/// The `[defaults]` of the workspace `root` is a member of, if any.
+pub(crate) fn workspace_defaults(root: &Path) -> Result<Option<toml::Table>, ConfigError> {
+    match find_workspace(root)? {
+        Some(workspace) if workspace.has_member(root)? => Ok(Some(workspace.config.defaults)),
+        _ => Ok(None),
+    }
+}
+
+/// Parse an interval string like "1h", "30m", "5s", or a compound one
+/// like "1h30m" into seconds.
 pub fn parse_interval(interval: &str) -> Result<u64, String> {
     let interval = interval.trim();
     if interval.is_empty() {
         return Err("Empty interval".to_string());
     }
 
-    let (num_str, suffix) = interval.split_at(interval.len() - 1);
-    let num: u64 = num_str
-        .parse()
-        .map_err(|_| format!("Invalid number in interval: {num_str}"))?;
+    let mut total: u64 = 0;
+    let mut rest = interval;
+    while !rest.is_empty() {
+        let end = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .ok_or_else(|| format!("Missing unit after '{rest}' in interval: use s, m, h, or d"))?;
+        let (num_str, after) = rest.split_at(end);
+        let num: u64 = num_str
+            .parse()
+            .map_err(|_| format!("Invalid number in interval: {interval}"))?;
+        let suffix = after.chars().next().unwrap_or_default();
+        let unit = match suffix {
+            's' => 1,
+            'm' => 60,
+            'h' => 3600,
+            'd' => 86400,
+            _ => {
+                return Err(format!(
+                    "Unknown interval suffix: {suffix}. Use s, m, h, or d."
+                ))
+            }
+        };
+        total = num
+            .checked_mul(unit)
+            .and_then(|secs| total.checked_add(secs))
+            .ok_or_else(|| format!("Interval too long: {interval}"))?;
+        rest = after[suffix.len_utf8()..].trim_start();
+    }
+    Ok(total)
+}
+
+/// A `[schedule] interval`: a duration between runs, or a cron expression
+/// saying when to run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScheduleSpec {
+    Every(u64),
+    Cron(CronExpr),
+}
+
+/// A five-field cron expression (`"*/15 9-17 * * mon-fri"`) or a macro
+/// such as `@daily`. Each field holds the values it allows, or `None` for
+/// `*`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronExpr {
+    pub expr: String,
+    pub minutes: Option<Vec<u32>>,
+    pub hours: Option<Vec<u32>>,
+    pub days: Option<Vec<u32>>,
+    pub months: Option<Vec<u32>>,
+    pub weekdays: Option<Vec<u32>>,
+}
+
+/// Parse a schedule interval: a duration (see [`parse_interval`]) or a
+/// cron expression.
+pub fn parse_schedule(interval: &str) -> Result<ScheduleSpec, String> {
+    let interval = interval.trim();
+    if interval.starts_with('@') || interval.split_whitespace().count() == 5 {
+        parse_cron(interval).map(ScheduleSpec::Cron)
+    } else {
+        parse_interval(interval).map(ScheduleSpec::Every)
+    }
+}
+
+const MONTH_NAMES: &[&str] = &[
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const WEEKDAY_NAMES: &[&str] = &["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+fn parse_cron(expr: &str) -> Result<CronExpr, String> {
+    let fields = match expr.to_ascii_lowercase().as_str() {
+        "@hourly" => "0 * * * *".to_string(),
+        "@daily" | "@midnight" => "0 0 * * *".to_string(),
+        "@weekly" => "0 0 * * 0".to_string(),
+        "@monthly" => "0 0 1 * *".to_string(),
+        "@yearly" | "@annually" => "0 0 1 1 *".to_string(),
+        macro_name if macro_name.starts_with('@') => {
+            return Err(format!(
+                "Unknown cron macro {expr}: use @hourly, @daily, @weekly, @monthly, or @yearly"
+            ))
+        }
+        _ => expr.to_string(),
+    };
+    let fields: Vec<&str> = fields.split_whitespace().collect();
+    let [minute, hour, day, month, weekday] = fields[..] else {
+        return Err(format!(
+            "Invalid cron expression '{expr}': expected 5 fields"
+        ));
+    };
+    let field = |value: &str, name: &str, min: u32, max: u32, names: &[&str]| {
+        parse_cron_field(value, min, max, names)
+            .map_err(|e| format!("Invalid cron {name} '{value}' in '{expr}': {e}"))
+    };
+    let weekdays = field(weekday, "weekday", 0, 7, WEEKDAY_NAMES)?.map(|days| {
+        let mut days: Vec<u32> = days.into_iter().map(|d| d % 7).collect();
+        days.sort_unstable();
+        days.dedup();
+        days
+    });
+    Ok(CronExpr {
+        expr: expr.to_string(),
+        minutes: field(minute, "minute", 0, 59, &[])?,
+        hours: field(hour, "hour", 0, 23, &[])?,
+        days: field(day, "day", 1, 31, &[])?,
+        months: field(month, "month", 1, 12, MONTH_NAMES)?,
+        weekdays,
+    })
+}
+
+/// Values allowed by one cron field, or `None` for `*`. `names[i]` stands
+/// for `min + i`.
+fn parse_cron_field(
+    field: &str,
+    min: u32,
+    max: u32,
+    names: &[&str],
+) -> Result<Option<Vec<u32>>, String> {
+    if field == "*" {
+        return Ok(None);
+    }
+    let value = |v: &str| -> Result<u32, String> {
+        let v = v.to_ascii_lowercase();
+        names
+            .iter()
+            .position(|name| *name == v)
+            .map(|i| min + i as u32)
+            .or_else(|| v.parse().ok())
+            .filter(|n| (min..=max).contains(n))
+            .ok_or_else(|| format!("'{v}' is not in {min}-{max}"))
+    };
+    let mut values = Vec::new();
+    for item in field.split(',') {
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => (
+                range,
+                step.parse::<u32>()
+                    .ok()
+                    .filter(|step| *step > 0)
+                    .ok_or_else(|| format!("invalid step '{step}'"))?,
+            ),
+            None => (item, 1),
+        };
+        let (start, end) = match range.split_once('-') {
+            _ if range == "*" => (min, max),
+            Some((start, end)) => (value(start)?, value(end)?),
+            None if step > 1 => (value(range)?, max),
+            None => (value(range)?, value(range)?),
+        };
+        if start > end {
+            return Err(format!("range {range} runs backwards"));
+        }
+        values.extend((start..=end).step_by(step as usize));
+    }
+    values.sort_unstable();
+    values.dedup();
+    Ok(Some(values))
+}
+
+/// Parse a local hour window like "9-17" (9:00 to 16:59) or "22-6" (wrapping
+/// past midnight) into `(start, end)`.
+pub fn parse_hours(hours: &str) -> Result<(u32, u32), String> {
+    let (start, end) = hours
+        .split_once('-')
+        .ok_or_else(|| format!("Invalid hours '{hours}': expected START-END, e.g. 9-17"))?;
+    let parse = |h: &str| -> Result<u32, String> {
+        h.trim()
+            .parse::<u32>()
+            .ok()
+            .filter(|h| *h <= 24)
+            .ok_or_else(|| format!("Invalid hour '{}' in '{hours}': use 0-24", h.trim()))
+    };
+    let (start, end) = (parse(start)?, parse(end)?);
+    if start % 24 == end % 24 {
+        return Err(format!("Invalid hours '{hours}': the window is empty"));
+    }
+    Ok((start % 24, end % 24))
+}
+
+/// When scheduled runs may happen: `start..end` in minutes after local
+/// midnight (wrapping past it when `end <= start`; `0..0` is all day) on
+/// `weekdays` (0 = Sunday, all days when `None`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActiveWindow {
+    pub start: u32,
+    pub end: u32,
+    pub weekdays: Option<Vec<u32>>,
+}
+
+impl ActiveWindow {
+    /// Whether `minute` (after midnight) on `weekday` is inside the window.
+    /// A window wrapping past midnight belongs to the day it starts on.
+    pub fn contains(&self, minute: u32, weekday: u32) -> bool {
+        let (in_hours, day) = if self.start < self.end {
+            ((self.start..self.end).contains(&minute), weekday)
+        } else if minute >= self.start {
+            (true, weekday)
+        } else {
+            (minute < self.end, (weekday + 6) % 7)
+        };
+        in_hours
+            && self
+                .weekdays
+                .as_ref()
+                .is_none_or(|days| days.contains(&day))
+    }
+
+    /// The hours with at least one minute inside the window.
+    pub fn hours(&self) -> Vec<u32> {
+        (0..24)
+            .filter(|h| {
+                let (from, to) = (h * 60, h * 60 + 60);
+                if self.start < self.end {
+                    from < self.end && to > self.start
+                } else {
+                    to > self.start || from < self.end
+                }
+            })
+            .collect()
+    }
+}
 
-    match suffix {
-        "s" => Ok(num),
-        "m" => Ok(num * 60),
-        "h" => Ok(num * 3600),
-        "d" => Ok(num * 86400),
-        _ => Err(format!(
-            "Unknown interval suffix: {suffix}. Use s, m, h, or d."
-        )),
+/// Parse `schedule.active_hours`, `"08:00-22:00"` or `"8-22"`, into
+/// minutes after midnight.
+pub fn parse_active_hours(hours: &str) -> Result<(u32, u32), String> {
+    let invalid = || {
+        format!("Invalid schedule.active_hours '{hours}': expected HH:MM-HH:MM, e.g. 08:00-22:00")
+    };
+    let (start, end) = hours.split_once('-').ok_or_else(invalid)?;
+    let parse = |time: &str| -> Result<u32, String> {
+        let (h, m) = time.trim().split_once(':').unwrap_or((time.trim(), "0"));
+        match (h.parse::<u32>(), m.parse::<u32>()) {
+            (Ok(h), Ok(m)) if m < 60 && h * 60 + m <= 24 * 60 => Ok((h * 60 + m) % (24 * 60)),
+            _ => Err(invalid()),
+        }
+    };
+    let (start, end) = (parse(start)?, parse(end)?);
+    if start == end {
+        return Err(format!(
+            "Invalid schedule.active_hours '{hours}': the window is empty"
+        ));
     }
+    Ok((start, end))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_active_window() {
+        assert_eq!(parse_active_hours("08:00-22:00").unwrap(), (480, 1320));
+        assert_eq!(parse_active_hours("22:30 - 6").unwrap(), (1350, 360));
+        assert_eq!(parse_active_hours("18:00-24:00").unwrap(), (1080, 0));
+        assert!(parse_active_hours("8:00").is_err());
+        assert!(parse_active_hours("8:60-9").is_err());
+        assert!(parse_active_hours("9-9:00").is_err());
+
+        let schedule = ScheduleConfig {
+            active_hours: Some("08:30-22:00".to_string()),
+            active_days: Some("mon-fri".to_string()),
+            ..ScheduleConfig::default()
+        };
+        let window = schedule.active_window().unwrap().unwrap();
+        assert_eq!(window.weekdays, Some(vec![1, 2, 3, 4, 5]));
+        assert_eq!(window.hours(), (8..22).collect::<Vec<_>>());
+        assert!(window.contains(8 * 60 + 30, 1));
+        assert!(!window.contains(8 * 60 + 29, 1));
+        assert!(!window.contains(22 * 60, 1));
+        assert!(!window.contains(12 * 60, 0));
+
+        // Past midnight still counts as the evening's day.
+        let night = ActiveWindow {
+            start: 22 * 60,
+            end: 2 * 60,
+            weekdays: Some(vec![5]),
+        };
+        assert_eq!(night.hours(), [0, 1, 22, 23]);
+        assert!(night.contains(23 * 60, 5));
+        assert!(night.contains(60, 6));
+        assert!(!night.contains(60, 5));
+
+        assert_eq!(ScheduleConfig::default().active_window().unwrap(), None);
+        let days_only = ScheduleConfig {
+            active_days: Some("sat,sun".to_string()),
+            ..ScheduleConfig::default()
+        };
+        let window = days_only.active_window().unwrap().unwrap();
+        assert!(window.contains(3 * 60, 0) && !window.contains(3 * 60, 1));
+        assert_eq!(window.hours().len(), 24);
+        let bad = ScheduleConfig {
+            active_days: Some("weekdays".to_string()),
+            ..ScheduleConfig::default()
+        };
+        assert!(bad.active_window().is_err());
+    }
+
+    #[test]
+    fn test_parse_hours() {
+        assert_eq!(parse_hours("9-17").unwrap(), (9, 17));
+        assert_eq!(parse_hours("22 - 6").unwrap(), (22, 6));
+        assert_eq!(parse_hours("18-24").unwrap(), (18, 0));
+        assert!(parse_hours("9").is_err());
+        assert!(parse_hours("9-25").is_err());
+        assert!(parse_hours("5-5").is_err());
+    }
+
     #[test]
     fn test_parse_interval_seconds() {
         assert_eq!(parse_interval("30s").unwrap(), 30);
@@ -298,6 +2490,46 @@ mod tests {
         assert!(parse_interval("").is_err());
     }
 
+    #[test]
+    fn test_parse_interval_compound() {
+        assert_eq!(parse_interval("1h30m").unwrap(), 5400);
+        assert_eq!(parse_interval("1d 12h").unwrap(), 129600);
+        assert_eq!(parse_interval("2m30s").unwrap(), 150);
+        assert!(parse_interval("1h30").is_err());
+        assert!(parse_interval("h30m").is_err());
+    }
+
+    #[test]
+    fn test_parse_schedule() {
+        assert_eq!(parse_schedule("1h30m").unwrap(), ScheduleSpec::Every(5400));
+
+        let ScheduleSpec::Cron(cron) = parse_schedule("*/15 9-17 * jan,jul mon-fri").unwrap()
+        else {
+            panic!("expected a cron expression");
+        };
+        assert_eq!(cron.minutes, Some(vec![0, 15, 30, 45]));
+        assert_eq!(cron.hours, Some((9..=17).collect()));
+        assert_eq!(cron.days, None);
+        assert_eq!(cron.months, Some(vec![1, 7]));
+        assert_eq!(cron.weekdays, Some(vec![1, 2, 3, 4, 5]));
+
+        let ScheduleSpec::Cron(weekly) = parse_schedule("@weekly").unwrap() else {
+            panic!("expected a cron expression");
+        };
+        assert_eq!(weekly.expr, "@weekly");
+        assert_eq!(weekly.weekdays, Some(vec![0]));
+        let ScheduleSpec::Cron(sunday) = parse_schedule("0 12 * * 7").unwrap() else {
+            panic!("expected a cron expression");
+        };
+        assert_eq!(sunday.weekdays, Some(vec![0]));
+
+        assert!(parse_schedule("60 * * * *").is_err());
+        assert!(parse_schedule("0 17-9 * * *").is_err());
+        assert!(parse_schedule("*/0 * * * *").is_err());
+        assert!(parse_schedule("@sometimes").is_err());
+        assert!(parse_schedule("0 * * *").is_err());
+    }
+
     #[test]
     fn test_find_agent_root_not_found() {
         // Searching from root should find nothing (no boucle.toml in /)
@@ -342,6 +2574,482 @@ name = "minimal"
         assert_eq!(config.memory.state_file, "STATE.md");
         assert_eq!(config.loop_config.max_tokens, 200_000);
         assert_eq!(config.loop_config.llm_timeout_seconds, 7_200);
+        assert!(!config.git.rollback_on_failure);
+        assert!(!config.loop_config.auto_journal);
+    }
+
+    #[test]
+    fn test_load_git_rollback_on_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_content = r#"
+[agent]
+name = "rollback"
+
+[git]
+rollback_on_failure = true
+run_branch = "boucle/run-{timestamp}"
+protected_branches = ["main"]
+"#;
+        fs::write(dir.path().join("boucle.toml"), config_content).unwrap();
+        let config = load(dir.path()).unwrap();
+        assert!(config.git.rollback_on_failure);
+        assert_eq!(
+            config.git.run_branch.as_deref(),
+            Some("boucle/run-{timestamp}")
+        );
+        assert!(config.git.merge_run_branch);
+        assert_eq!(config.git.protected_branches, vec!["main"]);
+        assert_eq!(config.git.commit_name, "Boucle");
+        assert!(!config.git.push);
+        assert_eq!(config.git.remote, "origin");
+        assert_eq!(config.git.commit_email, "boucle@agent");
+        assert_eq!(config.git.sign, None);
+    }
+
+    #[test]
+    fn test_git_signing_options() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("boucle.toml"),
+            "[agent]\nname = \"signed\"\n\n[git]\nsign = \"ssh\"\nsigning_key = \"~/.ssh/agent.pub\"\n",
+        )
+        .unwrap();
+        let config = load(dir.path()).unwrap();
+        assert_eq!(config.git.sign, Some(SignFormat::Ssh));
+        assert_eq!(config.git.signing_key.as_deref(), Some("~/.ssh/agent.pub"));
+
+        fs::write(
+            dir.path().join("boucle.toml"),
+            "[agent]\nname = \"signed\"\n\n[git]\nsign = \"x509\"\n",
+        )
+        .unwrap();
+        assert!(load(dir.path()).is_err());
+    }
+
+    #[test]
+    fn test_load_hooks_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_content = r#"
+[agent]
+name = "hooks"
+
+[hooks]
+timeout = "60s"
+
+[hooks.post-llm]
+required = false
+
+[hooks.pre-run]
+timeout = "10s"
+
+[hooks.webhooks.ci]
+url = "https://example.com/hook"
+events = ["run-failed"]
+"#;
+        fs::write(dir.path().join("boucle.toml"), config_content).unwrap();
+        let config = load(dir.path()).unwrap();
+        assert!(!config.hooks.points.contains_key("webhooks"));
+        let ci = &config.hooks.webhooks["ci"];
+        assert_eq!(ci.events, vec!["run-failed"]);
+        assert_eq!((ci.retries, ci.timeout.as_str()), (2, "10s"));
+        assert!(!config.hooks.required("post-llm"));
+        assert!(config.hooks.required("pre-run"));
+        assert_eq!(config.hooks.timeout("pre-run"), "10s");
+        assert_eq!(config.hooks.timeout("post-llm"), "60s");
+    }
+
+    #[test]
+    fn test_load_agent_env() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_content = r#"
+[agent]
+name = "env"
+env_deny = ["AWS_*", "GITHUB_TOKEN"]
+
+[agent.env]
+FOO = "bar"
+"#;
+        fs::write(dir.path().join("boucle.toml"), config_content).unwrap();
+        let config = load(dir.path()).unwrap();
+        assert_eq!(config.agent.env["FOO"], "bar");
+        assert_eq!(config.agent.env_deny, vec!["AWS_*", "GITHUB_TOKEN"]);
+    }
+
+    #[test]
+    fn test_resolve_env_value_expands_host_vars() {
+        let var = |name: &str| (name == "HOME").then(|| "/home/x".to_string());
+        assert_eq!(
+            expand_vars("K", "${HOME}/bin:${HOME}", var).unwrap(),
+            "/home/x/bin:/home/x"
+        );
+        assert_eq!(expand_vars("K", "plain", var).unwrap(), "plain");
+        assert!(expand_vars("K", "${UNSET}", var).is_err());
+        assert!(expand_vars("K", "${OOPS", var).is_err());
+        let root = Path::new("/tmp");
+        assert_eq!(
+            resolve_env_value(root, &BTreeMap::new(), "K", "plain").unwrap(),
+            "plain"
+        );
+    }
+
+    #[test]
+    fn test_resolve_env_value_reads_secret_file() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("token.secret"), "s3cret\n").unwrap();
+        assert_eq!(
+            resolve_env_value(dir.path(), &BTreeMap::new(), "TOKEN", "file:token.secret").unwrap(),
+            "s3cret"
+        );
+        assert!(resolve_env_value(dir.path(), &BTreeMap::new(), "TOKEN", "file:missing").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_secrets_resolve_when_referenced() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("linear.secret"), "lin_api_file\n").unwrap();
+        let config_content = r#"
+[agent]
+name = "a"
+
+[secrets]
+linear = { file = "linear.secret" }
+vault = { command = "echo run >> runs.txt; echo from-vault" }
+failing = { command = "echo partial; exit 3" }
+home = { env = "BOUCLE_TEST_DEFINITELY_UNSET" }
+"#;
+        fs::write(dir.path().join("boucle.toml"), config_content).unwrap();
+
+        let secrets = load(dir.path()).unwrap().secrets;
+        let resolve =
+            |value: &str| resolve_env_value(dir.path(), &secrets, "agent.env.TOKEN", value);
+        assert_eq!(resolve("secret:linear").unwrap(), "lin_api_file");
+        assert_eq!(resolve("secret:vault").unwrap(), "from-vault");
+        assert_eq!(resolve("secret:vault").unwrap(), "from-vault");
+        let runs = fs::read_to_string(dir.path().join("runs.txt")).unwrap();
+        assert_eq!(runs.lines().count(), 1);
+
+        let err = resolve("secret:failing").unwrap_err().to_string();
+        assert!(
+            err.contains("agent.env.TOKEN: secret 'failing' command failed"),
+            "{err}"
+        );
+        assert!(!err.contains("partial"));
+        let err = resolve("secret:home").unwrap_err().to_string();
+        assert!(err.contains("BOUCLE_TEST_DEFINITELY_UNSET"), "{err}");
+        let err = resolve("secret:missing").unwrap_err().to_string();
+        assert!(err.contains("is not defined in [secrets]"), "{err}");
+    }
+
+    #[test]
+    fn test_env_denied_patterns() {
+        let deny = vec!["AWS_*".to_string(), "GITHUB_TOKEN".to_string()];
+        assert!(env_matches("AWS_SECRET_ACCESS_KEY", &deny));
+        assert!(env_matches("GITHUB_TOKEN", &deny));
+        assert!(!env_matches("GITHUB_TOKEN_OTHER", &deny));
+        assert!(!env_matches("PATH", &deny));
+    }
+
+    #[test]
+    fn test_env_overrides_layer_over_the_file() {
+        let mut table: toml::Table = toml::from_str(
+            "[agent]\nname = \"file-agent\"\nmodel = \"claude-sonnet-4\"\n\n[loop]\nmax_tokens = 1000\n",
+        )
+        .unwrap();
+        let vars = [
+            ("BOUCLE_AGENT_MODEL", "300"),
+            ("BOUCLE_AGENT_NAME", "set-for-hooks"),
+            ("BOUCLE_MEMORY_DIR", "/data/memory"),
+            ("BOUCLE_LOOP_LLM_TIMEOUT_SECONDS", "600"),
+            ("BOUCLE_GIT_PROTECTED_BRANCHES", r#"["main", "release"]"#),
+            ("BOUCLE_PLUGIN_LIMITS_MAX_CONCURRENT", "2"),
+            ("BOUCLE_ROOT", "/agent"),
+            ("HOME", "/root"),
+        ]
+        .map(|(name, value)| (name.to_string(), value.to_string()));
+
+        let applied = apply_env_overrides(&mut table, vars).unwrap();
+        assert_eq!(applied.len(), 5);
+        let config: Config = toml::Value::Table(table).try_into().unwrap();
+        assert_eq!(config.agent.name, "file-agent");
+        assert_eq!(config.agent.model, "300");
+        assert_eq!(config.memory.dir, "/data/memory");
+        assert_eq!(config.loop_config.max_tokens, 1000);
+        assert_eq!(config.loop_config.llm_timeout_seconds, 600);
+        assert_eq!(config.git.protected_branches, ["main", "release"]);
+        assert_eq!(config.plugin_limits.max_concurrent, 2);
+
+        let mut table: toml::Table = toml::from_str("[agent]\nname = \"a\"\n").unwrap();
+        let vars = [("BOUCLE_LOOP_MAX_TOKENS".to_string(), "lots".to_string())];
+        apply_env_overrides(&mut table, vars).unwrap();
+        assert!(toml::Value::Table(table).try_into::<Config>().is_err());
+    }
+
+    #[test]
+    fn test_live_config_reloads_and_keeps_last_good() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("boucle.toml");
+        fs::write(&path, "[agent]\nname = \"live\"\n").unwrap();
+        let mut live = LiveConfig::load(dir.path(), None).unwrap();
+        assert!(!live.refresh().unwrap());
+
+        fs::write(
+            &path,
+            "[agent]\nname = \"live\"\n\n[schedule]\ninterval = \"5m\"\n",
+        )
+        .unwrap();
+        assert!(live.refresh().unwrap());
+        assert_eq!(live.get().schedule.interval, "5m");
+
+        fs::write(
+            &path,
+            "[agent]\nname = \"live\"\n\n[schedule]\ninterval = 5\n",
+        )
+        .unwrap();
+        assert!(live.refresh().is_err());
+        assert_eq!(live.get().schedule.interval, "5m");
+        assert!(!live.refresh().unwrap());
+    }
+
+    #[test]
+    fn test_configured_dirs_resolve_against_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let write = |body: &str| {
+            fs::write(
+                root.join("boucle.toml"),
+                format!("[agent]\nname = \"dirs\"\n{body}"),
+            )
+            .unwrap()
+        };
+
+        let absolute_hooks = root.join("hooks");
+        write(&format!(
+            "[memory]\ndir = \"./state/../memory/\"\n\n[loop]\nhooks_dir = {:?}\nlog_dir = \".\"\n",
+            absolute_hooks.display().to_string()
+        ));
+        let config = load(root).unwrap();
+        assert_eq!(config.memory.dir, "memory");
+        assert_eq!(config.loop_config.hooks_dir.as_deref(), Some("hooks"));
+        assert_eq!(config.loop_config.log_dir.as_deref(), Some("."));
+
+        for outside in ["../shared", "/var/tmp/boucle-elsewhere"] {
+            write(&format!("[loop]\ncontext_dir = \"{outside}\"\n"));
+            let e = load(root).unwrap_err().to_string();
+            assert!(
+                e.contains("loop.context_dir") && e.contains("outside"),
+                "{e}"
+            );
+            write(&format!(
+                "[loop]\ncontext_dir = \"{outside}\"\nallow_external_dirs = true\n"
+            ));
+            assert_eq!(
+                load(root).unwrap().loop_config.context_dir.as_deref(),
+                Some(outside)
+            );
+        }
+
+        fs::write(root.join("notes"), "not a directory").unwrap();
+        write("[memory]\ndir = \"notes\"\n");
+        assert!(load(root)
+            .unwrap_err()
+            .to_string()
+            .contains("not a directory"));
+        write("[memory]\ndir = \" \"\n");
+        assert!(load(root)
+            .unwrap_err()
+            .to_string()
+            .contains("memory.dir is empty"));
+
+        write("[loop]\nlog_dir = \"var/logs\"\n");
+        let config = load(root).unwrap();
+        config.create_dirs(root).unwrap();
+        assert!(root.join("memory").is_dir() && root.join("var/logs").is_dir());
+    }
+
+    #[test]
+    fn test_effective_config_sources() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("boucle.toml"),
+            r#"
+[agent]
+name = "shown"
+
+[loop]
+max_tokens = 1000
+
+[hooks.post-llm]
+required = false
+
+[profile.slow]
+interval = "6h"
+
+[profile.slow.loop]
+llm_timeout_seconds = 60
+"#,
+        )
+        .unwrap();
+
+        let effective = effective(dir.path(), Some("slow")).unwrap();
+        let source = |path: &str| effective.sources[path].as_str();
+        assert_eq!(source("agent.name"), "boucle.toml");
+        assert_eq!(source("loop.max_tokens"), "boucle.toml");
+        assert_eq!(source("hooks.post-llm.required"), "boucle.toml");
+        assert_eq!(source("git.auto_commit"), "default");
+        assert_eq!(source("schedule.interval"), "profile slow");
+        assert_eq!(source("loop.llm_timeout_seconds"), "profile slow");
+        assert_eq!(source("loop.log_dir"), "profile slow");
+        assert_eq!(source("profiles.slow.interval"), "boucle.toml");
+
+        let shown = effective.to_annotated_toml();
+        assert!(shown.starts_with("[agent]\n"), "{shown}");
+        assert!(shown.contains("\n[loop]\n"), "{shown}");
+        assert!(
+            shown.contains("interval = \"6h\"  # profile slow\n"),
+            "{shown}"
+        );
+        assert!(
+            shown.contains("max_tokens = 1000  # boucle.toml\n"),
+            "{shown}"
+        );
+        assert!(shown.contains("\n[hooks.post-llm]\n"), "{shown}");
+        assert!(shown.contains("\n[secrets]\n"), "{shown}");
+        let reparsed: Config = toml::from_str(&shown).unwrap();
+        assert_eq!(reparsed.schedule.interval, "6h");
+
+        let json = effective.to_json();
+        assert_eq!(json["config"]["loop"]["max_tokens"], 1000);
+        assert_eq!(json["sources"]["git.auto_commit"], "default");
+    }
+
+    #[test]
+    fn test_unknown_keys() {
+        let table: toml::Table = toml::from_str(
+            r#"
+[agent]
+name = "a"
+naem = "typo"
+
+[loop_confg]
+max_tokens = 10
+
+[context.github]
+enabled = true
+repo = "x/y"
+
+[plugins.notify]
+anything = "goes"
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            unknown_keys(&table),
+            ["agent.naem", "context.github.repo", "loop_confg"]
+        );
+        let broken: toml::Table = toml::from_str("[loop]\nmax_tokens = \"x\"\n").unwrap();
+        assert!(unknown_keys(&broken).is_empty());
+    }
+
+    #[test]
+    fn test_apply_profile_overrides() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_content = r#"
+[agent]
+name = "profiled"
+model = "claude-sonnet-4"
+
+[loop]
+context_dir = "context.d"
+
+[profiles.nightly]
+model = "claude-opus-4"
+goals_file = "goals/nightly.md"
+interval = "1d"
+"#;
+        fs::write(dir.path().join("boucle.toml"), config_content).unwrap();
+        let mut config = load(dir.path()).unwrap();
+        config.apply_profile("nightly").unwrap();
+        assert_eq!(config.agent.model, "claude-opus-4");
+        assert_eq!(
+            config.loop_config.goals_file.as_deref(),
+            Some("goals/nightly.md")
+        );
+        // Not overridden by the profile
+        assert_eq!(config.loop_config.context_dir.as_deref(), Some("context.d"));
+        assert_eq!(config.schedule.interval, "1d");
+        assert_eq!(config.loop_config.log_dir.as_deref(), Some("logs/nightly"));
+    }
+
+    #[test]
+    fn test_profile_tables_merge_over_sections() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_content = r#"
+[agent]
+name = "profiled"
+model = "claude-opus-4"
+
+[loop]
+max_tokens = 200000
+context_dir = "context.d"
+
+[loop.context]
+sections = ["goals", "state"]
+
+[profile.dev]
+interval = "5m"
+
+[profile.dev.agent]
+model = "claude-haiku-4"
+
+[profile.dev.loop]
+max_tokens = 20000
+
+[profile.dev.loop.context]
+include = ["NOTES.md"]
+"#;
+        fs::write(dir.path().join("boucle.toml"), config_content).unwrap();
+
+        let config = load(dir.path()).unwrap();
+        assert_eq!(config.agent.model, "claude-opus-4");
+        assert_eq!(config.loop_config.max_tokens, 200000);
+
+        let config = load_profile(dir.path(), Some("dev")).unwrap();
+        assert_eq!(config.agent.name, "profiled");
+        assert_eq!(config.agent.model, "claude-haiku-4");
+        assert_eq!(config.loop_config.max_tokens, 20000);
+        assert_eq!(config.loop_config.context_dir.as_deref(), Some("context.d"));
+        assert_eq!(config.loop_config.context.sections, ["goals", "state"]);
+        assert_eq!(config.loop_config.context.include.len(), 1);
+        assert_eq!(config.schedule.interval, "5m");
+        assert_eq!(config.loop_config.log_dir.as_deref(), Some("logs/dev"));
+
+        assert!(load_profile(dir.path(), Some("prod")).is_err());
+
+        fs::write(
+            dir.path().join("boucle.toml"),
+            format!("{config_content}\n[profiles.prod]\ninterval = \"1h\"\n"),
+        )
+        .unwrap();
+        let err = load(dir.path()).unwrap_err().to_string();
+        assert!(
+            err.contains("[profiles.<name>]") && err.contains("[profile.<name>]"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_apply_profile_rejects_unknown_and_bad_names() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("boucle.toml"),
+            "[agent]\nname = \"x\"\n\n[profiles.fast]\nmodel = \"m\"\n",
+        )
+        .unwrap();
+        let mut config = load(dir.path()).unwrap();
+        let err = config.apply_profile("slow").unwrap_err().to_string();
+        assert!(err.contains("fast"), "{err}");
+        assert!(config.apply_profile("../escape").is_err());
     }
 
     #[test]
@@ -0,0 +1,198 @@
+//! `boucle config get` / `boucle config set`: read and change single
+//! settings by dotted path, such as `schedule.interval`.
+//!
+//! `get` reads the effective configuration, so it sees defaults, workspace
+//! defaults, the profile, and `BOUCLE_*` overrides. `set` edits boucle.toml
+//! itself with `toml_edit`, keeping comments and formatting, and refuses
+//! any change after which the file would no longer load.
+
+use super::{effective, unknown_keys, Config, ConfigError};
+use std::fs;
+use std::path::Path;
+use toml_edit::{DocumentMut, Item, Table, TableLike, Value};
+
+/// The effective value at `key`, or `None` when it is unset.
+pub fn get(
+    root: &Path,
+    profile: Option<&str>,
+    key: &str,
+) -> Result<Option<toml::Value>, ConfigError> {
+    let segments = split_key(key)?;
+    let effective = effective(root, profile)?;
+    let mut value = effective.table.get(segments[0]);
+    for segment in &segments[1..] {
+        value = value
+            .and_then(|v| v.as_table())
+            .and_then(|t| t.get(*segment));
+    }
+    Ok(value.cloned())
+}
+
+/// Set `key` to `value` in `root`'s boucle.toml. `value` is read as a TOML
+/// value when it is one the setting accepts (`30`, `true`, `["a", "b"]`) and
+/// as a string otherwise, like `BOUCLE_*` overrides. Returns the value the
+/// file held before, if any.
+pub fn set(root: &Path, key: &str, value: &str) -> Result<Option<String>, ConfigError> {
+    let segments = split_key(key)?;
+    let path = root.join("boucle.toml");
+    if !path.exists() {
+        return Err(ConfigError::NotFound);
+    }
+    let content = fs::read_to_string(&path)?;
+    let mut doc: DocumentMut = content
+        .parse()
+        .map_err(|e| ConfigError::Invalid(format!("boucle.toml: {e}")))?;
+
+    let typed = value.parse::<Value>().ok().filter(|typed| !typed.is_str());
+    let mut previous = None;
+    let mut last_error = None;
+    for candidate in typed.into_iter().chain([Value::from(value)]) {
+        let mut edited = doc.clone();
+        previous = insert(&mut edited, &segments, candidate)?;
+        match check(root, &edited, key) {
+            Ok(()) => {
+                doc = edited;
+                last_error = None;
+                break;
+            }
+            Err(e) => last_error = Some(e),
+        }
+    }
+    if let Some(e) = last_error {
+        return Err(e);
+    }
+
+    let tmp = root.join("boucle.toml.tmp");
+    fs::write(&tmp, doc.to_string())?;
+    fs::rename(&tmp, &path)?;
+    Ok(previous)
+}
+
+fn split_key(key: &str) -> Result<Vec<&str>, ConfigError> {
+    let segments: Vec<&str> = key.split('.').collect();
+    if segments.iter().any(|s| s.is_empty()) {
+        return Err(ConfigError::Invalid(format!("invalid key '{key}'")));
+    }
+    Ok(segments)
+}
+
+/// Put `value` at `segments`, creating missing tables and keeping the
+/// comments around an existing value. Returns the value it replaced.
+fn insert(
+    doc: &mut DocumentMut,
+    segments: &[&str],
+    mut value: Value,
+) -> Result<Option<String>, ConfigError> {
+    let (last, parents) = segments.split_last().expect("keys have a segment");
+    let mut table: &mut dyn TableLike = doc.as_table_mut();
+    for (i, segment) in parents.iter().enumerate() {
+        let item = table.entry(segment).or_insert_with(|| {
+            let mut new = Table::new();
+            // Only the innermost new table gets a `[header]`.
+            new.set_implicit(i + 1 < parents.len());
+            Item::Table(new)
+        });
+        let name = segments[..=i].join(".");
+        table = item
+            .as_table_like_mut()
+            .ok_or_else(|| ConfigError::Invalid(format!("{name} is not a table")))?;
+    }
+    let previous = match table.get_mut(last) {
+        Some(Item::Value(old)) => {
+            let decor = old.decor().clone();
+            *value.decor_mut() = decor;
+            Some(old.clone().decorated("", "").to_string())
+        }
+        Some(Item::None) | None => None,
+        Some(_) => {
+            return Err(ConfigError::Invalid(format!(
+                "{} is a table; set one of its keys instead",
+                segments.join(".")
+            )))
+        }
+    };
+    table.insert(last, Item::Value(value));
+    Ok(previous)
+}
+
+/// The edited file must still load, and `key` must be a real setting.
+fn check(root: &Path, doc: &DocumentMut, key: &str) -> Result<(), ConfigError> {
+    let table: toml::Table = toml::from_str(&doc.to_string())?;
+    toml::Value::Table(table.clone())
+        .try_into::<Config>()
+        .map_err(|e| ConfigError::Invalid(format!("{key}: {}", e.message())))?
+        .resolve_dirs(root)?;
+    let unknown = unknown_keys(&table);
+    if unknown
+        .iter()
+        .any(|u| key == u || key.starts_with(&format!("{u}.")))
+    {
+        return Err(ConfigError::Invalid(format!("unknown setting '{key}'")));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runner;
+
+    #[test]
+    fn test_set_and_get_preserve_comments() {
+        let dir = tempfile::tempdir().unwrap();
+        runner::init(dir.path(), "editor").unwrap();
+        let path = dir.path().join("boucle.toml");
+        let original = fs::read_to_string(&path).unwrap();
+        fs::write(
+            &path,
+            original.replace("interval = \"1h\"", "interval = \"1h\"  # hourly"),
+        )
+        .unwrap();
+
+        assert_eq!(
+            get(dir.path(), None, "schedule.interval").unwrap(),
+            Some(toml::Value::String("1h".to_string()))
+        );
+        let previous = set(dir.path(), "schedule.interval", "30m").unwrap();
+        assert_eq!(previous.as_deref(), Some("\"1h\""));
+        let edited = fs::read_to_string(&path).unwrap();
+        assert!(edited.contains("interval = \"30m\"  # hourly"), "{edited}");
+        assert_eq!(
+            get(dir.path(), None, "schedule.interval").unwrap(),
+            Some(toml::Value::String("30m".to_string()))
+        );
+
+        // Typed where the setting takes a number, a string where it does not.
+        set(dir.path(), "loop.max_tokens", "2000").unwrap();
+        assert_eq!(
+            crate::config::load(dir.path())
+                .unwrap()
+                .loop_config
+                .max_tokens,
+            2000
+        );
+        set(dir.path(), "git.commit_name", "1234").unwrap();
+        assert_eq!(
+            crate::config::load(dir.path()).unwrap().git.commit_name,
+            "1234"
+        );
+
+        // New nested tables get one header.
+        set(dir.path(), "plugins.deploy.target", "staging").unwrap();
+        let edited = fs::read_to_string(&path).unwrap();
+        assert!(
+            edited.contains("[plugins.deploy]\ntarget = \"staging\""),
+            "{edited}"
+        );
+        assert!(!edited.contains("[plugins]\n"));
+
+        let before = fs::read_to_string(&path).unwrap();
+        assert!(set(dir.path(), "loop.max_tokns", "5").is_err());
+        assert!(set(dir.path(), "loop.max_tokens", "lots").is_err());
+        assert!(set(dir.path(), "schedule", "5m").is_err());
+        assert!(set(dir.path(), "schedule..interval", "5m").is_err());
+        assert!(set(dir.path(), "memory.dir", "../elsewhere").is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), before);
+        assert_eq!(get(dir.path(), None, "loop.nothing").unwrap(), None);
+    }
+}
@@ -0,0 +1,185 @@
+//! `boucle config migrate`: upgrade boucle.toml to the current layout.
+//!
+//! boucle.toml carries a top-level `version`; a file without one is version
+//! 0. Each entry of [`MIGRATIONS`] moves a file from the version before it to
+//! its own, renaming keys or moving sections with `toml_edit` so comments
+//! and formatting survive. Changing the layout means adding a migration and
+//! bumping [`CURRENT_VERSION`]; the loader keeps accepting the old layout
+//! until the migration has been out for a while.
+
+use super::{Config, ConfigError};
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml_edit::DocumentMut;
+
+/// The boucle.toml layout this build writes and understands.
+pub const CURRENT_VERSION: u32 = 1;
+
+struct Migration {
+    /// Version the file is at afterwards.
+    version: u32,
+    /// Apply the migration, describing each change made.
+    apply: fn(&mut DocumentMut) -> Vec<String>,
+}
+
+const MIGRATIONS: &[Migration] = &[Migration {
+    version: 1,
+    apply: profiles_table,
+}];
+
+/// What [`migrate`] did, or would do.
+#[derive(Debug)]
+pub struct Migrated {
+    pub from: u32,
+    pub to: u32,
+    pub changes: Vec<String>,
+    /// Copy of the original file; `None` when nothing was written.
+    pub backup: Option<PathBuf>,
+}
+
+/// Upgrade `content` to [`CURRENT_VERSION`]: the new text and the changes.
+/// The text is `content` untouched when it is already current.
+pub fn upgrade(content: &str) -> Result<(String, Migrated), ConfigError> {
+    let mut doc: DocumentMut = content
+        .parse()
+        .map_err(|e| ConfigError::Invalid(format!("boucle.toml: {e}")))?;
+    let from = match doc.get("version").map(|v| v.as_integer()) {
+        None => 0,
+        Some(Some(v)) if v >= 0 => v as u32,
+        Some(_) => {
+            return Err(ConfigError::Invalid(
+                "boucle.toml: version must be a non-negative integer".to_string(),
+            ))
+        }
+    };
+    if from > CURRENT_VERSION {
+        return Err(newer_than_supported(from));
+    }
+    let mut changes = Vec::new();
+    for migration in MIGRATIONS.iter().filter(|m| m.version > from) {
+        changes.extend((migration.apply)(&mut doc));
+    }
+    let migrated = Migrated {
+        from,
+        to: CURRENT_VERSION,
+        changes,
+        backup: None,
+    };
+    if from == CURRENT_VERSION {
+        return Ok((content.to_string(), migrated));
+    }
+    doc.insert("version", toml_edit::value(i64::from(CURRENT_VERSION)));
+    Ok((doc.to_string(), migrated))
+}
+
+/// Migrate `root`'s boucle.toml in place, first copying it to
+/// `boucle.toml.v<from>.bak`. With `dry_run`, only report the changes.
+pub fn migrate(root: &Path, dry_run: bool) -> Result<Migrated, ConfigError> {
+    let path = root.join("boucle.toml");
+    if !path.exists() {
+        return Err(ConfigError::NotFound);
+    }
+    let content = fs::read_to_string(&path)?;
+    let (upgraded, mut migrated) = upgrade(&content)?;
+    if dry_run || upgraded == content {
+        return Ok(migrated);
+    }
+    toml::from_str::<Config>(&upgraded)
+        .map_err(|e| ConfigError::Invalid(format!("migrated boucle.toml would not load: {e}")))?;
+
+    let mut backup = root.join(format!("boucle.toml.v{}.bak", migrated.from));
+    let mut n = 1;
+    while backup.exists() {
+        backup = root.join(format!("boucle.toml.v{}.bak.{n}", migrated.from));
+        n += 1;
+    }
+    fs::copy(&path, &backup)?;
+    fs::write(&path, upgraded)?;
+    migrated.backup = Some(backup);
+    Ok(migrated)
+}
+
+pub(super) fn newer_than_supported(version: u32) -> ConfigError {
+    ConfigError::Invalid(format!(
+        "boucle.toml is version {version}, but this boucle understands up to version {CURRENT_VERSION}; upgrade boucle"
+    ))
+}
+
+/// Version 1: `[profile.<name>]` becomes `[profiles.<name>]`.
+fn profiles_table(doc: &mut DocumentMut) -> Vec<String> {
+    let Some(old) = doc.remove("profile") else {
+        return Vec::new();
+    };
+    let Some(old) = old.into_table().ok() else {
+        return vec!["removed `profile`, which was not a table".to_string()];
+    };
+    let mut changes = Vec::new();
+    match doc.get_mut("profiles").and_then(|p| p.as_table_mut()) {
+        Some(profiles) => {
+            for (name, profile) in old {
+                if profiles.contains_key(&name) {
+                    changes.push(format!(
+                        "dropped [profile.{name}]: [profiles.{name}] already exists"
+                    ));
+                } else {
+                    profiles.insert(&name, profile);
+                    changes.push(format!("moved [profile.{name}] to [profiles.{name}]"));
+                }
+            }
+        }
+        None => {
+            changes.extend(
+                old.iter()
+                    .map(|(name, _)| format!("moved [profile.{name}] to [profiles.{name}]")),
+            );
+            doc.insert("profiles", toml_edit::Item::Table(old));
+        }
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate_renames_profile_tables() {
+        let dir = tempfile::tempdir().unwrap();
+        let original = r#"# my agent
+[agent]
+name = "old"  # keep this comment
+
+[profile.fast]
+interval = "5m"
+
+[profile.fast.loop]
+max_tokens = 1000
+"#;
+        fs::write(dir.path().join("boucle.toml"), original).unwrap();
+
+        let planned = migrate(dir.path(), true).unwrap();
+        assert_eq!((planned.from, planned.to), (0, CURRENT_VERSION));
+        assert_eq!(planned.changes, ["moved [profile.fast] to [profiles.fast]"]);
+        assert!(planned.backup.is_none());
+
+        let migrated = migrate(dir.path(), false).unwrap();
+        let backup = migrated.backup.unwrap();
+        assert_eq!(fs::read_to_string(backup).unwrap(), original);
+        let upgraded = fs::read_to_string(dir.path().join("boucle.toml")).unwrap();
+        assert!(
+            upgraded.starts_with("version = 1\n# my agent\n[agent]\n"),
+            "{upgraded}"
+        );
+        assert!(upgraded.contains("name = \"old\"  # keep this comment"));
+        assert!(upgraded.contains("[profiles.fast]\ninterval = \"5m\""));
+        assert!(upgraded.contains("[profiles.fast.loop]\nmax_tokens = 1000"));
+        assert!(!upgraded.contains("[profile."));
+
+        let again = migrate(dir.path(), false).unwrap();
+        assert_eq!(again.from, CURRENT_VERSION);
+        assert!(again.changes.is_empty() && again.backup.is_none());
+
+        let future = format!("version = {}\n[agent]\nname = \"x\"\n", CURRENT_VERSION + 1);
+        assert!(upgrade(&future).is_err());
+    }
+}
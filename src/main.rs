@@ -8,11 +8,12 @@ mod config;
 mod improve;
 mod mcp;
 mod runner;
+mod telemetry;
 
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use runner::{command_plugins, plugin_install};
 use std::path::PathBuf;
 use std::process;
-use std::process::Command;
 
 #[derive(Parser)]
 #[command(name = "boucle")]
@@ -23,10 +24,87 @@ struct Cli {
     #[arg(short, long)]
     root: Option<PathBuf>,
 
+    /// Run profile from [profiles.<name>] in boucle.toml (default: $BOUCLE_PROFILE)
+    #[arg(long, global = true)]
+    profile: Option<String>,
+
+    /// Log more on stderr: -v for each run step, -vv for debug detail
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    verbose: u8,
+
+    /// Log only errors on stderr
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Format of the log on stderr
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        default_value = "text",
+        env = "BOUCLE_LOG_FORMAT"
+    )]
+    log_format: LogFormat,
+
     #[command(subcommand)]
     command: Commands,
 }
 
+#[derive(Clone, Copy, ValueEnum)]
+enum LogFormat {
+    Text,
+    /// One JSON object per line, with the run and phase spans
+    Json,
+}
+
+/// Send `tracing` events to stderr, and spans to the OTLP collector at
+/// `traces_url` if given. Warnings show by default. Returns the tracer
+/// provider to shut down before exiting.
+fn init_logging(
+    verbose: u8,
+    quiet: bool,
+    format: LogFormat,
+    traces_url: Option<String>,
+) -> Option<opentelemetry_sdk::trace::SdkTracerProvider> {
+    use std::io::IsTerminal;
+    use tracing_subscriber::filter::LevelFilter;
+    use tracing_subscriber::prelude::*;
+    let level = match (quiet, verbose) {
+        (true, _) => LevelFilter::ERROR,
+        (false, 0) => LevelFilter::WARN,
+        (false, 1) => LevelFilter::INFO,
+        (false, _) => LevelFilter::DEBUG,
+    };
+    let stderr = tracing_subscriber::fmt::layer()
+        .with_writer(std::io::stderr)
+        .with_target(false);
+    let stderr = match format {
+        LogFormat::Text => stderr
+            .without_time()
+            .with_ansi(std::io::stderr().is_terminal())
+            .boxed(),
+        LogFormat::Json => stderr.json().with_span_list(true).boxed(),
+    };
+    let otlp = traces_url.as_deref().map(telemetry::tracer);
+    let (tracer, provider, otlp_error) = match otlp {
+        Some(Ok((tracer, provider))) => (Some(tracer), Some(provider), None),
+        Some(Err(e)) => (None, None, Some(e)),
+        None => (None, None, None),
+    };
+    tracing_subscriber::registry()
+        .with(stderr.with_filter(level))
+        .with(tracer.map(|tracer| {
+            tracing_opentelemetry::layer()
+                .with_tracer(tracer)
+                .with_filter(LevelFilter::INFO)
+        }))
+        .init();
+    if let Some(e) = otlp_error {
+        tracing::warn!("Traces not exported: {e}");
+    }
+    provider
+}
+
 #[derive(Subcommand)]
 enum Commands {
     /// Initialize a new Boucle agent in the current directory
@@ -41,29 +119,122 @@ enum Commands {
         /// Show assembled context without calling the LLM
         #[arg(long)]
         dry_run: bool,
+
+        /// Run no lifecycle hooks this time
+        #[arg(long, conflicts_with = "only_hooks")]
+        skip_hooks: bool,
+
+        /// Run only these lifecycle hooks (comma-separated, e.g. pre-run,post-llm)
+        #[arg(long, value_delimiter = ',')]
+        only_hooks: Option<Vec<String>>,
+
+        /// Run every agent in boucle-workspace.toml, one after another
+        #[arg(long)]
+        all: bool,
+
+        /// First wait a random time up to schedule.jitter (cron and launchd schedules pass this)
+        #[arg(long)]
+        jitter: bool,
+
+        /// Run for the named [schedule.<name>], passing on its task
+        #[arg(long)]
+        schedule: Option<String>,
+
+        /// Started by the scheduler: catch up on missed runs per schedule.catch_up
+        #[arg(long)]
+        scheduled: bool,
+    },
+
+    /// Show or re-execute the input of a past iteration
+    Replay {
+        /// Run timestamp, as in the log file name (e.g. 2026-03-01_10-00-00)
+        timestamp: String,
+
+        /// Send the saved input to the LLM again
+        #[arg(long)]
+        execute: bool,
+
+        /// Model to replay against (defaults to the one the run used)
+        #[arg(long)]
+        model: Option<String>,
+    },
+
+    /// Print the context the next iteration would get, without calling the LLM
+    Context {
+        /// Only show this section (repeatable): security, goals, state, recall,
+        /// actions, files, plugins, status, last_log, budget
+        #[arg(long = "section")]
+        sections: Vec<String>,
+
+        /// Print bytes and estimated tokens per section instead of the text
+        #[arg(long, alias = "tokens")]
+        stats: bool,
+    },
+
+    /// Abort a running iteration (SIGTERM, then SIGKILL after a grace period)
+    Stop {
+        /// Seconds to wait after SIGTERM before sending SIGKILL
+        #[arg(long, default_value = "10")]
+        grace: u64,
     },
 
     /// Show agent status
-    Status,
+    Status {
+        /// Show every agent in boucle-workspace.toml
+        #[arg(long)]
+        all: bool,
+
+        /// Print JSON: lock state, cool-down, pause, failures, memory, last run
+        #[arg(long)]
+        json: bool,
+    },
 
     /// Show loop history
     Log {
         /// Number of entries to show
         #[arg(short, long, default_value = "10")]
         count: usize,
+
+        /// Show the files each iteration's commit changed (git diff --stat)
+        #[arg(long)]
+        diffs: bool,
+
+        /// Print the latest iteration's log as it is written, including the
+        /// LLM's output, and move on to each new iteration (until Ctrl-C)
+        #[arg(short, long, conflicts_with_all = ["count", "diffs"])]
+        follow: bool,
+
+        /// Print a JSON array of the iterations with their run records
+        #[arg(long, conflicts_with = "follow")]
+        json: bool,
     },
 
-    /// Set up scheduling (launchd on macOS, cron on Linux)
+    /// Print scheduler config (launchd on macOS, systemd or cron on Linux, Task Scheduler on Windows)
     Schedule {
-        /// Interval between iterations (e.g., "1h", "30m", "5m")
-        #[arg(short, long, default_value = "1h")]
-        interval: String,
+        #[command(subcommand)]
+        command: Option<ScheduleCommands>,
+
+        /// Interval between iterations (e.g., "1h", "1h30m", or a cron expression like "0 9 * * *"; default: schedule.interval)
+        #[arg(short, long, global = true)]
+        interval: Option<String>,
+
+        /// Set up every agent in boucle-workspace.toml
+        #[arg(long, global = true)]
+        all: bool,
+
+        /// On Linux, use a crontab line instead of systemd user units
+        #[arg(long, global = true)]
+        cron: bool,
     },
 
     /// Broca memory operations
     #[command(subcommand)]
     Memory(MemoryCommands),
 
+    /// Inspect and try out lifecycle hooks
+    #[command(subcommand)]
+    Hooks(HooksCommands),
+
     /// Start MCP server to expose Broca to other AI agents
     Mcp {
         /// Server port (for HTTP transport, optional)
@@ -81,9 +252,20 @@ enum Commands {
     /// Show aggregate loop statistics
     Stats,
 
+    /// Print run metrics in the Prometheus text format
+    Metrics,
+
+    /// Summarize the run records
+    #[command(subcommand)]
+    Report(ReportCommands),
+
     /// Validate boucle.toml configuration
     Validate,
 
+    /// Validate, show, edit, and migrate boucle.toml and the effective config
+    #[command(subcommand)]
+    Config(ConfigCommands),
+
     /// Self-observation engine: track patterns, score responses
     #[command(subcommand)]
     Improve(ImproveCommands),
@@ -100,8 +282,23 @@ enum Commands {
         fingerprint: String,
     },
 
+    /// Check a claude PreToolUse payload against git.protected_branches
+    #[command(hide = true)]
+    GuardCommand,
+
     /// List available plugins
-    Plugins,
+    Plugins {
+        /// Print the plugins as a JSON array
+        #[arg(long)]
+        json: bool,
+
+        #[command(subcommand)]
+        command: Option<PluginsCommands>,
+    },
+
+    /// Create, install, update, and remove command plugins
+    #[command(name = "plugin", subcommand)]
+    PluginCommand(PluginCommands),
 
     /// Run a plugin from the plugins/ directory
     #[command(external_subcommand)]
@@ -124,6 +321,172 @@ enum ImproveCommands {
     Init,
 }
 
+#[derive(Subcommand)]
+enum ReportCommands {
+    /// Tokens, cost, failures, and run time, by day
+    Usage {
+        /// How far back: a duration (7d, 12h) or a date (2026-10-01)
+        #[arg(long, default_value = "7d")]
+        since: String,
+
+        /// Print JSON instead of a table
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Markdown digest: iterations, cost, failures, memory added, goals and state changes
+    Digest {
+        /// How far back: a duration (7d, 12h) or a date (2026-10-01)
+        #[arg(long, default_value = "7d")]
+        since: String,
+
+        /// Write it to this file (relative to the agent root) instead of stdout
+        #[arg(short, long)]
+        output: Option<String>,
+
+        /// Commit the file (default: digests/<date>.md)
+        #[arg(long)]
+        commit: bool,
+
+        /// Send it to the [notify] channels that take the digest event
+        #[arg(long)]
+        notify: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigCommands {
+    /// Report unknown keys, missing paths, and bad values; exits 1 on errors
+    Validate,
+
+    /// Upgrade boucle.toml to the current layout, keeping a backup
+    Migrate {
+        /// Only list the changes
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Print the effective value of a setting, e.g. `schedule.interval`
+    Get {
+        /// Dotted key
+        key: String,
+    },
+
+    /// Change a setting in boucle.toml, keeping its comments
+    Set {
+        /// Dotted key, e.g. `schedule.interval`
+        key: String,
+
+        /// New value, read as TOML when the setting takes a number, bool, or array
+        value: String,
+    },
+
+    /// Print the effective configuration, noting where each setting came from
+    Show {
+        /// Print JSON instead of annotated TOML
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ScheduleCommands {
+    /// Install and enable the schedule (launchctl, systemctl --user, or crontab)
+    Install {
+        /// Only print the configuration, as plain `boucle schedule` does
+        #[arg(long)]
+        print: bool,
+    },
+
+    /// Remove an installed schedule
+    Uninstall,
+
+    /// Stand the agent down: `boucle run` skips until resumed
+    Pause {
+        /// Resume automatically after this: a duration (2h), date, or timestamp
+        #[arg(long)]
+        until: Option<String>,
+
+        /// Why, shown by `boucle status` and skipped runs
+        #[arg(long)]
+        reason: Option<String>,
+    },
+
+    /// Lift a pause
+    Resume,
+
+    /// Whether the schedule is installed, the last run, and the next one due
+    Status,
+
+    /// Show the scheduler log: each scheduled run started, skipped, or failed
+    Logs {
+        /// Number of lines to show
+        #[arg(short = 'n', long, default_value = "20")]
+        lines: usize,
+    },
+}
+
+#[derive(Subcommand)]
+enum PluginsCommands {
+    /// Show installed plugins whose source has changed
+    Outdated,
+}
+
+#[derive(Subcommand)]
+enum PluginCommands {
+    /// Create a plugin skeleton: script, manifest, and smoke test
+    New {
+        /// Plugin name; it runs as `boucle <name>`
+        name: String,
+
+        /// Script language: shell or python
+        #[arg(short, long, default_value = "shell")]
+        lang: String,
+
+        /// One-line description for the manifest and script header
+        #[arg(short, long)]
+        description: Option<String>,
+    },
+
+    /// Install a plugin with a manifest from a git URL or a local path
+    Install {
+        /// Git URL, plugin script, plugin directory, or another agent's root
+        source: String,
+
+        /// Plugin to take when the source has several
+        #[arg(short, long)]
+        name: Option<String>,
+    },
+
+    /// Fetch installed plugins again from where they came from
+    Update {
+        /// Plugin to update (default: all installed plugins)
+        name: Option<String>,
+    },
+
+    /// Delete an installed plugin's files
+    Remove {
+        /// Plugin name
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum HooksCommands {
+    /// List the scripts found for each lifecycle point
+    List,
+
+    /// Run one hook now with a synthetic payload
+    Test {
+        /// Hook name, e.g. pre-run or post-commit
+        name: String,
+
+        /// LLM exit code to report to hooks that run after the LLM
+        #[arg(long)]
+        exit_code: Option<i32>,
+    },
+}
+
 #[derive(Subcommand)]
 enum MemoryCommands {
     /// Store a new memory entry
@@ -149,6 +512,10 @@ enum MemoryCommands {
         /// Date this fact should be considered fresh until (YYYYMMDD or YYYY-MM-DD)
         #[arg(long)]
         valid_until: Option<String>,
+
+        /// Confidence from 0 to 1 (default: [memory.defaults.<type>], else 0.8)
+        #[arg(long)]
+        confidence: Option<f64>,
     },
 
     /// Search memory with relevance ranking
@@ -159,6 +526,10 @@ enum MemoryCommands {
         /// Maximum results
         #[arg(short, long, default_value = "5")]
         limit: usize,
+
+        /// Search the memory of every agent in boucle-workspace.toml
+        #[arg(long)]
+        all: bool,
     },
 
     /// Show a specific memory entry
@@ -213,6 +584,9 @@ enum MemoryCommands {
     /// Show memory statistics
     Stats,
 
+    /// Answer JSON requests from stdin, one per line (for scripts)
+    Batch,
+
     /// Build or rebuild the memory index
     Index,
 
@@ -251,14 +625,51 @@ enum MemoryCommands {
 fn main() {
     let cli = Cli::parse();
 
+    // `--all` works on the workspace around the root (or current directory)
+    let workspace = matches!(
+        cli.command,
+        Commands::Run { all: true, .. }
+            | Commands::Status { all: true, .. }
+            | Commands::Schedule { all: true, .. }
+            | Commands::Memory(MemoryCommands::Recall { all: true, .. })
+    )
+    .then(|| {
+        let start = cli
+            .root
+            .clone()
+            .unwrap_or_else(|| std::env::current_dir().unwrap());
+        match config::find_workspace(&start) {
+            Ok(Some(workspace)) => workspace,
+            Ok(None) => {
+                eprintln!(
+                    "Error: --all needs a {} above {}.",
+                    config::WORKSPACE_FILE,
+                    start.display()
+                );
+                process::exit(1);
+            }
+            Err(e) => {
+                eprintln!("Error: {e}");
+                process::exit(1);
+            }
+        }
+    });
+
     // Find or use the agent root
     let root = match cli.root {
         Some(r) => r,
         None => match config::find_agent_root(&std::env::current_dir().unwrap()) {
             Some(r) => r,
             None => {
-                if !matches!(cli.command, Commands::Init { .. }) {
+                if !matches!(cli.command, Commands::Init { .. }) && workspace.is_none() {
                     eprintln!("Error: No boucle.toml found. Run 'boucle init' first.");
+                    let cwd = std::env::current_dir().unwrap();
+                    if let Ok(Some(_)) = config::find_workspace(&cwd) {
+                        eprintln!(
+                            "This is a workspace: use --all or --root <member>, see {}.",
+                            config::WORKSPACE_FILE
+                        );
+                    }
                     process::exit(1);
                 }
                 std::env::current_dir().unwrap()
@@ -266,6 +677,20 @@ fn main() {
         },
     };
 
+    let profile = cli
+        .profile
+        .or_else(|| std::env::var("BOUCLE_PROFILE").ok())
+        .filter(|name| !name.is_empty());
+
+    // Only iterations are traced.
+    let traces_url = matches!(cli.command, Commands::Run { dry_run: false, .. })
+        .then(|| {
+            let root = workspace.is_none().then_some(root.as_path());
+            telemetry::traces_url(root, profile.as_deref())
+        })
+        .flatten();
+    let tracer_provider = init_logging(cli.verbose, cli.quiet, cli.log_format, traces_url);
+
     match cli.command {
         Commands::Init { name } => {
             if let Err(e) = runner::init(&root, &name) {
@@ -275,34 +700,188 @@ fn main() {
             println!("Initialized Boucle agent '{name}' in {}", root.display());
         }
 
-        Commands::Run { dry_run } => {
-            if let Err(e) = runner::run(&root, dry_run) {
+        Commands::Run {
+            dry_run,
+            skip_hooks,
+            only_hooks,
+            all: _,
+            jitter,
+            schedule,
+            scheduled,
+        } => {
+            let only_hooks = if skip_hooks {
+                Some(Vec::new())
+            } else {
+                only_hooks
+            };
+            let run = |root: &std::path::Path| {
+                let (profile, schedule) = (profile.as_deref(), schedule.as_deref());
+                let iterations = if scheduled && !dry_run {
+                    runner::schedule::scheduled_iterations(root, profile, schedule)?
+                } else {
+                    1
+                };
+                if iterations == 0 {
+                    return Ok(());
+                }
+                if jitter && !dry_run {
+                    runner::schedule::wait_jitter(root, profile, schedule)?;
+                }
+                let mut result = Ok(());
+                for i in 1..=iterations {
+                    if iterations > 1 {
+                        println!("--- Catch-up iteration {i} of {iterations} ---");
+                    }
+                    result = runner::run(root, dry_run, profile, only_hooks.as_deref(), schedule);
+                    if !dry_run {
+                        runner::metrics::write_textfile(root, profile);
+                    }
+                    if result.is_err() {
+                        break;
+                    }
+                }
+                if scheduled && !dry_run {
+                    runner::schedule::log_outcome(root, profile, schedule, &result);
+                }
+                result
+            };
+            let result = match workspace {
+                Some(ref workspace) => runner::workspace::for_each_member(workspace, run),
+                None => run(&root).map(|()| true),
+            };
+            if let Some(provider) = tracer_provider {
+                if let Err(e) = provider.shutdown() {
+                    tracing::warn!("Traces not exported: {e}");
+                }
+            }
+            exit_on_failure(result);
+        }
+
+        Commands::Replay {
+            timestamp,
+            execute,
+            model,
+        } => {
+            if let Err(e) = runner::replay(
+                &root,
+                &timestamp,
+                execute,
+                model.as_deref(),
+                profile.as_deref(),
+            ) {
                 eprintln!("Error: {e}");
                 process::exit(1);
             }
         }
 
-        Commands::Status => {
-            if let Err(e) = runner::status(&root) {
+        Commands::Context { sections, stats } => {
+            if let Err(e) = runner::show_context(&root, profile.as_deref(), &sections, stats) {
                 eprintln!("Error: {e}");
                 process::exit(1);
             }
         }
 
-        Commands::Log { count } => {
-            if let Err(e) = runner::show_log(&root, count) {
+        Commands::Stop { grace } => {
+            if let Err(e) = runner::stop(
+                &root,
+                profile.as_deref(),
+                std::time::Duration::from_secs(grace),
+            ) {
                 eprintln!("Error: {e}");
                 process::exit(1);
             }
         }
 
-        Commands::Schedule { interval } => {
-            if let Err(e) = runner::schedule(&root, &interval) {
+        Commands::Status { all: _, json } => exit_on_failure(match workspace {
+            Some(ref workspace) if json => {
+                runner::workspace::statuses_json(workspace, runner::status_report)
+            }
+            Some(ref workspace) => runner::workspace::for_each_member(workspace, |member| {
+                runner::status(member, false)
+            }),
+            None => runner::status(&root, json).map(|()| true),
+        }),
+
+        Commands::Log {
+            count,
+            diffs,
+            follow,
+            json,
+        } => {
+            let shown = if follow {
+                runner::follow::follow(&root, profile.as_deref())
+            } else {
+                runner::show_log(&root, count, diffs, json)
+            };
+            if let Err(e) = shown {
                 eprintln!("Error: {e}");
                 process::exit(1);
             }
         }
 
+        Commands::Schedule {
+            command,
+            interval,
+            all: _,
+            cron,
+        } => {
+            let (interval, profile) = (interval.as_deref(), profile.as_deref());
+            let schedule = |root: &std::path::Path| match command {
+                None | Some(ScheduleCommands::Install { print: true }) => {
+                    runner::schedule::print(root, interval, profile, cron)
+                }
+                Some(ScheduleCommands::Install { print: false }) => {
+                    runner::schedule::install(root, interval, profile, cron)
+                }
+                Some(ScheduleCommands::Uninstall) => runner::schedule::uninstall(root, profile),
+                Some(ScheduleCommands::Status) => runner::schedule::status(root, profile),
+                Some(ScheduleCommands::Logs { lines }) => {
+                    runner::schedule::show_logs(root, profile, lines)
+                }
+                Some(ScheduleCommands::Pause {
+                    ref until,
+                    ref reason,
+                }) => runner::schedule::pause(root, until.as_deref(), reason.as_deref())
+                    .map(|pause| println!("Paused {}.", pause.describe())),
+                Some(ScheduleCommands::Resume) => {
+                    runner::schedule::resume(root).map(|was_paused| {
+                        println!(
+                            "{}",
+                            if was_paused {
+                                "Resumed."
+                            } else {
+                                "Not paused."
+                            }
+                        )
+                    })
+                }
+            };
+            exit_on_failure(match workspace {
+                Some(ref workspace) => runner::workspace::for_each_member(workspace, schedule),
+                None => schedule(&root).map(|()| true),
+            });
+        }
+
+        Commands::Memory(MemoryCommands::Recall {
+            query,
+            limit,
+            all: true,
+        }) => {
+            let workspace = workspace.expect("--all resolves the workspace");
+            match runner::workspace::recall(&workspace, &query, limit) {
+                Ok(results) if results.is_empty() => println!("No matching memories found."),
+                Ok(results) => {
+                    for (i, (member, entry)) in results.iter().enumerate() {
+                        print_recalled(i, entry, Some(member.as_str()));
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Error: {e}");
+                    process::exit(1);
+                }
+            }
+        }
+
         Commands::Memory(mem_cmd) => {
             let cfg = match config::load(&root) {
                 Ok(c) => c,
@@ -321,68 +900,52 @@ fn main() {
                     tags,
                     ttl,
                     valid_until,
+                    confidence,
                 } => {
                     let tag_list: Vec<String> = tags
                         .map(|t| t.split(',').map(|s| s.trim().to_string()).collect())
                         .unwrap_or_default();
-                    match broca::remember_with_validity(
-                        &memory_dir,
-                        &entry_type,
-                        &title,
-                        &content,
-                        &tag_list,
-                        ttl,
-                        valid_until.as_deref(),
-                    ) {
-                        Ok(path) => println!("Stored: {}", path.display()),
+                    let entry = runner::with_memory_defaults(
+                        &cfg,
+                        broca::NewEntry {
+                            entry_type,
+                            title,
+                            content,
+                            tags: tag_list,
+                            ttl_days: ttl,
+                            valid_until,
+                            confidence,
+                        },
+                    );
+                    let path = match broca::remember_entry(&memory_dir, &entry) {
+                        Ok(path) => path,
                         Err(e) => {
                             eprintln!("Error: {e}");
                             process::exit(1);
                         }
+                    };
+                    let memory = runner::MemoryEvent::knowledge(
+                        "cli",
+                        &path,
+                        &entry.entry_type,
+                        &entry.title,
+                        &entry.tags,
+                    );
+                    if let Err(e) = runner::on_remember(&root, &cfg, memory, None, None) {
+                        eprintln!("Error: {e}");
+                        process::exit(1);
                     }
+                    println!("Stored: {}", path.display());
                 }
 
-                MemoryCommands::Recall { query, limit } => {
+                MemoryCommands::Recall { query, limit, .. } => {
                     match broca::recall(&memory_dir, &query, limit) {
                         Ok(results) => {
                             if results.is_empty() {
                                 println!("No matching memories found.");
                             } else {
                                 for (i, entry) in results.iter().enumerate() {
-                                    println!(
-                                        "{}. [{}] {} (confidence: {:.1}, score: {:.1})",
-                                        i + 1,
-                                        entry.entry_type,
-                                        entry.title,
-                                        entry.confidence,
-                                        entry.relevance_score
-                                    );
-                                    println!("   file: {}", entry.filename);
-                                    if let Some(ref sup) = entry.superseded_by {
-                                        println!("   ⚠ superseded by: {sup}");
-                                    }
-                                    if let Some(ttl_days) = entry.ttl_days {
-                                        println!("   ttl: {ttl_days}d");
-                                    }
-                                    if let Some(ref valid_until) = entry.valid_until {
-                                        println!("   valid until: {valid_until}");
-                                    }
-                                    if entry.is_stale {
-                                        let stale_reason = entry
-                                            .stale_reason
-                                            .as_deref()
-                                            .unwrap_or("freshness marker expired");
-                                        println!("   ⚠ stale: {stale_reason}");
-                                    }
-                                    if !entry.tags.is_empty() {
-                                        println!("   tags: {}", entry.tags.join(", "));
-                                    }
-                                    // Show content preview (first 100 chars)
-                                    let preview: String = entry.content.chars().take(100).collect();
-                                    let ellipsis =
-                                        if entry.content.len() > 100 { "..." } else { "" };
-                                    println!("   {preview}{ellipsis}");
-                                    println!();
+                                    print_recalled(i, entry, None);
                                 }
                             }
                         }
@@ -417,14 +980,31 @@ fn main() {
                     }
                 },
 
+                MemoryCommands::Batch => {
+                    let stdin = std::io::stdin().lock();
+                    if let Err(e) =
+                        runner::memory_batch::serve(&root, &cfg, stdin, std::io::stdout().lock())
+                    {
+                        eprintln!("Error: {e}");
+                        process::exit(1);
+                    }
+                }
+
                 MemoryCommands::Journal { content } => {
-                    match broca::journal(&memory_dir, &content) {
-                        Ok(path) => println!("Journal entry: {}", path.display()),
+                    let previous = std::fs::read_to_string(broca::journal_path(&memory_dir)).ok();
+                    let path = match broca::journal(&memory_dir, &content) {
+                        Ok(path) => path,
                         Err(e) => {
                             eprintln!("Error: {e}");
                             process::exit(1);
                         }
+                    };
+                    let memory = runner::MemoryEvent::journal("cli", &path);
+                    if let Err(e) = runner::on_remember(&root, &cfg, memory, previous, None) {
+                        eprintln!("Error: {e}");
+                        process::exit(1);
                     }
+                    println!("Journal entry: {}", path.display());
                 }
 
                 MemoryCommands::UpdateConfidence { entry, confidence } => {
@@ -624,6 +1204,27 @@ fn main() {
             }
         }
 
+        Commands::Hooks(cmd) => {
+            let result = match cmd {
+                HooksCommands::List => runner::list_hooks(&root),
+                HooksCommands::Test { name, exit_code } => {
+                    runner::test_hook(&root, &name, exit_code)
+                }
+            };
+            if let Err(e) = result {
+                eprintln!("Error: {e}");
+                process::exit(1);
+            }
+        }
+
+        Commands::GuardCommand => match runner::guard_command(&root) {
+            Ok(code) => process::exit(code),
+            Err(e) => {
+                eprintln!("Error: {e}");
+                process::exit(1);
+            }
+        },
+
         Commands::Improve(cmd) => match cmd {
             ImproveCommands::Run { budget } => {
                 if let Err(e) = improve::run_pipeline(&root, budget) {
@@ -657,7 +1258,7 @@ fn main() {
         }
 
         Commands::Mcp { port, stdio } => {
-            let cfg = match config::load(&root) {
+            let cfg = match config::LiveConfig::load(&root, profile.as_deref()) {
                 Ok(c) => c,
                 Err(e) => {
                     eprintln!("Error loading config: {e}");
@@ -667,7 +1268,7 @@ fn main() {
 
             // Create a tokio runtime for the async MCP server
             let rt = tokio::runtime::Runtime::new().unwrap();
-            if let Err(e) = rt.block_on(mcp::serve(&root, &cfg, port, stdio)) {
+            if let Err(e) = rt.block_on(mcp::serve(&root, cfg, port, stdio)) {
                 eprintln!("MCP server error: {e}");
                 process::exit(1);
             }
@@ -687,47 +1288,222 @@ fn main() {
             }
         }
 
-        Commands::Validate => {
-            if let Err(e) = runner::validate(&root) {
+        Commands::Metrics => {
+            if let Err(e) = runner::metrics::show(&root, profile.as_deref()) {
+                eprintln!("Error: {e}");
+                process::exit(1);
+            }
+        }
+
+        Commands::Report(ReportCommands::Usage { since, json }) => {
+            if let Err(e) = runner::report::usage(&root, profile.as_deref(), &since, json) {
+                eprintln!("Error: {e}");
+                process::exit(1);
+            }
+        }
+
+        Commands::Report(ReportCommands::Digest {
+            since,
+            output,
+            commit,
+            notify,
+        }) => {
+            if let Err(e) = runner::report::digest(
+                &root,
+                profile.as_deref(),
+                &since,
+                output.as_deref(),
+                commit,
+                notify,
+            ) {
+                eprintln!("Error: {e}");
+                process::exit(1);
+            }
+        }
+
+        Commands::Validate | Commands::Config(ConfigCommands::Validate) => {
+            match runner::validate(&root) {
+                Ok(true) => {}
+                Ok(false) => process::exit(1),
+                Err(e) => {
+                    eprintln!("Error: {e}");
+                    process::exit(1);
+                }
+            }
+        }
+
+        Commands::Config(ConfigCommands::Migrate { dry_run }) => {
+            match config::migrate::migrate(&root, dry_run) {
+                Ok(migrated) if migrated.from == migrated.to => {
+                    println!("boucle.toml is already at version {}.", migrated.to);
+                }
+                Ok(migrated) => {
+                    let verb = if dry_run { "Would migrate" } else { "Migrated" };
+                    println!(
+                        "{verb} boucle.toml from version {} to {}:",
+                        migrated.from, migrated.to
+                    );
+                    for change in &migrated.changes {
+                        println!("  - {change}");
+                    }
+                    println!("  - set version = {}", migrated.to);
+                    if let Some(backup) = migrated.backup {
+                        println!("Backup: {}", backup.display());
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Error: {e}");
+                    process::exit(1);
+                }
+            }
+        }
+
+        Commands::Config(ConfigCommands::Get { key }) => {
+            match config::edit::get(&root, profile.as_deref(), &key) {
+                Ok(Some(toml::Value::String(value))) => println!("{value}"),
+                Ok(Some(toml::Value::Table(table))) => {
+                    print!("{}", toml::to_string(&table).unwrap_or_default())
+                }
+                Ok(Some(value)) => println!("{value}"),
+                Ok(None) => {
+                    eprintln!("{key} is not set");
+                    process::exit(1);
+                }
+                Err(e) => {
+                    eprintln!("Error: {e}");
+                    process::exit(1);
+                }
+            }
+        }
+
+        Commands::Config(ConfigCommands::Set { key, value }) => {
+            match config::edit::set(&root, &key, &value) {
+                Ok(Some(previous)) => println!("Set {key} (was {previous})."),
+                Ok(None) => println!("Set {key}."),
+                Err(e) => {
+                    eprintln!("Error: {e}");
+                    process::exit(1);
+                }
+            }
+        }
+
+        Commands::Config(ConfigCommands::Show { json }) => {
+            match config::effective(&root, profile.as_deref()) {
+                Ok(effective) if json => println!(
+                    "{}",
+                    serde_json::to_string_pretty(&effective.to_json()).unwrap_or_default()
+                ),
+                Ok(effective) => print!("{}", effective.to_annotated_toml()),
+                Err(e) => {
+                    eprintln!("Error: {e}");
+                    process::exit(1);
+                }
+            }
+        }
+
+        Commands::Plugins {
+            command: Some(PluginsCommands::Outdated),
+            ..
+        } => match plugin_install::outdated(&root) {
+            Ok(results) if results.is_empty() => println!("No installed plugins."),
+            Ok(results) => {
+                let mut failed = false;
+                for result in results {
+                    match result {
+                        Ok(plugin) => {
+                            let installed = plugin.installed.as_deref().unwrap_or("-");
+                            let status = match &plugin.available {
+                                _ if !plugin.outdated => format!("{installed} (up to date)"),
+                                Some(available) if Some(available) != plugin.installed.as_ref() => {
+                                    format!("{installed} -> {available}")
+                                }
+                                _ => format!("{installed} (source changed)"),
+                            };
+                            println!("  {:20} {}", plugin.name, status);
+                        }
+                        Err(line) => {
+                            eprintln!("Error: {line}");
+                            failed = true;
+                        }
+                    }
+                }
+                if failed {
+                    process::exit(1);
+                }
+            }
+            Err(e) => {
                 eprintln!("Error: {e}");
                 process::exit(1);
             }
+        },
+
+        Commands::Plugins {
+            command: None,
+            json: true,
+        } => {
+            let plugins_dir = root.join("plugins");
+            let plugins = if plugins_dir.exists() {
+                match command_plugins::discover(&plugins_dir) {
+                    Ok(plugins) => plugins,
+                    Err(e) => {
+                        eprintln!("Error reading plugins directory: {e}");
+                        process::exit(1);
+                    }
+                }
+            } else {
+                Vec::new()
+            };
+            let entries: Vec<serde_json::Value> = plugins
+                .into_iter()
+                .map(|plugin| match plugin {
+                    Ok(plugin) => plugin.describe(&plugins_dir),
+                    Err(command_plugins::PluginError::Manifest(name, e)) => {
+                        serde_json::json!({"name": name, "error": e})
+                    }
+                    Err(e) => serde_json::json!({"error": e.to_string()}),
+                })
+                .collect();
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&entries).unwrap_or_default()
+            );
         }
 
-        Commands::Plugins => {
+        Commands::Plugins {
+            command: None,
+            json: false,
+        } => {
             let plugins_dir = root.join("plugins");
             if !plugins_dir.exists() {
                 println!("No plugins directory found at {}", plugins_dir.display());
                 println!("Create plugins/ and add scripts to extend boucle.");
                 return;
             }
-            match std::fs::read_dir(&plugins_dir) {
-                Ok(entries) => {
-                    let mut found = false;
-                    for entry in entries.flatten() {
-                        let path = entry.path();
-                        if path.is_file() {
-                            let name = path.file_stem().and_then(|s| s.to_str()).unwrap_or("?");
-                            // Read first line after shebang for description
-                            let desc = std::fs::read_to_string(&path)
-                                .ok()
-                                .and_then(|content| {
-                                    content
-                                        .lines()
-                                        .find(|l| l.starts_with("# description:"))
-                                        .map(|l| {
-                                            l.trim_start_matches("# description:")
-                                                .trim()
-                                                .to_string()
-                                        })
-                                })
-                                .unwrap_or_default();
-                            println!("  {name:20} {desc}");
-                            found = true;
+            match command_plugins::discover(&plugins_dir) {
+                Ok(plugins) if plugins.is_empty() => {
+                    println!("No plugins found in {}", plugins_dir.display());
+                }
+                Ok(plugins) => {
+                    for plugin in plugins {
+                        let plugin = match plugin {
+                            Ok(plugin) => plugin,
+                            Err(e) => {
+                                println!("  Error: {e}");
+                                continue;
+                            }
+                        };
+                        let mut desc: Vec<String> = plugin.description().into_iter().collect();
+                        if let Some(version) = plugin.version() {
+                            desc.push(format!("(v{version})"));
                         }
-                    }
-                    if !found {
-                        println!("No plugins found in {}", plugins_dir.display());
+                        if plugin.script.is_none() {
+                            desc.push("[no script]".to_string());
+                        }
+                        let missing = plugin.missing();
+                        if !missing.is_empty() {
+                            desc.push(format!("[needs {}]", missing.join(", ")));
+                        }
+                        println!("  {:20} {}", plugin.command_name(), desc.join(" "));
                     }
                 }
                 Err(e) => {
@@ -737,55 +1513,126 @@ fn main() {
             }
         }
 
-        Commands::Plugin(args) => {
-            if args.is_empty() {
-                eprintln!("No plugin specified.");
+        Commands::PluginCommand(PluginCommands::New {
+            name,
+            lang,
+            description,
+        }) => {
+            let command = name.replace('/', " ");
+            let first = name.split('/').next().unwrap_or_default();
+            if Cli::command().find_subcommand(first).is_some() {
+                eprintln!("Error: '{first}' is a built-in command; `boucle {command}` would never run the plugin.");
                 process::exit(1);
             }
-            let plugin_name = &args[0];
-            let plugin_args = &args[1..];
-            let plugins_dir = root.join("plugins");
+            match command_plugins::scaffold(
+                &root.join("plugins"),
+                &name,
+                &lang,
+                description.as_deref(),
+            ) {
+                Ok(files) => {
+                    println!("Created plugin '{name}':");
+                    for file in &files {
+                        let file = file.strip_prefix(&root).unwrap_or(file);
+                        println!("  {}", file.display());
+                    }
+                    println!();
+                    println!("Try it:     boucle {command} example");
+                    println!("Smoke test: sh plugins/tests/{name}.sh");
+                }
+                Err(e) => {
+                    eprintln!("Error: {e}");
+                    process::exit(1);
+                }
+            }
+        }
+
+        Commands::PluginCommand(PluginCommands::Install { source, name }) => {
+            let built_ins: Vec<String> = Cli::command()
+                .get_subcommands()
+                .map(|c| c.get_name().to_string())
+                .collect();
+            let built_ins: Vec<&str> = built_ins.iter().map(String::as_str).collect();
+            match plugin_install::install(&root, &source, name.as_deref(), &built_ins) {
+                Ok(name) => println!("Installed plugin '{name}' from {source}"),
+                Err(e) => {
+                    eprintln!("Error: {e}");
+                    process::exit(1);
+                }
+            }
+        }
 
-            // Find the plugin script (with or without extension)
-            let plugin_path = find_plugin(&plugins_dir, plugin_name);
-            match plugin_path {
-                Some(path) => {
-                    // Detect interpreter from shebang
-                    let interpreter = detect_interpreter(&path);
-                    let mut cmd = match interpreter {
-                        Some((interp, arg)) => {
-                            let mut c = Command::new(interp);
-                            if let Some(a) = arg {
-                                c.arg(a);
+        Commands::PluginCommand(PluginCommands::Update { name }) => {
+            match plugin_install::update(&root, name.as_deref()) {
+                Ok(results) if results.is_empty() => println!("No installed plugins."),
+                Ok(results) => {
+                    let mut failed = false;
+                    for result in results {
+                        match result {
+                            Ok(line) => println!("{line}"),
+                            Err(line) => {
+                                eprintln!("Error: {line}");
+                                failed = true;
                             }
-                            c.arg(&path);
-                            c
                         }
-                        None => Command::new(&path),
-                    };
-
-                    cmd.args(plugin_args)
-                        .env("BOUCLE_ROOT", &root)
-                        .env("BOUCLE_PLUGINS", &plugins_dir);
+                    }
+                    if failed {
+                        process::exit(1);
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Error: {e}");
+                    process::exit(1);
+                }
+            }
+        }
 
-                    // Add config-derived env vars if config exists
-                    if let Ok(cfg) = config::load(&root) {
-                        cmd.env("BOUCLE_MEMORY", root.join(&cfg.memory.dir));
+        Commands::PluginCommand(PluginCommands::Remove { name }) => {
+            match plugin_install::remove(&root, &name) {
+                Ok(files) => {
+                    println!("Removed plugin '{name}':");
+                    for file in files {
+                        println!("  plugins/{file}");
                     }
+                }
+                Err(e) => {
+                    eprintln!("Error: {e}");
+                    process::exit(1);
+                }
+            }
+        }
 
-                    match cmd.status() {
-                        Ok(status) => {
-                            process::exit(status.code().unwrap_or(1));
-                        }
-                        Err(e) => {
-                            eprintln!("Error running plugin '{plugin_name}': {e}");
-                            process::exit(1);
+        Commands::Plugin(args) => {
+            if args.is_empty() {
+                eprintln!("No plugin specified.");
+                process::exit(1);
+            }
+            let plugins_dir = root.join("plugins");
+            let (plugin, plugin_args) = match command_plugins::resolve(&plugins_dir, &args) {
+                Ok((plugin, consumed)) => (plugin, &args[consumed..]),
+                Err(command_plugins::PluginError::NotFound(_)) => {
+                    let plugin_name = &args[0];
+                    let grouped = command_plugins::namespace_plugins(&plugins_dir, plugin_name);
+                    if grouped.is_empty() {
+                        eprintln!("Unknown command '{plugin_name}'. Not a built-in or plugin.");
+                        eprintln!("Run 'boucle plugins' to see available plugins.");
+                    } else {
+                        eprintln!("'{plugin_name}' is a plugin namespace; run one of:");
+                        for plugin in grouped {
+                            eprintln!("  boucle {}", plugin.command_name());
                         }
                     }
+                    process::exit(1);
+                }
+                Err(e) => {
+                    eprintln!("Error: {e}");
+                    process::exit(1);
                 }
-                None => {
-                    eprintln!("Unknown command '{plugin_name}'. Not a built-in or plugin.");
-                    eprintln!("Run 'boucle plugins' to see available plugins.");
+            };
+            match plugin.run(&root, plugin_args) {
+                Ok(code) => process::exit(code),
+                Err(e) => {
+                    eprintln!("Error: {e}");
                     process::exit(1);
                 }
             }
@@ -793,39 +1640,55 @@ fn main() {
     }
 }
 
-/// Find a plugin script by name, checking with and without common extensions.
-fn find_plugin(plugins_dir: &std::path::Path, name: &str) -> Option<PathBuf> {
-    if !plugins_dir.exists() {
-        return None;
+/// Print one `memory recall` result; `member` is the workspace member it
+/// came from, for `--all`.
+fn print_recalled(i: usize, entry: &broca::ScoredEntry, member: Option<&str>) {
+    println!(
+        "{}. [{}] {} (confidence: {:.1}, score: {:.1})",
+        i + 1,
+        entry.entry_type,
+        entry.title,
+        entry.confidence,
+        entry.relevance_score
+    );
+    println!("   file: {}", entry.filename);
+    if let Some(member) = member {
+        println!("   agent: {member}");
     }
-    // Try exact name first, then with common extensions
-    let candidates = [
-        name.to_string(),
-        format!("{name}.py"),
-        format!("{name}.sh"),
-        format!("{name}.rb"),
-    ];
-    for candidate in &candidates {
-        let path = plugins_dir.join(candidate);
-        if path.is_file() {
-            return Some(path);
-        }
+    if let Some(ref sup) = entry.superseded_by {
+        println!("   ⚠ superseded by: {sup}");
+    }
+    if let Some(ttl_days) = entry.ttl_days {
+        println!("   ttl: {ttl_days}d");
     }
-    None
+    if let Some(ref valid_until) = entry.valid_until {
+        println!("   valid until: {valid_until}");
+    }
+    if entry.is_stale {
+        let stale_reason = entry
+            .stale_reason
+            .as_deref()
+            .unwrap_or("freshness marker expired");
+        println!("   ⚠ stale: {stale_reason}");
+    }
+    if !entry.tags.is_empty() {
+        println!("   tags: {}", entry.tags.join(", "));
+    }
+    // Show content preview (first 100 chars)
+    let preview: String = entry.content.chars().take(100).collect();
+    let ellipsis = if entry.content.len() > 100 { "..." } else { "" };
+    println!("   {preview}{ellipsis}");
+    println!();
 }
 
-/// Detect interpreter from shebang line.
-fn detect_interpreter(path: &std::path::Path) -> Option<(String, Option<String>)> {
-    let content = std::fs::read_to_string(path).ok()?;
-    let first_line = content.lines().next()?;
-    if !first_line.starts_with("#!") {
-        return None;
-    }
-    let shebang = first_line.trim_start_matches("#!").trim();
-    if shebang.starts_with("/usr/bin/env ") {
-        let interp = shebang.trim_start_matches("/usr/bin/env ").trim();
-        Some((interp.to_string(), None))
-    } else {
-        Some((shebang.to_string(), None))
+/// Exit 1 unless `result` is `Ok(true)`, printing the error if there is one.
+fn exit_on_failure(result: Result<bool, runner::RunnerError>) {
+    match result {
+        Ok(true) => {}
+        Ok(false) => process::exit(1),
+        Err(e) => {
+            eprintln!("Error: {e}");
+            process::exit(1);
+        }
     }
 }
@@ -4,14 +4,16 @@
 //! allowing other AI agents to use the file-based memory system.
 
 use crate::broca;
-use crate::config::Config;
+use crate::config::{Config, LiveConfig};
+use crate::runner;
+use crate::runner::command_plugins;
 use crate::runner::context::validate_external_content;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::error::Error;
+use std::fs;
 use std::io::{self, BufRead, BufReader, Write};
-use std::path::{Path, PathBuf};
-use std::{fs, process};
+use std::path::Path;
 
 const MCP_VERSION: &str = "2025-11-25";
 
@@ -38,25 +40,28 @@ struct JsonRpcError {
     data: Option<Value>,
 }
 
-/// Start the MCP server to expose Broca functionality
+/// Start the MCP server to expose Broca functionality.
+///
+/// boucle.toml is re-read when it changes, checked before each request; a
+/// version that does not load is reported and the previous one kept.
 pub async fn serve(
     root: &Path,
-    config: &Config,
+    mut config: LiveConfig,
     _port: Option<u16>,
     stdio: bool,
 ) -> Result<(), Box<dyn Error>> {
-    let memory_dir = root.join(&config.memory.dir);
+    let memory_dir = root.join(&config.get().memory.dir);
 
-    eprintln!("Starting Broca MCP Server...");
-    eprintln!("Memory directory: {}", memory_dir.display());
+    tracing::info!("Starting Broca MCP Server...");
+    tracing::info!("Memory directory: {}", memory_dir.display());
 
     if !stdio {
-        eprintln!("Error: Only stdio transport is currently supported");
+        tracing::error!("Only stdio transport is currently supported");
         return Err("Only stdio transport is supported".into());
     }
 
-    eprintln!("Transport: stdio");
-    eprintln!("Waiting for initialization...");
+    tracing::info!("Transport: stdio");
+    tracing::info!("Waiting for initialization...");
 
     let stdin = io::stdin();
     let mut reader = BufReader::new(stdin.lock());
@@ -72,7 +77,22 @@ pub async fn serve(
 
         match serde_json::from_str::<JsonRpcMessage>(&line) {
             Ok(message) => {
-                let response = handle_message(message, root, config).await?;
+                match config.refresh() {
+                    Ok(false) => {}
+                    Ok(true) => {
+                        tracing::info!("Reloaded boucle.toml");
+                        let changed = json!({
+                            "jsonrpc": "2.0",
+                            "method": "notifications/tools/list_changed"
+                        });
+                        writeln!(stdout, "{changed}")?;
+                        stdout.flush()?;
+                    }
+                    Err(e) => tracing::warn!(
+                        "boucle.toml changed but does not load, keeping the previous config: {e}"
+                    ),
+                }
+                let response = handle_message(message, root, config.get()).await?;
                 if let Some(response) = response {
                     let response_json = serde_json::to_string(&response)?;
                     writeln!(stdout, "{}", response_json)?;
@@ -80,7 +100,7 @@ pub async fn serve(
                 }
             }
             Err(e) => {
-                eprintln!("Failed to parse JSON-RPC message: {}", e);
+                tracing::warn!("Failed to parse JSON-RPC message: {}", e);
                 // Send parse error response
                 let error_response = JsonRpcMessage {
                     jsonrpc: "2.0".to_string(),
@@ -113,7 +133,7 @@ async fn handle_message(
 ) -> Result<Option<JsonRpcMessage>, Box<dyn Error>> {
     match message.method.as_deref() {
         Some("initialize") => handle_initialize(message),
-        Some("tools/list") => handle_tools_list(message, root),
+        Some("tools/list") => handle_tools_list(message, root, config),
         Some("tools/call") => handle_tools_call(message, root, config).await,
         Some(method) => {
             // Unknown method
@@ -142,7 +162,7 @@ fn handle_initialize(message: JsonRpcMessage) -> Result<Option<JsonRpcMessage>,
         "protocolVersion": MCP_VERSION,
         "capabilities": {
             "tools": {
-                "listChanged": false
+                "listChanged": true
             }
         },
         "serverInfo": {
@@ -172,6 +192,7 @@ fn handle_initialize(message: JsonRpcMessage) -> Result<Option<JsonRpcMessage>,
 fn handle_tools_list(
     message: JsonRpcMessage,
     root: &Path,
+    config: &Config,
 ) -> Result<Option<JsonRpcMessage>, Box<dyn Error>> {
     let mut tools: Vec<Value> = vec![
         json!({
@@ -185,7 +206,9 @@ fn handle_tools_list(
                     "title": { "type": "string", "description": "Optional title for the memory" },
                     "tags": { "type": "array", "items": {"type": "string"}, "description": "Optional tags for categorization" },
                     "ttl_days": { "type": "integer", "description": "Optional freshness TTL in days from creation", "minimum": 0 },
-                    "valid_until": { "type": "string", "description": "Optional freshness date, YYYYMMDD or YYYY-MM-DD. Recall warns after this date." }
+                    "valid_until": { "type": "string", "description": "Optional freshness date, YYYYMMDD or YYYY-MM-DD. Recall warns after this date." },
+                    "type": { "type": "string", "enum": ["fact", "decision", "observation", "error", "procedure"], "description": entry_type_description(config) },
+                    "confidence": { "type": "number", "minimum": 0, "maximum": 1, "description": "Optional confidence from 0 to 1; defaults to the type's configured confidence, else 0.8" }
                 },
                 "required": ["content"]
             }
@@ -374,10 +397,7 @@ async fn handle_tools_call(
         "broca_restore" => handle_broca_restore(arguments, root, config).await,
         "broca_archived" => handle_broca_archived(root, config).await,
         "broca_consolidate" => handle_broca_consolidate(arguments, root, config).await,
-        name if name.starts_with("plugin_") => {
-            let plugin_name = &name["plugin_".len()..];
-            handle_plugin_call(plugin_name, arguments, root).await
-        }
+        name if name.starts_with("plugin_") => handle_plugin_call(name, arguments, root).await,
         _ => {
             return Ok(Some(JsonRpcMessage {
                 jsonrpc: "2.0".to_string(),
@@ -438,6 +458,34 @@ async fn handle_tools_call(
     }
 }
 
+/// The remember tool's `type` description, listing the configured
+/// `[memory.defaults.<type>]` so the model knows what it gets for free.
+fn entry_type_description(config: &Config) -> String {
+    let mut description = "Entry type (default: fact)".to_string();
+    let defaults: Vec<String> = config
+        .memory
+        .defaults
+        .iter()
+        .map(|(entry_type, defaults)| {
+            let mut parts = Vec::new();
+            if let Some(confidence) = defaults.confidence {
+                parts.push(format!("confidence {confidence}"));
+            }
+            if !defaults.tags.is_empty() {
+                parts.push(format!("tags {}", defaults.tags.join(", ")));
+            }
+            if let Some(days) = defaults.ttl_days {
+                parts.push(format!("ttl {days} days"));
+            }
+            format!("{entry_type}: {}", parts.join(", "))
+        })
+        .collect();
+    if !defaults.is_empty() {
+        description.push_str(&format!(". Defaults by type: {}", defaults.join("; ")));
+    }
+    description
+}
+
 async fn handle_broca_remember(
     arguments: &Value,
     root: &Path,
@@ -466,17 +514,34 @@ async fn handle_broca_remember(
         .and_then(|v| v.as_u64())
         .map(|v| v as u32);
     let valid_until = arguments.get("valid_until").and_then(|v| v.as_str());
+    let entry_type = arguments
+        .get("type")
+        .and_then(|v| v.as_str())
+        .unwrap_or("fact");
+    let confidence = arguments.get("confidence").and_then(|v| v.as_f64());
 
     let memory_dir = root.join(&config.memory.dir);
-    let entry_path = broca::remember_with_validity(
-        &memory_dir,
-        "fact",
-        title,
-        content,
-        &tags,
-        ttl_days,
-        valid_until,
-    )?;
+    let entry = runner::with_memory_defaults(
+        config,
+        broca::NewEntry {
+            entry_type: entry_type.to_string(),
+            title: title.to_string(),
+            content: content.to_string(),
+            tags,
+            ttl_days,
+            valid_until: valid_until.map(String::from),
+            confidence,
+        },
+    );
+    let entry_path = broca::remember_entry(&memory_dir, &entry)?;
+    let memory = runner::MemoryEvent::knowledge(
+        "mcp",
+        &entry_path,
+        &entry.entry_type,
+        &entry.title,
+        &entry.tags,
+    );
+    runner::on_remember(root, config, memory, None, None)?;
 
     Ok(format!(
         "Stored memory with ID: {}",
@@ -553,7 +618,10 @@ async fn handle_broca_journal(
         .ok_or("Missing content")?;
 
     let memory_dir = root.join(&config.memory.dir);
+    let previous = fs::read_to_string(broca::journal_path(&memory_dir)).ok();
     let entry_path = broca::journal(&memory_dir, content)?;
+    let memory = runner::MemoryEvent::journal("mcp", &entry_path);
+    runner::on_remember(root, config, memory, previous, None)?;
 
     Ok(format!(
         "Added journal entry to: {}",
@@ -908,65 +976,82 @@ async fn handle_broca_consolidate(
 // --- Plugin-as-MCP-tools ---
 
 /// Discover plugins in plugins/ and generate MCP tool definitions for each.
+///
+/// A plugin with a manifest gets its description from it and, when it
+/// declares arguments, a typed input schema; others take an `args` array.
 fn discover_plugin_tools(root: &Path) -> Vec<Value> {
-    let plugins_dir = root.join("plugins");
-    if !plugins_dir.exists() {
+    let Ok(plugins) = command_plugins::discover(&root.join("plugins")) else {
         return Vec::new();
-    }
-
-    let mut tools = Vec::new();
-    let entries = match fs::read_dir(&plugins_dir) {
-        Ok(e) => e,
-        Err(_) => return Vec::new(),
     };
 
-    let mut sorted_entries: Vec<_> = entries.flatten().collect();
-    sorted_entries.sort_by_key(|e| e.file_name());
-
-    for entry in sorted_entries {
-        let path = entry.path();
-        if !path.is_file() {
+    let mut tools = Vec::new();
+    for plugin in plugins.into_iter().flatten() {
+        let Some(script) = &plugin.script else {
             continue;
-        }
-
-        let name = match path.file_stem().and_then(|s| s.to_str()) {
-            Some(n) => n.to_string(),
-            None => continue,
         };
-
-        let content = match fs::read_to_string(&path) {
-            Ok(c) => c,
-            Err(_) => continue,
-        };
-
-        let description = content
-            .lines()
-            .find(|l| l.starts_with("# description:"))
-            .map(|l| l.trim_start_matches("# description:").trim().to_string())
+        let name = &plugin.name;
+        let description = plugin
+            .description()
             .unwrap_or_else(|| format!("Plugin: {}", name));
 
-        // Extract docstring for usage info
-        let usage = extract_docstring(&content);
-        let full_description = match usage {
-            Some(ref u) => format!("{}\n\n{}", description, u),
-            None => description,
+        let (full_description, input_schema) = match plugin.args() {
+            Some(specs) => {
+                let properties: serde_json::Map<String, Value> = specs
+                    .iter()
+                    .map(|spec| {
+                        let mut property = json!({ "type": spec.kind.as_str() });
+                        if let Some(description) = &spec.description {
+                            property["description"] = json!(description);
+                        }
+                        (spec.name.clone(), property)
+                    })
+                    .collect();
+                let required: Vec<&str> = specs
+                    .iter()
+                    .filter(|spec| spec.required)
+                    .map(|spec| spec.name.as_str())
+                    .collect();
+                (
+                    description,
+                    json!({
+                        "type": "object",
+                        "properties": properties,
+                        "required": required
+                    }),
+                )
+            }
+            None => {
+                // Extract docstring for usage info
+                let usage = fs::read_to_string(script)
+                    .ok()
+                    .filter(|_| plugin.manifest.is_none())
+                    .and_then(|content| extract_docstring(&content));
+                let full_description = match usage {
+                    Some(ref u) => format!("{}\n\n{}", description, u),
+                    None => description,
+                };
+                (
+                    full_description,
+                    json!({
+                        "type": "object",
+                        "properties": {
+                            "args": {
+                                "type": "array",
+                                "items": { "type": "string" },
+                                "description": "Arguments to pass to the plugin (e.g. subcommand and its args)"
+                            }
+                        },
+                        "required": ["args"]
+                    }),
+                )
+            }
         };
 
         tools.push(json!({
-            "name": format!("plugin_{}", name),
+            "name": plugin.tool_name(),
             "title": format!("Plugin: {}", name),
             "description": full_description,
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "args": {
-                        "type": "array",
-                        "items": { "type": "string" },
-                        "description": "Arguments to pass to the plugin (e.g. subcommand and its args)"
-                    }
-                },
-                "required": ["args"]
-            }
+            "inputSchema": input_schema
         }));
     }
 
@@ -1011,96 +1096,24 @@ fn extract_docstring(content: &str) -> Option<String> {
     }
 }
 
-/// Find a plugin script by name in the plugins/ directory.
-fn find_plugin(plugins_dir: &Path, name: &str) -> Option<PathBuf> {
-    if !plugins_dir.exists() {
-        return None;
-    }
-    let candidates = [
-        name.to_string(),
-        format!("{}.py", name),
-        format!("{}.sh", name),
-        format!("{}.rb", name),
-    ];
-    for candidate in &candidates {
-        let path = plugins_dir.join(candidate);
-        if path.is_file() {
-            return Some(path);
-        }
-    }
-    None
-}
-
-/// Detect interpreter from a script's shebang line.
-fn detect_plugin_interpreter(path: &Path) -> Option<(String, Option<String>)> {
-    let content = fs::read_to_string(path).ok()?;
-    let first_line = content.lines().next()?;
-    if !first_line.starts_with("#!") {
-        return None;
-    }
-    let shebang = first_line.trim_start_matches("#!").trim();
-    if shebang.starts_with("/usr/bin/env ") {
-        let interp = shebang.trim_start_matches("/usr/bin/env ").trim();
-        Some((interp.to_string(), None))
-    } else {
-        Some((shebang.to_string(), None))
-    }
-}
-
 /// Execute a plugin and return its output, with security validation.
 async fn handle_plugin_call(
-    plugin_name: &str,
+    tool_name: &str,
     arguments: &Value,
     root: &Path,
 ) -> Result<String, Box<dyn Error>> {
-    let plugins_dir = root.join("plugins");
-    let plugin_path = find_plugin(&plugins_dir, plugin_name)
-        .ok_or_else(|| format!("Plugin not found: {}", plugin_name))?;
-
-    let args: Vec<String> = arguments
-        .get("args")
-        .and_then(|v| v.as_array())
-        .map(|arr| {
-            arr.iter()
-                .filter_map(|v| v.as_str())
-                .map(|s| s.to_string())
-                .collect()
-        })
-        .unwrap_or_default();
-
-    let interpreter = detect_plugin_interpreter(&plugin_path);
-    let mut cmd = match interpreter {
-        Some((interp, arg)) => {
-            let mut c = process::Command::new(&interp);
-            if let Some(a) = arg {
-                c.arg(a);
-            }
-            c.arg(&plugin_path);
-            c
-        }
-        None => process::Command::new(&plugin_path),
-    };
-
-    cmd.args(&args)
-        .env("BOUCLE_ROOT", root)
-        .env("BOUCLE_PLUGINS", &plugins_dir)
-        .current_dir(root);
-
-    // Add memory dir if config is available
-    if let Ok(cfg) = crate::config::load(root) {
-        cmd.env("BOUCLE_MEMORY", root.join(&cfg.memory.dir));
-    }
-
-    let output = cmd.output()?;
-
-    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
-
-    if !output.status.success() {
+    let plugin = command_plugins::find_tool(&root.join("plugins"), tool_name)?;
+    let plugin_name = plugin.name.as_str();
+    let args = plugin.positional_args(arguments)?;
+    let output = plugin.output(root, &args)?;
+    let success = output.success();
+    let (stdout, stderr) = (output.stdout, output.stderr);
+
+    if !success {
         return Err(format!(
             "Plugin '{}' exited with status {}.\nstderr: {}",
             plugin_name,
-            output.status.code().unwrap_or(-1),
+            output.code.unwrap_or(-1),
             stderr
         )
         .into());
@@ -1110,7 +1123,7 @@ async fn handle_plugin_call(
     let (validated_output, warnings) = validate_external_content(&stdout, plugin_name);
 
     if !warnings.is_empty() {
-        eprintln!(
+        tracing::warn!(
             "Security warnings for plugin '{}': {}",
             plugin_name,
             warnings.join(", ")
@@ -3,12 +3,22 @@
 //! This module contains standard plugins that ship with Boucle,
 //! demonstrating the middleware pattern and providing core functionality.
 
+use crate::config::{self, Config};
 use crate::runner::plugins::*;
+use crate::runner::{self, remote};
+use reqwest::blocking::Client;
+use reqwest::header::{HeaderValue, AUTHORIZATION};
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
 use std::collections::HashMap;
-use std::process::Command;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::Duration;
 
 /// Safely truncate a string at a UTF-8 character boundary.
-fn truncate_utf8(s: &str, max_bytes: usize) -> &str {
+pub(crate) fn truncate_utf8(s: &str, max_bytes: usize) -> &str {
     if s.len() <= max_bytes {
         return s;
     }
@@ -20,9 +30,115 @@ fn truncate_utf8(s: &str, max_bytes: usize) -> &str {
     &s[..end]
 }
 
+/// `[secrets]` entry holding the Linear API key, if the agent has one.
+const LINEAR_SECRET: &str = "linear";
+
+/// `[secrets]` entry holding a GitHub token; it takes precedence over
+/// `context.github.token_env`.
+const GITHUB_SECRET: &str = "github";
+
+/// Linear's GraphQL endpoint.
+const LINEAR_API_URL: &str = "https://api.linear.app/graphql";
+
+/// Retries after a failed Linear request (connection errors, 429, 5xx).
+const LINEAR_RETRIES: u32 = 2;
+
+/// Wait before the first retry; each further retry waits one step longer.
+const LINEAR_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+const LINEAR_ISSUES_QUERY: &str = r#"query($id: ID!) {
+    issues(filter: {
+        delegate: { id: { eq: $id } },
+        state: { type: { nin: ["completed", "canceled"] } }
+    }) {
+        nodes {
+            identifier
+            title
+            state { name }
+            priorityLabel
+            description
+            comments(first: 20, orderBy: createdAt) {
+                nodes {
+                    body
+                    user { name email }
+                    botActor { name }
+                }
+            }
+        }
+    }
+}"#;
+
+#[derive(Deserialize)]
+struct GraphqlResponse<T> {
+    data: Option<T>,
+    #[serde(default)]
+    errors: Vec<GraphqlError>,
+}
+
+#[derive(Deserialize)]
+struct GraphqlError {
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct Nodes<T> {
+    nodes: Vec<T>,
+}
+
+#[derive(Deserialize)]
+struct ViewerData {
+    viewer: Viewer,
+}
+
+#[derive(Deserialize)]
+struct Viewer {
+    id: String,
+}
+
+#[derive(Deserialize)]
+struct IssuesData {
+    issues: Nodes<LinearIssue>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LinearIssue {
+    identifier: String,
+    title: String,
+    state: LinearState,
+    priority_label: Option<String>,
+    description: Option<String>,
+    comments: Nodes<LinearComment>,
+}
+
+#[derive(Deserialize)]
+struct LinearState {
+    name: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LinearComment {
+    body: String,
+    user: Option<LinearUser>,
+    bot_actor: Option<LinearBot>,
+}
+
+#[derive(Deserialize)]
+struct LinearUser {
+    name: String,
+    email: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct LinearBot {
+    name: Option<String>,
+}
+
 /// Linear issues plugin - fetches issues delegated to the agent.
 pub struct LinearIssuesPlugin {
     meta: PluginMeta,
+    endpoint: String,
 }
 
 impl LinearIssuesPlugin {
@@ -34,10 +150,17 @@ impl LinearIssuesPlugin {
                 .external(true) // Linear API content is external
                 .priority(10) // Run early to inform other plugins
                 .build(),
+            endpoint: LINEAR_API_URL.to_string(),
         }
     }
 
-    fn get_auth_token(&self, root: &std::path::Path) -> Result<String, PluginError> {
+    /// The `linear` secret if `[secrets]` has one, else what
+    /// `auth-linear.sh` prints.
+    fn get_auth_token(&self, root: &Path, config: &Config) -> Result<String, PluginError> {
+        if config.secrets.contains_key(LINEAR_SECRET) {
+            return config::resolve_secret(root, &config.secrets, "linear-issues", LINEAR_SECRET)
+                .map_err(|e| PluginError::ExecutionFailed(e.to_string()));
+        }
         let auth_script = root.join("auth-linear.sh");
         let output = Command::new("bash")
             .arg(&auth_script)
@@ -53,41 +176,179 @@ impl LinearIssuesPlugin {
             ));
         }
 
-        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
-    }
-
-    fn execute_graphql(&self, token: &str, query: &str) -> Result<serde_json::Value, PluginError> {
-        let query_json = serde_json::json!({"query": query});
-        let query_str = serde_json::to_string(&query_json).map_err(|e| {
-            PluginError::ExecutionFailed(format!("JSON serialization failed: {}", e))
-        })?;
-
-        let output = Command::new("curl")
-            .args([
-                "-s",
-                "-X",
-                "POST",
-                "-H",
-                "Content-Type: application/json",
-                "-H",
-                &format!("Authorization: Bearer {}", token),
-                "-d",
-                &query_str,
-                "https://api.linear.app/graphql",
-            ])
-            .output()
-            .map_err(|e| PluginError::ExecutionFailed(format!("GraphQL request failed: {}", e)))?;
-
-        if !output.status.success() {
+        let token = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        if token.is_empty() {
             return Err(PluginError::ExecutionFailed(
-                "GraphQL request returned error".to_string(),
+                "Auth script printed no token".to_string(),
             ));
         }
+        Ok(token)
+    }
+
+    /// Run a GraphQL query, retrying connection errors, 429, and 5xx
+    /// responses.
+    fn execute_graphql<T: DeserializeOwned>(
+        &self,
+        client: &Client,
+        token: &str,
+        query: &str,
+        variables: serde_json::Value,
+    ) -> Result<T, PluginError> {
+        let mut authorization = HeaderValue::from_str(&linear_authorization(token))
+            .map_err(|_| PluginError::ExecutionFailed("Invalid Linear token".to_string()))?;
+        authorization.set_sensitive(true);
+        let body = serde_json::json!({"query": query, "variables": variables});
+
+        let mut attempts = 0;
+        let response = loop {
+            attempts += 1;
+            let result = client
+                .post(&self.endpoint)
+                .header(AUTHORIZATION, authorization.clone())
+                .json(&body)
+                .send();
+            let transient = match &result {
+                Ok(response) => {
+                    response.status() == StatusCode::TOO_MANY_REQUESTS
+                        || response.status().is_server_error()
+                }
+                Err(e) => e.is_connect() || e.is_timeout(),
+            };
+            if !transient || attempts > LINEAR_RETRIES {
+                break result.map_err(|e| {
+                    PluginError::ExecutionFailed(format!("GraphQL request failed: {}", e))
+                })?;
+            }
+            thread::sleep(LINEAR_RETRY_DELAY * attempts);
+        };
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(PluginError::ExecutionFailed(format!(
+                "GraphQL request returned HTTP {}",
+                status
+            )));
+        }
+        let response: GraphqlResponse<T> = response
+            .json()
+            .map_err(|e| PluginError::ExecutionFailed(format!("JSON parsing failed: {}", e)))?;
+        if let Some(error) = response.errors.first() {
+            return Err(PluginError::ExecutionFailed(format!(
+                "GraphQL error: {}",
+                error.message
+            )));
+        }
+        response
+            .data
+            .ok_or_else(|| PluginError::ExecutionFailed("GraphQL response has no data".to_string()))
+    }
+
+    fn fetch_issues(
+        &self,
+        context: &PluginContext,
+        token: &str,
+    ) -> Result<Vec<LinearIssue>, PluginError> {
+        let timeout = config::parse_interval(&context.config.context.plugin_timeout)
+            .map(Duration::from_secs)
+            .map_err(|e| {
+                PluginError::InvalidConfiguration(format!("context.plugin_timeout: {e}"))
+            })?;
+        let client = Client::builder()
+            .timeout(timeout)
+            .user_agent(concat!("boucle/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .map_err(|e| PluginError::ExecutionFailed(format!("HTTP client: {}", e)))?;
+
+        let viewer: ViewerData =
+            self.execute_graphql(&client, token, "{ viewer { id } }", serde_json::json!({}))?;
+        let issues: IssuesData = self.execute_graphql(
+            &client,
+            token,
+            LINEAR_ISSUES_QUERY,
+            serde_json::json!({"id": viewer.viewer.id}),
+        )?;
+        Ok(issues.issues.nodes)
+    }
+}
+
+/// The `Authorization` value for `token`: Linear takes personal API keys
+/// as they are and OAuth access tokens as bearer tokens.
+fn linear_authorization(token: &str) -> String {
+    if token.starts_with("lin_api_") || token.starts_with("Bearer ") {
+        token.to_string()
+    } else {
+        format!("Bearer {}", token)
+    }
+}
+
+/// The plugin's section for `issues`.
+fn render_linear(issues: &[LinearIssue]) -> String {
+    let mut content = String::from("## Linear Issues (delegated to me)\n\n");
+
+    if issues.is_empty() {
+        content.push_str("(No issues delegated to me)");
+        return content;
+    }
+    for issue in issues {
+        let priority = issue.priority_label.as_deref().unwrap_or("No priority");
+        content.push_str(&format!(
+            "- [{}] {} ({}, {})\n",
+            issue.identifier, issue.title, issue.state.name, priority
+        ));
+
+        if let Some(description) = &issue.description {
+            let truncated = truncate_utf8(description, 500);
+            for line in truncated.split('\n') {
+                content.push_str(&format!("  {}\n", line));
+            }
+        }
+
+        let comments = &issue.comments.nodes;
+        if !comments.is_empty() {
+            content.push_str("  --- Comments ---\n");
+
+            // Separate comments by author: external (Thomas) vs agent (Boucle)
+            let mut external_comments = Vec::new();
+            let mut agent_comments = Vec::new();
+
+            for comment in comments {
+                let author = comment
+                    .user
+                    .as_ref()
+                    .map(|user| user.name.as_str())
+                    .or_else(|| comment.bot_actor.as_ref()?.name.as_deref())
+                    .unwrap_or("unknown");
+                let email = comment
+                    .user
+                    .as_ref()
+                    .and_then(|user| user.email.as_deref())
+                    .unwrap_or("");
+                let is_agent = author == "Boucle" || author == "boucle" || email.contains("boucle");
+
+                if is_agent {
+                    agent_comments.push((author, comment.body.as_str()));
+                } else {
+                    external_comments.push((author, comment.body.as_str()));
+                }
+            }
+
+            // Show ALL external (human) comments with generous limit
+            for (author, body) in &external_comments {
+                let truncated = truncate_utf8(body, 800);
+                content.push_str(&format!("  [{}]: {}\n", author, truncated));
+            }
+
+            // Show only most recent 3 agent comments, shorter
+            let agent_start = agent_comments.len().saturating_sub(3);
+            for (author, body) in &agent_comments[agent_start..] {
+                let truncated = truncate_utf8(body, 300);
+                content.push_str(&format!("  [{}]: {}\n", author, truncated));
+            }
+        }
 
-        let response_str = String::from_utf8_lossy(&output.stdout);
-        serde_json::from_str(&response_str)
-            .map_err(|e| PluginError::ExecutionFailed(format!("JSON parsing failed: {}", e)))
+        content.push('\n');
     }
+    content
 }
 
 impl ContextPlugin for LinearIssuesPlugin {
@@ -96,177 +357,282 @@ impl ContextPlugin for LinearIssuesPlugin {
     }
 
     fn execute(&self, context: &PluginContext) -> Result<PluginResult, PluginError> {
-        let warnings = Vec::new();
-
-        // Get authentication token
-        let token = match self.get_auth_token(context.root) {
-            Ok(t) => t,
+        let issues = match self
+            .get_auth_token(context.root, context.config)
+            .and_then(|token| self.fetch_issues(context, &token))
+        {
+            Ok(issues) => issues,
             Err(e) => {
                 return Ok(PluginResult {
                     content: format!(
                         "## Linear Issues (delegated to me)\n\n(Could not fetch Linear issues: {})",
                         e
                     ),
-                    warnings,
-                    metadata: HashMap::new(),
+                    ..PluginResult::default()
                 });
             }
         };
 
-        // Get current user ID
-        let viewer_query = "{ viewer { id } }";
-        let viewer_result = match self.execute_graphql(&token, viewer_query) {
-            Ok(r) => r,
-            Err(e) => {
-                return Ok(PluginResult {
-                    content: format!(
-                        "## Linear Issues (delegated to me)\n\n(Could not fetch viewer info: {})",
-                        e
-                    ),
-                    warnings,
-                    metadata: HashMap::new(),
-                });
+        let mut metadata = HashMap::new();
+        metadata.insert("issue_count".to_string(), issues.len().to_string());
+
+        Ok(PluginResult {
+            content: render_linear(&issues),
+            metadata,
+            ..PluginResult::default()
+        })
+    }
+
+    fn should_run(&self, context: &PluginContext) -> bool {
+        // Only run if there is a way to get a token
+        context.config.secrets.contains_key(LINEAR_SECRET)
+            || context.root.join("auth-linear.sh").exists()
+    }
+}
+
+/// GitHub plugin - fetches issues assigned to the agent and pull requests
+/// waiting for its review.
+pub struct GithubIssuesPlugin {
+    meta: PluginMeta,
+}
+
+impl GithubIssuesPlugin {
+    pub fn new() -> Self {
+        Self {
+            meta: PluginMetaBuilder::new("github-issues")
+                .description("Fetch GitHub issues and review requests for Boucle")
+                .version("1.0.0")
+                .external(true) // Issue and PR text is external
+                .priority(10) // Run early, like the Linear plugin
+                .build(),
+        }
+    }
+
+    /// Open issues or pull requests matching the search `query` in the
+    /// configured repositories, most recently updated first.
+    fn search(
+        &self,
+        context: &PluginContext,
+        query: &str,
+    ) -> Result<Vec<serde_json::Value>, PluginError> {
+        let github = &context.config.context.github;
+        let timeout = config::parse_interval(&context.config.context.plugin_timeout)
+            .map(Duration::from_secs)
+            .map_err(|e| {
+                PluginError::InvalidConfiguration(format!("context.plugin_timeout: {e}"))
+            })?;
+        let mut query = query.to_string();
+        for repo in &github.repos {
+            query.push_str(&format!(" repo:{repo}"));
+        }
+        let limit = github.limit.to_string();
+
+        let token = if context.config.secrets.contains_key(GITHUB_SECRET) {
+            let token = config::resolve_secret(
+                context.root,
+                &context.config.secrets,
+                "github-issues",
+                GITHUB_SECRET,
+            )
+            .map_err(|e| PluginError::ExecutionFailed(e.to_string()))?;
+            Some(token)
+        } else {
+            std::env::var(&github.token_env)
+                .ok()
+                .filter(|token| !token.trim().is_empty())
+        };
+        let body = match token {
+            Some(token) => {
+                let url = format!(
+                    "https://api.github.com/search/issues?q={}&sort=updated&per_page={limit}",
+                    encode_query(&query)
+                );
+                let headers = [
+                    format!("Authorization: Bearer {}", token.trim()),
+                    "Accept: application/vnd.github+json".to_string(),
+                ];
+                let response = remote::get(&url, &headers, timeout, None)
+                    .map_err(PluginError::ExecutionFailed)?;
+                if !(200..300).contains(&response.status) {
+                    return Err(PluginError::ExecutionFailed(format!(
+                        "HTTP {}",
+                        response.status
+                    )));
+                }
+                response.body
             }
+            _ => gh_search(context.root, &query, &limit, timeout)?,
         };
 
-        let my_id = viewer_result["data"]["viewer"]["id"]
-            .as_str()
-            .ok_or_else(|| PluginError::ExecutionFailed("Invalid viewer response".to_string()))?;
-
-        // Fetch delegated issues
-        let issues_query = format!(
-            r#"{{
-                issues(filter: {{
-                    delegate: {{ id: {{ eq: "{}" }} }},
-                    state: {{ type: {{ nin: ["completed", "canceled"] }} }}
-                }}) {{
-                    nodes {{
-                        identifier
-                        title
-                        state {{ name }}
-                        priority
-                        priorityLabel
-                        description
-                        comments(first: 20, orderBy: createdAt) {{
-                            nodes {{
-                                body
-                                createdAt
-                                user {{ name email }}
-                                botActor {{ name }}
-                            }}
-                        }}
-                    }}
-                }}
-            }}"#,
-            my_id
-        );
+        let mut result: serde_json::Value = serde_json::from_str(&body)
+            .map_err(|e| PluginError::ExecutionFailed(format!("JSON parsing failed: {}", e)))?;
+        match result["items"].take() {
+            serde_json::Value::Array(items) => Ok(items),
+            _ => Err(PluginError::ExecutionFailed(
+                "Invalid search response".to_string(),
+            )),
+        }
+    }
+}
 
-        let issues_result = match self.execute_graphql(&token, &issues_query) {
-            Ok(r) => r,
-            Err(e) => {
-                return Ok(PluginResult {
-                    content: format!(
-                        "## Linear Issues (delegated to me)\n\n(Could not fetch issues: {})",
-                        e
-                    ),
-                    warnings,
-                    metadata: HashMap::new(),
-                });
+/// Run a GitHub search through `gh api`, which uses the CLI's login.
+fn gh_search(
+    root: &Path,
+    query: &str,
+    limit: &str,
+    timeout: Duration,
+) -> Result<String, PluginError> {
+    if runner::find_on_path("gh").is_none() {
+        return Err(PluginError::ExecutionFailed(
+            "no token in the configured token_env and gh is not installed".to_string(),
+        ));
+    }
+    let mut cmd = Command::new("gh");
+    cmd.args(["api", "-X", "GET", "search/issues", "-f"])
+        .arg(format!("q={query}"))
+        .args(["-f", "sort=updated", "-f"])
+        .arg(format!("per_page={limit}"))
+        .current_dir(root)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    runner::configure_child_process_group(&mut cmd);
+    let child = cmd
+        .spawn()
+        .map_err(|e| PluginError::ExecutionFailed(format!("Failed to run gh: {}", e)))?;
+    let output = runner::wait_with_output_timeout(child, timeout)
+        .map_err(|e| PluginError::ExecutionFailed(e.to_string()))?;
+    if output.timed_out {
+        return Err(PluginError::ExecutionFailed(format!(
+            "gh timed out after {}s",
+            timeout.as_secs()
+        )));
+    }
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let detail = stderr.lines().rev().find(|l| !l.trim().is_empty());
+        return Err(PluginError::ExecutionFailed(
+            detail.unwrap_or("gh api failed").trim().to_string(),
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Percent-encode a query string value.
+fn encode_query(value: &str) -> String {
+    let mut encoded = String::new();
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
             }
-        };
+            b' ' => encoded.push('+'),
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
 
-        let nodes = issues_result["data"]["issues"]["nodes"]
-            .as_array()
-            .ok_or_else(|| PluginError::ExecutionFailed("Invalid issues response".to_string()))?;
+/// "owner/name#number" for a search result.
+fn github_reference(item: &serde_json::Value) -> String {
+    let repo = item["repository_url"]
+        .as_str()
+        .and_then(|url| url.split("/repos/").nth(1))
+        .unwrap_or("?");
+    format!("{}#{}", repo, item["number"].as_u64().unwrap_or_default())
+}
 
-        // Format output
-        let mut content = String::from("## Linear Issues (delegated to me)\n\n");
+/// The plugin's section for `issues` assigned to the agent and pull
+/// requests in `reviews`.
+fn render_github(issues: &[serde_json::Value], reviews: &[serde_json::Value]) -> String {
+    let mut content = String::from("## GitHub Issues (assigned to me)\n\n");
+    if issues.is_empty() {
+        content.push_str("(No issues assigned to me)\n");
+    }
+    for issue in issues {
+        let title = issue["title"].as_str().unwrap_or("No title");
+        let labels: Vec<&str> = issue["labels"]
+            .as_array()
+            .map(|labels| labels.iter().filter_map(|l| l["name"].as_str()).collect())
+            .unwrap_or_default();
+        content.push_str(&format!("- [{}] {}", github_reference(issue), title));
+        if !labels.is_empty() {
+            content.push_str(&format!(" ({})", labels.join(", ")));
+        }
+        content.push('\n');
+        if let Some(body) = issue["body"].as_str() {
+            for line in truncate_utf8(body, 500).lines() {
+                content.push_str(&format!("  {}\n", line));
+            }
+        }
+    }
 
-        if nodes.is_empty() {
-            content.push_str("(No issues delegated to me)");
+    content.push_str("\n## GitHub Pull Requests (review requested)\n\n");
+    if reviews.is_empty() {
+        content.push_str("(No review requests)\n");
+    }
+    for pr in reviews {
+        let title = pr["title"].as_str().unwrap_or("No title");
+        let author = pr["user"]["login"].as_str().unwrap_or("unknown");
+        let draft = if pr["draft"].as_bool() == Some(true) {
+            ", draft"
         } else {
-            for node in nodes {
-                let identifier = node["identifier"].as_str().unwrap_or("?");
-                let title = node["title"].as_str().unwrap_or("No title");
-                let state = node["state"]["name"].as_str().unwrap_or("Unknown");
-                let priority = node["priorityLabel"].as_str().unwrap_or("No priority");
-
-                content.push_str(&format!(
-                    "- [{}] {} ({}, {})\n",
-                    identifier, title, state, priority
-                ));
-
-                if let Some(description) = node["description"].as_str() {
-                    let truncated = truncate_utf8(description, 500);
-                    let desc_lines: Vec<&str> = truncated.split('\n').collect();
-                    for line in desc_lines {
-                        content.push_str(&format!("  {}\n", line));
-                    }
-                }
+            ""
+        };
+        content.push_str(&format!(
+            "- [{}] {} (by @{}{})\n",
+            github_reference(pr),
+            title,
+            author,
+            draft
+        ));
+    }
+    content
+}
 
-                if let Some(comments) = node["comments"]["nodes"].as_array() {
-                    if !comments.is_empty() {
-                        content.push_str("  --- Comments ---\n");
-
-                        // Separate comments by author: external (Thomas) vs agent (Boucle)
-                        let mut external_comments = Vec::new();
-                        let mut agent_comments = Vec::new();
-
-                        for comment in comments {
-                            let author = comment["user"]["name"]
-                                .as_str()
-                                .or_else(|| comment["botActor"]["name"].as_str())
-                                .unwrap_or("unknown");
-                            let email = comment["user"]["email"].as_str().unwrap_or("");
-                            let body = comment["body"].as_str().unwrap_or("");
-                            let is_agent = author == "Boucle"
-                                || author == "boucle"
-                                || email.contains("boucle");
-
-                            if is_agent {
-                                agent_comments.push((author, body));
-                            } else {
-                                external_comments.push((author, body));
-                            }
-                        }
-
-                        // Show ALL external (human) comments with generous limit
-                        for (author, body) in &external_comments {
-                            let truncated = truncate_utf8(body, 800);
-                            content.push_str(&format!("  [{}]: {}\n", author, truncated));
-                        }
-
-                        // Show only most recent 3 agent comments, shorter
-                        let agent_start = agent_comments.len().saturating_sub(3);
-                        for (author, body) in &agent_comments[agent_start..] {
-                            let truncated = truncate_utf8(body, 300);
-                            content.push_str(&format!("  [{}]: {}\n", author, truncated));
-                        }
-                    }
-                }
+impl ContextPlugin for GithubIssuesPlugin {
+    fn meta(&self) -> &PluginMeta {
+        &self.meta
+    }
 
-                content.push('\n');
+    fn execute(&self, context: &PluginContext) -> Result<PluginResult, PluginError> {
+        let fetched = self
+            .search(context, "is:open is:issue assignee:@me")
+            .and_then(|issues| {
+                let reviews = self.search(context, "is:open is:pr review-requested:@me")?;
+                Ok((issues, reviews))
+            });
+        let (issues, reviews) = match fetched {
+            Ok(fetched) => fetched,
+            Err(e) => {
+                return Ok(PluginResult {
+                    content: format!(
+                        "## GitHub Issues (assigned to me)\n\n(Could not fetch GitHub issues: {})",
+                        e
+                    ),
+                    ..PluginResult::default()
+                });
             }
-        }
+        };
 
         let mut metadata = HashMap::new();
-        metadata.insert("issue_count".to_string(), nodes.len().to_string());
+        metadata.insert("issue_count".to_string(), issues.len().to_string());
+        metadata.insert("review_count".to_string(), reviews.len().to_string());
 
         Ok(PluginResult {
-            content,
-            warnings,
+            content: render_github(&issues, &reviews),
             metadata,
+            ..PluginResult::default()
         })
     }
 
     fn should_run(&self, context: &PluginContext) -> bool {
-        // Only run if auth script exists
-        context.root.join("auth-linear.sh").exists()
+        context.config.context.github.enabled
     }
 }
 
 /// System status plugin - provides basic system information.
-// Kept although unregistered (context::assemble renders System Status
+// Kept although unregistered (context::assemble_for_run renders System Status
 // itself); available for explicit registration and exercised by tests,
 // which plain `cargo clippy` does not compile.
 #[allow(dead_code)]
@@ -322,12 +688,7 @@ impl ContextPlugin for SystemStatusPlugin {
         }
 
         // Git status
-        if let Ok(output) = Command::new("git")
-            .args(["status", "--porcelain"])
-            .current_dir(context.root)
-            .output()
-        {
-            let changes = String::from_utf8_lossy(&output.stdout);
+        if let Ok(changes) = runner::git::status(context.root) {
             let count = changes.lines().filter(|l| !l.is_empty()).count();
             content.push_str(&format!("- Git uncommitted changes: {}\n", count));
         }
@@ -337,21 +698,24 @@ impl ContextPlugin for SystemStatusPlugin {
 
         Ok(PluginResult {
             content,
-            warnings: Vec::new(),
             metadata,
+            ..PluginResult::default()
         })
     }
 }
 
 /// Create and return all built-in plugins.
 pub fn create_builtin_plugins() -> Vec<Box<dyn ContextPlugin>> {
-    // SystemStatusPlugin is deliberately NOT registered: context::assemble
+    // SystemStatusPlugin is deliberately NOT registered: context::assemble_for_run
     // already renders its own "## System Status [TRUSTED SYSTEM DATA]"
     // section, so registering the plugin duplicated the section in every
     // prompt — and the plugin copy reported a hardcoded "Loop iteration: 0"
     // (assemble is always called with iteration 0 in production). The plugin
     // type stays available for explicit registration and tests.
-    vec![Box::new(LinearIssuesPlugin::new())]
+    vec![
+        Box::new(LinearIssuesPlugin::new()),
+        Box::new(GithubIssuesPlugin::new()),
+    ]
 }
 
 #[cfg(test)]
@@ -376,6 +740,7 @@ mod tests {
             config: &cfg,
             iteration: 5,
             data: HashMap::new(),
+            flags: Default::default(),
         };
 
         let result = plugin.execute(&context).unwrap();
@@ -397,15 +762,167 @@ mod tests {
             config: &cfg,
             iteration: 1,
             data: HashMap::new(),
+            flags: Default::default(),
         };
 
         // Should not run if auth script doesn't exist
         assert!(!plugin.should_run(&context));
     }
 
+    /// Serve `responses` (JSON bodies, or a bare status for an error) in
+    /// order, sending each request back.
+    fn serve(responses: Vec<&'static str>) -> (String, std::sync::mpsc::Receiver<String>) {
+        use std::io::{BufRead, BufReader, Read, Write};
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/graphql", listener.local_addr().unwrap());
+        let (tx, rx) = std::sync::mpsc::channel();
+        thread::spawn(move || {
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request = String::new();
+                let mut length = 0;
+                loop {
+                    let mut line = String::new();
+                    if reader.read_line(&mut line).unwrap() == 0 || line == "\r\n" {
+                        break;
+                    }
+                    if let Some(value) = line.to_lowercase().strip_prefix("content-length:") {
+                        length = value.trim().parse().unwrap();
+                    }
+                    request.push_str(&line.to_lowercase());
+                }
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+                request.push_str(&String::from_utf8_lossy(&body));
+                let (status, body) = match response.parse::<u16>() {
+                    Ok(status) => (status, ""),
+                    Err(_) => (200, response),
+                };
+                write!(
+                    stream,
+                    "HTTP/1.1 {status} X\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                )
+                .unwrap();
+                tx.send(request).unwrap();
+            }
+        });
+        (url, rx)
+    }
+
+    #[test]
+    fn test_linear_plugin_fetches_delegated_issues() {
+        let dir = tempfile::tempdir().unwrap();
+        runner::init(dir.path(), "test-agent").unwrap();
+        std::fs::write(dir.path().join("auth-linear.sh"), "echo lin_api_test\n").unwrap();
+        let cfg = config::load(dir.path()).unwrap();
+        let (url, requests) = serve(vec![
+            "503",
+            r#"{"data": {"viewer": {"id": "user-1"}}}"#,
+            r#"{"data": {"issues": {"nodes": [{
+                "identifier": "ENG-7",
+                "title": "Ship it",
+                "state": {"name": "In Progress"},
+                "priorityLabel": "High",
+                "description": "Do the thing",
+                "comments": {"nodes": [
+                    {"body": "Please hurry", "user": {"name": "Thomas", "email": "t@example.com"}},
+                    {"body": "On it", "user": null, "botActor": {"name": "Boucle"}}
+                ]}
+            }]}}}"#,
+        ]);
+        let plugin = LinearIssuesPlugin {
+            endpoint: url,
+            ..LinearIssuesPlugin::new()
+        };
+        let context = PluginContext {
+            root: dir.path(),
+            config: &cfg,
+            iteration: 1,
+            data: HashMap::new(),
+            flags: Default::default(),
+        };
+
+        let result = plugin.execute(&context).unwrap();
+        assert_eq!(
+            result.content,
+            "## Linear Issues (delegated to me)\n\n\
+             - [ENG-7] Ship it (In Progress, High)\n  Do the thing\n  --- Comments ---\n  \
+             [Thomas]: Please hurry\n  [Boucle]: On it\n\n"
+        );
+        assert_eq!(result.metadata["issue_count"], "1");
+
+        requests.recv().unwrap();
+        let viewer = requests.recv().unwrap();
+        assert!(
+            viewer.contains("authorization: lin_api_test\r\n"),
+            "{viewer}"
+        );
+        let issues = requests.recv().unwrap();
+        assert!(
+            issues.contains(r#""variables":{"id":"user-1"}"#),
+            "{issues}"
+        );
+        assert_eq!(linear_authorization("oauth-token"), "Bearer oauth-token");
+    }
+
+    #[test]
+    fn test_github_plugin_is_enabled_in_config() {
+        let dir = tempfile::tempdir().unwrap();
+        runner::init(dir.path(), "test-agent").unwrap();
+        let plugin = GithubIssuesPlugin::new();
+        let should_run = || {
+            let cfg = config::load(dir.path()).unwrap();
+            let context = PluginContext {
+                root: dir.path(),
+                config: &cfg,
+                iteration: 1,
+                data: HashMap::new(),
+                flags: Default::default(),
+            };
+            plugin.should_run(&context)
+        };
+
+        assert!(!should_run());
+        let mut toml = std::fs::read_to_string(dir.path().join("boucle.toml")).unwrap();
+        toml.push_str("\n[context.github]\nenabled = true\nrepos = [\"acme/app\"]\n");
+        std::fs::write(dir.path().join("boucle.toml"), toml).unwrap();
+        assert!(should_run());
+    }
+
+    #[test]
+    fn test_render_github() {
+        let issues: Vec<serde_json::Value> = vec![serde_json::json!({
+            "number": 12,
+            "title": "Crash on start",
+            "repository_url": "https://api.github.com/repos/acme/app",
+            "labels": [{"name": "bug"}, {"name": "p1"}],
+            "body": "Steps:\n1. run it",
+        })];
+        let reviews = vec![serde_json::json!({
+            "number": 34,
+            "title": "Fix the crash",
+            "repository_url": "https://api.github.com/repos/acme/app",
+            "user": {"login": "octo"},
+            "draft": true,
+        })];
+
+        let content = render_github(&issues, &reviews);
+        assert!(
+            content.contains("- [acme/app#12] Crash on start (bug, p1)\n  Steps:\n  1. run it\n")
+        );
+        assert!(content.contains("- [acme/app#34] Fix the crash (by @octo, draft)\n"));
+
+        let empty = render_github(&[], &[]);
+        assert!(empty.contains("(No issues assigned to me)"));
+        assert!(empty.contains("(No review requests)"));
+        assert_eq!(encode_query("is:open repo:a/b"), "is%3Aopen+repo%3Aa%2Fb");
+    }
+
     #[test]
     fn test_system_status_plugin_still_constructible() {
-        // Unregistered by default (context::assemble renders System Status
+        // Unregistered by default (context::assemble_for_run renders System Status
         // itself) but kept for explicit registration.
         let plugin = SystemStatusPlugin::new();
         assert_eq!(plugin.meta().name, "system-status");
@@ -414,11 +931,12 @@ mod tests {
     #[test]
     fn test_create_builtin_plugins() {
         let plugins = create_builtin_plugins();
-        assert_eq!(plugins.len(), 1);
+        assert_eq!(plugins.len(), 2);
 
         let names: Vec<&str> = plugins.iter().map(|p| p.meta().name.as_str()).collect();
         assert!(names.contains(&"linear-issues"));
-        // system-status is intentionally unregistered: context::assemble
+        assert!(names.contains(&"github-issues"));
+        // system-status is intentionally unregistered: context::assemble_for_run
         // renders its own System Status section (see create_builtin_plugins).
         assert!(!names.contains(&"system-status"));
     }
@@ -0,0 +1,1433 @@
+//! Command plugins: the scripts in `plugins/` that run as `boucle <name>`
+//! and as `plugin_<name>` MCP tools.
+//!
+//! A plugin may ship a `plugins/<name>.toml` manifest declaring its
+//! description, version, required environment variables, arguments,
+//! timeout, and output cache TTL. Without one, the description comes from the script's
+//! `# description:` comment and arguments are passed through unchecked.
+//!
+//! Plugins can be grouped one level deep: `plugins/github/issues.py` is the
+//! plugin `github/issues`, run as `boucle github issues` and offered as the
+//! `plugin_github_issues` tool.
+//!
+//! `context.d/` scripts can have the same kind of manifest next to them;
+//! there `after` orders them among the context plugins.
+
+use crate::config;
+use crate::runner::context_cache;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use std::{fmt, fs, io, process, thread};
+
+/// Extensions tried, in order, after the bare name.
+const SCRIPT_EXTENSIONS: &[&str] = &["py", "sh", "rb", "ps1", "cmd", "bat"];
+
+/// Cached output of command plugins, one file per plugin and arguments.
+const CACHE_DIR: &str = ".boucle-cache/plugins";
+
+/// Concurrency slots held by running command plugins.
+const SLOTS_DIR: &str = ".boucle-cache/plugin-slots";
+
+/// How long a slot file may stay empty while its PID is being written.
+const SLOT_CLAIM_GRACE: Duration = Duration::from_secs(10);
+
+/// Directory of `plugins/` holding smoke tests rather than a namespace.
+const TESTS_DIR: &str = "tests";
+
+/// A `plugins/<name>.toml` (or `context.d/<name>.toml`) manifest.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub version: Option<String>,
+    /// Environment variables the plugin needs; it is not started without them.
+    #[serde(default)]
+    pub env: Vec<String>,
+    /// Positional arguments, in order. Unset means any arguments are passed
+    /// through; an empty list means the plugin takes none.
+    #[serde(default)]
+    pub args: Option<Vec<ArgSpec>>,
+    /// Kill the plugin after this long, e.g. "30s".
+    #[serde(default)]
+    pub timeout: Option<String>,
+    /// Reuse the output of a successful run with the same arguments and
+    /// settings for this long, e.g. "10m".
+    #[serde(default)]
+    pub cache_ttl: Option<String>,
+    /// Context plugins that must run before this one.
+    #[serde(default)]
+    pub after: Vec<String>,
+    /// Programs that must be on `PATH`; the plugin is not started without them.
+    #[serde(default)]
+    pub requires: Vec<String>,
+    /// Also send the `[plugins.<name>]` table from boucle.toml as JSON on
+    /// stdin; it is always in `BOUCLE_PLUGIN_*` variables.
+    #[serde(default)]
+    pub config_on_stdin: bool,
+    /// Run the plugin with a clean environment and a read-only agent root.
+    #[serde(default)]
+    pub sandbox: Option<config::SandboxConfig>,
+}
+
+/// One positional argument in a manifest.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ArgSpec {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default, rename = "type")]
+    pub kind: ArgType,
+    #[serde(default = "default_arg_required")]
+    pub required: bool,
+}
+
+fn default_arg_required() -> bool {
+    true
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ArgType {
+    #[default]
+    String,
+    Integer,
+    Number,
+    Boolean,
+}
+
+impl ArgType {
+    /// The JSON Schema type name.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ArgType::String => "string",
+            ArgType::Integer => "integer",
+            ArgType::Number => "number",
+            ArgType::Boolean => "boolean",
+        }
+    }
+
+    fn accepts(self, value: &str) -> bool {
+        match self {
+            ArgType::String => true,
+            ArgType::Integer => value.parse::<i64>().is_ok(),
+            ArgType::Number => value.parse::<f64>().is_ok_and(f64::is_finite),
+            ArgType::Boolean => value == "true" || value == "false",
+        }
+    }
+}
+
+impl Manifest {
+    /// Parse and check a manifest.
+    pub fn parse(text: &str) -> Result<Manifest, String> {
+        let manifest: Manifest = toml::from_str(text).map_err(|e| e.message().to_string())?;
+        if let Some(timeout) = &manifest.timeout {
+            config::parse_interval(timeout).map_err(|e| format!("timeout '{timeout}': {e}"))?;
+        }
+        if let Some(ttl) = &manifest.cache_ttl {
+            config::parse_interval(ttl).map_err(|e| format!("cache_ttl '{ttl}': {e}"))?;
+        }
+        let args = manifest.args.as_deref().unwrap_or_default();
+        for (i, arg) in args.iter().enumerate() {
+            if arg.name.trim().is_empty() {
+                return Err(format!("args[{i}] has an empty name"));
+            }
+            if args[..i].iter().any(|a| a.name == arg.name) {
+                return Err(format!("argument '{}' is declared twice", arg.name));
+            }
+            if arg.required && args[..i].iter().any(|a| !a.required) {
+                return Err(format!(
+                    "required argument '{}' follows an optional one",
+                    arg.name
+                ));
+            }
+        }
+        if manifest
+            .after
+            .iter()
+            .chain(&manifest.requires)
+            .any(|n| n.trim().is_empty())
+        {
+            return Err("after and requires cannot list an empty name".to_string());
+        }
+        Ok(manifest)
+    }
+
+    /// Required environment variables that are unset or empty.
+    pub fn missing_env(&self) -> Vec<String> {
+        self.env
+            .iter()
+            .filter(|var| std::env::var_os(var).is_none_or(|v| v.is_empty()))
+            .cloned()
+            .collect()
+    }
+
+    /// Required programs that are not on `PATH`.
+    pub fn missing_requirements(&self) -> Vec<String> {
+        self.requires
+            .iter()
+            .filter(|program| super::find_on_path(program).is_none())
+            .cloned()
+            .collect()
+    }
+
+    /// Why the plugin cannot start here, if it cannot.
+    pub fn unmet(&self) -> Option<String> {
+        let mut reasons = Vec::new();
+        let missing = self.missing_env();
+        if !missing.is_empty() {
+            reasons.push(format!(
+                "needs environment variable(s): {}",
+                missing.join(", ")
+            ));
+        }
+        let missing = self.missing_requirements();
+        if !missing.is_empty() {
+            reasons.push(format!(
+                "requires {}, not found on PATH",
+                missing.join(", ")
+            ));
+        }
+        (!reasons.is_empty()).then(|| reasons.join("; "))
+    }
+}
+
+/// Load `<stem>.toml` from `dir`, if there is one.
+pub fn load_manifest(dir: &Path, stem: &str) -> Result<Option<Manifest>, String> {
+    match fs::read_to_string(dir.join(format!("{stem}.toml"))) {
+        Ok(text) => Manifest::parse(&text).map(Some),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// A plugin script and its manifest, if it has one.
+#[derive(Debug)]
+pub struct Plugin {
+    pub name: String,
+    /// The script; `None` when only a manifest exists.
+    pub script: Option<PathBuf>,
+    pub manifest: Option<Manifest>,
+}
+
+/// Why a plugin could not be loaded or started.
+#[derive(Debug)]
+pub enum PluginError {
+    NotFound(String),
+    Manifest(String, String),
+    Unmet(String, String),
+    Settings(String, String),
+    Sandbox(String, String),
+    Args(String, String),
+    Io(String, io::Error),
+    TimedOut(String, u64),
+    OutputLimit(String, usize),
+    Busy(String, usize),
+}
+
+impl fmt::Display for PluginError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PluginError::NotFound(name) => write!(f, "plugin '{name}' not found"),
+            PluginError::Manifest(name, e) => write!(f, "plugins/{name}.toml: {e}"),
+            PluginError::Unmet(name, reason) => write!(f, "plugin '{name}' {reason}"),
+            PluginError::Args(name, e) | PluginError::Settings(name, e) => {
+                write!(f, "plugin '{name}': {e}")
+            }
+            PluginError::Sandbox(name, e) => write!(f, "plugin '{name}' sandbox: {e}"),
+            PluginError::Io(name, e) => write!(f, "error running plugin '{name}': {e}"),
+            PluginError::TimedOut(name, secs) => {
+                write!(f, "plugin '{name}' timed out after {secs}s")
+            }
+            PluginError::OutputLimit(name, bytes) => {
+                write!(
+                    f,
+                    "plugin '{name}' stopped after printing more than {bytes} bytes"
+                )
+            }
+            PluginError::Busy(name, max) => write!(
+                f,
+                "plugin '{name}' not started: {max} plugins are already running \
+                 (plugin_limits.max_concurrent)"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PluginError {}
+
+/// What a plugin printed, for callers that capture its output.
+pub struct PluginOutput {
+    /// Exit code; `None` when the plugin was killed by a signal.
+    pub code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl PluginOutput {
+    pub fn success(&self) -> bool {
+        self.code == Some(0)
+    }
+}
+
+/// Every plugin in `plugins_dir` and its namespaces, sorted by name. A
+/// manifest that does not parse is returned as an error for that plugin;
+/// files that are neither a runnable script nor a manifest are skipped.
+pub fn discover(plugins_dir: &Path) -> io::Result<Vec<Result<Plugin, PluginError>>> {
+    let mut names = file_stems(plugins_dir)?;
+    for entry in fs::read_dir(plugins_dir)?.flatten() {
+        let namespace = entry.file_name().to_string_lossy().to_string();
+        if entry.path().is_dir() && is_namespace(&namespace) {
+            let stems = file_stems(&entry.path())?;
+            names.extend(stems.into_iter().map(|stem| format!("{namespace}/{stem}")));
+        }
+    }
+    names.sort();
+    names.dedup();
+    Ok(names
+        .into_iter()
+        .map(|name| load(plugins_dir, &name))
+        .filter(|plugin| !matches!(plugin, Err(PluginError::NotFound(_))))
+        .collect())
+}
+
+/// Stems of the files directly in `dir`.
+fn file_stems(dir: &Path) -> io::Result<Vec<String>> {
+    let mut stems = Vec::new();
+    for entry in fs::read_dir(dir)?.flatten() {
+        let path = entry.path();
+        if let (true, Some(stem)) = (path.is_file(), path.file_stem().and_then(|s| s.to_str())) {
+            stems.push(stem.to_string());
+        }
+    }
+    Ok(stems)
+}
+
+fn is_namespace(name: &str) -> bool {
+    name != TESTS_DIR && check_name(name).is_ok()
+}
+
+/// Find the plugin called `name`.
+pub fn find(plugins_dir: &Path, name: &str) -> Result<Plugin, PluginError> {
+    if check_name(name).is_err() {
+        return Err(PluginError::NotFound(name.to_string()));
+    }
+    let plugin = load(plugins_dir, name)?;
+    if plugin.script.is_none() {
+        return Err(PluginError::NotFound(name.to_string()));
+    }
+    Ok(plugin)
+}
+
+/// The plugin a command line names, and how many of `words` name it:
+/// `github issues` for `plugins/github/issues.py`, else the first word.
+pub fn resolve(plugins_dir: &Path, words: &[String]) -> Result<(Plugin, usize), PluginError> {
+    if let [namespace, name, ..] = words {
+        match find(plugins_dir, &format!("{namespace}/{name}")) {
+            Err(PluginError::NotFound(_)) => {}
+            found => return found.map(|plugin| (plugin, 2)),
+        }
+    }
+    let first = words.first().map(String::as_str).unwrap_or_default();
+    find(plugins_dir, first).map(|plugin| (plugin, 1))
+}
+
+/// The plugin offered as MCP tool `tool` (see [`Plugin::tool_name`]).
+pub fn find_tool(plugins_dir: &Path, tool: &str) -> Result<Plugin, PluginError> {
+    let name = tool.strip_prefix("plugin_").unwrap_or(tool);
+    match find(plugins_dir, name) {
+        Err(PluginError::NotFound(_)) => {}
+        found => return found,
+    }
+    discover(plugins_dir)
+        .unwrap_or_default()
+        .into_iter()
+        .flatten()
+        .find(|plugin| plugin.script.is_some() && plugin.tool_name() == tool)
+        .ok_or_else(|| PluginError::NotFound(name.to_string()))
+}
+
+/// The plugins in namespace `namespace`, for suggesting what to run.
+pub fn namespace_plugins(plugins_dir: &Path, namespace: &str) -> Vec<Plugin> {
+    if !is_namespace(namespace) || !plugins_dir.join(namespace).is_dir() {
+        return Vec::new();
+    }
+    let prefix = format!("{namespace}/");
+    discover(plugins_dir)
+        .unwrap_or_default()
+        .into_iter()
+        .flatten()
+        .filter(|plugin| plugin.name.starts_with(&prefix) && plugin.script.is_some())
+        .collect()
+}
+
+fn load(plugins_dir: &Path, name: &str) -> Result<Plugin, PluginError> {
+    let manifest =
+        load_manifest(plugins_dir, name).map_err(|e| PluginError::Manifest(name.to_string(), e))?;
+    let script = std::iter::once(name.to_string())
+        .chain(SCRIPT_EXTENSIONS.iter().map(|ext| format!("{name}.{ext}")))
+        .map(|candidate| plugins_dir.join(candidate))
+        .find(|path| path.is_file());
+    if script.is_none() && manifest.is_none() {
+        return Err(PluginError::NotFound(name.to_string()));
+    }
+    Ok(Plugin {
+        name: name.to_string(),
+        script,
+        manifest,
+    })
+}
+
+impl Plugin {
+    /// How the plugin is run: `github issues` for `github/issues`.
+    pub fn command_name(&self) -> String {
+        self.name.replace('/', " ")
+    }
+
+    /// The plugin's MCP tool: `plugin_github_issues` for `github/issues`.
+    pub fn tool_name(&self) -> String {
+        format!("plugin_{}", self.name.replace('/', "_"))
+    }
+
+    /// The manifest description, or the script's `# description:` line.
+    pub fn description(&self) -> Option<String> {
+        if let Some(description) = self.manifest.as_ref().and_then(|m| m.description.clone()) {
+            return Some(description);
+        }
+        let content = fs::read_to_string(self.script.as_ref()?).ok()?;
+        content
+            .lines()
+            .find_map(|l| l.strip_prefix("# description:"))
+            .map(|d| d.trim().to_string())
+    }
+
+    pub fn version(&self) -> Option<&str> {
+        self.manifest.as_ref()?.version.as_deref()
+    }
+
+    /// What `boucle plugins --json` reports: where the plugin lives, how it
+    /// runs, its manifest as written, and its MCP tool (plugins without a
+    /// script are not exposed).
+    pub fn describe(&self, plugins_dir: &Path) -> serde_json::Value {
+        let display = |path: &Path| path.display().to_string();
+        let manifest_path = self
+            .manifest
+            .as_ref()
+            .map(|_| display(&plugins_dir.join(format!("{}.toml", self.name))));
+        serde_json::json!({
+            "name": self.name,
+            "command": self.command_name(),
+            "path": self.script.as_deref().map(display),
+            "manifest_path": manifest_path,
+            "interpreter": self.script.as_deref().and_then(super::script_interpreter),
+            "description": self.description(),
+            "version": self.version(),
+            "manifest": self.manifest,
+            "missing": self.missing(),
+            "mcp_tool": self.script.as_ref().map(|_| self.tool_name()),
+        })
+    }
+
+    /// Declared arguments, if the manifest lists them.
+    pub fn args(&self) -> Option<&[ArgSpec]> {
+        self.manifest.as_ref()?.args.as_deref()
+    }
+
+    /// Required environment variables and programs that are missing.
+    pub fn missing(&self) -> Vec<String> {
+        let Some(manifest) = &self.manifest else {
+            return Vec::new();
+        };
+        let mut missing = manifest.missing_env();
+        missing.extend(manifest.missing_requirements());
+        missing
+    }
+
+    /// The manifest's `timeout`, or else `[plugin_limits] timeout`.
+    fn timeout(&self, limits: &config::PluginLimitsConfig) -> Result<Duration, PluginError> {
+        let manifest = self.manifest.as_ref().and_then(|m| m.timeout.as_deref());
+        if let Some(Ok(secs)) = manifest.map(config::parse_interval) {
+            return Ok(Duration::from_secs(secs));
+        }
+        config::parse_interval(&limits.timeout)
+            .map(Duration::from_secs)
+            .map_err(|e| {
+                PluginError::Settings(self.name.clone(), format!("plugin_limits.timeout: {e}"))
+            })
+    }
+
+    fn cache_ttl(&self) -> Option<Duration> {
+        let ttl = self.manifest.as_ref()?.cache_ttl.as_deref()?;
+        config::parse_interval(ttl).ok().map(Duration::from_secs)
+    }
+
+    /// The cache entry for a run with `args`, and a fingerprint of the
+    /// arguments and `[plugins.<name>]` settings it was made with.
+    fn cache_entry(
+        &self,
+        root: &Path,
+        cfg: Option<&config::Config>,
+        args: &[String],
+    ) -> (PathBuf, u64) {
+        let settings = cfg
+            .and_then(|cfg| cfg.plugins.get(&self.name).map(|t| t.to_string()))
+            .unwrap_or_default();
+        let parts: Vec<&str> = args
+            .iter()
+            .map(String::as_str)
+            .chain(["\0settings", &settings])
+            .collect();
+        let fingerprint = context_cache::content_hash(&parts);
+        let args_hash =
+            context_cache::content_hash(&args.iter().map(String::as_str).collect::<Vec<_>>());
+        let path = root
+            .join(CACHE_DIR)
+            .join(format!("{}-{args_hash:016x}.json", self.name));
+        (path, fingerprint)
+    }
+
+    /// Check `args` against the declared arguments: count and types.
+    pub fn check_args(&self, args: &[String]) -> Result<(), PluginError> {
+        let Some(specs) = self.args() else {
+            return Ok(());
+        };
+        let fail = |e: String| Err(PluginError::Args(self.name.clone(), e));
+        let required = specs.iter().filter(|s| s.required).count();
+        if args.len() < required {
+            let missing: Vec<_> = specs[args.len()..required]
+                .iter()
+                .map(|s| s.name.as_str())
+                .collect();
+            return fail(format!("missing argument(s): {}", missing.join(", ")));
+        }
+        if args.len() > specs.len() {
+            return fail(format!(
+                "takes at most {} argument(s), got {}",
+                specs.len(),
+                args.len()
+            ));
+        }
+        for (spec, value) in specs.iter().zip(args) {
+            if !spec.kind.accepts(value) {
+                return fail(format!(
+                    "argument '{}' must be {}, got '{value}'",
+                    spec.name,
+                    spec.kind.as_str()
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Turn MCP tool arguments into the positional list the script takes:
+    /// the declared arguments by name, or the `args` array without a
+    /// manifest. An optional argument can only be left out if every later
+    /// one is too.
+    pub fn positional_args(
+        &self,
+        arguments: &serde_json::Value,
+    ) -> Result<Vec<String>, PluginError> {
+        let Some(specs) = self.args() else {
+            return Ok(arguments
+                .get("args")
+                .and_then(|v| v.as_array())
+                .map(|arr| {
+                    arr.iter()
+                        .filter_map(|v| v.as_str())
+                        .map(|s| s.to_string())
+                        .collect()
+                })
+                .unwrap_or_default());
+        };
+        let fail = |e: String| Err(PluginError::Args(self.name.clone(), e));
+        let mut args = Vec::new();
+        let mut skipped: Option<&str> = None;
+        for spec in specs {
+            let value = match arguments.get(&spec.name) {
+                None | Some(serde_json::Value::Null) => {
+                    skipped.get_or_insert(&spec.name);
+                    continue;
+                }
+                Some(serde_json::Value::String(s)) => s.clone(),
+                Some(serde_json::Value::Number(n)) => n.to_string(),
+                Some(serde_json::Value::Bool(b)) => b.to_string(),
+                Some(_) => {
+                    return fail(format!(
+                        "argument '{}' must be {}",
+                        spec.name,
+                        spec.kind.as_str()
+                    ))
+                }
+            };
+            if let Some(skipped) = skipped {
+                return fail(format!(
+                    "argument '{}' needs '{skipped}' to be set as well",
+                    spec.name
+                ));
+            }
+            args.push(value);
+        }
+        self.check_args(&args)?;
+        Ok(args)
+    }
+
+    /// Check the environment and arguments, then build the command. Also
+    /// returns the `[plugins.<name>]` table as JSON when the manifest asks
+    /// for it on stdin.
+    fn command(
+        &self,
+        root: &Path,
+        cfg: Option<&config::Config>,
+        args: &[String],
+    ) -> Result<(process::Command, Option<String>), PluginError> {
+        let script = self
+            .script
+            .as_ref()
+            .ok_or_else(|| PluginError::NotFound(self.name.clone()))?;
+        if let Some(reason) = self.manifest.as_ref().and_then(Manifest::unmet) {
+            return Err(PluginError::Unmet(self.name.clone(), reason));
+        }
+        self.check_args(args)?;
+
+        let mut cmd = super::script_command(script);
+        cmd.args(args)
+            .env("BOUCLE_ROOT", root)
+            .env("BOUCLE_BIN", super::boucle_bin())
+            .env("BOUCLE_PLUGINS", root.join("plugins"));
+        let mut input = None;
+        let mut memory_dir = root.join(config::MemoryConfig::default().dir);
+        if let Some(cfg) = cfg {
+            memory_dir = root.join(&cfg.memory.dir);
+            cmd.env("BOUCLE_MEMORY", &memory_dir);
+            if let Some(table) = cfg.plugins.get(&self.name) {
+                let settings = resolve_settings(root, &cfg.secrets, &self.name, table)
+                    .map_err(|e| PluginError::Settings(self.name.clone(), e))?;
+                for (key, value) in &settings {
+                    let value = match value {
+                        serde_json::Value::String(s) => s.clone(),
+                        other => other.to_string(),
+                    };
+                    cmd.env(settings_env_name(key), value);
+                }
+                if self.manifest.as_ref().is_some_and(|m| m.config_on_stdin) {
+                    input = Some(serde_json::Value::Object(settings).to_string());
+                }
+            }
+        }
+        if let Some(sandbox) = self.manifest.as_ref().and_then(|m| m.sandbox.as_ref()) {
+            cmd = super::sandbox::wrap(cmd, sandbox, root, &memory_dir)
+                .map_err(|e| PluginError::Sandbox(self.name.clone(), e))?;
+        }
+        Ok((cmd, input))
+    }
+
+    /// Start the command, writing `input` (if any) to its stdin. A captured
+    /// plugin gets its own process group so a timeout stops its children
+    /// too; one attached to the terminal stays in the foreground group.
+    fn spawn(
+        &self,
+        mut cmd: process::Command,
+        input: Option<String>,
+        captured: bool,
+    ) -> Result<process::Child, PluginError> {
+        if input.is_some() {
+            cmd.stdin(process::Stdio::piped());
+        }
+        if captured {
+            super::configure_child_process_group(&mut cmd);
+        }
+        let mut child = cmd
+            .spawn()
+            .map_err(|e| PluginError::Io(self.name.clone(), e))?;
+        if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+            // A plugin that exits without reading its settings is fine.
+            let _ = stdin.write_all(input.as_bytes());
+        }
+        Ok(child)
+    }
+
+    /// Run the plugin attached to the terminal and return its exit code.
+    pub fn run(&self, root: &Path, args: &[String]) -> Result<i32, PluginError> {
+        let cfg = config::load(root).ok();
+        self.run_attached(root, cfg.as_ref(), args)
+            .inspect_err(|e| log_violation(root, cfg.as_ref(), e))
+    }
+
+    fn run_attached(
+        &self,
+        root: &Path,
+        cfg: Option<&config::Config>,
+        args: &[String],
+    ) -> Result<i32, PluginError> {
+        let io_error = |e| PluginError::Io(self.name.clone(), e);
+        let limits = limits(cfg);
+        let timeout = self.timeout(&limits)?;
+        let (cmd, input) = self.command(root, cfg, args)?;
+        let _slot = self.take_slot(root, &limits)?;
+        let mut child = self.spawn(cmd, input, false)?;
+
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Some(status) = child.try_wait().map_err(io_error)? {
+                return Ok(status.code().unwrap_or(1));
+            }
+            if Instant::now() >= deadline {
+                super::terminate_child_group(child.id(), true);
+                let _ = child.wait();
+                return Err(PluginError::TimedOut(self.name.clone(), timeout.as_secs()));
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+    }
+
+    /// Run the plugin in `root` and capture what it prints. With a
+    /// `cache_ttl`, a successful run's stdout is reused for identical calls
+    /// until the TTL passes or the script changes.
+    pub fn output(&self, root: &Path, args: &[String]) -> Result<PluginOutput, PluginError> {
+        let cfg = config::load(root).ok();
+        self.output_cached(root, cfg.as_ref(), args)
+            .inspect_err(|e| log_violation(root, cfg.as_ref(), e))
+    }
+
+    fn output_cached(
+        &self,
+        root: &Path,
+        cfg: Option<&config::Config>,
+        args: &[String],
+    ) -> Result<PluginOutput, PluginError> {
+        let (cmd, input) = self.command(root, cfg, args)?;
+        let (Some(ttl), Some(script)) = (self.cache_ttl(), &self.script) else {
+            return self.capture(root, cfg, cmd, input);
+        };
+        let (entry, fingerprint) = self.cache_entry(root, cfg, args);
+        if let Some(stdout) = context_cache::load_entry(&entry, script, fingerprint, ttl) {
+            return Ok(PluginOutput {
+                code: Some(0),
+                stdout,
+                stderr: String::new(),
+            });
+        }
+        let output = self.capture(root, cfg, cmd, input)?;
+        if output.success() {
+            // A cache that cannot be written only costs the next call a run.
+            let _ = context_cache::store_entry(&entry, fingerprint, &output.stdout);
+        }
+        Ok(output)
+    }
+
+    fn capture(
+        &self,
+        root: &Path,
+        cfg: Option<&config::Config>,
+        mut cmd: process::Command,
+        input: Option<String>,
+    ) -> Result<PluginOutput, PluginError> {
+        let limits = limits(cfg);
+        let timeout = self.timeout(&limits)?;
+        cmd.current_dir(root)
+            .stdin(process::Stdio::null())
+            .stdout(process::Stdio::piped())
+            .stderr(process::Stdio::piped());
+        let _slot = self.take_slot(root, &limits)?;
+        let child = self.spawn(cmd, input, true)?;
+
+        let output = super::wait_with_output_limits(child, timeout, Some(limits.max_output_bytes))
+            .map_err(|e| PluginError::Io(self.name.clone(), io::Error::other(e.to_string())))?;
+        if output.timed_out {
+            return Err(PluginError::TimedOut(self.name.clone(), timeout.as_secs()));
+        }
+        if output.output_exceeded {
+            return Err(PluginError::OutputLimit(
+                self.name.clone(),
+                limits.max_output_bytes,
+            ));
+        }
+        Ok(PluginOutput {
+            code: output.status.code(),
+            stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+        })
+    }
+
+    /// Take a `max_concurrent` slot, or fail if all of them are in use.
+    fn take_slot(
+        &self,
+        root: &Path,
+        limits: &config::PluginLimitsConfig,
+    ) -> Result<Option<Slot>, PluginError> {
+        if limits.max_concurrent == 0 {
+            return Ok(None);
+        }
+        let slot = Slot::take(&root.join(SLOTS_DIR), limits.max_concurrent)
+            .map_err(|e| PluginError::Io(self.name.clone(), e))?;
+        slot.map(Some)
+            .ok_or_else(|| PluginError::Busy(self.name.clone(), limits.max_concurrent))
+    }
+}
+
+/// `[plugin_limits]`, or its defaults without a readable boucle.toml.
+fn limits(cfg: Option<&config::Config>) -> config::PluginLimitsConfig {
+    cfg.map_or_else(Default::default, |cfg| cfg.plugin_limits.clone())
+}
+
+/// Append a limit violation to `plugins.log` in the log directory.
+fn log_violation(root: &Path, cfg: Option<&config::Config>, error: &PluginError) {
+    if !matches!(
+        error,
+        PluginError::TimedOut(..) | PluginError::OutputLimit(..) | PluginError::Busy(..)
+    ) {
+        return;
+    }
+    let log_dir = root.join(
+        cfg.and_then(|cfg| cfg.loop_config.log_dir.as_deref())
+            .unwrap_or(super::LOG_DIR_DEFAULT),
+    );
+    let timestamp = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S");
+    if fs::create_dir_all(&log_dir).is_ok() {
+        let _ = super::log(
+            &log_dir.join("plugins.log"),
+            &format!("{timestamp} {error}"),
+        );
+    }
+}
+
+/// One of the `max_concurrent` plugin slots: a file holding the PID of the
+/// boucle process using it, removed when dropped.
+struct Slot(PathBuf);
+
+impl Slot {
+    /// Claim a free slot in `dir`, reclaiming any left by a process that
+    /// has exited. `None` when all `max` are in use.
+    fn take(dir: &Path, max: usize) -> io::Result<Option<Slot>> {
+        fs::create_dir_all(dir)?;
+        for i in 0..max {
+            let path = dir.join(format!("{i}.pid"));
+            for _ in 0..2 {
+                match fs::OpenOptions::new()
+                    .write(true)
+                    .create_new(true)
+                    .open(&path)
+                {
+                    Ok(mut file) => {
+                        write!(file, "{}", process::id())?;
+                        return Ok(Some(Slot(path)));
+                    }
+                    Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                        if !Slot::is_stale(&path) {
+                            break;
+                        }
+                        let _ = fs::remove_file(&path);
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Whether the process holding the slot at `path` is gone. A slot whose
+    /// PID has not been written yet is only stale once it is old.
+    fn is_stale(path: &Path) -> bool {
+        match fs::read_to_string(path).map(|text| text.trim().parse::<u32>()) {
+            Ok(Ok(pid)) => !super::is_process_running(pid),
+            Ok(Err(_)) => fs::metadata(path)
+                .and_then(|meta| meta.modified())
+                .is_ok_and(|modified| modified.elapsed().unwrap_or_default() > SLOT_CLAIM_GRACE),
+            Err(_) => false,
+        }
+    }
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+/// A `[plugins.<name>]` table as JSON, with top-level strings resolved like
+/// `[agent.env]` values (`file:` and `${VAR}`).
+fn resolve_settings(
+    root: &Path,
+    secrets: &BTreeMap<String, config::SecretSource>,
+    name: &str,
+    table: &toml::Table,
+) -> Result<serde_json::Map<String, serde_json::Value>, String> {
+    table
+        .iter()
+        .map(|(key, value)| {
+            let value = match value {
+                toml::Value::String(s) => {
+                    let setting = format!("plugins.{name}.{key}");
+                    config::resolve_env_value(root, secrets, &setting, s)
+                        .map_err(|e| e.to_string())?
+                        .into()
+                }
+                other => serde_json::to_value(other).map_err(|e| e.to_string())?,
+            };
+            Ok((key.clone(), value))
+        })
+        .collect()
+}
+
+/// `team-id` becomes `BOUCLE_PLUGIN_TEAM_ID`.
+fn settings_env_name(key: &str) -> String {
+    let key: String = key
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("BOUCLE_PLUGIN_{key}")
+}
+
+/// Languages `boucle plugin new` can generate a skeleton in.
+pub const LANGUAGES: &[&str] = &["shell", "python"];
+
+/// Check that `name` can be a plugin file name.
+pub fn check_name(name: &str) -> Result<(), String> {
+    let segment = |s: &str| {
+        s.starts_with(|c: char| c.is_ascii_alphanumeric())
+            && s.chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    };
+    let valid = match name.split_once('/') {
+        Some((namespace, name)) => namespace != TESTS_DIR && segment(namespace) && segment(name),
+        None => segment(name),
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "invalid plugin name '{name}': use letters, digits, '-' and '_', \
+             optionally under one namespace (github/issues)"
+        ))
+    }
+}
+
+/// Write a new plugin skeleton: the script, its manifest, and a smoke test
+/// in `plugins/tests/`. Nothing is overwritten. Returns the files created.
+pub fn scaffold(
+    plugins_dir: &Path,
+    name: &str,
+    language: &str,
+    description: Option<&str>,
+) -> Result<Vec<PathBuf>, String> {
+    check_name(name)?;
+    let description = description.unwrap_or("TODO: say what this plugin does");
+    if description.contains('\n') {
+        return Err("the description must be a single line".to_string());
+    }
+    let command = name.replace('/', " ");
+    let (extension, script) = match language {
+        "shell" | "sh" => ("sh", shell_skeleton(&command, description)),
+        "python" | "py" => ("py", python_skeleton(&command, description)),
+        _ => {
+            return Err(format!(
+                "unknown language '{language}': expected one of {}",
+                LANGUAGES.join(", ")
+            ))
+        }
+    };
+
+    let files = [
+        (plugins_dir.join(format!("{name}.{extension}")), script),
+        (
+            plugins_dir.join(format!("{name}.toml")),
+            manifest_skeleton(description),
+        ),
+        (
+            plugins_dir.join("tests").join(format!("{name}.sh")),
+            smoke_test_skeleton(name, &command),
+        ),
+    ];
+    if let Ok(existing) = find(plugins_dir, name) {
+        let path = existing.script.unwrap_or_default();
+        return Err(format!(
+            "plugin '{name}' already exists: {}",
+            path.display()
+        ));
+    }
+    if let Some((path, _)) = files.iter().find(|(path, _)| path.exists()) {
+        return Err(format!("{} already exists", path.display()));
+    }
+
+    for (path, content) in &files {
+        let dir = path.parent().unwrap_or(plugins_dir);
+        fs::create_dir_all(dir).map_err(|e| format!("create {}: {e}", dir.display()))?;
+        fs::write(path, content).map_err(|e| format!("write {}: {e}", path.display()))?;
+        if path.extension().is_some_and(|ext| ext != "toml") {
+            make_executable(path).map_err(|e| format!("chmod {}: {e}", path.display()))?;
+        }
+    }
+    Ok(files.into_iter().map(|(path, _)| path).collect())
+}
+
+#[cfg(unix)]
+fn make_executable(path: &Path) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o755))
+}
+
+#[cfg(not(unix))]
+fn make_executable(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+fn manifest_skeleton(description: &str) -> String {
+    format!(
+        r#"description = {}
+version = "0.1.0"
+timeout = "30s"
+# Environment variables the plugin needs; it is not started without them.
+# env = ["API_TOKEN"]
+
+[[args]]
+name = "input"
+description = "What to work on"
+"#,
+        toml::Value::from(description)
+    )
+}
+
+fn shell_skeleton(command: &str, description: &str) -> String {
+    let manifest = command.rsplit(' ').next().unwrap_or(command);
+    format!(
+        r#"#!/bin/sh
+# description: {description}
+#
+# Usage: boucle {command} <input>
+#
+# Arguments and the timeout are declared in {manifest}.toml. BOUCLE_ROOT,
+# BOUCLE_PLUGINS, and BOUCLE_MEMORY are set when run through boucle.
+set -eu
+
+usage() {{
+    echo "Usage: boucle {command} <input>"
+}}
+
+case "${{1:-}}" in
+    -h|--help) usage; exit 0 ;;
+    "") usage >&2; exit 2 ;;
+esac
+input="$1"
+
+# TODO: replace with the plugin's work.
+echo "{command}: $input"
+"#
+    )
+}
+
+fn python_skeleton(command: &str, description: &str) -> String {
+    let manifest = command.rsplit(' ').next().unwrap_or(command);
+    format!(
+        r#"#!/usr/bin/env python3
+# description: {description}
+"""Usage: boucle {command} <input>
+
+Arguments and the timeout are declared in {manifest}.toml. BOUCLE_ROOT,
+BOUCLE_PLUGINS, and BOUCLE_MEMORY are set when run through boucle.
+"""
+import argparse
+import sys
+
+
+def main(argv):
+    parser = argparse.ArgumentParser(prog="boucle {command}", description={})
+    parser.add_argument("input", help="What to work on")
+    args = parser.parse_args(argv)
+
+    # TODO: replace with the plugin's work.
+    print(f"{command}: {{args.input}}")
+    return 0
+
+
+if __name__ == "__main__":
+    sys.exit(main(sys.argv[1:]))
+"#,
+        serde_json::Value::from(description)
+    )
+}
+
+fn smoke_test_skeleton(name: &str, command: &str) -> String {
+    format!(
+        r#"#!/bin/sh
+# Smoke test for the {name} plugin. Run from the agent root:
+#   sh plugins/tests/{name}.sh
+# Set BOUCLE to test with a boucle binary that is not on PATH.
+set -eu
+
+out=$("${{BOUCLE:-boucle}}" {command} example) || {{
+    echo "FAIL: boucle {command} exited with $?" >&2
+    exit 1
+}}
+case "$out" in
+    *example*) echo "ok: {command}" ;;
+    *) echo "FAIL: unexpected output: $out" >&2; exit 1 ;;
+esac
+"#
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(dir: &Path, name: &str, content: &str) {
+        fs::write(dir.join(name), content).unwrap();
+    }
+
+    #[test]
+    fn test_manifest_is_preferred_over_the_script_comment() {
+        let dir = tempfile::tempdir().unwrap();
+        write(
+            dir.path(),
+            "notify.sh",
+            "#!/bin/sh\n# description: old comment\necho \"$1\"\n",
+        );
+        write(
+            dir.path(),
+            "notify.toml",
+            r#"
+description = "Post a message"
+version = "1.2.0"
+env = ["BOUCLE_TEST_UNSET_TOKEN"]
+requires = ["sh", "boucle-test-missing-tool"]
+timeout = "5s"
+
+[[args]]
+name = "channel"
+
+[[args]]
+name = "count"
+type = "integer"
+required = false
+"#,
+        );
+        write(dir.path(), "plain.py", "# description: a plain script\n");
+        write(dir.path(), "notes.md", "not a plugin\n");
+
+        let plugins: Vec<_> = discover(dir.path())
+            .unwrap()
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(plugins.len(), 2);
+        let notify = &plugins[0];
+        assert_eq!(notify.description().as_deref(), Some("Post a message"));
+        assert_eq!(notify.version(), Some("1.2.0"));
+        assert_eq!(
+            notify.missing(),
+            ["BOUCLE_TEST_UNSET_TOKEN", "boucle-test-missing-tool"]
+        );
+        let err = notify
+            .run(dir.path(), &["general".to_string()])
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "plugin 'notify' needs environment variable(s): BOUCLE_TEST_UNSET_TOKEN; \
+             requires boucle-test-missing-tool, not found on PATH"
+        );
+        assert_eq!(plugins[1].description().as_deref(), Some("a plain script"));
+        assert!(plugins[1].check_args(&["anything".to_string()]).is_ok());
+    }
+
+    #[test]
+    fn test_describe() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), "notify.py", "#!/usr/bin/python3\nprint('hi')\n");
+        write(
+            dir.path(),
+            "notify.toml",
+            "description = \"Post a message\"\n[[args]]\nname = \"count\"\ntype = \"integer\"\n",
+        );
+        write(
+            dir.path(),
+            "draft.toml",
+            "description = \"Not written yet\"\n",
+        );
+
+        let plugins: Vec<_> = discover(dir.path())
+            .unwrap()
+            .into_iter()
+            .map(|plugin| plugin.unwrap().describe(dir.path()))
+            .collect();
+        let draft = &plugins[0];
+        assert!(draft["path"].is_null());
+        assert!(draft["mcp_tool"].is_null());
+        let notify = &plugins[1];
+        assert_eq!(notify["command"], "notify");
+        assert_eq!(notify["interpreter"], "/usr/bin/python3");
+        assert_eq!(notify["description"], "Post a message");
+        assert_eq!(
+            notify["manifest_path"],
+            dir.path().join("notify.toml").display().to_string()
+        );
+        assert_eq!(notify["manifest"]["args"][0]["type"], "integer");
+        assert_eq!(notify["manifest"]["args"][0]["required"], true);
+        assert_eq!(notify["mcp_tool"], "plugin_notify");
+    }
+
+    #[test]
+    fn test_check_args() {
+        let plugin = Plugin {
+            name: "p".to_string(),
+            script: None,
+            manifest: Some(
+                Manifest::parse(
+                    r#"
+[[args]]
+name = "channel"
+
+[[args]]
+name = "count"
+type = "integer"
+required = false
+"#,
+                )
+                .unwrap(),
+            ),
+        };
+        let args = |a: &[&str]| a.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert!(plugin.check_args(&args(&["general"])).is_ok());
+        assert!(plugin.check_args(&args(&["general", "3"])).is_ok());
+        let err = plugin.check_args(&args(&[])).unwrap_err().to_string();
+        assert_eq!(err, "plugin 'p': missing argument(s): channel");
+        let err = plugin.check_args(&args(&["general", "x"])).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "plugin 'p': argument 'count' must be integer, got 'x'"
+        );
+        assert!(plugin.check_args(&args(&["a", "1", "b"])).is_err());
+
+        let positional = plugin
+            .positional_args(&serde_json::json!({"channel": "ops", "count": 2}))
+            .unwrap();
+        assert_eq!(positional, ["ops", "2"]);
+        assert!(plugin
+            .positional_args(&serde_json::json!({"count": 2}))
+            .is_err());
+    }
+
+    #[test]
+    fn test_scaffold() {
+        let dir = tempfile::tempdir().unwrap();
+        let plugins_dir = dir.path().join("plugins");
+        let files = scaffold(&plugins_dir, "weather", "python", Some("Say \"hi\"")).unwrap();
+        assert_eq!(files.len(), 3);
+        assert!(plugins_dir.join("tests/weather.sh").is_file());
+
+        let plugin = find(&plugins_dir, "weather").unwrap();
+        assert_eq!(plugin.description().as_deref(), Some("Say \"hi\""));
+        assert_eq!(plugin.args().unwrap()[0].name, "input");
+        let script = fs::read_to_string(plugin.script.unwrap()).unwrap();
+        assert!(script.contains(r#"description="Say \"hi\"")"#), "{script}");
+
+        let err = scaffold(&plugins_dir, "weather", "shell", None).unwrap_err();
+        assert!(err.contains("already exists"), "{err}");
+        assert!(scaffold(&plugins_dir, "../x", "shell", None).is_err());
+        assert!(scaffold(&plugins_dir, "x", "perl", None).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_namespaced_plugins() {
+        let dir = tempfile::tempdir().unwrap();
+        let plugins_dir = dir.path().join("plugins");
+        scaffold(&plugins_dir, "github/issues", "shell", Some("Open issues")).unwrap();
+        scaffold(&plugins_dir, "github", "shell", None).unwrap();
+        assert!(plugins_dir.join("tests/github/issues.sh").is_file());
+        assert!(scaffold(&plugins_dir, "tests/x", "shell", None).is_err());
+        assert!(scaffold(&plugins_dir, "a/b/c", "shell", None).is_err());
+
+        let names: Vec<String> = discover(&plugins_dir)
+            .unwrap()
+            .into_iter()
+            .map(|p| p.unwrap().name)
+            .collect();
+        assert_eq!(names, ["github", "github/issues"]);
+
+        let words = |line: &str| line.split(' ').map(String::from).collect::<Vec<_>>();
+        let (plugin, consumed) = resolve(&plugins_dir, &words("github issues open")).unwrap();
+        assert_eq!((plugin.name.as_str(), consumed), ("github/issues", 2));
+        assert_eq!(plugin.command_name(), "github issues");
+        assert_eq!(plugin.tool_name(), "plugin_github_issues");
+        let (plugin, consumed) = resolve(&plugins_dir, &words("github open")).unwrap();
+        assert_eq!((plugin.name.as_str(), consumed), ("github", 1));
+
+        let plugin = find_tool(&plugins_dir, "plugin_github_issues").unwrap();
+        let output = plugin.output(dir.path(), &["x".to_string()]).unwrap();
+        assert_eq!(output.stdout, "github issues: x\n");
+        assert!(find(&plugins_dir, "../plugins/github").is_err());
+        assert_eq!(namespace_plugins(&plugins_dir, "github").len(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_shell_skeleton_runs() {
+        let dir = tempfile::tempdir().unwrap();
+        let plugins_dir = dir.path().join("plugins");
+        scaffold(&plugins_dir, "echoer", "shell", None).unwrap();
+        let plugin = find(&plugins_dir, "echoer").unwrap();
+
+        let output = plugin.output(dir.path(), &["hello".to_string()]).unwrap();
+        assert!(output.success(), "{}", output.stderr);
+        assert_eq!(output.stdout, "echoer: hello\n");
+        assert!(plugin.output(dir.path(), &[]).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_plugin_settings_from_boucle_toml() {
+        let dir = tempfile::tempdir().unwrap();
+        crate::runner::init(dir.path(), "test-agent").unwrap();
+        let plugins_dir = dir.path().join("plugins");
+        fs::create_dir_all(&plugins_dir).unwrap();
+        write(
+            &plugins_dir,
+            "issues.sh",
+            "#!/bin/sh\necho \"$BOUCLE_PLUGIN_TEAM_ID $BOUCLE_PLUGIN_LABELS $BOUCLE_PLUGIN_TOKEN\"\ncat\n",
+        );
+        write(&plugins_dir, "issues.toml", "config_on_stdin = true\n");
+        write(dir.path(), "team.key", "s3cret\n");
+        let mut toml = fs::read_to_string(dir.path().join("boucle.toml")).unwrap();
+        toml.push_str(
+            "\n[plugins.issues]\nteam-id = 42\nlabels = [\"bug\"]\ntoken = \"file:team.key\"\n",
+        );
+        fs::write(dir.path().join("boucle.toml"), &toml).unwrap();
+
+        let plugin = find(&plugins_dir, "issues").unwrap();
+        let output = plugin.output(dir.path(), &[]).unwrap();
+        let mut lines = output.stdout.lines();
+        assert_eq!(lines.next(), Some(r#"42 ["bug"] s3cret"#));
+        let stdin: serde_json::Value = serde_json::from_str(lines.next().unwrap()).unwrap();
+        assert_eq!(
+            stdin,
+            serde_json::json!({"team-id": 42, "labels": ["bug"], "token": "s3cret"})
+        );
+
+        toml.push_str("extra = \"${BOUCLE_TEST_UNSET_SETTING}\"\n");
+        fs::write(dir.path().join("boucle.toml"), &toml).unwrap();
+        let err = plugin.output(dir.path(), &[]).err().unwrap().to_string();
+        assert!(err.contains("BOUCLE_TEST_UNSET_SETTING"), "{err}");
+    }
+
+    #[test]
+    fn test_output_cache_is_keyed_by_args_and_settings() {
+        let dir = tempfile::tempdir().unwrap();
+        crate::runner::init(dir.path(), "test-agent").unwrap();
+        let plugins_dir = dir.path().join("plugins");
+        fs::create_dir_all(&plugins_dir).unwrap();
+        write(
+            &plugins_dir,
+            "quote.sh",
+            "#!/bin/sh\necho run >> \"$BOUCLE_ROOT/runs\"\necho \"$1 $BOUCLE_PLUGIN_UNIT\"\n",
+        );
+        write(&plugins_dir, "quote.toml", "cache_ttl = \"1h\"\n");
+        let plugin = find(&plugins_dir, "quote").unwrap();
+        let runs = || {
+            fs::read_to_string(dir.path().join("runs"))
+                .unwrap_or_default()
+                .lines()
+                .count()
+        };
+        let args = |a: &str| vec![a.to_string()];
+
+        assert_eq!(
+            plugin.output(dir.path(), &args("ACME")).unwrap().stdout,
+            "ACME \n"
+        );
+        assert_eq!(
+            plugin.output(dir.path(), &args("ACME")).unwrap().stdout,
+            "ACME \n"
+        );
+        assert_eq!(runs(), 1);
+        plugin.output(dir.path(), &args("INIT")).unwrap();
+        assert_eq!(runs(), 2);
+
+        let mut toml = fs::read_to_string(dir.path().join("boucle.toml")).unwrap();
+        toml.push_str("\n[plugins.quote]\nunit = \"EUR\"\n");
+        fs::write(dir.path().join("boucle.toml"), &toml).unwrap();
+        let output = plugin.output(dir.path(), &args("ACME")).unwrap();
+        assert_eq!(output.stdout, "ACME EUR\n");
+        assert_eq!(runs(), 3);
+
+        assert!(Manifest::parse("cache_ttl = \"often\"").is_err());
+    }
+
+    #[test]
+    fn test_plugin_limits() {
+        let dir = tempfile::tempdir().unwrap();
+        crate::runner::init(dir.path(), "test-agent").unwrap();
+        let plugins_dir = dir.path().join("plugins");
+        fs::create_dir_all(&plugins_dir).unwrap();
+        write(
+            &plugins_dir,
+            "flood.sh",
+            "#!/bin/sh
+yes boucle\n",
+        );
+        write(&plugins_dir, "hang.sh", "#!/bin/sh\nsleep 30\n");
+        write(&plugins_dir, "echo.sh", "#!/bin/sh\necho ok\n");
+        let mut toml = fs::read_to_string(dir.path().join("boucle.toml")).unwrap();
+        toml.push_str(
+            "\n[plugin_limits]\ntimeout = \"1s\"\nmax_output_bytes = 1000\nmax_concurrent = 1\n",
+        );
+        fs::write(dir.path().join("boucle.toml"), &toml).unwrap();
+
+        let flood = find(&plugins_dir, "flood").unwrap();
+        let err = flood.output(dir.path(), &[]).err().unwrap();
+        assert!(matches!(err, PluginError::OutputLimit(_, 1000)), "{err}");
+        let hang = find(&plugins_dir, "hang").unwrap();
+        let err = hang.output(dir.path(), &[]).err().unwrap();
+        assert!(matches!(err, PluginError::TimedOut(_, 1)), "{err}");
+
+        let echo = find(&plugins_dir, "echo").unwrap();
+        let held = Slot::take(&dir.path().join(SLOTS_DIR), 1).unwrap().unwrap();
+        let err = echo.output(dir.path(), &[]).err().unwrap();
+        assert!(matches!(err, PluginError::Busy(_, 1)), "{err}");
+        drop(held);
+        assert_eq!(echo.output(dir.path(), &[]).unwrap().stdout, "ok\n");
+
+        let log = fs::read_to_string(dir.path().join("logs/plugins.log")).unwrap();
+        let lines: Vec<&str> = log.lines().collect();
+        assert_eq!(lines.len(), 3, "{log}");
+        assert!(lines[0].ends_with("plugin 'flood' stopped after printing more than 1000 bytes"));
+        assert!(lines[1].ends_with("plugin 'hang' timed out after 1s"));
+        assert!(lines[2].contains("plugin 'echo' not started"));
+    }
+
+    #[test]
+    fn test_slots_left_by_exited_processes_are_reclaimed() {
+        let dir = tempfile::tempdir().unwrap();
+        let slots = dir.path().join(SLOTS_DIR);
+        let first = Slot::take(&slots, 2).unwrap().unwrap();
+        let second = Slot::take(&slots, 2).unwrap().unwrap();
+        assert!(Slot::take(&slots, 2).unwrap().is_none());
+
+        // A PID that cannot belong to a running process.
+        std::mem::forget(second);
+        fs::write(slots.join("1.pid"), "999999999").unwrap();
+        assert!(Slot::take(&slots, 2).unwrap().is_some());
+        drop(first);
+        assert!(!slots.join("0.pid").exists());
+    }
+
+    #[test]
+    fn test_manifest_parse_errors() {
+        assert!(Manifest::parse("descripton = \"typo\"").is_err());
+        assert!(Manifest::parse("timeout = \"soon\"").is_err());
+        let err =
+            Manifest::parse("[[args]]\nname = \"a\"\nrequired = false\n[[args]]\nname = \"b\"\n")
+                .unwrap_err();
+        assert_eq!(err, "required argument 'b' follows an optional one");
+    }
+}
@@ -0,0 +1,185 @@
+//! Output cache for slow context plugins.
+//!
+//! A script opts in with `[context.plugins.<name>] cache_ttl = "15m"`, a
+//! `cache_ttl` in its manifest, or a `# cache-ttl: 15m` comment near its
+//! top. Within the TTL the previous output is reused instead of running the
+//! script again. Editing the script or its `args`/`env` settings
+//! invalidates its cache. Command plugins keep their entries elsewhere but
+//! share the format (see `load_entry`).
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::{fs, io};
+
+/// Cache directory, relative to the agent root.
+pub const CACHE_DIR: &str = ".boucle-cache/context";
+
+/// How many leading lines are searched for a `cache-ttl:` comment.
+const HEADER_LINES: usize = 10;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedOutput {
+    cached_at_unix: u64,
+    settings: u64,
+    output: String,
+}
+
+/// TTL declared in a script's header comment, e.g. `# cache-ttl: 15m`.
+pub fn header_ttl(script: &str) -> Option<String> {
+    script.lines().take(HEADER_LINES).find_map(|line| {
+        let comment = line
+            .trim_start()
+            .trim_start_matches(['#', '/', '-', ';'])
+            .trim();
+        comment
+            .strip_prefix("cache-ttl:")
+            .map(|ttl| ttl.trim().to_string())
+    })
+}
+
+/// Fingerprint of the settings a script was run with, so a cached output is
+/// not reused after `args` or `env` change.
+pub fn settings_fingerprint(args: &[String], env: &BTreeMap<String, String>) -> u64 {
+    let env = env.iter().flat_map(|(k, v)| [k.as_str(), v.as_str()]);
+    let parts: Vec<&str> = args
+        .iter()
+        .map(String::as_str)
+        .chain(["\0env"])
+        .chain(env)
+        .collect();
+    content_hash(&parts)
+}
+
+/// FNV-1a over `parts`, stable across builds so hashes written to disk stay
+/// comparable after an upgrade.
+pub fn content_hash(parts: &[&str]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for part in parts {
+        for byte in part.bytes().chain(std::iter::once(0xff)) {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    hash
+}
+
+fn cache_path(root: &Path, plugin_name: &str) -> PathBuf {
+    root.join(CACHE_DIR).join(format!("{plugin_name}.json"))
+}
+
+fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Cached output of `script`, if it is younger than `ttl` and neither the
+/// script nor its settings have changed since it was cached.
+pub fn load(
+    root: &Path,
+    plugin_name: &str,
+    script: &Path,
+    settings: u64,
+    ttl: Duration,
+) -> Option<String> {
+    load_entry(&cache_path(root, plugin_name), script, settings, ttl)
+}
+
+/// Like [`load`], for a cache entry at `path`.
+pub fn load_entry(path: &Path, script: &Path, settings: u64, ttl: Duration) -> Option<String> {
+    let content = fs::read_to_string(path).ok()?;
+    let cached: CachedOutput = serde_json::from_str(&content).ok()?;
+    if cached.settings != settings {
+        return None;
+    }
+    let now = unix_secs(SystemTime::now());
+    if now.saturating_sub(cached.cached_at_unix) >= ttl.as_secs() {
+        return None;
+    }
+    let modified = fs::metadata(script).and_then(|m| m.modified()).ok()?;
+    if unix_secs(modified) > cached.cached_at_unix {
+        return None;
+    }
+    Some(cached.output)
+}
+
+/// Remember a successful run of a plugin.
+pub fn store(root: &Path, plugin_name: &str, settings: u64, output: &str) -> Result<(), io::Error> {
+    store_entry(&cache_path(root, plugin_name), settings, output)
+}
+
+/// Like [`store`], for a cache entry at `path`.
+pub fn store_entry(path: &Path, settings: u64, output: &str) -> Result<(), io::Error> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let cached = CachedOutput {
+        cached_at_unix: unix_secs(SystemTime::now()),
+        settings,
+        output: output.to_string(),
+    };
+    fs::write(
+        path,
+        serde_json::to_string(&cached).map_err(io::Error::other)?,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_header_ttl() {
+        assert_eq!(
+            header_ttl("#!/bin/sh\n# cache-ttl: 15m\necho hi").as_deref(),
+            Some("15m")
+        );
+        assert_eq!(
+            header_ttl("#!/usr/bin/env node\n// cache-ttl: 1h\n").as_deref(),
+            Some("1h")
+        );
+        assert!(header_ttl("#!/bin/sh\necho cache-ttl: 5m").is_none());
+    }
+
+    #[test]
+    fn test_content_hash_separates_parts() {
+        assert_ne!(content_hash(&["ab", "c"]), content_hash(&["a", "bc"]));
+        assert_eq!(content_hash(&["a"]), content_hash(&["a"]));
+    }
+
+    #[test]
+    fn test_store_and_load_respect_ttl() {
+        let dir = tempfile::tempdir().unwrap();
+        let script = dir.path().join("linear");
+        fs::write(&script, "#!/bin/sh\necho issues").unwrap();
+        // Cache timestamps have second resolution; make sure the script is
+        // strictly older than the entry.
+        let past = SystemTime::now() - Duration::from_secs(10);
+        fs::File::options()
+            .write(true)
+            .open(&script)
+            .unwrap()
+            .set_modified(past)
+            .unwrap();
+
+        assert!(load(dir.path(), "linear", &script, 1, Duration::from_secs(60)).is_none());
+        store(dir.path(), "linear", 1, "issues\n").unwrap();
+        assert_eq!(
+            load(dir.path(), "linear", &script, 1, Duration::from_secs(60)).as_deref(),
+            Some("issues\n")
+        );
+        assert!(load(dir.path(), "linear", &script, 1, Duration::ZERO).is_none());
+        assert!(load(dir.path(), "linear", &script, 2, Duration::from_secs(60)).is_none());
+
+        let future = SystemTime::now() + Duration::from_secs(10);
+        fs::File::options()
+            .write(true)
+            .open(&script)
+            .unwrap()
+            .set_modified(future)
+            .unwrap();
+        assert!(load(dir.path(), "linear", &script, 1, Duration::from_secs(60)).is_none());
+    }
+}
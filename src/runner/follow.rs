@@ -0,0 +1,216 @@
+//! `boucle log --follow`: watch iterations as they run.
+//!
+//! Prints the newest iteration log, then whatever is appended to it, along
+//! with the LLM's output as the runner copies it to `<log>.stream` during
+//! the call. When a newer iteration starts, it moves on to that one. Runs
+//! until interrupted.
+
+use super::{load_config, record, RunnerError};
+use std::fs;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+/// How often the log directory is checked for new output.
+const POLL: Duration = Duration::from_millis(250);
+
+/// Longest tool input shown for a tool call.
+const TOOL_INPUT_CHARS: usize = 120;
+
+/// Follow the agent's iteration logs on stdout.
+pub fn follow(root: &Path, profile: Option<&str>) -> Result<(), RunnerError> {
+    let cfg = load_config(root, profile)?;
+    let log_dir = root.join(
+        cfg.loop_config
+            .log_dir
+            .as_deref()
+            .unwrap_or(super::LOG_DIR_DEFAULT),
+    );
+    let mut out = io::stdout().lock();
+    let mut tail: Option<Tail> = None;
+    if latest_log(&log_dir).is_none() {
+        writeln!(out, "No logs yet; waiting for a run...")?;
+    }
+    loop {
+        let newest = latest_log(&log_dir);
+        if let Some(ref mut current) = tail {
+            current.poll(&mut out)?;
+        }
+        match newest {
+            Some(log) if tail.as_ref().is_none_or(|t| t.log != log) => {
+                if tail.is_some() {
+                    writeln!(out)?;
+                }
+                let mut next = Tail::new(log);
+                next.poll(&mut out)?;
+                tail = Some(next);
+            }
+            _ => thread::sleep(POLL),
+        }
+    }
+}
+
+/// The newest iteration log in `log_dir`.
+fn latest_log(log_dir: &Path) -> Option<PathBuf> {
+    fs::read_dir(log_dir)
+        .ok()?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "log"))
+        .max()
+}
+
+/// How far one iteration's log and LLM stream have been printed.
+struct Tail {
+    log: PathBuf,
+    log_offset: u64,
+    stream_offset: u64,
+    /// The stream was there at the last poll, so the LLM was running and
+    /// new log lines come after its output.
+    streaming: bool,
+}
+
+impl Tail {
+    fn new(log: PathBuf) -> Self {
+        Tail {
+            log,
+            log_offset: 0,
+            stream_offset: 0,
+            streaming: false,
+        }
+    }
+
+    /// Print what was appended to the log and the stream since the last poll.
+    fn poll(&mut self, out: &mut impl Write) -> io::Result<()> {
+        let logged = read_from(&self.log, &mut self.log_offset)?;
+        let stream_file = record::stream_path(&self.log);
+        let mut lines = String::new();
+        for line in read_from(&stream_file, &mut self.stream_offset)?.lines() {
+            for rendered in render_stream_line(line) {
+                lines.push_str(&format!("  {rendered}\n"));
+            }
+        }
+        if self.streaming {
+            out.write_all(lines.as_bytes())?;
+            out.write_all(logged.as_bytes())?;
+        } else {
+            out.write_all(logged.as_bytes())?;
+            out.write_all(lines.as_bytes())?;
+        }
+        self.streaming = stream_file.exists();
+        out.flush()
+    }
+}
+
+/// What was appended to `path` since `offset`, which moves past it. A
+/// missing file has nothing new.
+fn read_from(path: &Path, offset: &mut u64) -> io::Result<String> {
+    let mut file = match fs::File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(String::new()),
+        Err(e) => return Err(e),
+    };
+    file.seek(SeekFrom::Start(*offset))?;
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes)?;
+    // Stop at the last newline so a line being written is read whole later.
+    let end = bytes.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+    *offset += end as u64;
+    Ok(String::from_utf8_lossy(&bytes[..end]).into_owned())
+}
+
+/// Readable lines for one line of LLM output. Claude's stream events show
+/// as the assistant's text and its tool calls; the result is left to the
+/// log. Anything else is shown as it is.
+fn render_stream_line(line: &str) -> Vec<String> {
+    let Ok(event) = serde_json::from_str::<serde_json::Value>(line) else {
+        return vec![line.to_string()];
+    };
+    match event["type"].as_str() {
+        Some("assistant") => event["message"]["content"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .flat_map(|block| match block["type"].as_str() {
+                Some("text") => block["text"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .lines()
+                    .map(str::to_string)
+                    .collect(),
+                Some("tool_use") => vec![format!(
+                    "> {} {}",
+                    block["name"].as_str().unwrap_or("tool"),
+                    tool_input(&block["input"])
+                )],
+                _ => Vec::new(),
+            })
+            .collect(),
+        Some("system" | "user" | "result") => Vec::new(),
+        _ => vec![line.to_string()],
+    }
+}
+
+/// The part of a tool's input worth a glance: the command, path, or pattern
+/// when it has one.
+fn tool_input(input: &serde_json::Value) -> String {
+    let text = ["command", "file_path", "path", "pattern", "url"]
+        .iter()
+        .find_map(|key| input[*key].as_str().map(str::to_string))
+        .unwrap_or_else(|| input.to_string());
+    let text = text.replace('\n', " ");
+    if text.chars().count() > TOOL_INPUT_CHARS {
+        let cut: String = text.chars().take(TOOL_INPUT_CHARS).collect();
+        format!("{cut}...")
+    } else {
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_stream_line() {
+        let text = r#"{"type":"assistant","message":{"content":[{"type":"text","text":"Checking the tests.\nThen the docs."},{"type":"tool_use","name":"Bash","input":{"command":"cargo test"}}]}}"#;
+        assert_eq!(
+            render_stream_line(text),
+            ["Checking the tests.", "Then the docs.", "> Bash cargo test"]
+        );
+        assert!(render_stream_line(r#"{"type":"user","message":{}}"#).is_empty());
+        assert!(render_stream_line(r#"{"type":"result","result":"done"}"#).is_empty());
+        assert_eq!(
+            render_stream_line("thinking about it"),
+            ["thinking about it"]
+        );
+    }
+
+    #[test]
+    fn test_poll_prints_log_then_stream_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("2026-10-18_09-00-00.log");
+        let stream = record::stream_path(&log);
+        fs::write(&log, "Running LLM via claude...\n").unwrap();
+        fs::write(&stream, "first\nsecond, half").unwrap();
+        let mut tail = Tail::new(log.clone());
+        let mut out = Vec::new();
+        tail.poll(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&out),
+            "Running LLM via claude...\n  first\n"
+        );
+
+        // Once the LLM was running, new log lines follow its output.
+        fs::write(&stream, "first\nsecond, half done\n").unwrap();
+        let mut file = fs::OpenOptions::new().append(true).open(&log).unwrap();
+        writeln!(file, "LLM exit code: 0").unwrap();
+        out.clear();
+        tail.poll(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&out),
+            "  second, half done\nLLM exit code: 0\n"
+        );
+    }
+}
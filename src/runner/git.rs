@@ -0,0 +1,1986 @@
+//! Git helpers for the loop runner.
+//!
+//! Everything the runner does with git goes through here, on top of libgit2
+//! (the `git2` crate), so an agent runs where no `git` binary is installed
+//! and every failure comes back as an error naming the operation. Commits
+//! run the repository's `pre-commit`, `commit-msg`, and `post-commit` hooks
+//! as git would, and are signed with `gpg` or `ssh-keygen`; the merges and
+//! rebases the runner makes (run branches, `git.sync`) run no hooks.
+//! Remotes are reached through [`super::git_transport`], never prompting,
+//! since a scheduled run has nobody to answer. A `git.sync` command other
+//! than a plain `pull` or `fetch` is handed to the `git` program.
+
+use super::git_transport;
+use crate::config::{self, SignFormat};
+use git2::build::{CheckoutBuilder, RepoBuilder};
+use git2::{
+    BranchType, Commit, Delta, DiffFormat, DiffOptions, DiffStatsFormat, ErrorCode, FetchOptions,
+    Index, IndexAddOption, IndexMatchedPath, Oid, PushOptions, RebaseOptions, RemoteCallbacks,
+    Repository, ResetType, Signature, Status, StatusOptions,
+};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::{fs, io, process};
+
+/// Gitignore-style patterns, in the agent root, for files the runner's own
+/// commits leave out.
+pub const IGNORE_FILE: &str = ".boucleignore";
+
+/// What the runner keeps in the agent root between iterations (caches,
+/// locks, and its `.boucle-*.json` state), as `.gitignore` patterns.
+/// Iteration commits leave these out whatever `.gitignore` says, and
+/// `boucle init` adds them to it.
+pub const RUNNER_STATE: &[&str] = &["/.boucle-cache/", "/.boucle*.lock", "/.boucle-*.json"];
+
+/// Ref that holds the most recent pre-LLM snapshot. Overwritten every
+/// iteration; kept around after a rollback so the state can be inspected.
+pub const SNAPSHOT_REF: &str = "refs/boucle/snapshot";
+
+/// A point-in-time capture of the working tree taken before the LLM runs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    /// Commit HEAD pointed at when the snapshot was taken.
+    pub head: String,
+    /// Commit whose tree is the full working tree (tracked + untracked,
+    /// minus ignored files) at snapshot time.
+    pub commit: String,
+}
+
+/// Returns true if `root` is inside a git work tree.
+pub fn is_repo(root: &Path) -> bool {
+    Repository::discover(root).is_ok_and(|repo| !repo.is_bare())
+}
+
+/// Whether the runner uses git for `root`: `git.enabled`, or when unset,
+/// whether `root` is in a repository.
+pub fn enabled(root: &Path, git: &config::GitConfig) -> bool {
+    git.enabled.unwrap_or_else(|| is_repo(root))
+}
+
+/// Capture the working tree without touching it or the real index.
+///
+/// Untracked files are staged into a throwaway index so that a rollback can
+/// restore files the agent had not committed yet. Returns `Ok(None)` when
+/// there is nothing to anchor a snapshot to (not a repo, or no commits yet).
+pub fn snapshot(
+    root: &Path,
+    label: &str,
+    commit_name: &str,
+    commit_email: &str,
+) -> Result<Option<Snapshot>, io::Error> {
+    let _span = span("snapshot");
+    if !is_repo(root) {
+        return Ok(None);
+    }
+    let ops = GitOps::open(root)?;
+    let Some(head) = ops.head_commit()? else {
+        return Ok(None);
+    };
+
+    // A second handle whose index lives in memory, so `add` leaves the
+    // real one alone.
+    let scratch = Repository::open(ops.repo.path()).during("add")?;
+    let mut index = Index::new().during("add")?;
+    scratch.set_index(&mut index).during("add")?;
+    let mut index = scratch.index().during("add")?;
+    index
+        .read_tree(&head.tree().during("read-tree")?)
+        .during("read-tree")?;
+    index
+        .add_all(["*"], IndexAddOption::DEFAULT, None)
+        .during("add")?;
+    index.update_all(["*"], None).during("add")?;
+    let tree = index.write_tree_to(&ops.repo).during("write-tree")?;
+
+    let signature = Signature::now(commit_name, commit_email).during("commit-tree")?;
+    let commit = ops
+        .repo
+        .commit(
+            None,
+            &signature,
+            &signature,
+            &format!("boucle snapshot: {label}"),
+            &ops.repo.find_tree(tree).during("commit-tree")?,
+            &[&head],
+        )
+        .during("commit-tree")?;
+    ops.repo
+        .reference(SNAPSHOT_REF, commit, true, "boucle snapshot")
+        .during("update-ref")?;
+    Ok(Some(Snapshot {
+        head: head.id().to_string(),
+        commit: commit.to_string(),
+    }))
+}
+
+/// Restore the working tree, index, and HEAD to a snapshot.
+///
+/// `keep` lists root-relative paths the runner owns during an iteration
+/// (the log directory, the lock file) — they are neither cleaned nor
+/// overwritten, so the current iteration's log survives its own rollback.
+pub fn rollback(root: &Path, snapshot: &Snapshot, keep: &[&str]) -> Result<(), io::Error> {
+    let _span = span("rollback");
+    let ops = GitOps::open(root)?;
+    let repo = &ops.repo;
+    let kept = |path: &str| {
+        keep.iter().any(|keep| {
+            let keep = keep.trim_start_matches("./").trim_end_matches('/');
+            path == keep
+                || path
+                    .strip_prefix(keep)
+                    .is_some_and(|rest| rest.starts_with('/'))
+        })
+    };
+
+    let head = repo.revparse_single(&snapshot.head).during("reset")?;
+    repo.reset(&head, ResetType::Hard, None).during("reset")?;
+
+    // What `git clean -fd` would remove: untracked files, and the
+    // directories that leaves empty.
+    let workdir = ops.workdir()?;
+    let root_dir = workdir.join(&ops.prefix);
+    let mut options = StatusOptions::new();
+    options
+        .include_untracked(true)
+        .include_ignored(false)
+        .recurse_untracked_dirs(true);
+    let untracked: Vec<String> = repo
+        .statuses(Some(&mut options))
+        .during("clean")?
+        .iter()
+        .filter(|entry| entry.status().is_wt_new())
+        .filter_map(|entry| entry.path().map(str::to_string))
+        .collect();
+    for path in untracked {
+        if ops.in_root(&path).is_none_or(kept) {
+            continue;
+        }
+        let file = workdir.join(&path);
+        fs::remove_file(&file)?;
+        let mut dir = file.parent();
+        while let Some(parent) = dir.filter(|d| *d != root_dir) {
+            if fs::remove_dir(parent).is_err() {
+                break;
+            }
+            dir = parent.parent();
+        }
+    }
+
+    // Put back what differs from the snapshot, file by file.
+    let commit = Oid::from_str(&snapshot.commit)
+        .and_then(|oid| repo.find_commit(oid))
+        .during("checkout")?;
+    let tree = commit.tree().during("checkout")?;
+    let diff = repo
+        .diff_tree_to_workdir(Some(&tree), None)
+        .during("checkout")?;
+    let paths: Vec<String> = diff
+        .deltas()
+        .filter(|delta| delta.status() != Delta::Added)
+        .filter_map(|delta| delta.old_file().path())
+        .map(|path| path.to_string_lossy().into_owned())
+        .filter(|path| ops.in_root(path).is_some_and(|rel| !kept(rel)))
+        .collect();
+    if !paths.is_empty() {
+        let mut checkout = CheckoutBuilder::new();
+        checkout.force().disable_pathspec_match(true);
+        for path in &paths {
+            checkout.path(path);
+        }
+        repo.checkout_tree(tree.as_object(), Some(&mut checkout))
+            .during("checkout")?;
+    }
+
+    // The checkout also staged the snapshot; put the index back to HEAD so
+    // files that were untracked before the run are untracked again.
+    let mut index = repo.index().during("reset")?;
+    index
+        .read_tree(&head.peel_to_tree().during("reset")?)
+        .during("reset")?;
+    index.write().during("reset")
+}
+/// The iteration commit message: `git.commit_message` with `{agent}`,
+/// `{iteration}`, `{timestamp}`, and `{summary}` filled in.
+pub fn commit_message(
+    template: &str,
+    agent: &str,
+    iteration: usize,
+    timestamp: &str,
+    summary: &str,
+) -> String {
+    template
+        .replace("{agent}", agent)
+        .replace("{iteration}", &iteration.to_string())
+        .replace("{timestamp}", timestamp)
+        .replace("{summary}", summary)
+}
+
+/// Longest `{summary}`, in characters, so it fits a commit subject.
+const SUMMARY_MAX_CHARS: usize = 72;
+
+/// One line saying what an iteration did: the first line of prose in the
+/// LLM's final message (headings and code skipped), or when there is none,
+/// the files from `git status --porcelain`.
+pub fn commit_summary(response: &str, status: &str) -> String {
+    let mut in_code = false;
+    let said = response.lines().find_map(|line| {
+        let line = line.trim();
+        if line.starts_with("```") {
+            in_code = !in_code;
+            return None;
+        }
+        if in_code || line.starts_with('#') {
+            return None;
+        }
+        let line = line.trim_start_matches(['*', '-', '>', ' ']).trim();
+        (!line.is_empty()).then_some(line)
+    });
+    if let Some(line) = said {
+        let line = line.trim_end_matches(['*', ':']);
+        if line.chars().count() <= SUMMARY_MAX_CHARS {
+            return line.to_string();
+        }
+        let cut: String = line.chars().take(SUMMARY_MAX_CHARS - 1).collect();
+        return format!("{}…", cut.trim_end());
+    }
+
+    // `XY path`, or `XY old -> new` for a rename.
+    let files: Vec<&str> = status
+        .lines()
+        .filter_map(|line| line.get(3..))
+        .map(|path| path.rsplit(" -> ").next().unwrap_or(path).trim_matches('"'))
+        .collect();
+    match files.as_slice() {
+        [] => "no changes".to_string(),
+        [one] => format!("update {one}"),
+        [first, second] => format!("update {first} and {second}"),
+        [first, rest @ ..] => format!("update {first} and {} other files", rest.len()),
+    }
+}
+
+/// Push HEAD to `branch` on `remote` (a configured remote or a URL).
+pub fn push(root: &Path, remote: &str, branch: &str) -> Result<(), io::Error> {
+    let _span = span("push");
+    check_branch_name(branch, "push")?;
+    let ops = GitOps::open(root)?;
+    git_transport::register();
+    let mut remote = ops
+        .repo
+        .find_remote(remote)
+        .or_else(|_| ops.repo.remote_anonymous(remote))
+        .during("push")?;
+    let mut rejected = None;
+    {
+        let mut callbacks = RemoteCallbacks::new();
+        callbacks.push_update_reference(|refname, status| {
+            if let Some(status) = status {
+                rejected = Some(format!("{refname} was rejected: {status}"));
+            }
+            Ok(())
+        });
+        let mut options = PushOptions::new();
+        options.remote_callbacks(callbacks);
+        remote
+            .push(&[format!("HEAD:refs/heads/{branch}")], Some(&mut options))
+            .during("push")?;
+    }
+    match rejected {
+        Some(reason) => Err(io::Error::other(format!("git push failed: {reason}"))),
+        None => Ok(()),
+    }
+}
+
+/// Stage changes for the iteration commit. With neither `paths` (files or
+/// directories under the agent root) nor a `.boucleignore`, that is
+/// `git add -A`. Otherwise only changed files under `paths` and not matched
+/// by `.boucleignore` are staged. [`RUNNER_STATE`] and the `leave_out`
+/// patterns (in the same form) never are. Returns whether anything is
+/// staged, now or left over from a commit an earlier iteration couldn't
+/// make.
+pub fn stage(root: &Path, paths: &[String], leave_out: &[&str]) -> Result<bool, io::Error> {
+    let _span = span("add");
+    let ops = GitOps::open(root)?;
+    let ignore = fs::read_to_string(root.join(IGNORE_FILE)).ok();
+    let state = RUNNER_STATE
+        .iter()
+        .chain(leave_out)
+        .copied()
+        .collect::<Vec<_>>()
+        .join("\n");
+    let mut index = ops.repo.index().during("add")?;
+    if paths.is_empty() && ignore.is_none() {
+        // libgit2 skips a path when the callback returns 1.
+        let mut skip = |path: &Path, _: &[u8]| {
+            let path = path.to_string_lossy();
+            i32::from(ops.in_root(&path).is_some_and(|rel| ignored(&state, rel)))
+        };
+        index
+            .add_all(
+                ["*"],
+                IndexAddOption::DEFAULT,
+                Some(&mut skip as &mut IndexMatchedPath),
+            )
+            .during("add")?;
+        index
+            .update_all(["*"], Some(&mut skip as &mut IndexMatchedPath))
+            .during("add")?;
+    } else {
+        let workdir = ops.workdir()?;
+        let files = ops
+            .changed_files()?
+            .into_iter()
+            .filter(|file| in_paths(file, paths))
+            .filter(|file| !ignored(&state, file))
+            .filter(|file| !ignored(ignore.as_deref().unwrap_or_default(), file));
+        for file in files {
+            let path = format!("{}{file}", ops.prefix);
+            let added = match workdir.join(&path).symlink_metadata() {
+                Ok(_) => index.add_path(Path::new(&path)),
+                Err(_) => index.remove_path(Path::new(&path)),
+            };
+            added.during("add")?;
+        }
+    }
+    index.write().during("add")?;
+    ops.has_staged()
+}
+
+fn in_paths(file: &str, paths: &[String]) -> bool {
+    paths.is_empty()
+        || paths.iter().any(|path| {
+            let path = path.trim_start_matches("./").trim_end_matches('/');
+            path.is_empty()
+                || file == path
+                || file
+                    .strip_prefix(path)
+                    .is_some_and(|rest| rest.starts_with('/'))
+        })
+}
+
+/// Whether `.boucleignore` content excludes `file` (a directory when it
+/// ends in `/`). Follows `.gitignore`: `#` comments, `!` to re-include, a
+/// trailing `/` for directories only, a leading or inner `/` to anchor at
+/// the agent root, and `*`, `?`, `**`. The last matching pattern wins.
+fn ignored(patterns: &str, file: &str) -> bool {
+    let options = glob::MatchOptions {
+        require_literal_separator: true,
+        ..Default::default()
+    };
+    // Each parent directory, then the file itself.
+    let is_dir = file.ends_with('/');
+    let parts: Vec<&str> = file.trim_end_matches('/').split('/').collect();
+    let candidates: Vec<(String, bool)> = (1..=parts.len())
+        .map(|n| (parts[..n].join("/"), n < parts.len() || is_dir))
+        .collect();
+    let mut excluded = false;
+    for line in patterns.lines() {
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (negate, line) = match line.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        let dir_only = line.ends_with('/');
+        let line = line.trim_end_matches('/');
+        let anchored = line.contains('/');
+        let Ok(pattern) = glob::Pattern::new(line.trim_start_matches('/')) else {
+            continue;
+        };
+        let hit = candidates.iter().any(|(path, is_dir)| {
+            if dir_only && !is_dir {
+                return false;
+            }
+            let subject = if anchored {
+                path.as_str()
+            } else {
+                path.rsplit('/').next().unwrap_or(path)
+            };
+            pattern.matches_with(subject, options)
+        });
+        if hit {
+            excluded = !negate;
+        }
+    }
+    excluded
+}
+
+/// How a `git.sync` command ended.
+#[derive(Debug, PartialEq, Eq)]
+pub enum SyncOutcome {
+    Synced,
+    /// It stopped on these conflicting files and was aborted.
+    Conflict(Vec<String>),
+}
+
+/// A `git.sync` command: `fetch`, or `pull` with the options the runner
+/// knows how to carry out.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct SyncCommand {
+    /// Integrate upstream after fetching (`pull` rather than `fetch`).
+    pull: bool,
+    /// Rebase rather than merge; `None` follows `pull.rebase`.
+    rebase: Option<bool>,
+    ff_only: bool,
+    autostash: bool,
+    remote: Option<String>,
+    branch: Option<String>,
+}
+
+impl SyncCommand {
+    /// Parse `git.sync`, e.g. `pull --rebase --autostash origin main`.
+    pub fn parse(command: &str) -> Result<Self, String> {
+        let mut words = command.split_whitespace();
+        let mut sync = SyncCommand::default();
+        match words.next() {
+            Some("pull") => sync.pull = true,
+            Some("fetch") => {}
+            Some(other) => return Err(format!("'{other}' is not supported (use pull or fetch)")),
+            None => return Err("empty command".to_string()),
+        }
+        for word in words {
+            match word {
+                "-q" | "--quiet" => {}
+                "-r" | "--rebase" if sync.pull => sync.rebase = Some(true),
+                "--no-rebase" if sync.pull => sync.rebase = Some(false),
+                "--ff-only" if sync.pull => sync.ff_only = true,
+                "--autostash" if sync.pull => sync.autostash = true,
+                "--no-autostash" if sync.pull => sync.autostash = false,
+                option if option.starts_with('-') => {
+                    return Err(format!("option '{option}' is not supported"));
+                }
+                remote if sync.remote.is_none() => sync.remote = Some(remote.to_string()),
+                branch if sync.pull && sync.branch.is_none() => {
+                    sync.branch = Some(branch.to_string());
+                }
+                extra => return Err(format!("unexpected '{extra}'")),
+            }
+        }
+        Ok(sync)
+    }
+}
+
+/// Carry out `git.sync`, as `committer` for any merge commit. A rebase or
+/// merge that stops on conflicts is abandoned, so the tree is as it was,
+/// and the conflicting files are returned; any other failure is an error.
+/// Commands [`SyncCommand`] doesn't cover run through the `git` program.
+pub fn sync(
+    root: &Path,
+    command: &str,
+    committer: &config::GitConfig,
+) -> Result<SyncOutcome, io::Error> {
+    let _span = span("sync");
+    let Ok(sync) = SyncCommand::parse(command) else {
+        return sync_with_git(root, command);
+    };
+    let op = if sync.pull { "pull" } else { "fetch" };
+    let mut ops = GitOps::open(root)?;
+    git_transport::register();
+    let config = ops.repo.config().during(op)?;
+    let local = ops
+        .repo
+        .head()
+        .ok()
+        .filter(|head| head.is_branch())
+        .and_then(|head| head.shorthand().map(str::to_string));
+    let remote = match sync.remote {
+        Some(remote) => remote,
+        None => local
+            .as_ref()
+            .and_then(|branch| config.get_string(&format!("branch.{branch}.remote")).ok())
+            .unwrap_or_else(|| "origin".to_string()),
+    };
+    {
+        let mut fetched = ops.repo.find_remote(&remote).during(op)?;
+        fetched
+            .fetch(&[] as &[&str], Some(&mut FetchOptions::new()), None)
+            .during(op)?;
+    }
+    if !sync.pull {
+        return Ok(SyncOutcome::Synced);
+    }
+
+    let local =
+        local.ok_or_else(|| io::Error::other("git pull failed: HEAD is not on a branch"))?;
+    let branch = match sync.branch {
+        Some(branch) => branch,
+        None => config
+            .get_string(&format!("branch.{local}.merge"))
+            .map(|merge| merge.trim_start_matches("refs/heads/").to_string())
+            .unwrap_or_else(|_| local.clone()),
+    };
+    let upstream = ops
+        .repo
+        .refname_to_id(&format!("refs/remotes/{remote}/{branch}"))
+        .during(op)?;
+    let rebase = sync
+        .rebase
+        .unwrap_or_else(|| config.get_bool("pull.rebase").unwrap_or(false));
+    let analysis = {
+        let theirs = ops.repo.find_annotated_commit(upstream).during(op)?;
+        ops.repo.merge_analysis(&[&theirs]).during(op)?.0
+    };
+    if analysis.is_up_to_date() {
+        return Ok(SyncOutcome::Synced);
+    }
+    if sync.ff_only && !analysis.is_fast_forward() {
+        return Err(io::Error::other(
+            "git pull failed: not possible to fast-forward",
+        ));
+    }
+
+    let stashed = sync.autostash && ops.is_dirty()?;
+    if stashed {
+        let stasher =
+            Signature::now(&committer.commit_name, &committer.commit_email).during("stash")?;
+        ops.repo
+            .stash_save(&stasher, "boucle sync autostash", None)
+            .during("stash")?;
+    }
+    let conflicts = if analysis.is_fast_forward() {
+        ops.advance_to(upstream, "pull: fast-forward")
+            .map(|()| Vec::new())
+    } else if rebase {
+        ops.rebase_onto(upstream, committer)
+    } else {
+        let message = format!("Merge remote-tracking branch '{remote}/{branch}'");
+        ops.merge(upstream, &message, committer)
+    };
+    if stashed {
+        ops.repo.stash_pop(0, None).during("stash pop")?;
+    }
+    Ok(match conflicts? {
+        files if files.is_empty() => SyncOutcome::Synced,
+        files => SyncOutcome::Conflict(files),
+    })
+}
+
+/// Run `git <command>` without prompting, for a `git.sync` that libgit2
+/// can't carry out. Conflicts are handled as in [`sync`].
+fn sync_with_git(root: &Path, command: &str) -> Result<SyncOutcome, io::Error> {
+    let args: Vec<&str> = command.split_whitespace().collect();
+    let Err(err) = git_program(root, &args) else {
+        return Ok(SyncOutcome::Synced);
+    };
+    let conflicts: Vec<String> = git_program(root, &["diff", "--name-only", "--diff-filter=U"])?
+        .lines()
+        .map(str::to_string)
+        .collect();
+    if conflicts.is_empty() {
+        return Err(err);
+    }
+    git_program(root, &["rebase", "--abort"])
+        .or_else(|_| git_program(root, &["merge", "--abort"]))?;
+    Ok(SyncOutcome::Conflict(conflicts))
+}
+
+/// Run the `git` program and return its stdout, or an error carrying its
+/// stderr.
+fn git_program(root: &Path, args: &[&str]) -> Result<String, io::Error> {
+    let subcommand = args.first().copied().unwrap_or_default();
+    let output = process::Command::new("git")
+        .current_dir(root)
+        .args(args)
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .output()
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => io::Error::new(
+                e.kind(),
+                format!("git {subcommand} needs the git program, which is not installed"),
+            ),
+            _ => e,
+        })?;
+    let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+    if output.status.success() {
+        return Ok(stdout);
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let detail = match stderr.trim() {
+        "" => stdout.trim(),
+        stderr => stderr,
+    };
+    Err(io::Error::other(format!(
+        "git {subcommand} failed: {detail}"
+    )))
+}
+
+/// `git status --porcelain`: one line per changed or untracked file,
+/// leaving out [`RUNNER_STATE`].
+pub fn status(root: &Path) -> Result<String, io::Error> {
+    let _span = span("status");
+    let ops = GitOps::open(root)?;
+    let mut options = StatusOptions::new();
+    options
+        .include_untracked(true)
+        .include_ignored(false)
+        .recurse_untracked_dirs(false)
+        .renames_head_to_index(true);
+    let statuses = ops.repo.statuses(Some(&mut options)).during("status")?;
+    let state = RUNNER_STATE.join("\n");
+    let mut lines = String::new();
+    for entry in statuses.iter() {
+        let path = String::from_utf8_lossy(entry.path_bytes());
+        if ops.in_root(&path).is_some_and(|rel| ignored(&state, rel)) {
+            continue;
+        }
+        let status = entry.status();
+        let renamed = entry
+            .head_to_index()
+            .filter(|_| status.is_index_renamed())
+            .and_then(|delta| Some((delta.old_file().path()?, delta.new_file().path()?)));
+        let shown = match renamed {
+            Some((from, to)) => format!(
+                "{} -> {}",
+                quote_path(&from.to_string_lossy()),
+                quote_path(&to.to_string_lossy())
+            ),
+            None => quote_path(&path),
+        };
+        lines.push_str(&format!("{} {shown}\n", status_code(status)));
+    }
+    Ok(lines)
+}
+
+/// The two-letter code `git status --porcelain` shows for `status`.
+fn status_code(status: Status) -> String {
+    if status.is_conflicted() {
+        return "UU".to_string();
+    }
+    if status.is_wt_new() {
+        return "??".to_string();
+    }
+    let staged = if status.is_index_new() {
+        'A'
+    } else if status.is_index_modified() {
+        'M'
+    } else if status.is_index_deleted() {
+        'D'
+    } else if status.is_index_renamed() {
+        'R'
+    } else if status.is_index_typechange() {
+        'T'
+    } else {
+        ' '
+    };
+    let unstaged = if status.is_wt_modified() {
+        'M'
+    } else if status.is_wt_deleted() {
+        'D'
+    } else if status.is_wt_typechange() {
+        'T'
+    } else {
+        ' '
+    };
+    format!("{staged}{unstaged}")
+}
+
+/// `path` as git prints it: in double quotes with C escapes when it holds
+/// spaces, quotes, backslashes, control characters, or non-ASCII bytes.
+fn quote_path(path: &str) -> String {
+    let plain = |b: u8| b.is_ascii_graphic() && b != b'"' && b != b'\\';
+    if path.bytes().all(plain) {
+        return path.to_string();
+    }
+    let mut quoted = String::from("\"");
+    for b in path.bytes() {
+        match b {
+            b'"' => quoted.push_str("\\\""),
+            b'\\' => quoted.push_str("\\\\"),
+            b'\t' => quoted.push_str("\\t"),
+            b'\n' => quoted.push_str("\\n"),
+            b' '..=b'~' => quoted.push(char::from(b)),
+            _ => quoted.push_str(&format!("\\{b:03o}")),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Commit what is staged, as `git.commit_name` and signed when `git.sign`
+/// is set.
+pub fn commit(root: &Path, git: &config::GitConfig, message: &str) -> Result<(), io::Error> {
+    let _span = span("commit");
+    let ops = GitOps::open(root)?;
+    ops.run_hook("pre-commit", &[])?;
+    let mut index = ops.repo.index().during("commit")?;
+    // Pick up anything the hook staged.
+    index.read(false).during("commit")?;
+    if index.has_conflicts() {
+        return Err(io::Error::other(
+            "git commit failed: the index has unresolved conflicts",
+        ));
+    }
+    let tree = index.write_tree().during("commit")?;
+    let head = ops.head_commit()?;
+    let parents: Vec<&Commit> = head.iter().collect();
+    ops.commit_tree(git, message, tree, &parents)?;
+    Ok(())
+}
+
+/// Commit `path` alone, whatever else is staged, as [`commit`] would.
+pub fn commit_file(
+    root: &Path,
+    git: &config::GitConfig,
+    message: &str,
+    path: &str,
+) -> Result<(), io::Error> {
+    let _span = span("commit");
+    let ops = GitOps::open(root)?;
+    let workdir = ops.workdir()?;
+    let file = ops.root.join(path);
+    let path = file.strip_prefix(&workdir).map_err(|_| {
+        io::Error::other(format!(
+            "git commit failed: {path} is outside the repository"
+        ))
+    })?;
+
+    let mut index = ops.repo.index().during("add")?;
+    index.add_path(path).during("add")?;
+    index.write().during("add")?;
+    ops.run_hook("pre-commit", &[])?;
+    index.read(false).during("add")?;
+    let entry = index
+        .get_path(path, 0)
+        .ok_or_else(|| io::Error::other("git add failed: the file was not staged"))?;
+
+    // HEAD plus this one file, leaving the rest of the index for later.
+    let head = ops.head_commit()?;
+    let mut only = Index::new().during("commit")?;
+    if let Some(head) = &head {
+        only.read_tree(&head.tree().during("commit")?)
+            .during("commit")?;
+    }
+    only.add(&entry).during("commit")?;
+    let tree = only.write_tree_to(&ops.repo).during("commit")?;
+    let parents: Vec<&Commit> = head.iter().collect();
+    ops.commit_tree(git, message, tree, &parents)?;
+    Ok(())
+}
+
+/// The newest commit on HEAD from before `date` (RFC 3339), or `None` when
+/// there is none.
+pub fn commit_before(root: &Path, date: &str) -> Result<Option<String>, io::Error> {
+    let _span = span("rev-list");
+    let before = chrono::DateTime::parse_from_rfc3339(date)
+        .map_err(|e| io::Error::other(format!("git rev-list failed: {date}: {e}")))?
+        .timestamp();
+    let ops = GitOps::open(root)?;
+    let Some(head) = ops.head_commit()? else {
+        return Ok(None);
+    };
+    let mut walk = ops.repo.revwalk().during("rev-list")?;
+    walk.set_sorting(git2::Sort::TIME).during("rev-list")?;
+    walk.push(head.id()).during("rev-list")?;
+    for oid in walk {
+        let oid = oid.during("rev-list")?;
+        let commit = ops.repo.find_commit(oid).during("rev-list")?;
+        if commit.committer().when().seconds() <= before {
+            return Ok(Some(oid.to_string()));
+        }
+    }
+    Ok(None)
+}
+
+/// `git diff` of `paths` (relative to the agent root) from `base` (a
+/// commit, or nothing when `None`) to the working tree.
+pub fn diff_paths(root: &Path, base: Option<&str>, paths: &[&str]) -> Result<String, io::Error> {
+    let _span = span("diff");
+    let ops = GitOps::open(root)?;
+    let tree = base
+        .map(|base| ops.repo.revparse_single(base)?.peel_to_tree())
+        .transpose()
+        .during("diff")?;
+    let mut options = DiffOptions::new();
+    for path in paths {
+        options.pathspec(format!("{}{path}", ops.prefix));
+    }
+    let diff = ops
+        .repo
+        .diff_tree_to_workdir_with_index(tree.as_ref(), Some(&mut options))
+        .during("diff")?;
+    let mut text = String::new();
+    diff.print(DiffFormat::Patch, |_, _, line| {
+        if matches!(line.origin(), '+' | '-' | ' ') {
+            text.push(line.origin());
+        }
+        text.push_str(&String::from_utf8_lossy(line.content()));
+        true
+    })
+    .during("diff")?;
+    Ok(text)
+}
+
+/// Full hash of HEAD, or `None` before the first commit.
+pub fn head(root: &Path) -> Result<Option<String>, io::Error> {
+    let ops = GitOps::open(root)?;
+    let head = ops.head_commit()?.map(|commit| commit.id().to_string());
+    Ok(head)
+}
+
+/// `git show --stat` for one commit, without its message.
+pub fn diff_stat(root: &Path, commit: &str) -> Result<String, io::Error> {
+    let _span = span("show");
+    let ops = GitOps::open(root)?;
+    let commit = ops
+        .repo
+        .revparse_single(commit)
+        .and_then(|object| object.peel_to_commit())
+        .during("show")?;
+    let parent = match commit.parent(0) {
+        Ok(parent) => Some(parent.tree().during("show")?),
+        Err(_) => None,
+    };
+    let tree = commit.tree().during("show")?;
+    let stats = ops
+        .repo
+        .diff_tree_to_tree(parent.as_ref(), Some(&tree), None)
+        .and_then(|diff| diff.stats())
+        .and_then(|stats| stats.to_buf(DiffStatsFormat::FULL, 80))
+        .during("show")?;
+    Ok(String::from_utf8_lossy(&stats).into_owned())
+}
+
+/// `git log --oneline -1`, or `None` before the first commit.
+pub fn last_commit(root: &Path) -> Result<Option<String>, io::Error> {
+    let ops = GitOps::open(root)?;
+    let Some(commit) = ops.head_commit()? else {
+        return Ok(None);
+    };
+    let short = commit.as_object().short_id().during("log")?;
+    Ok(Some(format!(
+        "{} {}",
+        short.as_str().unwrap_or_default(),
+        commit.summary().unwrap_or_default()
+    )))
+}
+
+/// Name of the checked-out branch, or `None` on a detached HEAD.
+pub fn current_branch(root: &Path) -> Result<Option<String>, io::Error> {
+    let ops = GitOps::open(root)?;
+    let head = ops.repo.find_reference("HEAD").during("symbolic-ref")?;
+    Ok(head
+        .symbolic_target()
+        .and_then(|target| target.strip_prefix("refs/heads/"))
+        .map(str::to_string))
+}
+
+/// Expand a `run_branch` template. Supports `{timestamp}` and `{agent}`.
+pub fn expand_branch_template(template: &str, timestamp: &str, agent: &str) -> String {
+    template
+        .replace("{timestamp}", timestamp)
+        .replace("{agent}", agent)
+}
+
+/// Create `branch` at HEAD and switch to it, carrying uncommitted changes.
+/// With `reuse`, an existing `branch` is switched to instead, so its commits
+/// keep piling up on it.
+pub fn start_run_branch(root: &Path, branch: &str, reuse: bool) -> Result<(), io::Error> {
+    let _span = span("checkout");
+    check_branch_name(branch, "checkout")?;
+    let ops = GitOps::open(root)?;
+    if ops.repo.find_branch(branch, BranchType::Local).is_ok() {
+        if reuse {
+            return ops.switch(branch);
+        }
+        return Err(io::Error::other(format!(
+            "git checkout failed: a branch named '{branch}' already exists"
+        )));
+    }
+    let head = ops.head_commit()?.ok_or_else(|| {
+        io::Error::other("git checkout failed: there is no commit to branch from")
+    })?;
+    ops.repo.branch(branch, &head, false).during("checkout")?;
+    ops.repo
+        .set_head(&format!("refs/heads/{branch}"))
+        .during("checkout")
+}
+
+/// Number of commits on `branch` that `base` doesn't have.
+pub fn commits_ahead(root: &Path, base: &str, branch: &str) -> Result<u64, io::Error> {
+    let ops = GitOps::open(root)?;
+    let base = ops.commit_id(base, "rev-list")?;
+    let branch = ops.commit_id(branch, "rev-list")?;
+    let (ahead, _) = ops
+        .repo
+        .graph_ahead_behind(branch, base)
+        .during("rev-list")?;
+    Ok(ahead as u64)
+}
+
+/// How a run branch was wrapped up at the end of an iteration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RunBranchOutcome {
+    /// The iteration made no commits; the branch was deleted.
+    Empty,
+    /// The branch was merged into the base branch and deleted.
+    Merged,
+    /// The branch was kept for review (by config, or because the merge
+    /// conflicted).
+    Kept { conflict: bool },
+}
+
+/// Return to `base` and merge `branch` into it, committing any merge as
+/// `merge`'s committer, or leave it for review when `merge` is `None`.
+pub fn finish_run_branch(
+    root: &Path,
+    base: &str,
+    branch: &str,
+    merge: Option<&config::GitConfig>,
+) -> Result<RunBranchOutcome, io::Error> {
+    let _span = span("merge");
+    let ahead = commits_ahead(root, base, branch)?;
+    let ops = GitOps::open(root)?;
+    ops.switch(base)?;
+
+    let delete = || {
+        ops.repo
+            .find_branch(branch, BranchType::Local)
+            .and_then(|mut branch| branch.delete())
+            .during("branch")
+    };
+    if ahead == 0 {
+        delete()?;
+        return Ok(RunBranchOutcome::Empty);
+    }
+    let Some(committer) = merge else {
+        return Ok(RunBranchOutcome::Kept { conflict: false });
+    };
+
+    let theirs = ops.commit_id(&format!("refs/heads/{branch}"), "merge")?;
+    let fast_forward = {
+        let annotated = ops.repo.find_annotated_commit(theirs).during("merge")?;
+        ops.repo
+            .merge_analysis(&[&annotated])
+            .during("merge")?
+            .0
+            .is_fast_forward()
+    };
+    if fast_forward {
+        ops.advance_to(theirs, &format!("merge {branch}: fast-forward"))?;
+    } else if !ops
+        .merge(theirs, &format!("Merge {branch}"), committer)?
+        .is_empty()
+    {
+        return Ok(RunBranchOutcome::Kept { conflict: true });
+    }
+    delete()?;
+    Ok(RunBranchOutcome::Merged)
+}
+
+/// Clone `source` (a URL or a local path) into `into` and return the commit
+/// checked out. Remote sources are fetched shallow; libgit2 can't do that
+/// from a local repository.
+pub fn clone(source: &str, into: &Path) -> Result<String, io::Error> {
+    let _span = span("clone");
+    let failed =
+        |e: git2::Error| io::Error::other(format!("git clone {source} failed: {}", e.message()));
+    git_transport::register();
+    let mut options = FetchOptions::new();
+    if !source.starts_with("file://") && !Path::new(source).exists() {
+        options.depth(1);
+    }
+    let repo = RepoBuilder::new()
+        .fetch_options(options)
+        .clone(source, into)
+        .map_err(failed)?;
+    let head = repo
+        .head()
+        .and_then(|head| head.peel_to_commit())
+        .map_err(failed)?;
+    Ok(head.id().to_string())
+}
+
+/// An open repository and where the agent root sits in its work tree.
+struct GitOps {
+    repo: Repository,
+    /// The agent root.
+    root: PathBuf,
+    /// The agent root relative to the work tree, with a trailing `/`, or
+    /// empty when they are the same.
+    prefix: String,
+}
+
+impl GitOps {
+    fn open(root: &Path) -> Result<Self, io::Error> {
+        let repo = Repository::discover(root).during("open")?;
+        let root = root.canonicalize()?;
+        let workdir = repo
+            .workdir()
+            .ok_or_else(|| io::Error::other("git open failed: the repository has no work tree"))?
+            .canonicalize()?;
+        let prefix = match root.strip_prefix(&workdir) {
+            Ok(rel) if !rel.as_os_str().is_empty() => {
+                format!("{}/", rel.to_string_lossy().replace('\\', "/"))
+            }
+            _ => String::new(),
+        };
+        Ok(GitOps { repo, root, prefix })
+    }
+
+    fn workdir(&self) -> Result<PathBuf, io::Error> {
+        self.repo
+            .workdir()
+            .map(Path::to_path_buf)
+            .ok_or_else(|| io::Error::other("git failed: the repository has no work tree"))
+    }
+
+    /// `path` (relative to the work tree) relative to the agent root, or
+    /// `None` when it is outside it.
+    fn in_root<'p>(&self, path: &'p str) -> Option<&'p str> {
+        path.strip_prefix(self.prefix.as_str())
+    }
+
+    /// The commit HEAD points at, or `None` before the first commit.
+    fn head_commit(&self) -> Result<Option<Commit<'_>>, io::Error> {
+        match self.repo.head() {
+            Ok(head) => head.peel_to_commit().map(Some).during("rev-parse"),
+            Err(e) if matches!(e.code(), ErrorCode::UnbornBranch | ErrorCode::NotFound) => Ok(None),
+            Err(e) => Err(e).during("rev-parse"),
+        }
+    }
+
+    fn commit_id(&self, rev: &str, op: &str) -> Result<Oid, io::Error> {
+        self.repo
+            .revparse_single(rev)
+            .and_then(|object| object.peel_to_commit())
+            .map(|commit| commit.id())
+            .during(op)
+    }
+
+    /// Changed and untracked files under the agent root, relative to it.
+    /// Both sides of a rename are listed.
+    fn changed_files(&self) -> Result<Vec<String>, io::Error> {
+        let mut options = StatusOptions::new();
+        options
+            .include_untracked(true)
+            .include_ignored(false)
+            .recurse_untracked_dirs(true);
+        let statuses = self.repo.statuses(Some(&mut options)).during("status")?;
+        Ok(statuses
+            .iter()
+            .filter_map(|entry| {
+                entry
+                    .path()
+                    .and_then(|p| self.in_root(p))
+                    .map(str::to_string)
+            })
+            .collect())
+    }
+
+    /// Whether tracked files differ from HEAD, staged or not.
+    fn is_dirty(&self) -> Result<bool, io::Error> {
+        let mut options = StatusOptions::new();
+        options.include_untracked(false).include_ignored(false);
+        let statuses = self.repo.statuses(Some(&mut options)).during("status")?;
+        Ok(!statuses.is_empty())
+    }
+
+    /// Whether the index differs from HEAD.
+    fn has_staged(&self) -> Result<bool, io::Error> {
+        let head = match self.head_commit()? {
+            Some(commit) => Some(commit.tree().during("diff")?),
+            None => None,
+        };
+        let index = self.repo.index().during("diff")?;
+        let diff = self
+            .repo
+            .diff_tree_to_index(head.as_ref(), Some(&index), None)
+            .during("diff")?;
+        Ok(diff.deltas().len() > 0)
+    }
+
+    /// Commit `tree` on top of `parents` and move the current branch to it.
+    /// The index must already match `tree`.
+    fn commit_tree(
+        &self,
+        git: &config::GitConfig,
+        message: &str,
+        tree: Oid,
+        parents: &[&Commit],
+    ) -> Result<Oid, io::Error> {
+        if parents.first().is_some_and(|head| head.tree_id() == tree) {
+            return Err(io::Error::other("git commit failed: nothing to commit"));
+        }
+        let message = self.commit_msg_hook(message)?;
+        let oid = self.create_commit(git, &message, tree, parents)?;
+        // As with git, a failing `post-commit` doesn't undo the commit.
+        let _ = self.run_hook("post-commit", &[]);
+        self.move_head(
+            oid,
+            &format!("commit: {}", message.lines().next().unwrap_or_default()),
+        )?;
+        Ok(oid)
+    }
+
+    /// Run the repository's `name` hook, from `core.hooksPath` or
+    /// `.git/hooks`, when there is one. As with git, a hook that exits
+    /// non-zero stops the commit.
+    fn run_hook(&self, name: &str, args: &[&str]) -> Result<(), io::Error> {
+        let workdir = self.workdir()?;
+        let dir = match self
+            .repo
+            .config()
+            .and_then(|c| c.get_path("core.hooksPath"))
+        {
+            Ok(dir) => workdir.join(dir),
+            Err(_) => self.repo.path().join("hooks"),
+        };
+        let hook = dir.join(name);
+        let runnable = if cfg!(unix) {
+            super::context::is_executable(&hook).unwrap_or(false)
+        } else {
+            hook.is_file()
+        };
+        if !runnable {
+            return Ok(());
+        }
+        let output = super::script_command(&hook)
+            .args(args)
+            .current_dir(&workdir)
+            .env("GIT_INDEX_FILE", self.repo.path().join("index"))
+            .stdin(process::Stdio::null())
+            .output()
+            .map_err(|e| io::Error::other(format!("git commit failed: {name} hook: {e}")))?;
+        if output.status.success() {
+            return Ok(());
+        }
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let detail = [stderr.trim(), stdout.trim()]
+            .into_iter()
+            .find(|text| !text.is_empty())
+            .unwrap_or("exited non-zero");
+        Err(io::Error::other(format!(
+            "git commit failed: {name} hook: {detail}"
+        )))
+    }
+
+    /// `message` as the `commit-msg` hook leaves it in `COMMIT_EDITMSG`.
+    fn commit_msg_hook(&self, message: &str) -> Result<String, io::Error> {
+        let file = self.repo.path().join("COMMIT_EDITMSG");
+        // Newline-terminated, as git writes it, so hooks can append lines.
+        let written = format!("{}\n", message.trim_end_matches('\n'));
+        fs::write(&file, &written)?;
+        self.run_hook("commit-msg", &[&file.to_string_lossy()])?;
+        let edited = fs::read_to_string(&file)?;
+        Ok(if edited == written {
+            message.to_string()
+        } else {
+            edited
+        })
+    }
+
+    /// Write a commit of `tree`, by `git.commit_name` and signed when
+    /// `git.sign` is set, without moving any ref.
+    fn create_commit(
+        &self,
+        git: &config::GitConfig,
+        message: &str,
+        tree: Oid,
+        parents: &[&Commit],
+    ) -> Result<Oid, io::Error> {
+        let signature = Signature::now(&git.commit_name, &git.commit_email).during("commit")?;
+        let tree = self.repo.find_tree(tree).during("commit")?;
+        let message = git2::message_prettify(message, None).during("commit")?;
+        let Some(format) = git.sign else {
+            return self
+                .repo
+                .commit(None, &signature, &signature, &message, &tree, parents)
+                .during("commit");
+        };
+        let buffer = self
+            .repo
+            .commit_create_buffer(&signature, &signature, &message, &tree, parents)
+            .during("commit")?;
+        let content = buffer
+            .as_str()
+            .ok_or_else(|| io::Error::other("git commit failed: the commit is not UTF-8"))?;
+        let signed = self.sign(format, git.signing_key.as_deref(), &signature, content)?;
+        self.repo
+            .commit_signed(content, &signed, None)
+            .during("commit")
+    }
+
+    /// Sign a commit as git does: `gpg -bsau <key>`, or `ssh-keygen -Y sign`
+    /// for `ssh`, with `gpg.program` / `gpg.ssh.program` when set and
+    /// `user.signingkey` when `key` isn't.
+    fn sign(
+        &self,
+        format: SignFormat,
+        key: Option<&str>,
+        signer: &Signature,
+        content: &str,
+    ) -> Result<String, io::Error> {
+        let config = self.repo.config().during("commit")?;
+        let key = key
+            .map(str::to_string)
+            .or_else(|| config.get_string("user.signingkey").ok());
+        match format {
+            SignFormat::Gpg => {
+                let program = config
+                    .get_string("gpg.program")
+                    .unwrap_or_else(|_| "gpg".to_string());
+                let key = key.unwrap_or_else(|| {
+                    format!(
+                        "{} <{}>",
+                        signer.name().unwrap_or_default(),
+                        signer.email().unwrap_or_default()
+                    )
+                });
+                self.run_signer(&program, &["--status-fd=2", "-bsau", &key], content)
+            }
+            SignFormat::Ssh => {
+                let program = config
+                    .get_string("gpg.ssh.program")
+                    .unwrap_or_else(|_| "ssh-keygen".to_string());
+                let key = key.ok_or_else(|| {
+                    io::Error::other(
+                        "git commit failed: ssh signing needs git.signing_key or user.signingkey",
+                    )
+                })?;
+                if let Some(literal) = key.strip_prefix("key::") {
+                    // A public key whose private half is in the SSH agent.
+                    let file = self.repo.path().join("boucle-signing-key.pub");
+                    fs::write(&file, literal)?;
+                    let file_arg = file.to_string_lossy().into_owned();
+                    let signed = self.run_signer(
+                        &program,
+                        &["-Y", "sign", "-n", "git", "-U", "-f", &file_arg],
+                        content,
+                    );
+                    let _ = fs::remove_file(&file);
+                    return signed;
+                }
+                let key = match key.strip_prefix("~/") {
+                    Some(rest) => std::env::var_os("HOME")
+                        .map(|home| Path::new(&home).join(rest).to_string_lossy().into_owned())
+                        .unwrap_or(key),
+                    None => key,
+                };
+                self.run_signer(&program, &["-Y", "sign", "-n", "git", "-f", &key], content)
+            }
+        }
+    }
+
+    /// Run a signing program in the agent root on `content` and return the
+    /// signature it prints.
+    fn run_signer(&self, program: &str, args: &[&str], content: &str) -> Result<String, io::Error> {
+        let mut child = process::Command::new(program)
+            .args(args)
+            .current_dir(&self.root)
+            .stdin(process::Stdio::piped())
+            .stdout(process::Stdio::piped())
+            .stderr(process::Stdio::piped())
+            .spawn()
+            .map_err(|e| {
+                io::Error::new(
+                    e.kind(),
+                    format!("git commit failed: could not run {program}: {e}"),
+                )
+            })?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(content.as_bytes())?;
+        }
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let detail = stderr.lines().last().unwrap_or_default();
+            return Err(io::Error::other(format!(
+                "git commit failed: {program} could not sign: {detail}"
+            )));
+        }
+        String::from_utf8(output.stdout).map_err(|_| {
+            io::Error::other(format!(
+                "git commit failed: {program} printed a non-UTF-8 signature"
+            ))
+        })
+    }
+
+    /// Point the checked-out branch (or a detached HEAD) at `oid`, leaving
+    /// the work tree and index alone.
+    fn move_head(&self, oid: Oid, reflog: &str) -> Result<(), io::Error> {
+        let head = self.repo.find_reference("HEAD").during("update-ref")?;
+        match head.symbolic_target() {
+            Some(branch) => {
+                self.repo
+                    .reference(branch, oid, true, reflog)
+                    .during("update-ref")?;
+                Ok(())
+            }
+            None => self.repo.set_head_detached(oid).during("update-ref"),
+        }
+    }
+
+    /// Check out `oid` and move the current branch there. Local changes are
+    /// carried over, or it fails before touching anything if they would be
+    /// overwritten.
+    fn advance_to(&self, oid: Oid, reflog: &str) -> Result<(), io::Error> {
+        let target = self.repo.find_commit(oid).during("checkout")?;
+        self.repo
+            .checkout_tree(target.as_object(), Some(CheckoutBuilder::new().safe()))
+            .during("checkout")?;
+        self.move_head(oid, reflog)
+    }
+
+    /// Switch to local branch `name`, carrying local changes as
+    /// `git checkout` does.
+    fn switch(&self, name: &str) -> Result<(), io::Error> {
+        let refname = format!("refs/heads/{name}");
+        let target = self.repo.revparse_single(&refname).during("checkout")?;
+        self.repo
+            .checkout_tree(&target, Some(CheckoutBuilder::new().safe()))
+            .during("checkout")?;
+        self.repo.set_head(&refname).during("checkout")
+    }
+
+    /// Merge commit `theirs` into HEAD as `committer`. The merge is worked
+    /// out in memory first, so a conflict leaves the tree alone; returns
+    /// the conflicting files, if any.
+    fn merge(
+        &self,
+        theirs: Oid,
+        message: &str,
+        committer: &config::GitConfig,
+    ) -> Result<Vec<String>, io::Error> {
+        let ours = self.head_commit()?.ok_or_else(|| {
+            io::Error::other("git merge failed: there is no commit to merge into")
+        })?;
+        let theirs = self.repo.find_commit(theirs).during("merge")?;
+        let mut index = self
+            .repo
+            .merge_commits(&ours, &theirs, None)
+            .during("merge")?;
+        if index.has_conflicts() {
+            return conflict_paths(&index);
+        }
+        let tree = index.write_tree_to(&self.repo).during("merge")?;
+        let merged = self.create_commit(committer, message, tree, &[&ours, &theirs])?;
+        self.advance_to(merged, message)?;
+        Ok(Vec::new())
+    }
+
+    /// Replay HEAD's commits onto `upstream` as `committer`. Like
+    /// [`GitOps::merge`], it works in memory and returns the conflicting
+    /// files instead of stopping halfway.
+    fn rebase_onto(
+        &self,
+        upstream: Oid,
+        committer: &config::GitConfig,
+    ) -> Result<Vec<String>, io::Error> {
+        let head = self.repo.head().during("rebase")?;
+        let branch = self
+            .repo
+            .reference_to_annotated_commit(&head)
+            .during("rebase")?;
+        let onto = self.repo.find_annotated_commit(upstream).during("rebase")?;
+        let mut options = RebaseOptions::new();
+        options.inmemory(true);
+        let mut rebase = self
+            .repo
+            .rebase(Some(&branch), Some(&onto), None, Some(&mut options))
+            .during("rebase")?;
+        let signature =
+            Signature::now(&committer.commit_name, &committer.commit_email).during("rebase")?;
+        let mut last = upstream;
+        while let Some(operation) = rebase.next() {
+            operation.during("rebase")?;
+            let index = rebase.inmemory_index().during("rebase")?;
+            if index.has_conflicts() {
+                let files = conflict_paths(&index);
+                rebase.abort().during("rebase")?;
+                return files;
+            }
+            match rebase.commit(None, &signature, None) {
+                Ok(oid) => last = oid,
+                // Already upstream.
+                Err(e) if e.code() == ErrorCode::Applied => {}
+                Err(e) => {
+                    let _ = rebase.abort();
+                    return Err(e).during("rebase");
+                }
+            }
+        }
+        rebase.finish(None).during("rebase")?;
+        self.advance_to(last, "pull --rebase")?;
+        Ok(Vec::new())
+    }
+}
+
+/// The files with conflicts in `index`, relative to the work tree.
+fn conflict_paths(index: &Index) -> Result<Vec<String>, io::Error> {
+    let mut files = Vec::new();
+    for conflict in index.conflicts().during("merge")? {
+        let conflict = conflict.during("merge")?;
+        if let Some(entry) = conflict.our.or(conflict.their).or(conflict.ancestor) {
+            files.push(String::from_utf8_lossy(&entry.path).into_owned());
+        }
+    }
+    files.sort();
+    files.dedup();
+    Ok(files)
+}
+
+fn check_branch_name(branch: &str, op: &str) -> Result<(), io::Error> {
+    if git2::Branch::name_is_valid(branch).unwrap_or(false) {
+        return Ok(());
+    }
+    Err(io::Error::other(format!(
+        "git {op} failed: '{branch}' is not a valid branch name"
+    )))
+}
+
+/// Turns a libgit2 error into one naming the git operation that failed.
+trait During<T> {
+    fn during(self, op: &str) -> Result<T, io::Error>;
+}
+
+impl<T> During<T> for Result<T, git2::Error> {
+    fn during(self, op: &str) -> Result<T, io::Error> {
+        self.map_err(|e| io::Error::other(format!("git {op} failed: {}", e.message())))
+    }
+}
+
+fn span(command: &str) -> tracing::span::EnteredSpan {
+    tracing::info_span!("git", command).entered()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::fs;
+
+    /// Initialize a git repo with one commit containing `tracked.txt`.
+    pub(crate) fn init_repo(root: &Path) {
+        let repo = Repository::init(root).unwrap();
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "Test").unwrap();
+        config.set_str("user.email", "test@example.com").unwrap();
+        fs::write(root.join("tracked.txt"), "original\n").unwrap();
+        add_all(root);
+        commit(root, &config::GitConfig::default(), "init").unwrap();
+    }
+
+    /// `git add -A`.
+    pub(crate) fn add_all(root: &Path) {
+        let repo = Repository::open(root).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_all(["*"], IndexAddOption::DEFAULT, None).unwrap();
+        index.update_all(["*"], None).unwrap();
+        index.write().unwrap();
+    }
+
+    fn commit_file(root: &Path, name: &str, content: &str) {
+        fs::write(root.join(name), content).unwrap();
+        super::commit_file(root, &config::GitConfig::default(), name, name).unwrap();
+    }
+
+    fn branch_exists(root: &Path, branch: &str) -> bool {
+        Repository::open(root)
+            .unwrap()
+            .find_branch(branch, BranchType::Local)
+            .is_ok()
+    }
+
+    /// `git diff --cached --name-only`.
+    fn staged(root: &Path) -> String {
+        let repo = Repository::open(root).unwrap();
+        let head = repo.head().unwrap().peel_to_tree().unwrap();
+        let diff = repo.diff_tree_to_index(Some(&head), None, None).unwrap();
+        let names: Vec<String> = diff
+            .deltas()
+            .map(|delta| delta.new_file().path().unwrap().display().to_string())
+            .collect();
+        names.join("\n")
+    }
+
+    #[test]
+    fn test_commit_message() {
+        assert_eq!(
+            commit_message(
+                "Loop iteration: {timestamp}",
+                "bot",
+                3,
+                "2026-03-01_10-00-00",
+                "s"
+            ),
+            "Loop iteration: 2026-03-01_10-00-00"
+        );
+        assert_eq!(
+            commit_message("{agent} #{iteration}", "bot", 3, "t", "s"),
+            "bot #3"
+        );
+        assert_eq!(
+            commit_message("boucle({agent}): {summary}", "bot", 3, "t", "fix docs"),
+            "boucle(bot): fix docs"
+        );
+    }
+
+    #[test]
+    fn test_commit_summary() {
+        let status = " M memory/STATE.md\n?? notes.md\nR  a.md -> b.md\n";
+        assert_eq!(
+            commit_summary("\n## Summary\n\nTriaged 3 issues.\n", status),
+            "Triaged 3 issues."
+        );
+        assert_eq!(
+            commit_summary("```\nls\n```\n- **Fixed the flaky test**", ""),
+            "Fixed the flaky test"
+        );
+        let long = "word ".repeat(30);
+        let summary = commit_summary(&long, "");
+        assert!(summary.chars().count() <= SUMMARY_MAX_CHARS);
+        assert!(summary.starts_with("word word") && summary.ends_with('…'));
+        assert_eq!(
+            commit_summary("  \n", status),
+            "update memory/STATE.md and 2 other files"
+        );
+        assert_eq!(commit_summary("", "?? notes.md\n"), "update notes.md");
+        assert_eq!(
+            commit_summary("", "R  a.md -> b.md\n M c.md\n"),
+            "update b.md and c.md"
+        );
+    }
+
+    #[test]
+    fn test_expand_branch_template() {
+        assert_eq!(
+            expand_branch_template("boucle/{agent}/run-{timestamp}", "2026-01-01_00-00-00", "a"),
+            "boucle/a/run-2026-01-01_00-00-00"
+        );
+    }
+
+    #[test]
+    fn test_run_branch_merged_fast_forward() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        init_repo(root);
+        let base = current_branch(root).unwrap().unwrap();
+
+        start_run_branch(root, "boucle/run-1", false).unwrap();
+        assert_eq!(current_branch(root).unwrap().unwrap(), "boucle/run-1");
+        commit_file(root, "work.txt", "done");
+
+        let outcome = finish_run_branch(
+            root,
+            &base,
+            "boucle/run-1",
+            Some(&config::GitConfig::default()),
+        )
+        .unwrap();
+        assert_eq!(outcome, RunBranchOutcome::Merged);
+        assert_eq!(current_branch(root).unwrap().unwrap(), base);
+        assert!(root.join("work.txt").exists());
+        assert!(!branch_exists(root, "boucle/run-1"));
+    }
+
+    #[test]
+    fn test_diff_paths_since_commit_before() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        init_repo(root);
+        assert_eq!(commit_before(root, "2000-01-01T00:00:00Z").unwrap(), None);
+        let tomorrow = chrono::Utc::now() + chrono::Duration::days(1);
+        let base = commit_before(root, &tomorrow.to_rfc3339()).unwrap();
+        assert_eq!(base, head(root).unwrap());
+
+        fs::write(root.join("tracked.txt"), "changed\n").unwrap();
+        let diff = diff_paths(root, base.as_deref(), &["tracked.txt", "missing.md"]).unwrap();
+        assert!(diff.contains("-original\n+changed\n"), "{diff}");
+        let all = diff_paths(root, None, &["tracked.txt"]).unwrap();
+        assert!(all.contains("+changed\n"), "{all}");
+
+        fs::write(root.join("digest.md"), "# Digest\n").unwrap();
+        super::commit_file(
+            root,
+            &config::GitConfig::default(),
+            "Add digest",
+            "digest.md",
+        )
+        .unwrap();
+        assert!(status(root).unwrap().contains(" M tracked.txt"));
+        assert!(!status(root).unwrap().contains("digest.md"));
+    }
+
+    #[test]
+    fn test_run_branch_empty_is_deleted() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        init_repo(root);
+        let base = current_branch(root).unwrap().unwrap();
+
+        start_run_branch(root, "boucle/run-2", false).unwrap();
+        let outcome = finish_run_branch(
+            root,
+            &base,
+            "boucle/run-2",
+            Some(&config::GitConfig::default()),
+        )
+        .unwrap();
+        assert_eq!(outcome, RunBranchOutcome::Empty);
+        assert!(!branch_exists(root, "boucle/run-2"));
+    }
+
+    #[test]
+    fn test_run_branch_kept_for_review() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        init_repo(root);
+        let base = current_branch(root).unwrap().unwrap();
+
+        start_run_branch(root, "boucle/run-3", false).unwrap();
+        commit_file(root, "work.txt", "done");
+        let outcome = finish_run_branch(root, &base, "boucle/run-3", None).unwrap();
+        assert_eq!(outcome, RunBranchOutcome::Kept { conflict: false });
+        assert_eq!(current_branch(root).unwrap().unwrap(), base);
+        assert!(!root.join("work.txt").exists());
+        assert!(branch_exists(root, "boucle/run-3"));
+
+        // A stable branch for a pull request: the next run builds on it.
+        assert!(start_run_branch(root, "boucle/run-3", false).is_err());
+        start_run_branch(root, "boucle/run-3", true).unwrap();
+        commit_file(root, "more.txt", "done");
+        assert_eq!(commits_ahead(root, &base, "boucle/run-3").unwrap(), 2);
+    }
+
+    #[test]
+    fn test_run_branch_conflict_is_kept() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        init_repo(root);
+        let base = current_branch(root).unwrap().unwrap();
+
+        start_run_branch(root, "boucle/run-4", false).unwrap();
+        commit_file(root, "tracked.txt", "from run\n");
+        GitOps::open(root).unwrap().switch(&base).unwrap();
+        commit_file(root, "tracked.txt", "from base\n");
+        GitOps::open(root).unwrap().switch("boucle/run-4").unwrap();
+
+        let outcome = finish_run_branch(
+            root,
+            &base,
+            "boucle/run-4",
+            Some(&config::GitConfig::default()),
+        )
+        .unwrap();
+        assert_eq!(outcome, RunBranchOutcome::Kept { conflict: true });
+        assert_eq!(
+            fs::read_to_string(root.join("tracked.txt")).unwrap(),
+            "from base\n"
+        );
+        assert!(branch_exists(root, "boucle/run-4"));
+    }
+
+    #[test]
+    fn test_push_to_remote_branch() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("agent");
+        let remote = dir.path().join("remote.git");
+        fs::create_dir(&root).unwrap();
+        init_repo(&root);
+        Repository::init_bare(&remote).unwrap();
+        Repository::open(&root)
+            .unwrap()
+            .remote("origin", remote.to_str().unwrap())
+            .unwrap();
+
+        push(&root, "origin", "agent-work").unwrap();
+        assert!(branch_exists(&remote, "agent-work"));
+        let err = push(&root, "nowhere", "agent-work").unwrap_err();
+        assert!(err.to_string().starts_with("git push failed"), "{err}");
+        assert!(push(&root, "origin", "bad..name").is_err());
+    }
+
+    #[test]
+    fn test_boucleignore_patterns() {
+        let patterns = "# junk\n*.log\ntarget/\n/secrets.env\ndownloads/**/*.zip\n!keep.log\n";
+        assert!(ignored(patterns, "run.log"));
+        assert!(ignored(patterns, "memory/run.log"));
+        assert!(!ignored(patterns, "keep.log"));
+        assert!(ignored(patterns, "target/debug/app"));
+        assert!(!ignored(patterns, "target"));
+        assert!(ignored(patterns, "secrets.env"));
+        assert!(!ignored(patterns, "config/secrets.env"));
+        assert!(ignored(patterns, "downloads/a/b/x.zip"));
+        assert!(!ignored(patterns, "memory/STATE.md"));
+
+        let paths = ["memory/".to_string(), "STATE.md".to_string()];
+        assert!(in_paths("memory/notes.md", &paths));
+        assert!(in_paths("STATE.md", &paths));
+        assert!(!in_paths("memory-old/x", &paths));
+        assert!(in_paths("anything", &[]));
+    }
+
+    #[test]
+    fn test_stage_scoped_by_paths_and_boucleignore() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        init_repo(root);
+        fs::create_dir_all(root.join("memory")).unwrap();
+        fs::write(root.join("memory/notes.md"), "n").unwrap();
+        fs::write(root.join("memory/scratch.tmp"), "t").unwrap();
+        fs::write(root.join("junk.bin"), "j").unwrap();
+        fs::write(root.join(IGNORE_FILE), "*.tmp\n").unwrap();
+
+        assert!(stage(root, &["memory".to_string()], &[]).unwrap());
+        assert_eq!(staged(root), "memory/notes.md");
+
+        commit(root, &config::GitConfig::default(), "notes").unwrap();
+        assert!(!stage(root, &["memory".to_string()], &[]).unwrap());
+    }
+
+    #[test]
+    fn test_stage_and_status_leave_out_runner_state() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        init_repo(root);
+        fs::create_dir_all(root.join(".boucle-cache/context")).unwrap();
+        fs::write(root.join(".boucle-cache/context/a.json"), "{}").unwrap();
+        fs::write(root.join(".boucle-failures.json"), "{}").unwrap();
+        fs::write(root.join(".boucle-dev.lock"), "pid=1").unwrap();
+        assert_eq!(status(root).unwrap(), "");
+        assert!(!stage(root, &[], &[]).unwrap());
+
+        fs::write(root.join("notes.md"), "n").unwrap();
+        assert_eq!(status(root).unwrap(), "?? notes.md\n");
+        fs::write(root.join("run.log"), "l").unwrap();
+        assert!(stage(root, &[], &["/run.*"]).unwrap());
+        assert_eq!(staged(root), "notes.md");
+
+        fs::write(root.join(IGNORE_FILE), "*.tmp\n").unwrap();
+        let repo = Repository::open(root).unwrap();
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.reset(head.as_object(), ResetType::Mixed, None)
+            .unwrap();
+        assert!(stage(root, &[], &["/run.*"]).unwrap());
+        assert_eq!(staged(root), ".boucleignore\nnotes.md");
+    }
+
+    #[test]
+    fn test_commit_status_and_last_commit() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        init_repo(root);
+        assert_eq!(status(root).unwrap(), "");
+        fs::write(root.join("tracked.txt"), "changed\n").unwrap();
+        assert_eq!(status(root).unwrap(), " M tracked.txt\n");
+
+        add_all(root);
+        let git = config::GitConfig {
+            commit_name: "Bot".to_string(),
+            ..Default::default()
+        };
+        commit(root, &git, "work").unwrap();
+        let repo = Repository::open(root).unwrap();
+        let author = repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(author.author().name(), Some("Bot"));
+        assert!(last_commit(root).unwrap().unwrap().ends_with(" work"));
+        let stat = diff_stat(root, &head(root).unwrap().unwrap()).unwrap();
+        assert!(stat.contains("tracked.txt | 2 +-"), "{stat}");
+        assert!(stat.contains("1 file changed"), "{stat}");
+        let err = commit(root, &git, "nothing").unwrap_err();
+        assert!(err.to_string().starts_with("git commit failed"), "{err}");
+
+        fs::create_dir(root.join("new dir")).unwrap();
+        fs::write(root.join("new dir/a b.txt"), "x").unwrap();
+        assert_eq!(status(root).unwrap(), "?? \"new dir/\"\n");
+        assert_eq!(
+            quote_path("caf\u{e9}\t\"x\""),
+            "\"caf\\303\\251\\t\\\"x\\\"\""
+        );
+
+        let empty = tempfile::tempdir().unwrap();
+        assert!(status(empty.path()).is_err());
+        Repository::init(empty.path()).unwrap();
+        assert_eq!(last_commit(empty.path()).unwrap(), None);
+    }
+
+    #[test]
+    fn test_sync_aborts_on_conflict() {
+        let dir = tempfile::tempdir().unwrap();
+        let upstream = dir.path().join("upstream");
+        let root = dir.path().join("agent");
+        fs::create_dir(&upstream).unwrap();
+        init_repo(&upstream);
+        clone(upstream.to_str().unwrap(), &root).unwrap();
+        let agent = config::GitConfig {
+            commit_name: "Agent".to_string(),
+            commit_email: "a@x".to_string(),
+            ..Default::default()
+        };
+
+        commit_file(&upstream, "news.txt", "upstream");
+        assert_eq!(
+            sync(&root, "pull --rebase", &agent).unwrap(),
+            SyncOutcome::Synced
+        );
+        assert!(root.join("news.txt").exists());
+
+        // A merge instead of a rebase, with local work on both sides.
+        commit_file(&upstream, "more.txt", "upstream");
+        commit_file(&root, "mine.txt", "agent");
+        fs::write(root.join("news.txt"), "edited").unwrap();
+        assert_eq!(
+            sync(&root, "pull --no-rebase --autostash", &agent).unwrap(),
+            SyncOutcome::Synced
+        );
+        assert_eq!(status(&root).unwrap(), " M news.txt\n");
+        fs::write(root.join("news.txt"), "upstream").unwrap();
+        assert!(root.join("more.txt").exists() && root.join("mine.txt").exists());
+        let branch = current_branch(&upstream).unwrap().unwrap();
+        let summary = last_commit(&root).unwrap().unwrap();
+        assert!(
+            summary.ends_with(&format!("Merge remote-tracking branch 'origin/{branch}'")),
+            "{summary}"
+        );
+        assert_eq!(
+            sync(&root, "fetch -q", &agent).unwrap(),
+            SyncOutcome::Synced
+        );
+        // Not one libgit2 covers, so the git program runs it.
+        assert_eq!(
+            sync(&root, "remote update", &agent).unwrap(),
+            SyncOutcome::Synced
+        );
+
+        commit_file(&upstream, "tracked.txt", "theirs\n");
+        commit_file(&root, "tracked.txt", "ours\n");
+        let outcome = sync(&root, "pull --rebase", &agent).unwrap();
+        assert_eq!(
+            outcome,
+            SyncOutcome::Conflict(vec!["tracked.txt".to_string()])
+        );
+        assert_eq!(
+            fs::read_to_string(root.join("tracked.txt")).unwrap(),
+            "ours\n"
+        );
+        assert_eq!(status(&root).unwrap(), "");
+
+        assert!(sync(&root, "pull --rebase nowhere", &agent).is_err());
+    }
+
+    #[test]
+    fn test_sync_command_parse() {
+        let pull = SyncCommand::parse("pull --rebase --autostash origin main").unwrap();
+        assert_eq!(
+            pull,
+            SyncCommand {
+                pull: true,
+                rebase: Some(true),
+                ff_only: false,
+                autostash: true,
+                remote: Some("origin".to_string()),
+                branch: Some("main".to_string()),
+            }
+        );
+        assert_eq!(SyncCommand::parse("fetch").unwrap(), SyncCommand::default());
+        assert!(SyncCommand::parse("reset --hard").is_err());
+        assert!(SyncCommand::parse("pull --squash").is_err());
+        assert!(SyncCommand::parse("fetch --rebase").is_err());
+        assert!(SyncCommand::parse("pull origin main extra").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_commit_runs_repository_hooks() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        init_repo(root);
+        let hooks = root.join(".git/hooks");
+        fs::create_dir_all(&hooks).unwrap();
+        let hook = |name: &str, script: &str| {
+            fs::write(hooks.join(name), script).unwrap();
+            fs::set_permissions(hooks.join(name), fs::Permissions::from_mode(0o755)).unwrap();
+        };
+        hook(
+            "commit-msg",
+            "#!/bin/sh\necho 'Signed-off-by: Hook' >> \"$1\"\n",
+        );
+        hook("post-commit", "#!/bin/sh\nexit 1\n");
+        commit_file(root, "a.txt", "a");
+        let message = Repository::open(root)
+            .unwrap()
+            .head()
+            .unwrap()
+            .peel_to_commit()
+            .unwrap()
+            .message()
+            .unwrap()
+            .to_string();
+        assert_eq!(message, "a.txt\nSigned-off-by: Hook\n");
+
+        let before = last_commit(root).unwrap();
+        hook("pre-commit", "#!/bin/sh\necho 'lint failed' >&2\nexit 1\n");
+        fs::write(root.join("b.txt"), "b").unwrap();
+        add_all(root);
+        let err = commit(root, &config::GitConfig::default(), "b").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "git commit failed: pre-commit hook: lint failed"
+        );
+        assert_eq!(last_commit(root).unwrap(), before);
+    }
+
+    #[test]
+    fn test_snapshot_outside_repo_is_none() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(snapshot(dir.path(), "t", "n", "e@x").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_snapshot_does_not_touch_worktree() {
+        let dir = tempfile::tempdir().unwrap();
+        init_repo(dir.path());
+        fs::write(dir.path().join("untracked.txt"), "u").unwrap();
+
+        let snap = snapshot(dir.path(), "t", "n", "e@x").unwrap().unwrap();
+        let repo = Repository::open(dir.path()).unwrap();
+        assert_eq!(
+            repo.refname_to_id(SNAPSHOT_REF).unwrap().to_string(),
+            snap.commit
+        );
+        assert_eq!(status(dir.path()).unwrap(), "?? untracked.txt\n");
+        assert!(staged(dir.path()).is_empty());
+    }
+
+    #[test]
+    fn test_rollback_restores_pre_run_state() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        init_repo(root);
+        fs::write(root.join("tracked.txt"), "dirty before run\n").unwrap();
+        fs::write(root.join("notes.txt"), "untracked before run\n").unwrap();
+        fs::create_dir_all(root.join("logs")).unwrap();
+
+        let snap = snapshot(root, "t", "n", "e@x").unwrap().unwrap();
+
+        // Simulate a crashed iteration: edits, new files, even a commit.
+        fs::write(root.join("tracked.txt"), "half-edited\n").unwrap();
+        fs::remove_file(root.join("notes.txt")).unwrap();
+        fs::write(root.join("garbage.txt"), "junk").unwrap();
+        fs::write(root.join("logs/run.log"), "current log").unwrap();
+        super::commit_file(root, &config::GitConfig::default(), "bad", "tracked.txt").unwrap();
+
+        rollback(root, &snap, &["logs"]).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(root.join("tracked.txt")).unwrap(),
+            "dirty before run\n"
+        );
+        assert_eq!(
+            fs::read_to_string(root.join("notes.txt")).unwrap(),
+            "untracked before run\n"
+        );
+        assert!(!root.join("garbage.txt").exists());
+        assert_eq!(
+            fs::read_to_string(root.join("logs/run.log")).unwrap(),
+            "current log"
+        );
+        assert_eq!(head(root).unwrap().unwrap(), snap.head);
+        let status = status(root).unwrap();
+        assert!(status.contains("M tracked.txt"), "{status}");
+        assert!(status.contains("?? notes.txt"), "{status}");
+    }
+}
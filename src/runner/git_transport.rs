@@ -0,0 +1,394 @@
+//! Network transports for libgit2.
+//!
+//! boucle builds libgit2 without its own HTTPS and SSH support, which would
+//! bring OpenSSL into a build that otherwise uses rustls. These stand in:
+//! HTTPS goes through reqwest, one request per exchange of git's smart HTTP
+//! protocol, and SSH through the `ssh` program (or `GIT_SSH`), as git itself
+//! does. Neither prompts: HTTPS asks git's credential helper when the server
+//! wants a login, and `ssh` runs in batch mode with its agent and key files.
+
+use git2::transport::{Service, SmartSubtransport, SmartSubtransportStream, Transport};
+use reqwest::blocking::{Client, Response};
+use reqwest::header::CONTENT_TYPE;
+use reqwest::StatusCode;
+use std::io::{self, Read, Write};
+use std::process;
+use std::sync::{Arc, Mutex, Once};
+use std::time::Duration;
+
+/// How long to wait for a server to accept a connection. Transfers
+/// themselves have no time limit, as a clone can take a while.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Register the transports with libgit2, once per process. Must run before
+/// any fetch, push, or clone.
+pub(super) fn register() {
+    static REGISTER: Once = Once::new();
+    REGISTER.call_once(|| {
+        // SAFETY: registration must not race with libgit2 creating a
+        // transport. `call_once` runs this before the first git operation
+        // that talks to a remote can start, and nothing else registers.
+        unsafe {
+            let _ = git2::transport::register("https", |remote| {
+                Transport::smart(remote, true, Http::default())
+            });
+            for scheme in ["ssh", "ssh+git", "git+ssh"] {
+                let _ = git2::transport::register(scheme, |remote| {
+                    Transport::smart(remote, false, Ssh::default())
+                });
+            }
+        }
+    });
+}
+
+/// Smart HTTP over reqwest.
+#[derive(Default)]
+struct Http {
+    /// The repository URL after any redirect of the first request, which
+    /// the requests that follow go to.
+    base: Arc<Mutex<Option<String>>>,
+}
+
+impl SmartSubtransport for Http {
+    fn action(
+        &self,
+        url: &str,
+        action: Service,
+    ) -> Result<Box<dyn SmartSubtransportStream>, git2::Error> {
+        let (path, service, post) = match action {
+            Service::UploadPackLs => ("/info/refs?service=git-upload-pack", "upload-pack", false),
+            Service::UploadPack => ("/git-upload-pack", "upload-pack", true),
+            Service::ReceivePackLs => {
+                ("/info/refs?service=git-receive-pack", "receive-pack", false)
+            }
+            Service::ReceivePack => ("/git-receive-pack", "receive-pack", true),
+        };
+        let base = self
+            .base
+            .lock()
+            .ok()
+            .and_then(|base| base.clone())
+            .unwrap_or_else(|| url.trim_end_matches('/').to_string());
+        Ok(Box::new(HttpStream {
+            url: format!("{base}{path}"),
+            path,
+            service,
+            post,
+            body: Vec::new(),
+            response: None,
+            base: Arc::clone(&self.base),
+        }))
+    }
+
+    fn close(&self) -> Result<(), git2::Error> {
+        Ok(())
+    }
+}
+
+/// One request: what libgit2 writes is the body, sent on its first read.
+struct HttpStream {
+    url: String,
+    path: &'static str,
+    service: &'static str,
+    post: bool,
+    body: Vec<u8>,
+    response: Option<Response>,
+    base: Arc<Mutex<Option<String>>>,
+}
+
+impl HttpStream {
+    fn send(&mut self) -> io::Result<Response> {
+        let client = Client::builder()
+            .connect_timeout(CONNECT_TIMEOUT)
+            .timeout(None)
+            .user_agent(concat!("git/2.0 (boucle/", env!("CARGO_PKG_VERSION"), ")"))
+            .build()
+            .map_err(io::Error::other)?;
+        let mut response = self.request(&client, None)?;
+        if response.status() == StatusCode::UNAUTHORIZED {
+            let repository = self.url.strip_suffix(self.path).unwrap_or(&self.url);
+            if let Some(login) = credential_helper(repository) {
+                response = self.request(&client, Some(login))?;
+            }
+        }
+        let status = response.status();
+        if !status.is_success() {
+            return Err(io::Error::other(format!(
+                "{} returned {status}",
+                strip_query(&self.url)
+            )));
+        }
+
+        let expected = match self.post {
+            true => format!("application/x-git-{}-result", self.service),
+            false => format!("application/x-git-{}-advertisement", self.service),
+        };
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default();
+        if content_type != expected {
+            return Err(io::Error::other(format!(
+                "{} is not a smart HTTP git server (got '{content_type}')",
+                strip_query(&self.url)
+            )));
+        }
+        if !self.post {
+            let url = response.url().as_str();
+            if let (Some(base), Ok(mut shared)) = (url.strip_suffix(self.path), self.base.lock()) {
+                *shared = Some(base.to_string());
+            }
+        }
+        Ok(response)
+    }
+
+    fn request(&self, client: &Client, login: Option<(String, String)>) -> io::Result<Response> {
+        let mut request = match self.post {
+            true => client
+                .post(&self.url)
+                .header(
+                    CONTENT_TYPE,
+                    format!("application/x-git-{}-request", self.service),
+                )
+                .body(self.body.clone()),
+            false => client.get(&self.url),
+        };
+        if let Some((username, password)) = login {
+            request = request.basic_auth(username, Some(password));
+        }
+        request.send().map_err(io::Error::other)
+    }
+}
+
+impl Read for HttpStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.response.is_none() {
+            self.response = Some(self.send()?);
+        }
+        self.response
+            .as_mut()
+            .map_or(Ok(0), |response| response.read(buf))
+    }
+}
+
+impl Write for HttpStream {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.body.extend_from_slice(data);
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A login for `url` from git's configured credential helper, if any.
+fn credential_helper(url: &str) -> Option<(String, String)> {
+    let config = git2::Config::open_default().ok()?;
+    git2::CredentialHelper::new(url).config(&config).execute()
+}
+
+fn strip_query(url: &str) -> &str {
+    url.split('?').next().unwrap_or(url)
+}
+
+/// Git over `ssh`: one connection serves both the ref listing and the
+/// transfer that follows it.
+#[derive(Default)]
+struct Ssh {
+    child: Arc<Mutex<Option<process::Child>>>,
+}
+
+impl SmartSubtransport for Ssh {
+    fn action(
+        &self,
+        url: &str,
+        action: Service,
+    ) -> Result<Box<dyn SmartSubtransportStream>, git2::Error> {
+        let command = match action {
+            Service::UploadPackLs => "git-upload-pack",
+            Service::ReceivePackLs => "git-receive-pack",
+            // libgit2 reuses the listing's connection for these.
+            Service::UploadPack | Service::ReceivePack => {
+                return Err(git2::Error::from_str("ssh: no connection to reuse"));
+            }
+        };
+        let target = SshTarget::parse(url)
+            .ok_or_else(|| git2::Error::from_str(&format!("ssh: can't parse '{url}'")))?;
+        let program = std::env::var("GIT_SSH").unwrap_or_else(|_| "ssh".to_string());
+        let mut ssh = process::Command::new(&program);
+        ssh.args(["-o", "BatchMode=yes"]);
+        if let Some(port) = &target.port {
+            ssh.args(["-p", port]);
+        }
+        let mut child = ssh
+            .arg(&target.host)
+            .arg(format!(
+                "{command} '{}'",
+                target.path.replace('\'', "'\\''")
+            ))
+            .stdin(process::Stdio::piped())
+            .stdout(process::Stdio::piped())
+            .stderr(process::Stdio::piped())
+            .spawn()
+            .map_err(|e| git2::Error::from_str(&format!("could not run {program}: {e}")))?;
+
+        let stderr = Arc::new(Mutex::new(String::new()));
+        if let Some(mut pipe) = child.stderr.take() {
+            let stderr = Arc::clone(&stderr);
+            std::thread::spawn(move || {
+                let mut text = String::new();
+                let _ = pipe.read_to_string(&mut text);
+                if let Ok(mut shared) = stderr.lock() {
+                    *shared = text;
+                }
+            });
+        }
+        let stream = SshStream {
+            stdin: child.stdin.take(),
+            stdout: child.stdout.take(),
+            stderr,
+            child: Arc::clone(&self.child),
+        };
+        if let Ok(mut slot) = self.child.lock() {
+            *slot = Some(child);
+        }
+        Ok(Box::new(stream))
+    }
+
+    fn close(&self) -> Result<(), git2::Error> {
+        if let Some(mut child) = self.child.lock().ok().and_then(|mut slot| slot.take()) {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+        Ok(())
+    }
+}
+
+struct SshStream {
+    stdin: Option<process::ChildStdin>,
+    stdout: Option<process::ChildStdout>,
+    stderr: Arc<Mutex<String>>,
+    child: Arc<Mutex<Option<process::Child>>>,
+}
+
+impl Read for SshStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = match &mut self.stdout {
+            Some(stdout) => stdout.read(buf)?,
+            None => 0,
+        };
+        if read > 0 || buf.is_empty() {
+            return Ok(read);
+        }
+        // The connection ended; say why if `ssh` failed.
+        let failed = match self
+            .child
+            .lock()
+            .ok()
+            .as_mut()
+            .and_then(|slot| slot.as_mut())
+        {
+            Some(child) => child.wait().is_ok_and(|status| !status.success()),
+            None => false,
+        };
+        if failed {
+            // Give the stderr reader a moment to finish after the exit.
+            std::thread::sleep(Duration::from_millis(50));
+            let stderr = self.stderr.lock().map(|s| s.clone()).unwrap_or_default();
+            let reason = stderr.lines().rev().find(|line| !line.trim().is_empty());
+            return Err(io::Error::other(format!(
+                "ssh failed: {}",
+                reason.unwrap_or("no output").trim()
+            )));
+        }
+        Ok(0)
+    }
+}
+
+impl Write for SshStream {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        match &mut self.stdin {
+            Some(stdin) => stdin.write(data),
+            None => Err(io::Error::from(io::ErrorKind::BrokenPipe)),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.stdin {
+            Some(stdin) => stdin.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Where an SSH URL points: `ssh://[user@]host[:port]/path` or the
+/// scp-like `[user@]host:path`.
+#[derive(Debug, PartialEq, Eq)]
+struct SshTarget {
+    /// `[user@]host`, as `ssh` takes it.
+    host: String,
+    port: Option<String>,
+    path: String,
+}
+
+impl SshTarget {
+    fn parse(url: &str) -> Option<Self> {
+        let scheme = ["ssh://", "ssh+git://", "git+ssh://"]
+            .iter()
+            .find_map(|scheme| url.strip_prefix(scheme));
+        let Some(rest) = scheme else {
+            let (host, path) = url.split_once(':')?;
+            return Some(SshTarget {
+                host: host.to_string(),
+                port: None,
+                path: path.to_string(),
+            });
+        };
+        let (authority, path) = rest.split_at(rest.find('/')?);
+        // `/~user/repo` is relative to that user's home.
+        let path = path
+            .strip_prefix('/')
+            .filter(|p| p.starts_with('~'))
+            .unwrap_or(path);
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) if !port.is_empty() && port.bytes().all(|b| b.is_ascii_digit()) => {
+                (host, Some(port.to_string()))
+            }
+            _ => (authority, None),
+        };
+        Some(SshTarget {
+            host: host.to_string(),
+            port,
+            path: path.to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ssh_target_parse() {
+        let target = |host: &str, port: Option<&str>, path: &str| SshTarget {
+            host: host.to_string(),
+            port: port.map(str::to_string),
+            path: path.to_string(),
+        };
+        assert_eq!(
+            SshTarget::parse("git@github.com:org/agent.git"),
+            Some(target("git@github.com", None, "org/agent.git"))
+        );
+        assert_eq!(
+            SshTarget::parse("ssh://git@example.com:2222/srv/agent.git"),
+            Some(target("git@example.com", Some("2222"), "/srv/agent.git"))
+        );
+        assert_eq!(
+            SshTarget::parse("git+ssh://host/~bot/agent"),
+            Some(target("host", None, "~bot/agent"))
+        );
+        assert_eq!(SshTarget::parse("ssh://host-only"), None);
+    }
+}
@@ -0,0 +1,427 @@
+//! Built-in branch guard: keeps commits and pushes off protected branches.
+//!
+//! The branches come from `[git] protected_branches`. The guard acts at two
+//! points of an iteration:
+//!
+//!   pre-llm     — the claude backend gets a PreToolUse hook that runs
+//!                 `boucle guard-command` on every Bash call the model makes
+//!   pre-commit  — the runner refuses to make its own commit on a protected
+//!                 branch (and keeps a run branch instead of merging it into one)
+//!
+//! Commands are matched on their real argv, not by substring. A command line
+//! is split into shell words (quotes respected) and segments (on unquoted
+//! `;`, `&&`, `||`, `|`, `&` and newlines), so `echo "git commit"` or
+//! `grep 'git push' notes.md` are not mistaken for git invocations.
+
+use super::git;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+/// Exit code that makes the claude CLI refuse a PreToolUse'd tool call.
+const BLOCK_EXIT_CODE: i32 = 2;
+
+/// A `git commit` or `git push` found in a command line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GitCall {
+    Commit {
+        /// Directory from `git -C <dir>`, if given.
+        dir: Option<String>,
+    },
+    Push {
+        dir: Option<String>,
+        /// Destination branches named by the refspecs; empty means "the
+        /// current branch" (git's default `push.default=simple`).
+        targets: Vec<String>,
+        /// `--all`, `--mirror` or `--branches`: every local branch.
+        all: bool,
+    },
+}
+
+/// Whether `branch` matches an entry of the protected list: a branch name,
+/// or a glob such as `release/*` (`*` stays within one `/` segment, `**`
+/// spans several).
+pub fn is_protected(branch: &str, protected: &[String]) -> bool {
+    let options = glob::MatchOptions {
+        require_literal_separator: true,
+        ..Default::default()
+    };
+    protected.iter().any(|p| {
+        p == branch || glob::Pattern::new(p).is_ok_and(|p| p.matches_with(branch, options))
+    })
+}
+
+/// Every `git commit` / `git push` that `command` would actually run.
+pub fn git_calls(command: &str) -> Vec<GitCall> {
+    let mut calls = Vec::new();
+    for segment in segments(command) {
+        segment_calls(&segment, &mut calls);
+    }
+    calls
+}
+
+/// Why `command` must not run, or `None` if it touches no protected branch.
+///
+/// `cwd` is where the command would run; `git -C <dir>` is resolved
+/// against it to find the branch a commit would land on.
+pub fn check_command(command: &str, cwd: &Path, protected: &[String]) -> Option<String> {
+    if protected.is_empty() {
+        return None;
+    }
+    for call in git_calls(command) {
+        let (dir, targets, all, verb) = match call {
+            GitCall::Commit { dir } => (dir, Vec::new(), false, "commit on"),
+            GitCall::Push { dir, targets, all } => (dir, targets, all, "push to"),
+        };
+        if all {
+            return Some(format!(
+                "git push with --all/--mirror would update protected branches ({})",
+                protected.join(", ")
+            ));
+        }
+        let dir = match dir {
+            Some(d) => cwd.join(d),
+            None => cwd.to_path_buf(),
+        };
+        let current = || git::current_branch(&dir).ok().flatten();
+        let branches = if targets.is_empty() {
+            current().into_iter().collect()
+        } else {
+            targets
+                .into_iter()
+                .filter_map(|t| if t == "HEAD" { current() } else { Some(t) })
+                .collect::<Vec<_>>()
+        };
+        if let Some(branch) = branches.iter().find(|b| is_protected(b, protected)) {
+            return Some(format!("git {verb} protected branch '{branch}'"));
+        }
+    }
+    None
+}
+
+/// Claude CLI `--settings` JSON that routes Bash calls through
+/// `boucle guard-command` for this agent root.
+pub fn claude_settings(root: &Path) -> String {
+    let exe = super::boucle_bin();
+    let command = format!(
+        "{} --root {} guard-command",
+        shell_quote(&exe.to_string_lossy()),
+        shell_quote(&root.to_string_lossy())
+    );
+    serde_json::json!({
+        "hooks": {
+            "PreToolUse": [{
+                "matcher": "Bash",
+                "hooks": [{ "type": "command", "command": command }]
+            }]
+        }
+    })
+    .to_string()
+}
+
+/// `boucle guard-command`: read a PreToolUse payload from stdin and return
+/// the exit code for the claude CLI (2 blocks the tool call).
+pub fn guard_stdin(root: &Path, protected: &[String]) -> io::Result<i32> {
+    let mut input = String::new();
+    io::stdin().read_to_string(&mut input)?;
+    let payload: serde_json::Value = serde_json::from_str(&input).unwrap_or_default();
+    if payload["tool_name"] != "Bash" {
+        return Ok(0);
+    }
+    let Some(command) = payload["tool_input"]["command"].as_str() else {
+        return Ok(0);
+    };
+    let cwd = payload["cwd"]
+        .as_str()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| root.to_path_buf());
+    match check_command(command, &cwd, protected) {
+        Some(reason) => {
+            eprintln!(
+                "Blocked by the boucle branch guard: {reason}. \
+                 Commit on a feature branch and leave protected branches to a human."
+            );
+            Ok(BLOCK_EXIT_CODE)
+        }
+        None => Ok(0),
+    }
+}
+
+/// Single-quote `s` for a POSIX shell.
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// Split a command line into segments of shell words.
+///
+/// Quoted text stays inside its word, so separators and `#` in quotes are
+/// literal. Command substitutions are not expanded.
+fn segments(command: &str) -> Vec<Vec<String>> {
+    let mut segments = Vec::new();
+    let mut words: Vec<String> = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut chars = command.chars().peekable();
+
+    let end_word = |words: &mut Vec<String>, word: &mut String, in_word: &mut bool| {
+        if *in_word {
+            words.push(std::mem::take(word));
+            *in_word = false;
+        }
+    };
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                in_word = true;
+                for q in chars.by_ref() {
+                    if q == '\'' {
+                        break;
+                    }
+                    word.push(q);
+                }
+            }
+            '"' => {
+                in_word = true;
+                while let Some(q) = chars.next() {
+                    match q {
+                        '"' => break,
+                        '\\' => match chars.next() {
+                            Some(e @ ('"' | '\\' | '$' | '`')) => word.push(e),
+                            Some('\n') => {}
+                            Some(e) => {
+                                word.push('\\');
+                                word.push(e);
+                            }
+                            None => word.push('\\'),
+                        },
+                        _ => word.push(q),
+                    }
+                }
+            }
+            '\\' => {
+                if let Some(e) = chars.next() {
+                    if e != '\n' {
+                        in_word = true;
+                        word.push(e);
+                    }
+                }
+            }
+            '#' if !in_word => {
+                for q in chars.by_ref() {
+                    if q == '\n' {
+                        break;
+                    }
+                }
+                end_word(&mut words, &mut word, &mut in_word);
+                segments.push(std::mem::take(&mut words));
+            }
+            ';' | '&' | '|' | '\n' | '(' | ')' => {
+                end_word(&mut words, &mut word, &mut in_word);
+                segments.push(std::mem::take(&mut words));
+            }
+            c if c.is_whitespace() => end_word(&mut words, &mut word, &mut in_word),
+            _ => {
+                in_word = true;
+                word.push(c);
+            }
+        }
+    }
+    end_word(&mut words, &mut word, &mut in_word);
+    segments.push(words);
+    segments.retain(|s| !s.is_empty());
+    segments
+}
+
+/// Find the git call in one segment, following `env`, `sudo` and
+/// `sh -c '...'` wrappers.
+fn segment_calls(words: &[String], calls: &mut Vec<GitCall>) {
+    let mut i = 0;
+    while i < words.len() {
+        let word = words[i].as_str();
+        if is_assignment(word) {
+            i += 1;
+            continue;
+        }
+        match basename(word) {
+            "env" | "command" | "exec" | "nohup" | "time" | "sudo" | "nice" => {
+                i += 1;
+                while words.get(i).is_some_and(|w| w.starts_with('-')) {
+                    // `env -u NAME` / `sudo -u user` take a value.
+                    let takes_value = matches!(words[i].as_str(), "-u" | "-C" | "-g" | "-n");
+                    i += if takes_value { 2 } else { 1 };
+                }
+            }
+            "sh" | "bash" | "zsh" | "dash" => {
+                let script = words[i + 1..]
+                    .iter()
+                    .position(|w| w.starts_with('-') && !w.starts_with("--") && w.contains('c'))
+                    .and_then(|p| words.get(i + 1 + p + 1));
+                if let Some(script) = script {
+                    calls.extend(git_calls(script));
+                }
+                return;
+            }
+            "git" => {
+                if let Some(call) = parse_git(&words[i + 1..]) {
+                    calls.push(call);
+                }
+                return;
+            }
+            _ => return,
+        }
+    }
+}
+
+/// Parse the arguments after `git`.
+fn parse_git(args: &[String]) -> Option<GitCall> {
+    let mut dir = None;
+    let mut i = 0;
+    while let Some(arg) = args.get(i) {
+        match arg.as_str() {
+            "-C" => {
+                dir = args.get(i + 1).cloned();
+                i += 2;
+            }
+            "-c" | "--git-dir" | "--work-tree" | "--namespace" | "--config-env"
+            | "--super-prefix" => i += 2,
+            a if a.starts_with('-') => i += 1,
+            _ => break,
+        }
+    }
+    let rest = args.get(i + 1..).unwrap_or_default();
+    match args.get(i)?.as_str() {
+        "commit" => Some(GitCall::Commit { dir }),
+        "push" => {
+            let (targets, all) = push_targets(rest);
+            Some(GitCall::Push { dir, targets, all })
+        }
+        _ => None,
+    }
+}
+
+/// Destination branches of `git push <args>`.
+fn push_targets(args: &[String]) -> (Vec<String>, bool) {
+    let mut all = false;
+    let mut positional = Vec::new();
+    let mut i = 0;
+    while let Some(arg) = args.get(i) {
+        match arg.as_str() {
+            "--all" | "--mirror" | "--branches" => all = true,
+            "--repo" | "-o" | "--push-option" | "--receive-pack" | "--exec" => i += 1,
+            "--" => {
+                positional.extend(args[i + 1..].iter().cloned());
+                break;
+            }
+            a if a.starts_with('-') || a.contains(['<', '>']) => {}
+            a => positional.push(a.to_string()),
+        }
+        i += 1;
+    }
+    // The first positional is the remote; the rest are refspecs.
+    let targets = positional
+        .iter()
+        .skip(1)
+        .map(|spec| {
+            let spec = spec.trim_start_matches('+');
+            let dst = spec.rsplit_once(':').map_or(spec, |(_, dst)| dst);
+            dst.trim_start_matches("refs/heads/").to_string()
+        })
+        .collect();
+    (targets, all)
+}
+
+/// `NAME=value` prefix of a simple command.
+fn is_assignment(word: &str) -> bool {
+    word.split_once('=').is_some_and(|(name, _)| {
+        !name.is_empty()
+            && !name.starts_with(|c: char| c.is_ascii_digit())
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    })
+}
+
+fn basename(word: &str) -> &str {
+    word.rsplit('/').next().unwrap_or(word)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_git_calls_follow_real_argv() {
+        assert_eq!(
+            git_calls("git -C . commit -m 'fix: git push later'"),
+            vec![GitCall::Commit {
+                dir: Some(".".to_string())
+            }]
+        );
+        assert_eq!(
+            git_calls("cd repo && GIT_TRACE=1 env -u X /usr/bin/git -c a=b push origin HEAD:main"),
+            vec![GitCall::Push {
+                dir: None,
+                targets: vec!["main".to_string()],
+                all: false
+            }]
+        );
+        assert_eq!(
+            git_calls("bash -lc 'git add -A; git commit -qm wip'"),
+            vec![GitCall::Commit { dir: None }]
+        );
+
+        // Mentions of git that do not run it.
+        assert!(git_calls("echo \"git commit -m x\"").is_empty());
+        assert!(git_calls("grep -r 'git push' docs/ | wc -l").is_empty());
+        assert!(git_calls("git log --grep commit; git status").is_empty());
+        assert!(git_calls("# git commit\nls").is_empty());
+        assert!(git_calls("gh pr create --body \"then git push origin main\"").is_empty());
+    }
+
+    #[test]
+    fn test_push_targets() {
+        let words = |s: &str| s.split(' ').map(String::from).collect::<Vec<_>>();
+        assert_eq!(push_targets(&words("origin")), (Vec::new(), false));
+        assert_eq!(
+            push_targets(&words("-u origin +feature:refs/heads/main dev")),
+            (vec!["main".to_string(), "dev".to_string()], false)
+        );
+        assert!(push_targets(&words("--mirror backup")).1);
+    }
+
+    #[test]
+    fn test_protected_branch_patterns() {
+        let protected = vec!["main".to_string(), "release/*".to_string()];
+        assert!(is_protected("main", &protected));
+        assert!(is_protected("release/1.4", &protected));
+        assert!(!is_protected("release/1.4/hotfix", &protected));
+        assert!(!is_protected("release", &protected));
+        assert!(!is_protected("maintenance", &protected));
+        assert!(is_protected("a/b/c", &["a/**".to_string()]));
+        assert!(!is_protected("main", &[]));
+    }
+
+    #[test]
+    fn test_check_command_uses_current_branch() {
+        let dir = TempDir::new().unwrap();
+        git::tests::init_repo(dir.path());
+        let branch = git::current_branch(dir.path()).unwrap().unwrap();
+        let protected = vec![branch.clone()];
+
+        let reason = check_command("git commit -am wip", dir.path(), &protected).unwrap();
+        assert!(reason.contains(&format!("'{branch}'")), "{reason}");
+        assert!(check_command("git push", dir.path(), &protected).is_some());
+        assert!(check_command("git push origin HEAD", dir.path(), &protected).is_some());
+        assert!(check_command("git push origin feature", dir.path(), &protected).is_none());
+        assert!(check_command("echo git commit", dir.path(), &protected).is_none());
+        assert!(check_command("git commit -m x", dir.path(), &[]).is_none());
+
+        git::start_run_branch(dir.path(), "feature", false).unwrap();
+        assert!(check_command("git commit -m x", dir.path(), &protected).is_none());
+        assert!(check_command(
+            "git push origin feature:main",
+            dir.path(),
+            &["main".to_string()]
+        )
+        .is_some());
+    }
+}
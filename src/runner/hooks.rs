@@ -1,84 +1,640 @@
 //! Lifecycle hooks for the agent loop.
 //!
-//! Hooks are scripts in the hooks/ directory that run at specific points:
+//! Hooks are scripts in the hooks/ directory (or in `<hook>.d/` directories
+//! there, for several scripts per point) that run at specific points:
 //! - pre-run: before anything else
 //! - post-context: after context assembly
+//! - pre-llm: right before the LLM is called
 //! - post-llm: after the LLM runs
+//! - pre-commit: before git commit, with the changes staged; failing blocks the commit
 //! - post-commit: after git commit
+//! - post-run: after an iteration completes, whatever the LLM's exit code
+//! - on-abort: after an iteration is abandoned because a stage failed
+//! - on-remember: after a memory entry or journal note is written, from the
+//!   CLI, the MCP server, or the runner; a veto or failure undoes the write
+//!
+//! Each hook receives a [`HookPayload`] as one JSON document on stdin, and
+//! the same facts as `BOUCLE_*` environment variables.
+//! Whatever it prints goes to the iteration log. Printing
+//! `{"context": "...", "title": "..."}` instead adds a context section: hooks
+//! up to pre-llm feed this iteration's LLM input, later hooks the next one.
+//!
+//! A script with a `# boucle: async` header comment (or every script of a
+//! hook with `[hooks.<name>] async = true`) runs in the background: the loop
+//! moves on at once, and the runner waits for it before exiting and logs how
+//! it ended. Background scripts cannot veto or add context.
+//!
+//! A hook vetoes by exiting with [`BLOCK_EXIT_CODE`] or printing
+//! `{"decision": "block", "reason": "..."}`. A veto from a hook up to pre-llm
+//! skips the iteration; one from post-llm or pre-commit blocks the commit.
 
-use std::path::Path;
-use std::{fs, process};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use std::{fs, process, thread};
 
-use super::RunnerError;
+use super::record::{HookResult, HookStatus, HOOK_OUTPUT_BYTES};
+use super::{builtin_plugins, context, sandbox, RunnerError};
+use crate::config::{self, HooksConfig, SandboxConfig};
+use crate::runner;
 
 /// Valid hook names.
-const VALID_HOOKS: &[&str] = &["pre-run", "post-context", "post-llm", "post-commit"];
+pub(super) const VALID_HOOKS: &[&str] = &[
+    "pre-run",
+    "post-context",
+    "pre-llm",
+    "post-llm",
+    "pre-commit",
+    "post-commit",
+    "post-run",
+    "on-abort",
+    "on-remember",
+];
+
+/// What a hook is told about the iteration, written to its stdin as JSON.
+/// Fields that do not apply yet at `phase` are `null`.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct HookPayload {
+    /// The hook being run, e.g. `"pre-commit"`.
+    pub phase: String,
+    pub agent: String,
+    /// The task of the named schedule that started the run, if any.
+    pub task: Option<String>,
+    /// 1-based iteration number, counted from the run logs.
+    pub iteration: usize,
+    /// Iteration timestamp, as in the log file name.
+    pub timestamp: String,
+    pub log_file: String,
+    /// Absolute path of the memory directory.
+    pub memory_dir: String,
+    /// Size of the assembled context, from `post-context` on.
+    pub context_bytes: Option<usize>,
+    /// LLM exit code, from `post-llm` on.
+    pub exit_code: Option<i32>,
+    /// Why the iteration was abandoned (`on-abort` only).
+    pub error: Option<String>,
+    /// The entry just written (`on-remember` only).
+    pub memory: Option<MemoryEvent>,
+}
+
+/// A memory write, as reported to the `on-remember` hook.
+#[derive(Debug, Clone, Serialize)]
+pub struct MemoryEvent {
+    /// Who wrote it: `"cli"`, `"mcp"`, or `"runner"`.
+    pub source: String,
+    /// `"knowledge"` for a new entry, `"journal"` for a journal note.
+    pub kind: String,
+    /// The file written (for a journal note, the day's journal file).
+    pub path: String,
+    /// Entry type, e.g. `"fact"` (knowledge only).
+    pub entry_type: Option<String>,
+    /// Entry title (knowledge only).
+    pub title: Option<String>,
+    pub tags: Vec<String>,
+}
+
+impl MemoryEvent {
+    pub fn knowledge(
+        source: &str,
+        path: &Path,
+        entry_type: &str,
+        title: &str,
+        tags: &[String],
+    ) -> Self {
+        Self {
+            source: source.to_string(),
+            kind: "knowledge".to_string(),
+            path: path.display().to_string(),
+            entry_type: Some(entry_type.to_string()),
+            title: Some(title.to_string()),
+            tags: tags.to_vec(),
+        }
+    }
+
+    pub fn journal(source: &str, path: &Path) -> Self {
+        Self {
+            source: source.to_string(),
+            kind: "journal".to_string(),
+            path: path.display().to_string(),
+            entry_type: None,
+            title: None,
+            tags: Vec::new(),
+        }
+    }
+}
+
+/// Exit code with which a hook blocks instead of failing.
+pub const BLOCK_EXIT_CODE: i32 = 2;
+
+/// How many leading lines are searched for a `boucle: async` comment.
+const HEADER_LINES: usize = 10;
+
+/// Extensions tried, in order, after the bare hook name.
+const SCRIPT_EXTENSIONS: &[&str] = &["sh", "py", "rb", "ps1", "cmd", "bat"];
+
+/// Background hook scripts still running, by the log file of their
+/// iteration; see [`BackgroundGuard`].
+static BACKGROUND: Mutex<Vec<(String, thread::JoinHandle<()>)>> = Mutex::new(Vec::new());
+
+/// Results of the scripts run so far, by the log file of their iteration;
+/// collected into the run record with [`take_results`].
+static RESULTS: Mutex<Vec<(String, HookResult)>> = Mutex::new(Vec::new());
+
+/// Context that hooks returned for a later phase, saved here when that
+/// phase is the next iteration.
+pub const PENDING_CONTEXT_FILE: &str = ".boucle-hook-context.json";
+
+/// Context a hook asked to add.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HookContext {
+    pub hook: String,
+    pub title: Option<String>,
+    pub text: String,
+}
+
+/// What a successful hook printed.
+#[derive(Debug, Default)]
+pub struct HookOutput {
+    pub stdout: String,
+    pub context: Vec<HookContext>,
+    /// Reason, when the hook vetoed the run.
+    pub block: Option<String>,
+    /// Why an optional hook failed; its failure does not stop the run.
+    pub failure: Option<String>,
+    /// Scripts started in the background.
+    pub background: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Decision {
+    Allow,
+    Block,
+}
 
-/// Run a named hook if it exists.
-pub fn run_hook(hooks_dir: &Path, hook_name: &str, working_dir: &Path) -> Result<(), RunnerError> {
+/// The JSON a hook may print to contribute context or veto the run.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct HookResponse {
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default)]
+    context: Option<String>,
+    #[serde(default)]
+    decision: Option<Decision>,
+    #[serde(default)]
+    reason: Option<String>,
+}
+
+/// Run a named hook if it exists, passing `payload` (with `phase` set to
+/// `hook_name`) on stdin.
+///
+/// The hook is the `hook_name` script plus every executable in
+/// `hook_name.d/`, run in lexical order. All of them run even when one
+/// fails, and the hook fails if any did; a veto skips the rest. Each script
+/// gets the hook's `[hooks]` timeout. When the hook is not `required`, its
+/// failure is returned in [`HookOutput::failure`] instead of as an error.
+pub fn run_hook(
+    hooks_dir: &Path,
+    hook_name: &str,
+    working_dir: &Path,
+    config: &HooksConfig,
+    payload: &HookPayload,
+) -> Result<HookOutput, RunnerError> {
     if !VALID_HOOKS.contains(&hook_name) {
         return Err(RunnerError::Hook(format!("Unknown hook: {hook_name}")));
     }
+    let timeout = config.timeout(hook_name);
+    let timeout = config::parse_interval(timeout)
+        .map(Duration::from_secs)
+        .map_err(|e| RunnerError::Hook(format!("hooks timeout '{timeout}': {e}")))?;
 
-    if !hooks_dir.exists() {
-        return Ok(());
+    if !hooks_dir.exists() || !config.enabled(hook_name) {
+        return Ok(HookOutput::default());
     }
 
-    // Look for hook script (with or without extension)
-    let hook_path = find_hook_script(hooks_dir, hook_name);
-
-    let hook_path = match hook_path {
-        Some(p) => p,
-        None => return Ok(()), // No hook, that's fine
+    let scripts = hook_scripts(hooks_dir, hook_name)?;
+    let payload = HookPayload {
+        phase: hook_name.to_string(),
+        ..payload.clone()
+    };
+    let launch = Launch {
+        working_dir,
+        env: hook_env(working_dir, &payload),
+        payload_json: serde_json::to_string(&payload).map_err(std::io::Error::other)?,
+        sandbox: config.sandbox(hook_name),
+        memory_dir: Path::new(&payload.memory_dir),
     };
 
-    // Detect interpreter from shebang
-    let content = fs::read_to_string(&hook_path)?;
-    let interpreter = detect_shebang(&content);
-
-    let output = match interpreter {
-        Some(interp) => process::Command::new(interp)
-            .arg(&hook_path)
-            .current_dir(working_dir)
-            .output()?,
-        None => process::Command::new(&hook_path)
-            .current_dir(working_dir)
-            .output()?,
+    let mut combined = HookOutput::default();
+    let mut failures = Vec::new();
+    for (label, path) in &scripts {
+        let mut result = HookResult {
+            hook: hook_name.to_string(),
+            script: label.clone(),
+            ..HookResult::default()
+        };
+        if config.background(hook_name) || header_async(&fs::read_to_string(path)?) {
+            let child = spawn_script(path, &launch)?;
+            start_background(child, label.clone(), timeout, payload.log_file.clone());
+            combined.background.push(label.clone());
+            result.status = HookStatus::Background;
+            record_result(&payload.log_file, result);
+            continue;
+        }
+        let started = Instant::now();
+        let output = run_script(path, label, &launch, timeout, &mut result);
+        result.duration_ms = started.elapsed().as_millis() as u64;
+        record_result(&payload.log_file, result);
+        let output = match output {
+            Ok(output) => output,
+            Err(err) => {
+                failures.push(err.to_string());
+                continue;
+            }
+        };
+        if scripts.len() > 1 && !output.stdout.trim().is_empty() {
+            combined.stdout.push_str(&format!("--- {label} ---\n"));
+        }
+        combined.stdout.push_str(&output.stdout);
+        combined.context.extend(output.context);
+        if output.block.is_some() {
+            combined.block = output.block;
+            break;
+        }
+    }
+    if !failures.is_empty() {
+        let failure = failures.join("; ");
+        if config.required(hook_name) {
+            return Err(RunnerError::Hook(failure));
+        }
+        combined.failure = Some(failure);
+    }
+    Ok(combined)
+}
+
+/// Environment variables for a hook script, the ones context plugins get
+/// plus the run state from `payload`.
+fn hook_env(root: &Path, payload: &HookPayload) -> Vec<(&'static str, String)> {
+    let mut env = vec![
+        ("BOUCLE_ROOT", root.display().to_string()),
+        ("BOUCLE_BIN", runner::boucle_bin().display().to_string()),
+        ("BOUCLE_MEMORY", payload.memory_dir.clone()),
+        ("BOUCLE_ITERATION", payload.iteration.to_string()),
+        ("BOUCLE_AGENT_NAME", payload.agent.clone()),
+        ("BOUCLE_PHASE", payload.phase.clone()),
+        ("BOUCLE_LOG_FILE", payload.log_file.clone()),
+    ];
+    if let Some(ref task) = payload.task {
+        env.push(("BOUCLE_TASK", task.clone()));
+    }
+    if let Some(code) = payload.exit_code {
+        env.push(("BOUCLE_LAST_EXIT_CODE", code.to_string()));
+    }
+    if let Some(ref memory) = payload.memory {
+        env.push(("BOUCLE_MEMORY_ENTRY", memory.path.clone()));
+    }
+    env
+}
+
+/// The scripts making up hook `name`, labelled by their path in
+/// `hooks_dir`: the `name` script itself, then `name.d/` in lexical order.
+pub(super) fn hook_scripts(
+    hooks_dir: &Path,
+    name: &str,
+) -> Result<Vec<(String, PathBuf)>, std::io::Error> {
+    let mut scripts = Vec::new();
+    // The single script is labelled by the hook name alone.
+    if let Some(path) = find_hook_script(hooks_dir, name) {
+        scripts.push((name.to_string(), path));
+    }
+
+    let dir = hooks_dir.join(format!("{name}.d"));
+    if dir.is_dir() {
+        let mut entries: Vec<_> = fs::read_dir(&dir)?.filter_map(|e| e.ok()).collect();
+        entries.sort_by_key(|e| e.file_name());
+        for entry in entries {
+            let path = entry.path();
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            if !path.is_file() || file_name.starts_with('.') {
+                continue;
+            }
+            let content = fs::read(&path)?;
+            if detect_shebang(&String::from_utf8_lossy(&content)).is_none()
+                && !context::is_executable(&path)?
+            {
+                continue;
+            }
+            scripts.push((format!("{name}.d/{file_name}"), path));
+        }
+    }
+    Ok(scripts)
+}
+
+/// Files in `hooks_dir` (and its subdirectories) that no hook runs: a
+/// misspelt hook name, say, or a `.d/` entry that is not executable.
+pub(super) fn unused_files(hooks_dir: &Path) -> Result<Vec<String>, std::io::Error> {
+    let mut used = Vec::new();
+    for name in VALID_HOOKS {
+        used.extend(
+            hook_scripts(hooks_dir, name)?
+                .into_iter()
+                .map(|(_, path)| path),
+        );
+    }
+
+    let mut unused = Vec::new();
+    let mut entries: Vec<_> = fs::read_dir(hooks_dir)?.filter_map(|e| e.ok()).collect();
+    entries.sort_by_key(|e| e.file_name());
+    for entry in entries {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let path = entry.path();
+        if name.starts_with('.') {
+            continue;
+        }
+        if path.is_dir() {
+            let mut inner: Vec<_> = fs::read_dir(&path)?.filter_map(|e| e.ok()).collect();
+            inner.sort_by_key(|e| e.file_name());
+            for file in inner {
+                let file_name = file.file_name().to_string_lossy().to_string();
+                if file.path().is_file()
+                    && !file_name.starts_with('.')
+                    && !used.contains(&file.path())
+                {
+                    unused.push(format!("{name}/{file_name}"));
+                }
+            }
+        } else if !used.contains(&path) {
+            unused.push(name);
+        }
+    }
+    Ok(unused)
+}
+
+/// Whether a script's header comment asks for it to run in the background.
+pub(super) fn header_async(script: &str) -> bool {
+    script.lines().take(HEADER_LINES).any(|line| {
+        let comment = line
+            .trim_start()
+            .trim_start_matches(['#', '/', '-', ';'])
+            .trim();
+        comment == "boucle: async"
+    })
+}
+
+/// Wait for `child` on a thread of its own, then log how it ended to
+/// `log_file`.
+fn start_background(child: process::Child, label: String, timeout: Duration, log_file: String) {
+    let key = log_file.clone();
+    let handle = thread::spawn(move || {
+        let message = match runner::wait_with_output_timeout(child, timeout) {
+            Ok(output) if output.timed_out => format!(
+                "Background hook '{label}' timed out after {}s",
+                timeout.as_secs()
+            ),
+            Ok(output) => {
+                let mut message = match output.status.code() {
+                    Some(0) => format!("Background hook '{label}' finished"),
+                    Some(code) => format!("Background hook '{label}' failed (exit {code})"),
+                    None => format!("Background hook '{label}' was killed by a signal"),
+                };
+                for stream in [&output.stdout, &output.stderr] {
+                    let text = String::from_utf8_lossy(stream);
+                    let text = text.trim_end();
+                    if !text.is_empty() {
+                        message.push_str(&format!("\n  {}", text.replace('\n', "\n  ")));
+                    }
+                }
+                message
+            }
+            Err(e) => format!("Background hook '{label}' could not be waited for: {e}"),
+        };
+        if log_file.is_empty() || runner::log(Path::new(&log_file), &message).is_err() {
+            tracing::warn!("{message}");
+        }
+    });
+    if let Ok(mut background) = BACKGROUND.lock() {
+        background.push((key, handle));
+    }
+}
+
+/// Wait for the background hook scripts of the iteration logging to
+/// `log_file` to finish (or time out).
+fn wait_for_background(log_file: &str) {
+    let handles: Vec<_> = match BACKGROUND.lock() {
+        Ok(mut background) => {
+            let (mine, others) = std::mem::take(&mut *background)
+                .into_iter()
+                .partition(|(key, _)| key == log_file);
+            *background = others;
+            mine
+        }
+        Err(_) => return,
     };
+    for (_, handle) in handles {
+        let _ = handle.join();
+    }
+}
+
+/// Reaps an iteration's background hook scripts when it ends, whichever
+/// way it ends.
+pub struct BackgroundGuard {
+    pub log_file: String,
+}
+
+impl Drop for BackgroundGuard {
+    fn drop(&mut self) {
+        wait_for_background(&self.log_file);
+    }
+}
+
+/// What each script of a hook is started with.
+struct Launch<'a> {
+    working_dir: &'a Path,
+    env: Vec<(&'static str, String)>,
+    payload_json: String,
+    sandbox: Option<&'a SandboxConfig>,
+    memory_dir: &'a Path,
+}
+
+/// Start one hook script, writing the payload to its stdin.
+fn spawn_script(hook_path: &Path, launch: &Launch) -> Result<process::Child, RunnerError> {
+    let mut cmd = runner::script_command(hook_path);
+    cmd.current_dir(launch.working_dir)
+        .envs(launch.env.iter().map(|(key, value)| (*key, value)));
+    if let Some(config) = launch.sandbox {
+        cmd = sandbox::wrap(cmd, config, launch.working_dir, launch.memory_dir)
+            .map_err(|e| RunnerError::Hook(format!("sandbox for {}: {e}", hook_path.display())))?;
+    }
+    cmd.stdin(process::Stdio::piped())
+        .stdout(process::Stdio::piped())
+        .stderr(process::Stdio::piped());
+    runner::configure_child_process_group(&mut cmd);
+    let mut child = cmd.spawn()?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        // A hook that never reads stdin may exit before the write; that is
+        // not an error.
+        let _ = writeln!(stdin, "{}", launch.payload_json);
+    }
+    Ok(child)
+}
+
+/// Run one hook script, writing the payload to its stdin.
+fn run_script(
+    hook_path: &Path,
+    label: &str,
+    launch: &Launch,
+    timeout: Duration,
+    result: &mut HookResult,
+) -> Result<HookOutput, RunnerError> {
+    result.status = HookStatus::Failed;
+    let child = spawn_script(hook_path, launch)?;
+    let output = runner::wait_with_output_timeout(child, timeout)?;
+    let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+    let combined = [
+        stdout.trim_end(),
+        String::from_utf8_lossy(&output.stderr).trim_end(),
+    ]
+    .into_iter()
+    .filter(|text| !text.is_empty())
+    .collect::<Vec<_>>()
+    .join("\n");
+    result.output = builtin_plugins::truncate_utf8(&combined, HOOK_OUTPUT_BYTES).to_string();
+    result.exit_code = output.status.code();
+    if output.timed_out {
+        result.status = HookStatus::TimedOut;
+        return Err(RunnerError::Hook(format!(
+            "Hook '{label}' timed out after {}s",
+            timeout.as_secs()
+        )));
+    }
+
+    if output.status.code() == Some(BLOCK_EXIT_CODE) {
+        result.status = HookStatus::Blocked;
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let reason = [stderr.trim(), stdout.trim()]
+            .into_iter()
+            .find(|text| !text.is_empty())
+            .unwrap_or("no reason given")
+            .to_string();
+        return Ok(HookOutput {
+            stdout,
+            block: Some(reason),
+            ..HookOutput::default()
+        });
+    }
 
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
         return Err(RunnerError::Hook(format!(
-            "Hook '{hook_name}' failed (exit {}): {stderr}",
+            "Hook '{label}' failed (exit {}): {stderr}",
             output.status.code().unwrap_or(-1)
         )));
     }
 
-    Ok(())
+    result.status = HookStatus::Ok;
+    let Ok(response) = serde_json::from_str::<HookResponse>(stdout.trim()) else {
+        return Ok(HookOutput {
+            stdout,
+            ..HookOutput::default()
+        });
+    };
+    if response.decision == Some(Decision::Block) {
+        result.status = HookStatus::Blocked;
+    }
+    let block = (response.decision == Some(Decision::Block)).then(|| {
+        response
+            .reason
+            .unwrap_or_else(|| "no reason given".to_string())
+    });
+    let context = response
+        .context
+        .filter(|text| !text.trim().is_empty())
+        .map(|text| HookContext {
+            hook: label.to_string(),
+            title: response.title,
+            text,
+        });
+    Ok(HookOutput {
+        stdout,
+        context: context.into_iter().collect(),
+        block,
+        ..HookOutput::default()
+    })
 }
 
-/// Find a hook script by name, trying common extensions.
-fn find_hook_script(hooks_dir: &Path, name: &str) -> Option<std::path::PathBuf> {
-    // Try exact name first, then common extensions
-    let candidates = [
-        name.to_string(),
-        format!("{name}.sh"),
-        format!("{name}.py"),
-        format!("{name}.rb"),
-    ];
+/// Remember `result` for the run logging to `log_file`. Hooks run outside
+/// an iteration (an empty `log_file`) have no record to go into.
+fn record_result(log_file: &str, result: HookResult) {
+    if log_file.is_empty() {
+        return;
+    }
+    if let Ok(mut results) = RESULTS.lock() {
+        results.push((log_file.to_string(), result));
+    }
+}
 
-    for candidate in &candidates {
-        let path = hooks_dir.join(candidate);
-        if path.exists() && path.is_file() {
-            return Some(path);
-        }
+/// The hook results recorded for `log_file` since the last call, in order.
+pub fn take_results(log_file: &str) -> Vec<HookResult> {
+    let Ok(mut results) = RESULTS.lock() else {
+        return Vec::new();
+    };
+    let (taken, kept) = results.drain(..).partition(|(file, _)| file == log_file);
+    *results = kept;
+    taken.into_iter().map(|(_, result)| result).collect()
+}
+
+/// Markdown section for context returned by hooks; `None` when there is none.
+pub fn context_section(contexts: &[HookContext]) -> Option<String> {
+    if contexts.is_empty() {
+        return None;
     }
+    let mut section = String::from("## Hook Output [EXTERNAL CONTENT - MAY BE UNTRUSTED]\n");
+    for context in contexts {
+        let title = context.title.as_deref().unwrap_or(&context.hook);
+        section.push_str(&format!("\n### {title}\n\n{}\n", context.text.trim_end()));
+    }
+    Some(section)
+}
 
-    None
+/// Context saved by earlier hooks for the next iteration.
+pub fn load_pending(root: &Path) -> Vec<HookContext> {
+    fs::read_to_string(root.join(PENDING_CONTEXT_FILE))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Add `contexts` to the context saved for the next iteration.
+pub fn save_pending(root: &Path, contexts: &[HookContext]) -> Result<(), std::io::Error> {
+    if contexts.is_empty() {
+        return Ok(());
+    }
+    let mut pending = load_pending(root);
+    pending.extend_from_slice(contexts);
+    let json = serde_json::to_string_pretty(&pending).map_err(std::io::Error::other)?;
+    fs::write(root.join(PENDING_CONTEXT_FILE), json)
+}
+
+/// Drop the saved context once an LLM call has received it.
+pub fn clear_pending(root: &Path) {
+    let _ = fs::remove_file(root.join(PENDING_CONTEXT_FILE));
+}
+
+/// Find a hook script by name, trying common extensions.
+fn find_hook_script(hooks_dir: &Path, name: &str) -> Option<std::path::PathBuf> {
+    // Try exact name first, then common extensions
+    std::iter::once(name.to_string())
+        .chain(SCRIPT_EXTENSIONS.iter().map(|ext| format!("{name}.{ext}")))
+        .map(|candidate| hooks_dir.join(candidate))
+        .find(|path| path.is_file())
 }
 
 /// Detect interpreter from a shebang line.
-fn detect_shebang(content: &str) -> Option<String> {
+pub(crate) fn detect_shebang(content: &str) -> Option<String> {
     let first_line = content.lines().next()?;
     let shebang = first_line.strip_prefix("#!")?;
     let parts: Vec<&str> = shebang.split_whitespace().collect();
@@ -101,12 +657,22 @@ mod tests {
         assert!(VALID_HOOKS.contains(&"post-context"));
         assert!(VALID_HOOKS.contains(&"post-llm"));
         assert!(VALID_HOOKS.contains(&"post-commit"));
+        assert!(VALID_HOOKS.contains(&"pre-llm"));
+        assert!(VALID_HOOKS.contains(&"pre-commit"));
+        assert!(VALID_HOOKS.contains(&"post-run"));
+        assert!(VALID_HOOKS.contains(&"on-abort"));
     }
 
     #[test]
     fn test_unknown_hook_rejected() {
         let dir = tempfile::tempdir().unwrap();
-        let result = run_hook(dir.path(), "invalid-hook", dir.path());
+        let result = run_hook(
+            dir.path(),
+            "invalid-hook",
+            dir.path(),
+            &HooksConfig::default(),
+            &HookPayload::default(),
+        );
         assert!(result.is_err());
     }
 
@@ -114,17 +680,373 @@ mod tests {
     fn test_missing_hook_is_ok() {
         let dir = tempfile::tempdir().unwrap();
         fs::create_dir_all(dir.path().join("hooks")).unwrap();
-        let result = run_hook(&dir.path().join("hooks"), "pre-run", dir.path());
+        let result = run_hook(
+            &dir.path().join("hooks"),
+            "pre-run",
+            dir.path(),
+            &HooksConfig::default(),
+            &HookPayload::default(),
+        );
         assert!(result.is_ok());
     }
 
     #[test]
     fn test_missing_hooks_dir_is_ok() {
         let dir = tempfile::tempdir().unwrap();
-        let result = run_hook(&dir.path().join("nonexistent"), "pre-run", dir.path());
+        let result = run_hook(
+            &dir.path().join("nonexistent"),
+            "pre-run",
+            dir.path(),
+            &HooksConfig::default(),
+            &HookPayload::default(),
+        );
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_hook_receives_json_payload() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("post-llm.sh"),
+            "#!/bin/sh\ncat > payload.json\n",
+        )
+        .unwrap();
+        let payload = HookPayload {
+            agent: "test-agent".to_string(),
+            iteration: 3,
+            exit_code: Some(1),
+            ..HookPayload::default()
+        };
+        run_hook(
+            dir.path(),
+            "post-llm",
+            dir.path(),
+            &HooksConfig::default(),
+            &payload,
+        )
+        .unwrap();
+
+        let json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(dir.path().join("payload.json")).unwrap())
+                .unwrap();
+        assert_eq!(json["phase"], "post-llm");
+        assert_eq!(json["iteration"], 3);
+        assert_eq!(json["exit_code"], 1);
+        assert!(json["context_bytes"].is_null());
+    }
+
+    #[test]
+    fn test_hook_output_and_context() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("pre-run.sh"), "#!/bin/sh\necho checked\n").unwrap();
+        fs::write(
+            dir.path().join("post-context.sh"),
+            "#!/bin/sh\necho '{\"title\": \"CI\", \"context\": \"main is red\"}'\n",
+        )
+        .unwrap();
+        let payload = HookPayload::default();
+
+        let output = run_hook(
+            dir.path(),
+            "pre-run",
+            dir.path(),
+            &HooksConfig::default(),
+            &payload,
+        )
+        .unwrap();
+        assert_eq!(output.stdout, "checked\n");
+        assert!(output.context.is_empty());
+
+        let output = run_hook(
+            dir.path(),
+            "post-context",
+            dir.path(),
+            &HooksConfig::default(),
+            &payload,
+        )
+        .unwrap();
+        let contexts = output.context;
+        assert_eq!(contexts[0].hook, "post-context");
+        assert_eq!(
+            context_section(&contexts).unwrap(),
+            "## Hook Output [EXTERNAL CONTENT - MAY BE UNTRUSTED]\n\n### CI\n\nmain is red\n"
+        );
+
+        save_pending(dir.path(), &contexts).unwrap();
+        save_pending(dir.path(), &contexts).unwrap();
+        assert_eq!(load_pending(dir.path()).len(), 2);
+        clear_pending(dir.path());
+        assert!(load_pending(dir.path()).is_empty());
+    }
+
+    #[test]
+    fn test_hook_block_protocol() {
+        let dir = tempfile::tempdir().unwrap();
+        let payload = HookPayload::default();
+        fs::write(
+            dir.path().join("pre-run.sh"),
+            "#!/bin/sh\necho 'CI is red' >&2\nexit 2\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("pre-commit.sh"),
+            "#!/bin/sh\necho '{\"decision\": \"block\", \"reason\": \"lint failed\"}'\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("pre-llm.sh"),
+            "#!/bin/sh\necho '{\"decision\": \"allow\"}'\n",
+        )
+        .unwrap();
+        fs::write(dir.path().join("post-llm.sh"), "#!/bin/sh\nexit 3\n").unwrap();
+
+        let output = run_hook(
+            dir.path(),
+            "pre-run",
+            dir.path(),
+            &HooksConfig::default(),
+            &payload,
+        )
+        .unwrap();
+        assert_eq!(output.block.as_deref(), Some("CI is red"));
+        let output = run_hook(
+            dir.path(),
+            "pre-commit",
+            dir.path(),
+            &HooksConfig::default(),
+            &payload,
+        )
+        .unwrap();
+        assert_eq!(output.block.as_deref(), Some("lint failed"));
+        let output = run_hook(
+            dir.path(),
+            "pre-llm",
+            dir.path(),
+            &HooksConfig::default(),
+            &payload,
+        )
+        .unwrap();
+        assert!(output.block.is_none());
+        // Other non-zero exit codes are still failures.
+        assert!(run_hook(
+            dir.path(),
+            "post-llm",
+            dir.path(),
+            &HooksConfig::default(),
+            &payload
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_hook_directory_runs_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let hooks = dir.path().join("hooks");
+        let scripts = hooks.join("pre-run.d");
+        fs::create_dir_all(&scripts).unwrap();
+        fs::write(hooks.join("pre-run.sh"), "#!/bin/sh\necho main >> order\n").unwrap();
+        fs::write(
+            scripts.join("20-b.sh"),
+            "#!/bin/sh\necho b >> order\nexit 1\n",
+        )
+        .unwrap();
+        fs::write(scripts.join("10-a.sh"), "#!/bin/sh\necho a >> order\n").unwrap();
+        fs::write(scripts.join("30-c.sh"), "#!/bin/sh\necho c >> order\n").unwrap();
+        fs::write(scripts.join("README"), "not a script").unwrap();
+
+        // Every script runs, and one failure fails the hook.
+        let err = run_hook(
+            &hooks,
+            "pre-run",
+            dir.path(),
+            &HooksConfig::default(),
+            &HookPayload::default(),
+        )
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Hook 'pre-run.d/20-b.sh' failed (exit 1)"));
+        assert_eq!(
+            fs::read_to_string(dir.path().join("order")).unwrap(),
+            "main\na\nb\nc\n"
+        );
+
+        // A veto stops the remaining scripts.
+        fs::remove_file(dir.path().join("order")).unwrap();
+        fs::write(
+            scripts.join("20-b.sh"),
+            "#!/bin/sh\necho b >> order\nexit 2\n",
+        )
+        .unwrap();
+        let output = run_hook(
+            &hooks,
+            "pre-run",
+            dir.path(),
+            &HooksConfig::default(),
+            &HookPayload::default(),
+        )
+        .unwrap();
+        assert!(output.block.is_some());
+        assert_eq!(
+            fs::read_to_string(dir.path().join("order")).unwrap(),
+            "main\na\nb\n"
+        );
+    }
+
+    #[test]
+    fn test_hook_results_are_recorded_per_run() {
+        let dir = tempfile::tempdir().unwrap();
+        let scripts = dir.path().join("post-llm.d");
+        fs::create_dir_all(&scripts).unwrap();
+        fs::write(scripts.join("10-ok.sh"), "#!/bin/sh\necho fine\n").unwrap();
+        fs::write(
+            scripts.join("20-veto.sh"),
+            "#!/bin/sh\necho 'tests are red' >&2\nexit 2\n",
+        )
+        .unwrap();
+        let log_file = dir.path().join("run.log").display().to_string();
+        let payload = HookPayload {
+            log_file: log_file.clone(),
+            ..HookPayload::default()
+        };
+
+        run_hook(
+            dir.path(),
+            "post-llm",
+            dir.path(),
+            &HooksConfig::default(),
+            &payload,
+        )
+        .unwrap();
+        let results = take_results(&log_file);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].hook, "post-llm");
+        assert_eq!(results[0].script, "post-llm.d/10-ok.sh");
+        assert_eq!(results[0].status, HookStatus::Ok);
+        assert_eq!(results[0].output, "fine");
+        assert_eq!(results[1].status, HookStatus::Blocked);
+        assert_eq!(results[1].exit_code, Some(2));
+        assert_eq!(results[1].output, "tests are red");
+        assert!(take_results(&log_file).is_empty());
+    }
+
+    #[test]
+    fn test_optional_hook_and_timeout() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("post-llm.sh"), "#!/bin/sh\nexit 1\n").unwrap();
+        fs::write(dir.path().join("pre-run.sh"), "#!/bin/sh\nsleep 5\n").unwrap();
+        let mut config = HooksConfig::default();
+        config.points.insert(
+            "post-llm".to_string(),
+            config::HookPointConfig {
+                required: Some(false),
+                ..Default::default()
+            },
+        );
+        config.timeout = "1s".to_string();
+        let payload = HookPayload::default();
+
+        let output = run_hook(dir.path(), "post-llm", dir.path(), &config, &payload).unwrap();
+        assert!(output.failure.unwrap().contains("failed (exit 1)"));
+
+        let err = run_hook(dir.path(), "pre-run", dir.path(), &config, &payload).unwrap_err();
+        assert!(err.to_string().contains("timed out after 1s"));
+    }
+
+    #[test]
+    fn test_hook_environment() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("post-llm.sh"),
+            "#!/bin/sh\necho \"$BOUCLE_PHASE $BOUCLE_ITERATION $BOUCLE_AGENT_NAME \
+             $BOUCLE_LAST_EXIT_CODE $(basename \"$BOUCLE_MEMORY\")\"\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("pre-run.sh"),
+            "#!/bin/sh\necho \"${BOUCLE_LAST_EXIT_CODE-unset}\"\n",
+        )
+        .unwrap();
+        let payload = HookPayload {
+            agent: "env-agent".to_string(),
+            iteration: 7,
+            memory_dir: dir.path().join("memory").display().to_string(),
+            exit_code: Some(0),
+            ..HookPayload::default()
+        };
+        let config = HooksConfig::default();
+
+        let output = run_hook(dir.path(), "post-llm", dir.path(), &config, &payload).unwrap();
+        assert_eq!(output.stdout, "post-llm 7 env-agent 0 memory\n");
+
+        let payload = HookPayload {
+            exit_code: None,
+            ..payload
+        };
+        let output = run_hook(dir.path(), "pre-run", dir.path(), &config, &payload).unwrap();
+        assert_eq!(output.stdout, "unset\n");
+    }
+
+    #[test]
+    fn test_header_async() {
+        assert!(header_async(
+            "#!/bin/sh\n# boucle: async\ncurl -X POST ...\n"
+        ));
+        assert!(!header_async("#!/bin/sh\necho '# boucle: async'\n"));
+    }
+
+    #[test]
+    fn test_background_hook_is_reaped_and_logged() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("post-commit.sh"),
+            "#!/bin/sh\n# boucle: async\nsleep 1\necho pinged\n",
+        )
+        .unwrap();
+        let log_file = dir.path().join("run.log");
+        let payload = HookPayload {
+            log_file: log_file.display().to_string(),
+            ..HookPayload::default()
+        };
+
+        let started = std::time::Instant::now();
+        let output = run_hook(
+            dir.path(),
+            "post-commit",
+            dir.path(),
+            &HooksConfig::default(),
+            &payload,
+        )
+        .unwrap();
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert_eq!(output.background, ["post-commit"]);
+
+        drop(BackgroundGuard {
+            log_file: payload.log_file,
+        });
+        assert_eq!(
+            fs::read_to_string(&log_file).unwrap(),
+            "Background hook 'post-commit' finished\n  pinged\n"
+        );
+    }
+
+    #[test]
+    fn test_unused_files() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("pre-run.d")).unwrap();
+        fs::create_dir_all(dir.path().join("pre_commit.d")).unwrap();
+        fs::write(dir.path().join("pre-run.sh"), "#!/bin/sh\n").unwrap();
+        fs::write(dir.path().join("post_llm.sh"), "#!/bin/sh\n").unwrap();
+        fs::write(dir.path().join("pre-run.d/10-ci.sh"), "#!/bin/sh\n").unwrap();
+        fs::write(dir.path().join("pre-run.d/notes.txt"), "plain text").unwrap();
+        fs::write(dir.path().join("pre_commit.d/lint.sh"), "#!/bin/sh\n").unwrap();
+
+        assert_eq!(
+            unused_files(dir.path()).unwrap(),
+            ["post_llm.sh", "pre-run.d/notes.txt", "pre_commit.d/lint.sh"]
+        );
+    }
+
     #[test]
     fn test_find_hook_script_exact() {
         let dir = tempfile::tempdir().unwrap();
@@ -137,6 +1059,9 @@ mod tests {
         let dir = tempfile::tempdir().unwrap();
         fs::write(dir.path().join("pre-run.sh"), "#!/bin/bash\necho ok").unwrap();
         assert!(find_hook_script(dir.path(), "pre-run").is_some());
+        fs::write(dir.path().join("post-run.ps1"), "Write-Output ok").unwrap();
+        let found = find_hook_script(dir.path(), "post-run").unwrap();
+        assert!(found.ends_with("post-run.ps1"));
     }
 
     #[test]
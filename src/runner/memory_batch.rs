@@ -0,0 +1,230 @@
+//! `boucle memory batch`: Broca over stdin and stdout for scripts.
+//!
+//! Hooks, context scripts, and command plugins get `BOUCLE_BIN`, so they can
+//! start one `"$BOUCLE_BIN" --root "$BOUCLE_ROOT" memory batch` process and
+//! send it requests for as long as they run, instead of starting `boucle`
+//! once per call. Each line in is a JSON request with an `op` and its
+//! arguments; each line out is the response, flushed straight away:
+//!
+//! ```text
+//! {"id": 1, "op": "remember", "title": "API limit", "content": "100/min", "tags": ["api"]}
+//! {"id":1,"ok":true,"result":{"file":"20260301-100000-api-limit.md"}}
+//! {"op": "recall", "query": "API"}
+//! {"ok":true,"result":[{"file":"20260301-100000-api-limit.md","title":"API limit",...}]}
+//! ```
+//!
+//! Requests are answered one at a time, in order, so a script's writes never
+//! race each other. Remembered entries and journal notes go through the
+//! `on-remember` hook like `boucle memory remember` ones.
+
+use crate::broca::{self, ScoredEntry};
+use crate::config::Config;
+use crate::runner::{self, MemoryEvent};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+
+#[derive(Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum Request {
+    Remember {
+        title: String,
+        content: String,
+        #[serde(rename = "type", default = "default_entry_type")]
+        entry_type: String,
+        #[serde(default)]
+        tags: Vec<String>,
+        #[serde(default)]
+        ttl_days: Option<u32>,
+        #[serde(default)]
+        valid_until: Option<String>,
+        #[serde(default)]
+        confidence: Option<f64>,
+    },
+    Recall {
+        query: String,
+        #[serde(default = "default_limit")]
+        limit: usize,
+    },
+    Show {
+        entry: String,
+    },
+    SearchTag {
+        tag: String,
+    },
+    Journal {
+        content: String,
+    },
+}
+
+fn default_entry_type() -> String {
+    "fact".to_string()
+}
+
+fn default_limit() -> usize {
+    5
+}
+
+/// Answer each request line in `input` until it ends.
+pub fn serve(
+    root: &Path,
+    cfg: &Config,
+    input: impl BufRead,
+    mut output: impl Write,
+) -> io::Result<()> {
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (id, result) = match serde_json::from_str::<Value>(&line) {
+            Ok(mut request) => {
+                let id = request.as_object_mut().and_then(|r| r.remove("id"));
+                let result = serde_json::from_value(request)
+                    .map_err(|e| format!("invalid request: {e}"))
+                    .and_then(|request| handle(root, cfg, request));
+                (id, result)
+            }
+            Err(e) => (None, Err(format!("invalid JSON: {e}"))),
+        };
+
+        let mut response = match result {
+            Ok(result) => json!({"ok": true, "result": result}),
+            Err(error) => json!({"ok": false, "error": error}),
+        };
+        if let Some(id) = id {
+            response["id"] = id;
+        }
+        writeln!(output, "{response}")?;
+        output.flush()?;
+    }
+    Ok(())
+}
+
+fn handle(root: &Path, cfg: &Config, request: Request) -> Result<Value, String> {
+    let memory_dir = root.join(&cfg.memory.dir);
+    match request {
+        Request::Remember {
+            title,
+            content,
+            entry_type,
+            tags,
+            ttl_days,
+            valid_until,
+            confidence,
+        } => {
+            let entry = runner::with_memory_defaults(
+                cfg,
+                broca::NewEntry {
+                    entry_type,
+                    title,
+                    content,
+                    tags,
+                    ttl_days,
+                    valid_until,
+                    confidence,
+                },
+            );
+            let path = broca::remember_entry(&memory_dir, &entry).map_err(|e| e.to_string())?;
+            let memory = MemoryEvent::knowledge(
+                "batch",
+                &path,
+                &entry.entry_type,
+                &entry.title,
+                &entry.tags,
+            );
+            runner::on_remember(root, cfg, memory, None, None).map_err(|e| e.to_string())?;
+            Ok(json!({"file": file_name(&path)}))
+        }
+        Request::Recall { query, limit } => {
+            let entries = broca::recall(&memory_dir, &query, limit).map_err(|e| e.to_string())?;
+            Ok(entries.iter().map(entry_json).collect())
+        }
+        Request::Show { entry } => {
+            let content = broca::show(&memory_dir, &entry).map_err(|e| e.to_string())?;
+            Ok(json!({"content": content}))
+        }
+        Request::SearchTag { tag } => {
+            let entries = broca::search_tag(&memory_dir, &tag).map_err(|e| e.to_string())?;
+            Ok(entries
+                .iter()
+                .map(|entry| entry_json(&ScoredEntry::from(entry)))
+                .collect())
+        }
+        Request::Journal { content } => {
+            let previous = std::fs::read_to_string(broca::journal_path(&memory_dir)).ok();
+            let path = broca::journal(&memory_dir, &content).map_err(|e| e.to_string())?;
+            let memory = MemoryEvent::journal("batch", &path);
+            runner::on_remember(root, cfg, memory, previous, None).map_err(|e| e.to_string())?;
+            Ok(json!({"file": file_name(&path)}))
+        }
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+fn entry_json(entry: &ScoredEntry) -> Value {
+    json!({
+        "file": entry.filename,
+        "type": entry.entry_type.to_string(),
+        "title": entry.title,
+        "content": entry.content,
+        "tags": entry.tags,
+        "confidence": entry.confidence,
+        "score": entry.relevance_score,
+        "superseded_by": entry.superseded_by,
+        "stale": entry.stale_reason,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config;
+
+    #[test]
+    fn test_serve_answers_each_request_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        runner::init(dir.path(), "test-agent").unwrap();
+        let cfg = config::load(dir.path()).unwrap();
+        let input = [
+            r#"{"id": 1, "op": "remember", "title": "API limit", "content": "100 requests per minute", "tags": ["api"]}"#,
+            "",
+            r#"{"id": "two", "op": "recall", "query": "requests per minute"}"#,
+            r#"{"op": "search_tag", "tag": "API"}"#,
+            r#"{"op": "journal", "content": "checked the limit"}"#,
+            r#"{"op": "forget"}"#,
+            "not json",
+        ]
+        .join("\n");
+
+        let mut output = Vec::new();
+        serve(dir.path(), &cfg, input.as_bytes(), &mut output).unwrap();
+        let responses: Vec<Value> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        assert_eq!(responses.len(), 6);
+        assert_eq!(responses[0]["id"], 1);
+        let file = responses[0]["result"]["file"].as_str().unwrap();
+        assert!(file.ends_with("-api-limit.md"), "{file}");
+        assert_eq!(responses[1]["id"], "two");
+        assert_eq!(responses[1]["result"][0]["file"], file);
+        assert_eq!(responses[1]["result"][0]["type"], "fact");
+        assert_eq!(responses[2]["result"][0]["title"], "API limit");
+        assert_eq!(responses[3]["ok"], true);
+        assert!(responses[4]["error"]
+            .as_str()
+            .unwrap()
+            .starts_with("invalid request"));
+        assert!(responses[4].get("id").is_none());
+        assert_eq!(responses[5]["ok"], false);
+    }
+}
@@ -0,0 +1,296 @@
+//! Prometheus metrics for an agent.
+//!
+//! Everything is derived from what a run already leaves on disk: the run
+//! records in the log directory, the failure state and the memory directory.
+//! `boucle metrics` prints them in the text exposition format, and with
+//! `[metrics] textfile` the runner rewrites that file after each run for
+//! node_exporter's textfile collector.
+
+use super::{context, load_config, load_failure_state, record, RunnerError};
+use crate::config::Config;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Phases reported in `boucle_phase_duration_seconds`, in output order.
+const PHASES: &[&str] = &["lock", "context", "llm", "hooks", "commit"];
+
+/// Totals over the run records in a log directory.
+#[derive(Debug, Default)]
+struct Totals {
+    iterations: u64,
+    failed: u64,
+    /// Summed milliseconds and number of runs that recorded each phase.
+    phases: BTreeMap<&'static str, (u64, u64)>,
+    input_tokens: u64,
+    output_tokens: u64,
+    cache_read_tokens: u64,
+    cache_creation_tokens: u64,
+    cost_usd: f64,
+    last: Option<record::RunRecord>,
+}
+
+impl Totals {
+    fn add(&mut self, run: record::RunRecord) {
+        self.iterations += 1;
+        if run.failed() {
+            self.failed += 1;
+        }
+        for phase in PHASES {
+            let ms = match *phase {
+                "hooks" => Some(run.hooks.iter().map(|h| h.duration_ms).sum()),
+                // Records from before phases were kept only timed the LLM.
+                "llm" => Some(run.phases.get("llm").copied().unwrap_or(run.duration_ms)),
+                _ => run.phases.get(*phase).copied(),
+            };
+            if let Some(ms) = ms {
+                let (sum, count) = self.phases.entry(phase).or_default();
+                *sum += ms;
+                *count += 1;
+            }
+        }
+        if let Some(ref result) = run.result {
+            self.input_tokens += result.usage.input_tokens;
+            self.output_tokens += result.usage.output_tokens;
+            self.cache_read_tokens += result.usage.cache_read_input_tokens;
+            self.cache_creation_tokens += result.usage.cache_creation_input_tokens;
+            self.cost_usd += result.total_cost_usd.unwrap_or(0.0);
+        }
+        self.last = Some(run);
+    }
+}
+
+/// Print the agent's metrics to stdout.
+pub fn show(root: &Path, profile: Option<&str>) -> Result<(), RunnerError> {
+    let cfg = load_config(root, profile)?;
+    print!("{}", render(root, &cfg));
+    Ok(())
+}
+
+/// Rewrite `[metrics] textfile`, if set. Failures are only logged: metrics
+/// must not fail the run they describe.
+pub fn write_textfile(root: &Path, profile: Option<&str>) {
+    let Ok(cfg) = load_config(root, profile) else {
+        return;
+    };
+    let Some(ref textfile) = cfg.metrics.textfile else {
+        return;
+    };
+    let path = root.join(textfile);
+    // The collector may read at any time; a rename never shows it half a file.
+    let mut tmp = path.clone().into_os_string();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    let written = fs::write(&tmp, render(root, &cfg)).and_then(|()| fs::rename(&tmp, &path));
+    if let Err(e) = written {
+        tracing::warn!("Metrics not written to {}: {e}", path.display());
+    }
+}
+
+/// The metrics in the Prometheus text exposition format.
+fn render(root: &Path, cfg: &Config) -> String {
+    let log_dir = root.join(
+        cfg.loop_config
+            .log_dir
+            .as_deref()
+            .unwrap_or(super::LOG_DIR_DEFAULT),
+    );
+    let totals = totals(&log_dir);
+    let failures = load_failure_state(&root.join(super::FAILURE_STATE_FILE));
+    let agent = format!("agent=\"{}\"", escape(&cfg.agent.name));
+    let mut out = String::new();
+
+    metric(
+        &mut out,
+        "boucle_iterations_total",
+        "counter",
+        "Iterations that reached the LLM.",
+        &[(&agent, totals.iterations as f64)],
+    );
+    metric(
+        &mut out,
+        "boucle_iterations_failed_total",
+        "counter",
+        "Iterations whose LLM exited non-zero or timed out.",
+        &[(&agent, totals.failed as f64)],
+    );
+    metric(
+        &mut out,
+        "boucle_consecutive_failures",
+        "gauge",
+        "Failed runs since the last success, including runs that failed before the LLM.",
+        &[(&agent, failures.consecutive_failures as f64)],
+    );
+    if let Some(last_run) = totals.last.as_ref().and_then(|run| run.started()) {
+        metric(
+            &mut out,
+            "boucle_last_run_timestamp_seconds",
+            "gauge",
+            "Start of the latest iteration, in Unix time.",
+            &[(&agent, last_run.timestamp() as f64)],
+        );
+    }
+
+    let name = "boucle_phase_duration_seconds";
+    let _ = writeln!(
+        out,
+        "# HELP {name} Time spent in each phase of an iteration."
+    );
+    let _ = writeln!(out, "# TYPE {name} summary");
+    for (phase, (sum, count)) in &totals.phases {
+        let labels = format!("{agent},phase=\"{phase}\"");
+        let _ = writeln!(out, "{name}_sum{{{labels}}} {}", *sum as f64 / 1000.0);
+        let _ = writeln!(out, "{name}_count{{{labels}}} {count}");
+    }
+
+    if let Some(ref last) = totals.last {
+        let tokens = last.context_bytes.div_ceil(context::BYTES_PER_TOKEN);
+        metric(
+            &mut out,
+            "boucle_context_tokens",
+            "gauge",
+            "Estimated size of the latest iteration's context.",
+            &[(&agent, tokens as f64)],
+        );
+    }
+    let kinds = [
+        ("input", totals.input_tokens),
+        ("output", totals.output_tokens),
+        ("cache_read", totals.cache_read_tokens),
+        ("cache_creation", totals.cache_creation_tokens),
+    ];
+    let labels: Vec<_> = kinds
+        .iter()
+        .map(|(kind, n)| (format!("{agent},kind=\"{kind}\""), *n as f64))
+        .collect();
+    let labels: Vec<_> = labels.iter().map(|(l, n)| (l.as_str(), *n)).collect();
+    metric(
+        &mut out,
+        "boucle_llm_tokens_total",
+        "counter",
+        "Tokens reported by the backend.",
+        &labels,
+    );
+    metric(
+        &mut out,
+        "boucle_llm_cost_usd_total",
+        "counter",
+        "Cost reported by the backend, in US dollars.",
+        &[(&agent, totals.cost_usd)],
+    );
+    metric(
+        &mut out,
+        "boucle_memory_entries",
+        "gauge",
+        "Knowledge entries in Broca memory.",
+        &[(&agent, memory_entries(&root.join(&cfg.memory.dir)) as f64)],
+    );
+    out
+}
+
+fn totals(log_dir: &Path) -> Totals {
+    let mut totals = Totals::default();
+    for run in record::load_all(log_dir) {
+        totals.add(run);
+    }
+    totals
+}
+
+fn memory_entries(memory_dir: &Path) -> usize {
+    fs::read_dir(memory_dir.join("knowledge"))
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .filter(|e| e.path().extension().is_some_and(|ext| ext == "md"))
+                .count()
+        })
+        .unwrap_or(0)
+}
+
+fn metric(out: &mut String, name: &str, kind: &str, help: &str, samples: &[(&str, f64)]) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+    for (labels, value) in samples {
+        let _ = writeln!(out, "{name}{{{labels}}} {value}");
+    }
+}
+
+/// Escape a label value: backslash, double quote and newline.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runner::record::{HookResult, LlmResult, RunRecord, Usage};
+
+    #[test]
+    fn test_render_totals_runs() {
+        let dir = tempfile::tempdir().unwrap();
+        crate::runner::init(dir.path(), "metrics-agent").unwrap();
+        let logs = dir.path().join("logs");
+        fs::create_dir_all(&logs).unwrap();
+        let ok = RunRecord {
+            timestamp: "2026-01-02_03-04-05".to_string(),
+            exit_code: Some(0),
+            duration_ms: 2000,
+            context_bytes: 4000,
+            result: Some(LlmResult {
+                total_cost_usd: Some(0.25),
+                usage: Usage {
+                    input_tokens: 100,
+                    output_tokens: 10,
+                    ..Default::default()
+                },
+                ..Default::default()
+            }),
+            hooks: vec![HookResult {
+                duration_ms: 500,
+                ..Default::default()
+            }],
+            phases: BTreeMap::from([("context".to_string(), 1500), ("llm".to_string(), 2000)]),
+            ..Default::default()
+        };
+        record::save(&logs.join("2026-01-02_03-04-05.json"), &ok).unwrap();
+        let failed = RunRecord {
+            exit_code: Some(1),
+            duration_ms: 1000,
+            ..Default::default()
+        };
+        record::save(&logs.join("2026-01-01_00-00-00.json"), &failed).unwrap();
+
+        let cfg = crate::config::load(dir.path()).unwrap();
+        let text = render(dir.path(), &cfg);
+        let agent = "{agent=\"metrics-agent\"}";
+        assert!(text.contains(&format!("boucle_iterations_total{agent} 2\n")));
+        assert!(text.contains(&format!("boucle_iterations_failed_total{agent} 1\n")));
+        assert!(text.contains(&format!(
+            "boucle_last_run_timestamp_seconds{agent} 1767323045\n"
+        )));
+        assert!(text.contains(
+            "boucle_phase_duration_seconds_sum{agent=\"metrics-agent\",phase=\"llm\"} 3\n"
+        ));
+        assert!(text.contains(
+            "boucle_phase_duration_seconds_count{agent=\"metrics-agent\",phase=\"context\"} 1\n"
+        ));
+        assert!(text.contains(
+            "boucle_phase_duration_seconds_sum{agent=\"metrics-agent\",phase=\"hooks\"} 0.5\n"
+        ));
+        assert!(text.contains(&format!("boucle_context_tokens{agent} 1000\n")));
+        assert!(
+            text.contains("boucle_llm_tokens_total{agent=\"metrics-agent\",kind=\"input\"} 100\n")
+        );
+        assert!(text.contains(&format!("boucle_llm_cost_usd_total{agent} 0.25\n")));
+        assert!(text.contains("# TYPE boucle_memory_entries gauge\n"));
+    }
+
+    #[test]
+    fn test_escape_label_value() {
+        assert_eq!(escape("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
+}
@@ -0,0 +1,443 @@
+//! Run-outcome notifications.
+//!
+//! Each `[notify.channels.<name>]` gets a short message in Slack, Discord,
+//! a generic webhook, or email when one of its `events` happens:
+//!
+//! - `failure`: an iteration failed, before or in the LLM call.
+//! - `stopped`: the loop stopped making progress on its own — a rate-limit
+//!   cool-down, or the consecutive-failure threshold.
+//! - `budget-exceeded`: the day's cost passed `[notify] daily_budget_usd`.
+//! - `daily-summary`: yesterday's runs, sent by the first run of the day.
+//! - `digest`: the digest of `boucle report digest --notify`.
+//!
+//! Like webhooks, delivery is best effort: the outcome is logged and a dead
+//! channel never stops the loop.
+
+use super::{context, record, report, webhooks};
+use crate::config::{self, NotifyChannel, NotifyConfig, NotifyKind, SecretSource};
+use chrono::{Days, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+
+/// Events a channel can subscribe to.
+pub const EVENTS: &[&str] = &[
+    "failure",
+    "stopped",
+    "budget-exceeded",
+    "daily-summary",
+    "digest",
+];
+
+/// Events of a channel that does not list any. A digest is only sent when
+/// asked for, so it is among them.
+const DEFAULT_EVENTS: &[&str] = &["failure", "stopped", "budget-exceeded", "digest"];
+
+/// Days the budget and summary were last sent for.
+pub(super) const STATE_FILE: &str = ".boucle-notify.json";
+
+/// Limit for each delivery attempt.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Extra attempts after a network error, 429, or 5xx response.
+const RETRIES: u32 = 2;
+
+/// Longest Discord message.
+const DISCORD_LIMIT: usize = 2000;
+
+/// One message for the channels subscribed to `event`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notice {
+    pub event: &'static str,
+    pub subject: String,
+    pub text: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct State {
+    #[serde(default)]
+    budget_exceeded: Option<String>,
+    #[serde(default)]
+    summary_sent: Option<String>,
+}
+
+/// Send `notice` to every channel subscribed to its event and log the
+/// outcome.
+pub fn send(
+    root: &Path,
+    agent: &str,
+    notify: &NotifyConfig,
+    secrets: &BTreeMap<String, SecretSource>,
+    log_file: &Path,
+    notice: &Notice,
+) {
+    for message in send_all(root, agent, notify, secrets, notice) {
+        let _ = super::log(log_file, &message);
+    }
+}
+
+/// Send `notice` to every channel subscribed to its event. Returns one line
+/// per channel on how it went.
+pub fn send_all(
+    root: &Path,
+    agent: &str,
+    notify: &NotifyConfig,
+    secrets: &BTreeMap<String, SecretSource>,
+    notice: &Notice,
+) -> Vec<String> {
+    let mut outcomes = Vec::new();
+    for (name, channel) in &notify.channels {
+        if !subscribed(channel, notice.event) {
+            continue;
+        }
+        outcomes.push(match deliver(root, secrets, name, channel, agent, notice) {
+            Ok(outcome) => format!("Notify {name} ({}): {outcome}", notice.event),
+            Err(e) => format!("Notify {name} ({}) FAILED: {e}", notice.event),
+        });
+    }
+    outcomes
+}
+
+/// Send `failure` for an iteration that failed, and `stopped` when this
+/// failure is the one that reaches the consecutive-failure threshold.
+pub fn failure(
+    root: &Path,
+    agent: &str,
+    notify: &NotifyConfig,
+    secrets: &BTreeMap<String, SecretSource>,
+    log_file: &Path,
+    error: &str,
+    consecutive: u32,
+) {
+    send(
+        root,
+        agent,
+        notify,
+        secrets,
+        log_file,
+        &Notice {
+            event: "failure",
+            subject: format!("{agent}: iteration failed"),
+            text: format!("Failure #{consecutive} in a row: {error}"),
+        },
+    );
+    if consecutive == super::FAILURE_THRESHOLD {
+        send(
+            root,
+            agent,
+            notify,
+            secrets,
+            log_file,
+            &Notice {
+                event: "stopped",
+                subject: format!("{agent}: {consecutive} failures in a row"),
+                text: format!(
+                    "The last {consecutive} iterations failed; the loop is not making progress. \
+                     Latest error: {error}"
+                ),
+            },
+        );
+    }
+}
+
+/// Send `budget-exceeded` once a day when the cost of today's runs, this
+/// one included, reaches `daily_budget_usd`.
+pub fn check_budget(
+    root: &Path,
+    agent: &str,
+    notify: &NotifyConfig,
+    secrets: &BTreeMap<String, SecretSource>,
+    log_dir: &Path,
+    log_file: &Path,
+) {
+    let Some(budget) = notify.daily_budget_usd else {
+        return;
+    };
+    let today = Utc::now().date_naive();
+    let spent: f64 = record::load_all(log_dir)
+        .iter()
+        .filter(|run| run.started().is_some_and(|t| t.date_naive() == today))
+        .filter_map(|run| run.result.as_ref()?.total_cost_usd)
+        .sum();
+    let mut state = load_state(root);
+    if spent < budget || state.budget_exceeded.as_deref() == Some(&today.to_string()) {
+        return;
+    }
+    send(
+        root,
+        agent,
+        notify,
+        secrets,
+        log_file,
+        &Notice {
+            event: "budget-exceeded",
+            subject: format!("{agent}: daily budget exceeded"),
+            text: format!(
+                "Runs today have cost ${spent:.2}, over the ${budget:.2} daily budget. \
+                 The loop keeps running; pause it with `boucle schedule pause`."
+            ),
+        },
+    );
+    state.budget_exceeded = Some(today.to_string());
+    save_state(root, &state);
+}
+
+/// Send `daily-summary` for yesterday (UTC), unless already sent or there
+/// were no runs.
+pub fn daily_summary(
+    root: &Path,
+    agent: &str,
+    notify: &NotifyConfig,
+    secrets: &BTreeMap<String, SecretSource>,
+    log_dir: &Path,
+    log_file: &Path,
+) {
+    if !notify
+        .channels
+        .values()
+        .any(|channel| subscribed(channel, "daily-summary"))
+    {
+        return;
+    }
+    let today = Utc::now().date_naive();
+    let Some(yesterday) = today.checked_sub_days(Days::new(1)) else {
+        return;
+    };
+    let mut state = load_state(root);
+    if state
+        .summary_sent
+        .as_deref()
+        .and_then(|day| NaiveDate::parse_from_str(day, "%Y-%m-%d").ok())
+        .is_some_and(|day| day >= yesterday)
+    {
+        return;
+    }
+    state.summary_sent = Some(yesterday.to_string());
+    save_state(root, &state);
+
+    if let Some(notice) = summary(agent, &record::load_all(log_dir), yesterday) {
+        send(root, agent, notify, secrets, log_file, &notice);
+    }
+}
+
+/// The `daily-summary` notice for `day`; `None` without runs that day.
+fn summary(agent: &str, runs: &[record::RunRecord], day: NaiveDate) -> Option<Notice> {
+    let start = day.and_hms_opt(0, 0, 0)?.and_utc();
+    let end = day.and_hms_opt(23, 59, 59)?.and_utc();
+    let usage = report::usage_report(agent, runs, start, end).total;
+    if usage.runs == 0 {
+        return None;
+    }
+    let commits = runs
+        .iter()
+        .filter(|run| run.started().is_some_and(|t| t.date_naive() == day))
+        .filter(|run| run.commit.is_some())
+        .count();
+    Some(Notice {
+        event: "daily-summary",
+        subject: format!("{agent}: summary for {day}"),
+        text: format!(
+            "{} runs, {} failed, {commits} committed. {} input and {} output tokens, ${:.2}. \
+             Average run {}.",
+            usage.runs,
+            usage.failed,
+            report::tokens(usage.input_tokens + usage.cache_tokens),
+            report::tokens(usage.output_tokens),
+            usage.cost_usd,
+            report::duration(usage.avg_duration_ms),
+        ),
+    })
+}
+
+fn subscribed(channel: &NotifyChannel, event: &str) -> bool {
+    if channel.events.is_empty() {
+        DEFAULT_EVENTS.contains(&event)
+    } else {
+        channel.events.iter().any(|e| e == event)
+    }
+}
+
+fn deliver(
+    root: &Path,
+    secrets: &BTreeMap<String, SecretSource>,
+    name: &str,
+    channel: &NotifyChannel,
+    agent: &str,
+    notice: &Notice,
+) -> Result<String, String> {
+    let setting = format!("notify.channels.{name}");
+    if channel.kind == NotifyKind::Email {
+        let to = channel
+            .to
+            .as_deref()
+            .ok_or_else(|| format!("{setting}.to is not set"))?;
+        return email(root, &channel.sendmail, to, notice).map(|()| format!("sent to {to}"));
+    }
+    let url = channel
+        .url
+        .as_deref()
+        .ok_or_else(|| format!("{setting}.url is not set"))?;
+    let url = config::resolve_env_value(root, secrets, &format!("{setting}.url"), url)
+        .map_err(|e| e.to_string())?;
+    let body = body(channel.kind, agent, notice);
+    webhooks::send(&url, &[], &body, TIMEOUT, RETRIES).map(|status| format!("HTTP {status}"))
+}
+
+/// JSON for a Slack, Discord, or generic webhook.
+fn body(kind: NotifyKind, agent: &str, notice: &Notice) -> String {
+    let body = match kind {
+        NotifyKind::Slack => serde_json::json!({
+            "text": format!("*{}*\n{}", notice.subject, notice.text),
+        }),
+        NotifyKind::Discord => {
+            let content: String = format!("**{}**\n{}", notice.subject, notice.text)
+                .chars()
+                .take(DISCORD_LIMIT)
+                .collect();
+            serde_json::json!({ "content": content })
+        }
+        NotifyKind::Webhook | NotifyKind::Email => serde_json::json!({
+            "event": notice.event,
+            "agent": agent,
+            "subject": notice.subject,
+            "text": notice.text,
+        }),
+    };
+    body.to_string()
+}
+
+/// Pipe the message to `sendmail`, which reads the recipients from it.
+fn email(root: &Path, sendmail: &str, to: &str, notice: &Notice) -> Result<(), String> {
+    let message = format!(
+        "To: {to}\nSubject: {}\nContent-Type: text/plain; charset=utf-8\n\n{}\n",
+        notice.subject.replace('\n', " "),
+        notice.text
+    );
+    let mut cmd = context::shell_command(sendmail);
+    cmd.current_dir(root)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped());
+    super::configure_child_process_group(&mut cmd);
+    let mut child = cmd
+        .spawn()
+        .map_err(|e| format!("could not start '{sendmail}': {e}"))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(message.as_bytes())
+            .map_err(|e| format!("writing to '{sendmail}': {e}"))?;
+    }
+    let output = super::wait_with_output_timeout(child, TIMEOUT).map_err(|e| e.to_string())?;
+    if output.timed_out {
+        return Err(format!(
+            "'{sendmail}' timed out after {}s",
+            TIMEOUT.as_secs()
+        ));
+    }
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let detail = stderr.lines().rev().find(|l| !l.trim().is_empty());
+        return Err(format!(
+            "'{sendmail}' failed: {}",
+            detail.unwrap_or("no output").trim()
+        ));
+    }
+    Ok(())
+}
+
+fn load_state(root: &Path) -> State {
+    fs::read_to_string(root.join(STATE_FILE))
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+fn save_state(root: &Path, state: &State) {
+    if let Ok(json) = serde_json::to_string_pretty(state) {
+        let _ = fs::write(root.join(STATE_FILE), json);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runner::record::{LlmResult, RunRecord};
+
+    fn notice() -> Notice {
+        Notice {
+            event: "failure",
+            subject: "tri: iteration failed".to_string(),
+            text: "claude exited with code 1".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_body_per_channel_kind() {
+        let slack: serde_json::Value =
+            serde_json::from_str(&body(NotifyKind::Slack, "tri", &notice())).unwrap();
+        assert_eq!(
+            slack["text"],
+            "*tri: iteration failed*\nclaude exited with code 1"
+        );
+        let discord: serde_json::Value =
+            serde_json::from_str(&body(NotifyKind::Discord, "tri", &notice())).unwrap();
+        assert!(discord["content"].as_str().unwrap().starts_with("**tri"));
+        let webhook: serde_json::Value =
+            serde_json::from_str(&body(NotifyKind::Webhook, "tri", &notice())).unwrap();
+        assert_eq!(webhook["event"], "failure");
+        assert_eq!(webhook["agent"], "tri");
+    }
+
+    #[test]
+    fn test_email_pipes_message_to_sendmail() {
+        let dir = tempfile::tempdir().unwrap();
+        email(dir.path(), "cat > mail.txt", "ops@example.com", &notice()).unwrap();
+        let mail = fs::read_to_string(dir.path().join("mail.txt")).unwrap();
+        assert!(mail.starts_with("To: ops@example.com\nSubject: tri: iteration failed\n"));
+        assert!(mail.ends_with("\n\nclaude exited with code 1\n"));
+        let err = email(dir.path(), "exit 3", "ops@example.com", &notice()).unwrap_err();
+        assert!(err.contains("'exit 3' failed"), "{err}");
+    }
+
+    #[test]
+    fn test_summary_counts_one_day() {
+        let run = |timestamp: &str, exit_code, commit: Option<&str>| RunRecord {
+            timestamp: timestamp.to_string(),
+            exit_code: Some(exit_code),
+            commit: commit.map(str::to_string),
+            result: Some(LlmResult {
+                total_cost_usd: Some(0.5),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let runs = [
+            run("2026-10-16_23-00-00", 0, Some("abc")),
+            run("2026-10-17_09-00-00", 0, Some("def")),
+            run("2026-10-17_10-00-00", 1, None),
+        ];
+        let day = NaiveDate::from_ymd_opt(2026, 10, 17).unwrap();
+        let notice = summary("tri", &runs, day).unwrap();
+        assert_eq!(notice.subject, "tri: summary for 2026-10-17");
+        assert!(
+            notice.text.starts_with("2 runs, 1 failed, 1 committed."),
+            "{}",
+            notice.text
+        );
+        assert!(notice.text.contains("$1.00"));
+        let quiet = NaiveDate::from_ymd_opt(2026, 10, 18).unwrap();
+        assert!(summary("tri", &runs, quiet).is_none());
+    }
+
+    #[test]
+    fn test_default_events() {
+        let channel: NotifyChannel = toml::from_str("type = \"slack\"").unwrap();
+        assert!(subscribed(&channel, "failure"));
+        assert!(subscribed(&channel, "digest"));
+        assert!(!subscribed(&channel, "daily-summary"));
+    }
+}
@@ -0,0 +1,688 @@
+//! Installing command plugins from elsewhere: `boucle plugin install`,
+//! `update`, and `remove`, and `boucle plugins outdated`.
+//!
+//! A source is a git URL or a local path: a plugin script, a directory that
+//! holds plugins, or another agent's root (its `plugins/` is searched). Only
+//! plugins with a manifest are installed. A namespace directory
+//! (`plugins/github/`) is installed whole, helper files included. Where
+//! each one came from is recorded in `plugins/.installed.json` with its
+//! manifest version, so `update` can fetch it again, `outdated` can compare
+//! it with the source, and `remove` knows which files are its own.
+
+use super::command_plugins::{self, Plugin, PluginError};
+use super::git;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::{fs, io, process};
+
+/// Provenance of installed plugins, in `plugins/`.
+pub const INSTALLED_FILE: &str = ".installed.json";
+
+/// Where git sources are cloned while a plugin is copied out of them.
+const CHECKOUT_DIR: &str = ".boucle-cache/plugin-sources";
+
+/// Where an installed plugin came from and which files it owns.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Installed {
+    /// Git URL or absolute path it was installed from.
+    pub source: String,
+    /// Commit of a git source.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revision: Option<String>,
+    /// Manifest `version` of the plugin; unset for a namespace.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    pub installed_at: String,
+    /// Paths relative to `plugins/`.
+    pub files: Vec<String>,
+}
+
+/// A source ready to copy from. A git checkout is deleted on drop.
+struct Fetched {
+    dir: PathBuf,
+    /// Plugin name implied by a source that is a single script.
+    name: Option<String>,
+    revision: Option<String>,
+    checkout: Option<PathBuf>,
+}
+
+/// What one install copies: a plugin, or a namespace of them.
+struct Located {
+    name: String,
+    version: Option<String>,
+    /// (source path, path relative to `plugins/`)
+    files: Vec<(PathBuf, String)>,
+}
+
+impl Drop for Fetched {
+    fn drop(&mut self) {
+        if let Some(checkout) = &self.checkout {
+            let _ = fs::remove_dir_all(checkout);
+        }
+    }
+}
+
+/// Install one plugin or namespace from `source` and return its name.
+/// `name` picks one when the source holds several; names in `reserved` (the
+/// built-in commands) are refused.
+pub fn install(
+    root: &Path,
+    source: &str,
+    name: Option<&str>,
+    reserved: &[&str],
+) -> Result<String, String> {
+    let plugins_dir = root.join("plugins");
+    let mut installed = load_installed(&plugins_dir)?;
+    let fetched = fetch(root, source)?;
+    let Located {
+        name,
+        version,
+        files,
+    } = locate(&fetched, name)?;
+
+    let command = name.split('/').next().unwrap_or_default();
+    if reserved.contains(&command) {
+        return Err(format!(
+            "'{command}' is a built-in command; `boucle {command}` would never run the plugin"
+        ));
+    }
+    if let Some(existing) = installed.get(&name) {
+        return Err(format!(
+            "'{name}' is already installed from {}; use `boucle plugin update {name}`",
+            existing.source
+        ));
+    }
+    if command_plugins::find(&plugins_dir, &name).is_ok()
+        || plugins_dir.join(&name).is_dir()
+        || files.iter().any(|(_, to)| plugins_dir.join(to).exists())
+    {
+        return Err(format!("plugins/ already has a plugin named '{name}'"));
+    }
+
+    copy_files(&files, &plugins_dir)?;
+    installed.insert(
+        name.clone(),
+        Installed {
+            source: source_label(source, &fetched),
+            revision: fetched.revision.clone(),
+            version,
+            installed_at: chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+            files: files.into_iter().map(|(_, to)| to).collect(),
+        },
+    );
+    save_installed(&plugins_dir, &installed)?;
+    Ok(name)
+}
+
+/// Fetch installed plugins again from their sources: `name`, or all of
+/// them. Returns one line per plugin; a plugin that could not be updated is
+/// an `Err` line and leaves its files as they were.
+pub fn update(root: &Path, name: Option<&str>) -> Result<Vec<Result<String, String>>, String> {
+    let plugins_dir = root.join("plugins");
+    let mut installed = load_installed(&plugins_dir)?;
+    let names: Vec<String> = match name {
+        Some(name) if !installed.contains_key(name) => return Err(not_installed(name)),
+        Some(name) => vec![name.to_string()],
+        None => installed.keys().cloned().collect(),
+    };
+
+    let mut results = Vec::new();
+    for name in names {
+        let entry = installed.get_mut(&name).expect("listed above");
+        results.push(update_one(root, &plugins_dir, &name, entry));
+    }
+    save_installed(&plugins_dir, &installed)?;
+    Ok(results)
+}
+
+fn update_one(
+    root: &Path,
+    plugins_dir: &Path,
+    name: &str,
+    entry: &mut Installed,
+) -> Result<String, String> {
+    let fail = |e: String| format!("{name}: {e}");
+    let fetched = fetch(root, &entry.source).map_err(fail)?;
+    let Located { version, files, .. } = locate(&fetched, Some(name)).map_err(fail)?;
+
+    if same_files(&files, plugins_dir, entry) {
+        entry.revision = fetched.revision.clone();
+        entry.version = version;
+        return Ok(format!("{name}: up to date"));
+    }
+
+    for old in &entry.files {
+        if !files.iter().any(|(_, to)| to == old) {
+            remove_file(&plugins_dir.join(old)).map_err(fail)?;
+        }
+    }
+    copy_files(&files, plugins_dir).map_err(fail)?;
+    let old = label(entry.version.as_deref(), entry.revision.as_deref());
+    let new = label(version.as_deref(), fetched.revision.as_deref());
+    let message = match (old, new) {
+        (Some(old), Some(new)) if old != new => format!("{name}: updated ({old} -> {new})"),
+        _ => format!("{name}: updated"),
+    };
+    entry.revision = fetched.revision.clone();
+    entry.version = version;
+    entry.installed_at = chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    entry.files = files.into_iter().map(|(_, to)| to).collect();
+    Ok(message)
+}
+
+/// An installed plugin next to what its source holds now.
+#[derive(Debug, PartialEq)]
+pub struct Comparison {
+    pub name: String,
+    /// Installed version, or commit when the manifest has none.
+    pub installed: Option<String>,
+    /// The same for the source.
+    pub available: Option<String>,
+    /// Whether `update` would change any of its files.
+    pub outdated: bool,
+}
+
+/// Compare every installed plugin with its source, without changing
+/// anything. A source that cannot be fetched is an `Err` line.
+pub fn outdated(root: &Path) -> Result<Vec<Result<Comparison, String>>, String> {
+    let plugins_dir = root.join("plugins");
+    let installed = load_installed(&plugins_dir)?;
+    Ok(installed
+        .iter()
+        .map(|(name, entry)| {
+            let fail = |e: String| format!("{name}: {e}");
+            let fetched = fetch(root, &entry.source).map_err(fail)?;
+            let Located { version, files, .. } = locate(&fetched, Some(name)).map_err(fail)?;
+            Ok(Comparison {
+                name: name.clone(),
+                installed: label(entry.version.as_deref(), entry.revision.as_deref()),
+                available: label(version.as_deref(), fetched.revision.as_deref()),
+                outdated: !same_files(&files, &plugins_dir, entry),
+            })
+        })
+        .collect())
+}
+
+/// Whether `files` from a source are exactly the installed plugin's files.
+fn same_files(files: &[(PathBuf, String)], plugins_dir: &Path, entry: &Installed) -> bool {
+    files.len() == entry.files.len()
+        && files.iter().all(|(from, to)| {
+            entry.files.contains(to) && fs::read(from).ok() == fs::read(plugins_dir.join(to)).ok()
+        })
+}
+
+/// How to show a version: `v<version>` from the manifest, else the short
+/// commit.
+fn label(version: Option<&str>, revision: Option<&str>) -> Option<String> {
+    version
+        .map(|version| format!("v{version}"))
+        .or_else(|| revision.map(|revision| short(revision).to_string()))
+}
+
+/// Delete an installed plugin's files and forget it. Returns the files
+/// removed, relative to `plugins/`.
+pub fn remove(root: &Path, name: &str) -> Result<Vec<String>, String> {
+    let plugins_dir = root.join("plugins");
+    let mut installed = load_installed(&plugins_dir)?;
+    let entry = installed.remove(name).ok_or_else(|| not_installed(name))?;
+    for file in &entry.files {
+        let path = plugins_dir.join(file);
+        remove_file(&path)?;
+        // A namespace directory goes with its last file.
+        if let Some(dir) = path.parent().filter(|dir| *dir != plugins_dir) {
+            let _ = fs::remove_dir(dir);
+        }
+    }
+    save_installed(&plugins_dir, &installed)?;
+    Ok(entry.files)
+}
+
+/// Installed plugins by name, from `plugins/.installed.json`. A file path
+/// that would reach outside `plugins/` is an error, since `update` and
+/// `remove` delete what is listed.
+pub fn load_installed(plugins_dir: &Path) -> Result<BTreeMap<String, Installed>, String> {
+    let path = plugins_dir.join(INSTALLED_FILE);
+    let installed: BTreeMap<String, Installed> = match fs::read_to_string(&path) {
+        Ok(text) => serde_json::from_str(&text).map_err(|e| format!("{}: {e}", path.display()))?,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+        Err(e) => return Err(format!("{}: {e}", path.display())),
+    };
+    for (name, entry) in &installed {
+        if let Some(file) = entry.files.iter().find(|file| !is_inside(file)) {
+            return Err(format!(
+                "{}: '{name}' lists '{file}', which is outside plugins/",
+                path.display()
+            ));
+        }
+    }
+    Ok(installed)
+}
+
+/// Whether `file` is a plain relative path, with no `..` to climb out.
+fn is_inside(file: &str) -> bool {
+    Path::new(file)
+        .components()
+        .all(|part| matches!(part, Component::Normal(_) | Component::CurDir))
+}
+
+fn save_installed(
+    plugins_dir: &Path,
+    installed: &BTreeMap<String, Installed>,
+) -> Result<(), String> {
+    let path = plugins_dir.join(INSTALLED_FILE);
+    if installed.is_empty() {
+        return remove_file(&path);
+    }
+    let json = serde_json::to_string_pretty(installed).map_err(|e| e.to_string())?;
+    fs::write(&path, json + "\n").map_err(|e| format!("{}: {e}", path.display()))
+}
+
+fn not_installed(name: &str) -> String {
+    format!(
+        "'{name}' was not installed with `boucle plugin install`; its files in plugins/ are yours to edit or delete"
+    )
+}
+
+fn is_git_url(source: &str) -> bool {
+    ["https://", "http://", "ssh://", "git://", "file://", "git@"]
+        .iter()
+        .any(|prefix| source.starts_with(prefix))
+        || (source.ends_with(".git") && !Path::new(source).exists())
+}
+
+/// Clone a git source, or resolve a local path.
+fn fetch(root: &Path, source: &str) -> Result<Fetched, String> {
+    if !is_git_url(source) {
+        let path = fs::canonicalize(source).map_err(|e| format!("{source}: {e}"))?;
+        if path.is_file() {
+            return Ok(Fetched {
+                dir: path.parent().unwrap_or(Path::new("/")).to_path_buf(),
+                name: path.file_stem().map(|s| s.to_string_lossy().to_string()),
+                revision: None,
+                checkout: None,
+            });
+        }
+        return Ok(Fetched {
+            dir: path,
+            name: None,
+            revision: None,
+            checkout: None,
+        });
+    }
+
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or_default();
+    let checkout = root
+        .join(CHECKOUT_DIR)
+        .join(format!("{}-{nanos}", process::id()));
+    fs::create_dir_all(root.join(CHECKOUT_DIR)).map_err(|e| format!("{CHECKOUT_DIR}: {e}"))?;
+    let revision = git::clone(source, &checkout).map_err(|e| {
+        let _ = fs::remove_dir_all(&checkout);
+        e.to_string()
+    })?;
+    Ok(Fetched {
+        dir: checkout.clone(),
+        name: None,
+        revision: Some(revision),
+        checkout: Some(checkout),
+    })
+}
+
+/// Absolute path for local sources, so `update` works from anywhere.
+fn source_label(source: &str, fetched: &Fetched) -> String {
+    if fetched.checkout.is_some() {
+        return source.to_string();
+    }
+    match &fetched.name {
+        Some(name) => fs::canonicalize(source)
+            .map(|p| p.display().to_string())
+            .unwrap_or_else(|_| fetched.dir.join(name).display().to_string()),
+        None => fetched.dir.display().to_string(),
+    }
+}
+
+/// What to install from a fetched source: `name` (a plugin or namespace),
+/// the script the source points at, or the only plugin with a manifest
+/// there (or the only namespace holding them).
+fn locate(fetched: &Fetched, name: Option<&str>) -> Result<Located, String> {
+    let dir = match fetched.dir.join("plugins") {
+        nested if nested.is_dir() => nested,
+        _ => fetched.dir.clone(),
+    };
+    let requested = name.or(fetched.name.as_deref());
+    if let Some(namespace) = requested.filter(|n| !n.contains('/') && dir.join(n).is_dir()) {
+        return locate_namespace(&dir, namespace);
+    }
+    let plugin = match requested {
+        Some(name) => {
+            command_plugins::check_name(name)?;
+            command_plugins::find(&dir, name).map_err(|e| e.to_string())?
+        }
+        None => {
+            let found =
+                command_plugins::discover(&dir).map_err(|e| format!("{}: {e}", dir.display()))?;
+            let (mut plugins, errors): (Vec<_>, Vec<_>) =
+                found.into_iter().partition(Result::is_ok);
+            plugins.retain(|p| {
+                p.as_ref()
+                    .is_ok_and(|p| p.script.is_some() && p.manifest.is_some())
+            });
+            match plugins.len() {
+                1 => plugins.remove(0).expect("kept only Ok"),
+                0 => {
+                    let mut message = format!("no plugin with a manifest in {}", dir.display());
+                    for e in errors.into_iter().filter_map(Result::err) {
+                        message.push_str(&format!("\n  {e}"));
+                    }
+                    return Err(message);
+                }
+                _ => {
+                    let names: Vec<_> = plugins
+                        .into_iter()
+                        .filter_map(Result::ok)
+                        .map(|p| p.name)
+                        .collect();
+                    let namespace = names[0].split_once('/').map(|(namespace, _)| namespace);
+                    if let Some(namespace) = namespace.filter(|namespace| {
+                        let prefix = format!("{namespace}/");
+                        names.iter().all(|name| name.starts_with(&prefix))
+                    }) {
+                        return locate_namespace(&dir, namespace);
+                    }
+                    return Err(format!(
+                        "{} has several plugins ({}); pick one with --name",
+                        dir.display(),
+                        names.join(", ")
+                    ));
+                }
+            }
+        }
+    };
+    command_plugins::check_name(&plugin.name)?;
+    if plugin.manifest.is_none() {
+        return Err(format!(
+            "plugin '{0}' has no manifest ({0}.toml); only plugins with one can be installed",
+            plugin.name
+        ));
+    }
+    Ok(Located {
+        name: plugin.name.clone(),
+        version: plugin.version().map(String::from),
+        files: plugin_files(&dir, &plugin),
+    })
+}
+
+/// A whole namespace in `dir`: every file in it, helpers included, and its
+/// smoke tests in `tests/<namespace>/`. At least one plugin in it needs a
+/// manifest.
+fn locate_namespace(dir: &Path, namespace: &str) -> Result<Located, String> {
+    command_plugins::check_name(namespace)?;
+    let prefix = format!("{namespace}/");
+    let found = command_plugins::discover(dir).map_err(|e| format!("{}: {e}", dir.display()))?;
+    let mut plugins = 0;
+    for plugin in found {
+        match plugin {
+            Ok(plugin) if plugin.name.starts_with(&prefix) && plugin.manifest.is_some() => {
+                plugins += 1;
+            }
+            Err(PluginError::Manifest(name, e)) if name.starts_with(&prefix) => {
+                return Err(PluginError::Manifest(name, e).to_string());
+            }
+            _ => {}
+        }
+    }
+    if plugins == 0 {
+        return Err(format!(
+            "no plugin with a manifest in {}",
+            dir.join(namespace).display()
+        ));
+    }
+
+    let mut files = Vec::new();
+    for relative in [namespace.to_string(), format!("tests/{namespace}")] {
+        let Ok(entries) = fs::read_dir(dir.join(&relative)) else {
+            continue;
+        };
+        for path in entries.flatten().map(|entry| entry.path()) {
+            let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+            if path.is_file() && !file_name.starts_with('.') {
+                files.push((path.clone(), format!("{relative}/{file_name}")));
+            }
+        }
+    }
+    files.sort_by(|a, b| a.1.cmp(&b.1));
+    Ok(Located {
+        name: namespace.to_string(),
+        version: None,
+        files,
+    })
+}
+
+/// Files that make up a plugin in `dir`, as (source path, path relative to
+/// `plugins/`): the script, its manifest, and its smoke test if it has one.
+fn plugin_files(dir: &Path, plugin: &Plugin) -> Vec<(PathBuf, String)> {
+    let Some(script) = &plugin.script else {
+        return Vec::new();
+    };
+    let name = &plugin.name;
+    let mut files = vec![
+        (
+            script.clone(),
+            script
+                .strip_prefix(dir)
+                .unwrap_or(script)
+                .to_string_lossy()
+                .to_string(),
+        ),
+        (dir.join(format!("{name}.toml")), format!("{name}.toml")),
+    ];
+    let test = dir.join("tests").join(format!("{name}.sh"));
+    if test.is_file() {
+        files.push((test, format!("tests/{name}.sh")));
+    }
+    files
+}
+
+fn copy_files(files: &[(PathBuf, String)], plugins_dir: &Path) -> Result<(), String> {
+    for (from, to) in files {
+        let to = plugins_dir.join(to);
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("{}: {e}", parent.display()))?;
+        }
+        fs::copy(from, &to)
+            .map_err(|e| format!("copy {} to {}: {e}", from.display(), to.display()))?;
+    }
+    Ok(())
+}
+
+fn remove_file(path: &Path) -> Result<(), String> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => {
+            Err(format!("remove {}: {e}", path.display()))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn short(revision: &str) -> &str {
+    &revision[..revision.len().min(10)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An agent root with one installable plugin in its `plugins/`.
+    fn source_agent(description: &str) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let plugins_dir = dir.path().join("plugins");
+        command_plugins::scaffold(&plugins_dir, "weather", "shell", Some(description)).unwrap();
+        fs::write(plugins_dir.join("notes.sh"), "#!/bin/sh\n").unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_install_update_remove_from_a_path() {
+        let source = source_agent("v1");
+        let agent = tempfile::tempdir().unwrap();
+        let plugins_dir = agent.path().join("plugins");
+        let source_path = source.path().to_str().unwrap();
+
+        let name = install(agent.path(), source_path, None, &["run"]).unwrap();
+        assert_eq!(name, "weather");
+        let plugin = command_plugins::find(&plugins_dir, "weather").unwrap();
+        assert_eq!(plugin.description().as_deref(), Some("v1"));
+        assert!(plugins_dir.join("tests/weather.sh").is_file());
+        assert!(!plugins_dir.join("notes.sh").exists());
+        let installed = load_installed(&plugins_dir).unwrap();
+        assert_eq!(installed["weather"].files.len(), 3);
+
+        let err = install(agent.path(), source_path, None, &[]).unwrap_err();
+        assert!(err.contains("already installed"), "{err}");
+
+        assert_eq!(
+            update(agent.path(), None).unwrap(),
+            [Ok("weather: up to date".to_string())]
+        );
+        fs::write(
+            source.path().join("plugins/weather.toml"),
+            "description = \"v2\"\n",
+        )
+        .unwrap();
+        fs::remove_file(source.path().join("plugins/tests/weather.sh")).unwrap();
+        assert_eq!(
+            update(agent.path(), Some("weather")).unwrap(),
+            [Ok("weather: updated".to_string())]
+        );
+        let plugin = command_plugins::find(&plugins_dir, "weather").unwrap();
+        assert_eq!(plugin.description().as_deref(), Some("v2"));
+        assert!(!plugins_dir.join("tests/weather.sh").exists());
+
+        let removed = remove(agent.path(), "weather").unwrap();
+        assert_eq!(removed, ["weather.sh", "weather.toml"]);
+        assert!(command_plugins::find(&plugins_dir, "weather").is_err());
+        assert!(!plugins_dir.join(INSTALLED_FILE).exists());
+        assert!(remove(agent.path(), "weather").is_err());
+    }
+
+    #[test]
+    fn test_outdated_compares_versions_with_the_source() {
+        let source = source_agent("v1");
+        let agent = tempfile::tempdir().unwrap();
+        install(agent.path(), source.path().to_str().unwrap(), None, &[]).unwrap();
+        let comparison = |outdated: bool, available: &str| Comparison {
+            name: "weather".to_string(),
+            installed: Some("v0.1.0".to_string()),
+            available: Some(available.to_string()),
+            outdated,
+        };
+
+        assert_eq!(
+            outdated(agent.path()).unwrap(),
+            [Ok(comparison(false, "v0.1.0"))]
+        );
+        let manifest = source.path().join("plugins/weather.toml");
+        let text = fs::read_to_string(&manifest).unwrap();
+        fs::write(&manifest, text.replace("0.1.0", "0.2.0")).unwrap();
+        assert_eq!(
+            outdated(agent.path()).unwrap(),
+            [Ok(comparison(true, "v0.2.0"))]
+        );
+
+        assert_eq!(
+            update(agent.path(), None).unwrap(),
+            [Ok("weather: updated (v0.1.0 -> v0.2.0)".to_string())]
+        );
+        let installed = load_installed(&agent.path().join("plugins")).unwrap();
+        assert_eq!(installed["weather"].version.as_deref(), Some("0.2.0"));
+        fs::remove_dir_all(source.path()).unwrap();
+        let results = outdated(agent.path()).unwrap();
+        assert!(results[0]
+            .as_ref()
+            .is_err_and(|e| e.starts_with("weather: ")));
+    }
+
+    #[test]
+    fn test_remove_refuses_paths_outside_plugins() {
+        let agent = tempfile::tempdir().unwrap();
+        let plugins_dir = agent.path().join("plugins");
+        fs::create_dir_all(&plugins_dir).unwrap();
+        fs::write(agent.path().join("boucle.toml"), "").unwrap();
+        for file in ["../boucle.toml", "/etc/hostname"] {
+            let listing = serde_json::json!({
+                "evil": {"source": "x", "installed_at": "0", "files": ["evil.sh", file]}
+            });
+            fs::write(plugins_dir.join(INSTALLED_FILE), listing.to_string()).unwrap();
+            let err = remove(agent.path(), "evil").unwrap_err();
+            assert!(err.contains(&format!("lists '{file}'")), "{err}");
+        }
+        assert!(agent.path().join("boucle.toml").exists());
+    }
+
+    #[test]
+    fn test_install_refuses_unmanifested_and_clashing_plugins() {
+        let source = source_agent("v1");
+        let agent = tempfile::tempdir().unwrap();
+        let script = source.path().join("plugins/notes.sh");
+
+        let err = install(agent.path(), script.to_str().unwrap(), None, &[]).unwrap_err();
+        assert!(err.contains("has no manifest"), "{err}");
+
+        let source_path = source.path().to_str().unwrap();
+        let err = install(agent.path(), source_path, None, &["weather"]).unwrap_err();
+        assert!(err.contains("built-in"), "{err}");
+
+        command_plugins::scaffold(&agent.path().join("plugins"), "weather", "shell", None).unwrap();
+        let err = install(agent.path(), source_path, None, &[]).unwrap_err();
+        assert!(
+            err.contains("already has a plugin named 'weather'"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_install_a_namespace() {
+        let source = tempfile::tempdir().unwrap();
+        let source_plugins = source.path().join("plugins");
+        command_plugins::scaffold(&source_plugins, "github/issues", "shell", None).unwrap();
+        command_plugins::scaffold(&source_plugins, "github/prs", "python", None).unwrap();
+        fs::write(source_plugins.join("github/common.sh"), "# shared\n").unwrap();
+        let agent = tempfile::tempdir().unwrap();
+        let plugins_dir = agent.path().join("plugins");
+        let source_path = source.path().to_str().unwrap();
+
+        assert_eq!(
+            install(agent.path(), source_path, None, &[]).unwrap(),
+            "github"
+        );
+        assert!(command_plugins::find(&plugins_dir, "github/prs").is_ok());
+        assert!(plugins_dir.join("github/common.sh").is_file());
+        let installed = load_installed(&plugins_dir).unwrap();
+        assert_eq!(installed["github"].files.len(), 7);
+        let err = install(agent.path(), source_path, Some("github"), &[]).unwrap_err();
+        assert!(err.contains("already installed"), "{err}");
+
+        remove(agent.path(), "github").unwrap();
+        assert!(!plugins_dir.join("github").exists());
+        assert!(!plugins_dir.join("tests/github").exists());
+    }
+
+    #[test]
+    fn test_install_from_git() {
+        let source = source_agent("from git");
+        git::tests::init_repo(source.path());
+        let agent = tempfile::tempdir().unwrap();
+        let url = format!("file://{}", source.path().display());
+
+        install(agent.path(), &url, Some("weather"), &[]).unwrap();
+        let installed = load_installed(&agent.path().join("plugins")).unwrap();
+        assert_eq!(installed["weather"].source, url);
+        assert_eq!(installed["weather"].revision.as_ref().unwrap().len(), 40);
+        let checkouts = fs::read_dir(agent.path().join(CHECKOUT_DIR)).unwrap();
+        assert_eq!(checkouts.count(), 0);
+    }
+}
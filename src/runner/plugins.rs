@@ -5,7 +5,7 @@
 //! a more type-safe and performant middleware pattern.
 
 use crate::config::Config;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 
 /// Plugin context containing shared dependencies that plugins need.
@@ -19,6 +19,8 @@ pub struct PluginContext<'a> {
     pub iteration: usize,
     /// Additional plugin-specific data
     pub data: HashMap<String, String>,
+    /// Flags set by plugins that already ran (see `PluginResult::flags`)
+    pub flags: HashSet<String>,
 }
 
 /// Plugin metadata describing the plugin's purpose and behavior.
@@ -35,9 +37,12 @@ pub struct PluginMeta {
     pub is_external: bool,
     /// Plugin priority (lower numbers run first)
     pub priority: i32,
+    /// Plugins that must run before this one, whatever their priority
+    pub after: Vec<String>,
 }
 
 /// Result of plugin execution containing content and metadata.
+#[derive(Default)]
 #[allow(dead_code)]
 pub struct PluginResult {
     /// Generated content (markdown)
@@ -46,6 +51,14 @@ pub struct PluginResult {
     pub warnings: Vec<String>,
     /// Optional metadata for logging/debugging
     pub metadata: HashMap<String, String>,
+    /// Heading for the output; a numbered one if unset
+    pub title: Option<String>,
+    /// Placement in the plugins section, overriding `PluginMeta::priority`
+    pub priority: Option<i32>,
+    /// Trust level of this output, overriding `PluginMeta::is_external`
+    pub trusted: Option<bool>,
+    /// Flags for plugins that run later (`PluginContext::flags`)
+    pub flags: Vec<String>,
 }
 
 /// Error types for plugin operations.
@@ -116,8 +129,10 @@ impl PluginRegistry {
             return Ok(());
         }
 
-        // Sort plugins by priority
+        // Sort plugins by priority, then move each after the plugins it
+        // is declared to follow
         self.plugins.sort_by_key(|p| p.meta().priority);
+        self.order_after()?;
 
         // Initialize each plugin
         for plugin in &mut self.plugins {
@@ -128,11 +143,12 @@ impl PluginRegistry {
         Ok(())
     }
 
-    /// Execute all plugins and collect their outputs
+    /// Execute all plugins and collect their outputs, passing the flags
+    /// each result sets on to the plugins after it
     pub fn execute_all(
         &self,
-        context: &PluginContext,
-    ) -> Result<Vec<(String, PluginResult)>, PluginError> {
+        context: &mut PluginContext,
+    ) -> Result<Vec<(PluginMeta, PluginResult)>, PluginError> {
         if !self.initialized {
             return Err(PluginError::InitializationFailed(
                 "Registry not initialized".to_string(),
@@ -143,14 +159,46 @@ impl PluginRegistry {
 
         for plugin in &self.plugins {
             if plugin.should_run(context) {
-                let result = plugin.execute(context)?;
-                results.push((plugin.meta().name.clone(), result));
+                let span = tracing::info_span!("plugin", name = %plugin.meta().name);
+                let result = span.in_scope(|| plugin.execute(context))?;
+                context.flags.extend(result.flags.iter().cloned());
+                results.push((plugin.meta().clone(), result));
             }
         }
 
         Ok(results)
     }
 
+    /// Stable topological sort on `PluginMeta::after`: a plugin keeps its
+    /// place unless something it follows comes later. An `after` name
+    /// matches a plugin's name or, for scripts, its file stem; names that
+    /// match no registered plugin are ignored.
+    fn order_after(&mut self) -> Result<(), PluginError> {
+        let matches = |plugin: &dyn ContextPlugin, name: &str| {
+            let own = plugin.meta().name.as_str();
+            own == name || Path::new(own).file_stem().is_some_and(|stem| stem == name)
+        };
+        let mut pending = std::mem::take(&mut self.plugins);
+        while !pending.is_empty() {
+            let ready = pending.iter().position(|p| {
+                p.meta().after.iter().all(|a| {
+                    !pending
+                        .iter()
+                        .any(|other| !std::ptr::addr_eq(other, p) && matches(other.as_ref(), a))
+                })
+            });
+            let Some(index) = ready else {
+                let stuck: Vec<&str> = pending.iter().map(|p| p.meta().name.as_str()).collect();
+                return Err(PluginError::InitializationFailed(format!(
+                    "plugins {} are ordered after each other",
+                    stuck.join(", ")
+                )));
+            };
+            self.plugins.push(pending.remove(index));
+        }
+        Ok(())
+    }
+
     /// Cleanup all plugins
     #[allow(dead_code)]
     pub fn cleanup(&mut self) -> Result<(), PluginError> {
@@ -184,6 +232,7 @@ pub struct PluginMetaBuilder {
     version: String,
     is_external: bool,
     priority: i32,
+    after: Vec<String>,
 }
 
 impl PluginMetaBuilder {
@@ -194,6 +243,7 @@ impl PluginMetaBuilder {
             version: "1.0.0".to_string(),
             is_external: false,
             priority: 100,
+            after: Vec::new(),
         }
     }
 
@@ -217,6 +267,11 @@ impl PluginMetaBuilder {
         self
     }
 
+    pub fn after(mut self, after: Vec<String>) -> Self {
+        self.after = after;
+        self
+    }
+
     pub fn build(self) -> PluginMeta {
         PluginMeta {
             name: self.name,
@@ -224,6 +279,7 @@ impl PluginMetaBuilder {
             version: self.version,
             is_external: self.is_external,
             priority: self.priority,
+            after: self.after,
         }
     }
 }
@@ -233,7 +289,7 @@ mod tests {
     use super::*;
     use crate::config;
     use crate::runner;
-    use std::collections::HashMap;
+    use std::collections::{HashMap, HashSet};
 
     struct TestPlugin {
         meta: PluginMeta,
@@ -265,8 +321,8 @@ mod tests {
         fn execute(&self, _context: &PluginContext) -> Result<PluginResult, PluginError> {
             Ok(PluginResult {
                 content: format!("Output from {}", self.meta.name),
-                warnings: vec![],
-                metadata: HashMap::new(),
+                flags: vec![format!("{}-ran", self.meta.name)],
+                ..PluginResult::default()
             })
         }
 
@@ -295,19 +351,60 @@ mod tests {
 
         registry.register(plugin);
 
-        let context = PluginContext {
+        let mut context = PluginContext {
             root: dir.path(),
             config: &cfg,
             iteration: 1,
             data: HashMap::new(),
+            flags: HashSet::new(),
         };
 
         registry.initialize(&context).unwrap();
-        let results = registry.execute_all(&context).unwrap();
+        let results = registry.execute_all(&mut context).unwrap();
 
         assert_eq!(results.len(), 1);
-        assert_eq!(results[0].0, "test");
+        assert_eq!(results[0].0.name, "test");
         assert!(results[0].1.content.contains("Output from test"));
+        assert!(context.flags.contains("test-ran"));
+    }
+
+    #[test]
+    fn test_after_overrides_priority_order() {
+        let dir = tempfile::tempdir().unwrap();
+        runner::init(dir.path(), "test-agent").unwrap();
+        let cfg = config::load(dir.path()).unwrap();
+        let context = PluginContext {
+            root: dir.path(),
+            config: &cfg,
+            iteration: 1,
+            data: HashMap::new(),
+            flags: HashSet::new(),
+        };
+        let plugin = |name: &str, priority: i32, after: &[&str]| {
+            let mut plugin = TestPlugin::new(name);
+            plugin.meta = PluginMetaBuilder::new(name)
+                .priority(priority)
+                .after(after.iter().map(|a| a.to_string()).collect())
+                .build();
+            Box::new(plugin)
+        };
+
+        let mut registry = PluginRegistry::new();
+        registry.register(plugin("report", 10, &["issues", "not-installed"]));
+        registry.register(plugin("issues", 50, &[]));
+        registry.register(plugin("status", 20, &[]));
+        registry.initialize(&context).unwrap();
+        assert_eq!(registry.plugin_names(), ["status", "issues", "report"]);
+
+        let mut registry = PluginRegistry::new();
+        registry.register(plugin("a", 1, &["b"]));
+        registry.register(plugin("b", 2, &["a"]));
+        registry.register(plugin("c", 3, &[]));
+        let err = registry.initialize(&context).unwrap_err().to_string();
+        assert_eq!(
+            err,
+            "Plugin initialization failed: plugins a, b are ordered after each other"
+        );
     }
 
     #[test]
@@ -322,7 +419,7 @@ mod tests {
         assert_eq!(meta.name, "example");
         assert_eq!(meta.description, "An example plugin");
         assert_eq!(meta.version, "2.0.0");
-        assert_eq!(meta.is_external, true);
+        assert!(meta.is_external);
         assert_eq!(meta.priority, 25);
     }
 }
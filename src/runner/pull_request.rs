@@ -0,0 +1,124 @@
+//! Pull requests for run branches.
+//!
+//! With `git.pull_request`, the run branch is pushed to `git.remote` and
+//! reviewed as a pull request instead of being merged. The first push opens
+//! it with `gh pr create`; when the branch name is stable across runs, later
+//! pushes update the pull request that is already open.
+
+use crate::runner;
+use std::path::Path;
+use std::process::{self, Stdio};
+use std::time::Duration;
+
+/// Limit for each `gh` call.
+const GH_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// A pull request for a run branch.
+#[derive(Debug, PartialEq, Eq)]
+pub struct PullRequest {
+    pub url: String,
+    /// Already open before this push, which updated it.
+    pub updated: bool,
+}
+
+/// Push `branch` (checked out) and open a pull request into `base`, or find
+/// the one already open for it.
+pub fn publish(root: &Path, remote: &str, base: &str, branch: &str) -> Result<PullRequest, String> {
+    if runner::find_on_path("gh").is_none() {
+        return Err("gh is not installed".to_string());
+    }
+    runner::git::push(root, remote, branch).map_err(|e| e.to_string())?;
+
+    let open = gh(
+        root,
+        &[
+            "pr",
+            "view",
+            branch,
+            "--json",
+            "url,state",
+            "-q",
+            "select(.state == \"OPEN\") | .url",
+        ],
+    )
+    .unwrap_or_default();
+    if !open.is_empty() {
+        return Ok(PullRequest {
+            url: open,
+            updated: true,
+        });
+    }
+    // `--fill` titles it after the commit, or the branch when there are
+    // several, and lists the commits in the body.
+    let created = gh(
+        root,
+        &["pr", "create", "--base", base, "--head", branch, "--fill"],
+    )?;
+    let url = created
+        .lines()
+        .rev()
+        .find(|line| line.starts_with("http"))
+        .unwrap_or(&created)
+        .to_string();
+    Ok(PullRequest {
+        url,
+        updated: false,
+    })
+}
+
+/// Context for the next iteration, so the agent knows its work is waiting
+/// on review.
+pub fn context(pull_request: &PullRequest, base: &str, branch: &str) -> String {
+    let verb = if pull_request.updated {
+        "updated"
+    } else {
+        "opened"
+    };
+    format!(
+        "The last iteration's commits are on branch `{branch}`, {verb} for review as {} \
+         (into `{base}`). They are not on `{base}` until the pull request is merged.",
+        pull_request.url
+    )
+}
+
+/// Run `gh` and return its trimmed stdout, or the last line it printed to
+/// stderr.
+fn gh(root: &Path, args: &[&str]) -> Result<String, String> {
+    let mut cmd = process::Command::new("gh");
+    cmd.args(args)
+        .current_dir(root)
+        .env("GH_PROMPT_DISABLED", "1")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    runner::configure_child_process_group(&mut cmd);
+    let child = cmd.spawn().map_err(|e| format!("Failed to run gh: {e}"))?;
+    let output = runner::wait_with_output_timeout(child, GH_TIMEOUT).map_err(|e| e.to_string())?;
+    if output.timed_out {
+        return Err(format!("gh timed out after {}s", GH_TIMEOUT.as_secs()));
+    }
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let detail = stderr.lines().rev().find(|l| !l.trim().is_empty());
+        return Err(detail.unwrap_or("gh failed").trim().to_string());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_context_names_branch_and_url() {
+        let pull_request = PullRequest {
+            url: "https://github.com/o/r/pull/7".to_string(),
+            updated: true,
+        };
+        let text = context(&pull_request, "main", "boucle/agent");
+        assert!(
+            text.contains("`boucle/agent`, updated for review as https://github.com/o/r/pull/7")
+        );
+        assert!(text.contains("not on `main`"));
+    }
+}
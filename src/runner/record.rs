@@ -0,0 +1,464 @@
+//! Structured run records.
+//!
+//! Each non-dry-run iteration writes `<log>.json` next to its text log. The
+//! text log stays human-oriented; the record is what tooling (stats, cost
+//! tracking, session resumption) should read. The LLM's input is saved as
+//! `<log>.context.md` and `<log>.system-prompt.md` for `boucle replay`.
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+/// Everything known about one iteration once the LLM has returned.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct RunRecord {
+    #[serde(default)]
+    pub timestamp: String,
+    #[serde(default)]
+    pub agent: String,
+    #[serde(default)]
+    pub model: String,
+    /// CLI that ran the model: "claude" or "codex".
+    #[serde(default)]
+    pub backend: String,
+    #[serde(default)]
+    pub exit_code: Option<i32>,
+    #[serde(default)]
+    pub timed_out: bool,
+    #[serde(default)]
+    pub duration_ms: u64,
+    #[serde(default)]
+    pub context_bytes: usize,
+    /// Parsed backend result; `None` when the output was not structured.
+    #[serde(default)]
+    pub result: Option<LlmResult>,
+    /// Context sections the LLM was given, in order.
+    #[serde(default)]
+    pub sections: Vec<SectionDigest>,
+    /// Every hook script the iteration ran, in order.
+    #[serde(default)]
+    pub hooks: Vec<HookResult>,
+    /// The `git.push` at the end of the iteration, when enabled.
+    #[serde(default)]
+    pub push: Option<PushResult>,
+    /// Why the iteration's changes were not committed: the branch guard, a
+    /// `post-llm` veto, or the `pre-commit` hook.
+    #[serde(default)]
+    pub commit_blocked: Option<String>,
+    /// Commit the iteration made, if it made one.
+    #[serde(default)]
+    pub commit: Option<String>,
+    /// URL of the pull request opened or updated for the run branch.
+    #[serde(default)]
+    pub pull_request: Option<String>,
+    /// Milliseconds spent in each phase: "lock", "context", "llm" and
+    /// "commit". Time in hook scripts is in `hooks`.
+    #[serde(default)]
+    pub phases: BTreeMap<String, u64>,
+}
+
+impl RunRecord {
+    /// The LLM exited non-zero or timed out.
+    pub fn failed(&self) -> bool {
+        self.timed_out || self.exit_code != Some(0)
+    }
+
+    /// When the iteration started, from its timestamp (UTC).
+    pub fn started(&self) -> Option<DateTime<Utc>> {
+        NaiveDateTime::parse_from_str(&self.timestamp, "%Y-%m-%d_%H-%M-%S")
+            .ok()
+            .map(|t| t.and_utc())
+    }
+
+    /// Wall time of the iteration's phases and hooks; the LLM call alone for
+    /// records that predate phase timings.
+    pub fn total_ms(&self) -> u64 {
+        if self.phases.is_empty() {
+            return self.duration_ms;
+        }
+        self.phases.values().sum::<u64>() + self.hooks.iter().map(|h| h.duration_ms).sum::<u64>()
+    }
+}
+
+/// Where `git.push` pushed, and why it failed if it did.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct PushResult {
+    pub remote: String,
+    pub branch: String,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// What one hook script did.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct HookResult {
+    /// Lifecycle point, e.g. "pre-commit".
+    pub hook: String,
+    /// Script label: the hook name, or "<hook>.d/<file>".
+    pub script: String,
+    pub status: HookStatus,
+    #[serde(default)]
+    pub exit_code: Option<i32>,
+    #[serde(default)]
+    pub duration_ms: u64,
+    /// Stdout then stderr, cut at `HOOK_OUTPUT_BYTES`.
+    #[serde(default)]
+    pub output: String,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum HookStatus {
+    #[default]
+    Ok,
+    Failed,
+    Blocked,
+    TimedOut,
+    /// Started in the background; how it ended is in the text log.
+    Background,
+}
+
+impl HookStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            HookStatus::Ok => "ok",
+            HookStatus::Failed => "failed",
+            HookStatus::Blocked => "blocked",
+            HookStatus::TimedOut => "timed out",
+            HookStatus::Background => "background",
+        }
+    }
+}
+
+/// Longest hook output kept in a record.
+pub const HOOK_OUTPUT_BYTES: usize = 2000;
+
+/// Fingerprint of one context section, compared across iterations.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct SectionDigest {
+    pub key: String,
+    /// Content hash of the full section text.
+    pub hash: u64,
+    pub bytes: usize,
+    /// Consecutive iterations the section was replaced by an "unchanged"
+    /// marker; 0 when it was sent in full.
+    #[serde(default)]
+    pub elided: u32,
+}
+
+/// The structured part of a `claude -p --output-format json` response.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct LlmResult {
+    #[serde(default)]
+    pub text: String,
+    #[serde(default)]
+    pub session_id: Option<String>,
+    #[serde(default)]
+    pub stop_reason: Option<String>,
+    /// Result subtype, e.g. "success" or "error_max_turns".
+    #[serde(default)]
+    pub subtype: Option<String>,
+    #[serde(default)]
+    pub is_error: bool,
+    #[serde(default)]
+    pub num_turns: Option<u64>,
+    #[serde(default)]
+    pub total_cost_usd: Option<f64>,
+    #[serde(default)]
+    pub usage: Usage,
+}
+
+/// Token counts reported by the backend.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct Usage {
+    #[serde(default)]
+    pub input_tokens: u64,
+    #[serde(default)]
+    pub output_tokens: u64,
+    #[serde(default)]
+    pub cache_creation_input_tokens: u64,
+    #[serde(default)]
+    pub cache_read_input_tokens: u64,
+}
+
+/// Path of the record that belongs to a run log.
+pub fn record_path(log_file: &Path) -> PathBuf {
+    log_file.with_extension("json")
+}
+
+/// Path of the assembled context saved for a run log.
+pub fn context_path(log_file: &Path) -> PathBuf {
+    log_file.with_extension("context.md")
+}
+
+/// Path of the LLM output copied live during a run, for `boucle log --follow`.
+/// It is removed once the output is in the log.
+pub fn stream_path(log_file: &Path) -> PathBuf {
+    log_file.with_extension("stream")
+}
+
+/// Path of the system prompt saved for a run log.
+pub fn system_prompt_path(log_file: &Path) -> PathBuf {
+    log_file.with_extension("system-prompt.md")
+}
+
+/// Whether a file in the log directory is a saved replay input rather than
+/// output from the agent.
+pub fn is_replay_input(path: &Path) -> bool {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy())
+        .unwrap_or_default();
+    name.ends_with(".context.md") || name.ends_with(".system-prompt.md")
+}
+
+/// Save what the LLM was given so the iteration can be replayed.
+///
+/// The system prompt file is only written when the prompt is non-empty.
+pub fn save_inputs(log_file: &Path, system_prompt: &str, context: &str) -> Result<(), io::Error> {
+    fs::write(context_path(log_file), context)?;
+    if !system_prompt.is_empty() {
+        fs::write(system_prompt_path(log_file), system_prompt)?;
+    }
+    Ok(())
+}
+
+/// Load the saved `(system_prompt, context)` of a run.
+pub fn load_inputs(log_file: &Path) -> Result<(String, String), io::Error> {
+    let context = fs::read_to_string(context_path(log_file))?;
+    let system_prompt = match fs::read_to_string(system_prompt_path(log_file)) {
+        Ok(prompt) => prompt,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e),
+    };
+    Ok((system_prompt, context))
+}
+
+/// Parse `claude --output-format json` or `stream-json` stdout.
+///
+/// Tolerates leading noise (warnings printed before the JSON object) by
+/// falling back to the last line that parses, which is also where the
+/// result event of a stream is. Returns `None` for anything
+/// that is not a result object, so callers can log the raw output instead.
+pub fn parse_claude_json(stdout: &str) -> Option<LlmResult> {
+    let trimmed = stdout.trim();
+    let value: serde_json::Value = serde_json::from_str(trimmed).ok().or_else(|| {
+        trimmed
+            .lines()
+            .rev()
+            .find_map(|line| serde_json::from_str(line.trim()).ok())
+    })?;
+    // Newer CLIs can emit an array of events; the result is the last one.
+    let value = match value {
+        serde_json::Value::Array(events) => {
+            events.into_iter().rev().find(|e| e["type"] == "result")?
+        }
+        other => other,
+    };
+    if !value.is_object() || (value.get("result").is_none() && value["type"] != "result") {
+        return None;
+    }
+
+    let usage = &value["usage"];
+    let count = |key: &str| usage[key].as_u64().unwrap_or(0);
+    Some(LlmResult {
+        text: value["result"].as_str().unwrap_or_default().to_string(),
+        session_id: value["session_id"].as_str().map(str::to_string),
+        stop_reason: value["stop_reason"].as_str().map(str::to_string),
+        subtype: value["subtype"].as_str().map(str::to_string),
+        is_error: value["is_error"].as_bool().unwrap_or(false),
+        num_turns: value["num_turns"].as_u64(),
+        total_cost_usd: value["total_cost_usd"]
+            .as_f64()
+            .or_else(|| value["cost_usd"].as_f64()),
+        usage: Usage {
+            input_tokens: count("input_tokens"),
+            output_tokens: count("output_tokens"),
+            cache_creation_input_tokens: count("cache_creation_input_tokens"),
+            cache_read_input_tokens: count("cache_read_input_tokens"),
+        },
+    })
+}
+
+/// Write a record as pretty JSON.
+pub fn save(path: &Path, record: &RunRecord) -> Result<(), io::Error> {
+    let json = serde_json::to_string_pretty(record).map_err(io::Error::other)?;
+    fs::write(path, json)
+}
+
+/// Read a record; `None` if missing or unreadable.
+pub fn load(path: &Path) -> Option<RunRecord> {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+}
+
+/// Every run record in `log_dir`, oldest first.
+pub fn load_all(log_dir: &Path) -> Vec<RunRecord> {
+    let mut records: Vec<PathBuf> = fs::read_dir(log_dir)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+                .collect()
+        })
+        .unwrap_or_default();
+    // Log names are timestamps, so the newest sorts last.
+    records.sort();
+    records.iter().filter_map(|path| load(path)).collect()
+}
+
+/// Number of iterations that reached the LLM, counted by their records.
+pub fn count_runs(log_dir: &Path) -> usize {
+    fs::read_dir(log_dir)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .filter(|e| e.path().extension().is_some_and(|ext| ext == "json"))
+                .count()
+        })
+        .unwrap_or(0)
+}
+
+/// Context sections of the newest run in `log_dir` that recorded any.
+pub fn latest_sections(log_dir: &Path) -> Vec<SectionDigest> {
+    let Ok(entries) = fs::read_dir(log_dir) else {
+        return Vec::new();
+    };
+    let mut records: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    // Log names are timestamps, so the newest sorts last.
+    records.sort();
+    records
+        .iter()
+        .rev()
+        .filter_map(|path| load(path))
+        .map(|record| record.sections)
+        .find(|sections| !sections.is_empty())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CLAUDE_RESULT: &str = r#"{"type":"result","subtype":"success","is_error":false,"duration_ms":1234,"num_turns":3,"result":"Did the thing.","session_id":"abc-123","total_cost_usd":0.0421,"stop_reason":"end_turn","usage":{"input_tokens":100,"cache_creation_input_tokens":20,"cache_read_input_tokens":300,"output_tokens":50}}"#;
+
+    #[test]
+    fn test_parse_claude_json_result() {
+        let parsed = parse_claude_json(CLAUDE_RESULT).unwrap();
+        assert_eq!(parsed.text, "Did the thing.");
+        assert_eq!(parsed.session_id.as_deref(), Some("abc-123"));
+        assert_eq!(parsed.stop_reason.as_deref(), Some("end_turn"));
+        assert_eq!(parsed.subtype.as_deref(), Some("success"));
+        assert_eq!(parsed.num_turns, Some(3));
+        assert_eq!(parsed.total_cost_usd, Some(0.0421));
+        assert_eq!(parsed.usage.input_tokens, 100);
+        assert_eq!(parsed.usage.output_tokens, 50);
+        assert_eq!(parsed.usage.cache_read_input_tokens, 300);
+    }
+
+    #[test]
+    fn test_parse_claude_json_with_leading_noise_and_arrays() {
+        let noisy = format!("warning: something\n{CLAUDE_RESULT}\n");
+        assert_eq!(parse_claude_json(&noisy).unwrap().text, "Did the thing.");
+
+        let events = format!(r#"[{{"type":"system","subtype":"init"}},{CLAUDE_RESULT}]"#);
+        assert_eq!(
+            parse_claude_json(&events).unwrap().session_id.as_deref(),
+            Some("abc-123")
+        );
+
+        let stream = format!(
+            "{}\n{}\n{CLAUDE_RESULT}\n",
+            r#"{"type":"system","subtype":"init","session_id":"abc-123"}"#,
+            r#"{"type":"assistant","message":{"content":[{"type":"text","text":"On it."}]}}"#
+        );
+        assert_eq!(parse_claude_json(&stream).unwrap().num_turns, Some(3));
+    }
+
+    #[test]
+    fn test_parse_claude_json_rejects_plain_text() {
+        assert!(parse_claude_json("Just some prose output").is_none());
+        assert!(parse_claude_json(r#"{"unrelated": true}"#).is_none());
+        assert!(parse_claude_json("").is_none());
+    }
+
+    #[test]
+    fn test_record_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let log_file = dir.path().join("2026-01-01_00-00-00.log");
+        let path = record_path(&log_file);
+        assert_eq!(path, dir.path().join("2026-01-01_00-00-00.json"));
+
+        let record = RunRecord {
+            timestamp: "2026-01-01_00-00-00".to_string(),
+            backend: "claude".to_string(),
+            exit_code: Some(0),
+            result: parse_claude_json(CLAUDE_RESULT),
+            ..Default::default()
+        };
+        save(&path, &record).unwrap();
+        assert_eq!(load(&path), Some(record));
+        assert!(load(&dir.path().join("missing.json")).is_none());
+    }
+
+    #[test]
+    fn test_latest_sections_skips_records_without_sections() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(latest_sections(dir.path()).is_empty());
+
+        let digest = |key: &str| SectionDigest {
+            key: key.to_string(),
+            hash: 1,
+            bytes: 10,
+            elided: 0,
+        };
+        let with_sections = |sections| RunRecord {
+            sections,
+            ..Default::default()
+        };
+        save(
+            &dir.path().join("2026-01-01_00-00-00.json"),
+            &with_sections(vec![digest("old")]),
+        )
+        .unwrap();
+        save(
+            &dir.path().join("2026-01-02_00-00-00.json"),
+            &with_sections(vec![digest("goals")]),
+        )
+        .unwrap();
+        save(
+            &dir.path().join("2026-01-03_00-00-00.json"),
+            &with_sections(Vec::new()),
+        )
+        .unwrap();
+
+        assert_eq!(latest_sections(dir.path()), vec![digest("goals")]);
+    }
+
+    #[test]
+    fn test_replay_inputs_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let log_file = dir.path().join("2026-01-01_00-00-00.log");
+
+        save_inputs(&log_file, "", "## Goals\n").unwrap();
+        assert!(!system_prompt_path(&log_file).exists());
+        assert_eq!(
+            load_inputs(&log_file).unwrap(),
+            (String::new(), "## Goals\n".to_string())
+        );
+
+        save_inputs(&log_file, "Be terse.", "## Goals\n").unwrap();
+        assert_eq!(load_inputs(&log_file).unwrap().0, "Be terse.");
+        assert!(is_replay_input(&context_path(&log_file)));
+        assert!(is_replay_input(&system_prompt_path(&log_file)));
+        assert!(!is_replay_input(&log_file.with_extension("last-msg.md")));
+    }
+}
@@ -0,0 +1,338 @@
+//! Remote context sources.
+//!
+//! Each `[context.remote.<name>]` URL is fetched at most once
+//! per `ttl`. Once the TTL has passed the request carries the cached `ETag`
+//! and `Last-Modified` values, so an unchanged page costs a 304 instead of a
+//! full download. Responses live in `.boucle-cache/remote/`.
+
+use crate::config::{self, RemoteSourceConfig, SecretSource};
+use crate::runner::context_cache::content_hash;
+use reqwest::blocking::Client;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, ETAG, LAST_MODIFIED};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::{fs, io};
+
+/// Cache directory, relative to the agent root.
+const CACHE_DIR: &str = ".boucle-cache/remote";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedResponse {
+    fetched_at_unix: u64,
+    settings: u64,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    body: String,
+}
+
+/// Final status, validators, and body of an HTTP exchange, after redirects.
+#[derive(Debug, PartialEq)]
+pub(super) struct Response {
+    pub(super) status: u16,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    pub(super) body: String,
+}
+
+fn cache_path(root: &Path, name: &str) -> PathBuf {
+    root.join(CACHE_DIR).join(format!("{name}.json"))
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Body of remote source `name`, from the cache while it is fresh or the
+/// server reports it unchanged.
+pub fn fetch(
+    root: &Path,
+    name: &str,
+    source: &RemoteSourceConfig,
+    secrets: &BTreeMap<String, SecretSource>,
+    default_timeout: &str,
+) -> Result<String, String> {
+    let ttl = config::parse_interval(&source.ttl).map_err(|e| format!("ttl: {e}"))?;
+    let timeout = config::parse_interval(source.timeout.as_deref().unwrap_or(default_timeout))
+        .map_err(|e| format!("timeout: {e}"))?;
+    let headers = source
+        .headers
+        .iter()
+        .map(|(key, value)| {
+            let setting = format!("context.remote.{name}.headers.{key}");
+            config::resolve_env_value(root, secrets, &setting, value)
+                .map(|value| format!("{key}: {value}"))
+                .map_err(|e| e.to_string())
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let max_bytes = source.max_bytes.to_string();
+    let settings_parts: Vec<&str> = [source.url.as_str(), max_bytes.as_str()]
+        .into_iter()
+        .chain(
+            source
+                .headers
+                .iter()
+                .flat_map(|(k, v)| [k.as_str(), v.as_str()]),
+        )
+        .collect();
+    let settings = content_hash(&settings_parts);
+
+    let path = cache_path(root, name);
+    let cached = fs::read_to_string(&path)
+        .ok()
+        .and_then(|content| serde_json::from_str::<CachedResponse>(&content).ok())
+        .filter(|cached| cached.settings == settings);
+    let now = unix_now();
+    if let Some(cached) = &cached {
+        if now.saturating_sub(cached.fetched_at_unix) < ttl {
+            return Ok(cached.body.clone());
+        }
+    }
+
+    let response = request(
+        &source.url,
+        headers,
+        cached.as_ref(),
+        Duration::from_secs(timeout),
+        source.max_bytes,
+    )?;
+    let entry = match (response.status, cached) {
+        (304, Some(mut cached)) => {
+            cached.fetched_at_unix = now;
+            cached
+        }
+        (200..=299, _) => {
+            let mut body = response.body;
+            if body.len() > source.max_bytes {
+                let mut end = source.max_bytes;
+                while !body.is_char_boundary(end) {
+                    end -= 1;
+                }
+                body.truncate(end);
+                body.push_str("\n\n[... cut at max_bytes ...]");
+            }
+            CachedResponse {
+                fetched_at_unix: now,
+                settings,
+                etag: response.etag,
+                last_modified: response.last_modified,
+                body,
+            }
+        }
+        (status, _) => return Err(format!("HTTP {status}")),
+    };
+    if let Err(e) = store(&path, &entry) {
+        tracing::warn!("Remote source {name}: cache not written: {e}");
+    }
+    Ok(entry.body)
+}
+
+fn store(path: &Path, entry: &CachedResponse) -> Result<(), io::Error> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(
+        path,
+        serde_json::to_string(entry).map_err(io::Error::other)?,
+    )
+}
+
+/// GET `url`, revalidating against `cached` when it has validators. Reads
+/// one byte past `max_bytes`, enough to tell the body was cut.
+fn request(
+    url: &str,
+    mut headers: Vec<String>,
+    cached: Option<&CachedResponse>,
+    timeout: Duration,
+    max_bytes: usize,
+) -> Result<Response, String> {
+    if let Some(cached) = cached {
+        if let Some(etag) = &cached.etag {
+            headers.push(format!("If-None-Match: {etag}"));
+        }
+        if let Some(last_modified) = &cached.last_modified {
+            headers.push(format!("If-Modified-Since: {last_modified}"));
+        }
+    }
+    get(url, &headers, timeout, Some(max_bytes.saturating_add(1)))
+}
+
+/// GET `url`, sending `headers` ("Name: value") and following redirects.
+/// Only the first `limit` bytes of the body are read, when given.
+pub(super) fn get(
+    url: &str,
+    headers: &[String],
+    timeout: Duration,
+    limit: Option<usize>,
+) -> Result<Response, String> {
+    let response = client(timeout)?
+        .get(url)
+        .headers(header_map(headers)?)
+        .send()
+        .map_err(|e| describe(&e, timeout))?;
+    let status = response.status().as_u16();
+    let header = |name| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+    };
+    let etag = header(ETAG);
+    let last_modified = header(LAST_MODIFIED);
+    let mut body = Vec::new();
+    response
+        .take(limit.map_or(u64::MAX, |limit| limit as u64))
+        .read_to_end(&mut body)
+        .map_err(|e| format!("reading the response: {e}"))?;
+    let body = String::from_utf8_lossy(&body).into_owned();
+    Ok(Response {
+        status,
+        etag,
+        last_modified,
+        body,
+    })
+}
+
+/// HTTP client for one exchange. Header names keep their case on the wire,
+/// as some endpoints still expect.
+pub(super) fn client(timeout: Duration) -> Result<Client, String> {
+    Client::builder()
+        .timeout(timeout)
+        .http1_title_case_headers()
+        .user_agent(concat!("boucle/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .map_err(|e| format!("HTTP client: {e}"))
+}
+
+/// `headers` ("Name: value") as a header map. Values are marked sensitive
+/// so tokens stay out of debug output.
+pub(super) fn header_map(headers: &[String]) -> Result<HeaderMap, String> {
+    let mut map = HeaderMap::new();
+    for header in headers {
+        let (name, value) = header
+            .split_once(':')
+            .ok_or_else(|| format!("malformed header '{header}'"))?;
+        let name = HeaderName::from_bytes(name.trim().as_bytes())
+            .map_err(|_| format!("invalid header name '{}'", name.trim()))?;
+        let mut value = HeaderValue::from_str(value.trim())
+            .map_err(|_| format!("invalid value for header '{name}'"))?;
+        value.set_sensitive(true);
+        map.append(name, value);
+    }
+    Ok(map)
+}
+
+/// A failed request, with the underlying cause (DNS, TLS, refused...).
+pub(super) fn describe(error: &reqwest::Error, timeout: Duration) -> String {
+    if error.is_timeout() {
+        return format!("timed out after {}s", timeout.as_secs());
+    }
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        message.push_str(&format!(": {cause}"));
+        source = cause.source();
+    }
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+    use std::thread;
+
+    /// Serve `responses` in order, sending each request's headers back.
+    fn serve(responses: Vec<&'static str>) -> (String, mpsc::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/status", listener.local_addr().unwrap());
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = String::new();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                loop {
+                    let mut line = String::new();
+                    if reader.read_line(&mut line).unwrap() == 0 || line == "\r\n" {
+                        break;
+                    }
+                    request.push_str(&line);
+                }
+                stream.write_all(response.as_bytes()).unwrap();
+                tx.send(request).unwrap();
+            }
+        });
+        (url, rx)
+    }
+
+    #[test]
+    fn test_get_follows_redirects() {
+        let (url, requests) = serve(vec![
+            "HTTP/1.1 301 Moved Permanently\r\nLocation: /new\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            "HTTP/1.1 200 OK\r\nETag: \"v2\"\r\nContent-Length: 8\r\nConnection: close\r\n\r\nall good",
+        ]);
+        let headers = ["Authorization: Bearer token".to_string()];
+        assert_eq!(
+            get(&url, &headers, Duration::from_secs(10), None).unwrap(),
+            Response {
+                status: 200,
+                etag: Some("\"v2\"".to_string()),
+                last_modified: None,
+                body: "all good".to_string(),
+            }
+        );
+        assert!(requests.recv().unwrap().starts_with("GET /status"));
+        assert!(requests.recv().unwrap().starts_with("GET /new"));
+        assert!(get(&url, &headers[..0], Duration::from_secs(1), None).is_err());
+        assert!(header_map(&["no colon".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_fetch_revalidates_with_etag() {
+        let dir = tempfile::tempdir().unwrap();
+        let (url, requests) = serve(vec![
+            "HTTP/1.1 200 OK\r\nETag: \"v1\"\r\nContent-Length: 12\r\nConnection: close\r\n\r\nall systems!",
+            "HTTP/1.1 304 Not Modified\r\nETag: \"v1\"\r\nConnection: close\r\n\r\n",
+        ]);
+        let mut source = RemoteSourceConfig {
+            url,
+            headers: BTreeMap::from([("X-Token".to_string(), "secret".to_string())]),
+            ttl: "1h".to_string(),
+            timeout: None,
+            max_bytes: 3,
+        };
+
+        assert_eq!(
+            fetch(dir.path(), "status", &source, &BTreeMap::new(), "30s").unwrap(),
+            "all\n\n[... cut at max_bytes ...]"
+        );
+        let first = requests.recv().unwrap();
+        assert!(first.contains("X-Token: secret"));
+        assert!(!first.contains("If-None-Match"));
+
+        // Fresh within the TTL: no request at all.
+        fetch(dir.path(), "status", &source, &BTreeMap::new(), "30s").unwrap();
+
+        source.ttl = "0s".to_string();
+        assert!(
+            fetch(dir.path(), "status", &source, &BTreeMap::new(), "30s")
+                .unwrap()
+                .starts_with("all")
+        );
+        let second = requests.recv().unwrap();
+        assert!(second.contains("If-None-Match: \"v1\""));
+        assert!(requests.try_recv().is_err());
+    }
+}
@@ -0,0 +1,536 @@
+//! Reports over the run records: `boucle report usage` and `boucle report
+//! digest`.
+
+use super::{git, guard, load_config, notify, record, RunnerError};
+use crate::broca;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+/// Most failures and memory entries a digest lists one by one.
+const DIGEST_ITEMS: usize = 20;
+
+/// Longest goals and state diff a digest shows, in lines.
+const DIGEST_DIFF_LINES: usize = 200;
+
+/// What a set of iterations used.
+#[derive(Debug, Default, Serialize, PartialEq)]
+pub struct Usage {
+    pub runs: u64,
+    pub failed: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    /// Input tokens written to or read from the prompt cache.
+    pub cache_tokens: u64,
+    pub cost_usd: f64,
+    pub avg_duration_ms: u64,
+    #[serde(skip)]
+    total_ms: u64,
+}
+
+impl Usage {
+    fn add(&mut self, run: &record::RunRecord) {
+        self.runs += 1;
+        if run.failed() {
+            self.failed += 1;
+        }
+        if let Some(ref result) = run.result {
+            self.input_tokens += result.usage.input_tokens;
+            self.output_tokens += result.usage.output_tokens;
+            self.cache_tokens +=
+                result.usage.cache_read_input_tokens + result.usage.cache_creation_input_tokens;
+            self.cost_usd += result.total_cost_usd.unwrap_or(0.0);
+        }
+        self.total_ms += run.total_ms();
+        self.avg_duration_ms = self.total_ms / self.runs;
+    }
+}
+
+/// Usage since a point in time, by day, against the period before it.
+#[derive(Debug, Serialize)]
+pub struct UsageReport {
+    pub agent: String,
+    /// RFC 3339, like `until`.
+    pub since: String,
+    pub until: String,
+    pub total: Usage,
+    /// The same length of time just before `since`.
+    pub previous: Usage,
+    /// Days (UTC, `YYYY-MM-DD`) with at least one run, oldest first.
+    pub days: BTreeMap<String, Usage>,
+    #[serde(skip)]
+    start: DateTime<Utc>,
+}
+
+/// Print what the agent's iterations used since `since`: a duration back
+/// from now (`7d`, `12h`) or a date (`2026-10-01`).
+pub fn usage(
+    root: &Path,
+    profile: Option<&str>,
+    since: &str,
+    json: bool,
+) -> Result<(), RunnerError> {
+    let cfg = load_config(root, profile)?;
+    let now = Utc::now();
+    let since = parse_since(since, now).map_err(|e| {
+        RunnerError::Config(crate::config::ConfigError::Invalid(format!("--since: {e}")))
+    })?;
+    let log_dir = root.join(
+        cfg.loop_config
+            .log_dir
+            .as_deref()
+            .unwrap_or(super::LOG_DIR_DEFAULT),
+    );
+    let report = usage_report(&cfg.agent.name, &record::load_all(&log_dir), since, now);
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&report).map_err(|e| RunnerError::Io(e.into()))?
+        );
+    } else {
+        print!("{}", render(&report));
+    }
+    Ok(())
+}
+
+/// Write a Markdown digest of the period since `since`: iterations, costs,
+/// failures, memory entries added, and how the goals and state files
+/// changed. It is printed, or written to `output` (relative to the agent
+/// root); `commit` writes it to `digests/<date>.md` unless `output` is
+/// given and commits it, and `notify` sends it to the `[notify]` channels.
+pub fn digest(
+    root: &Path,
+    profile: Option<&str>,
+    since: &str,
+    output: Option<&str>,
+    commit: bool,
+    notify: bool,
+) -> Result<(), RunnerError> {
+    let cfg = load_config(root, profile)?;
+    let now = Utc::now();
+    let since = parse_since(since, now).map_err(|e| {
+        RunnerError::Config(crate::config::ConfigError::Invalid(format!("--since: {e}")))
+    })?;
+    let log_dir = root.join(
+        cfg.loop_config
+            .log_dir
+            .as_deref()
+            .unwrap_or(super::LOG_DIR_DEFAULT),
+    );
+    let runs = record::load_all(&log_dir);
+    let report = usage_report(&cfg.agent.name, &runs, since, now);
+    let added: Vec<broca::Entry> = broca::entries(&root.join(&cfg.memory.dir))
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|entry| created(entry).is_some_and(|t| t >= since))
+        .collect();
+    let goals = goals_diff(root, &cfg, since);
+    let text = render_digest(&report, &runs, &added, goals.as_deref());
+
+    let output = output
+        .map(str::to_string)
+        .or_else(|| commit.then(|| format!("digests/{}.md", now.date_naive())));
+    match output {
+        Some(ref path) => {
+            let file = root.join(path);
+            if let Some(parent) = file.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&file, &text)?;
+            println!("Digest written to {path}");
+        }
+        None => print!("{text}"),
+    }
+
+    if let (true, Some(path)) = (commit, output) {
+        if !git::is_repo(root) {
+            return Err(RunnerError::Io(std::io::Error::other(
+                "--commit: the agent root is not in a git repository",
+            )));
+        }
+        if let Some(branch) = git::current_branch(root)?
+            .filter(|branch| guard::is_protected(branch, &cfg.git.protected_branches))
+        {
+            return Err(RunnerError::Io(std::io::Error::other(format!(
+                "--commit: '{branch}' is protected (git.protected_branches)"
+            ))));
+        }
+        let message = format!("Digest for {}: {path}", cfg.agent.name);
+        git::commit_file(root, &cfg.git, &message, &path)?;
+        println!("Committed {path}");
+    }
+
+    if notify {
+        let notice = notify::Notice {
+            event: "digest",
+            subject: format!(
+                "{}: digest since {}",
+                cfg.agent.name,
+                report.start.format("%Y-%m-%d")
+            ),
+            text,
+        };
+        let outcomes = notify::send_all(root, &cfg.agent.name, &cfg.notify, &cfg.secrets, &notice);
+        if outcomes.is_empty() {
+            println!("No [notify] channel takes the digest event.");
+        }
+        for outcome in outcomes {
+            println!("{outcome}");
+        }
+    }
+    Ok(())
+}
+
+fn parse_since(since: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>, String> {
+    if let Ok(date) = NaiveDate::parse_from_str(since.trim(), "%Y-%m-%d") {
+        return Ok(date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc());
+    }
+    let seconds = crate::config::parse_interval(since)
+        .map_err(|e| format!("{e} (or give a date, e.g. 2026-10-01)"))?;
+    Ok(now - chrono::Duration::seconds(seconds as i64))
+}
+
+pub(super) fn usage_report(
+    agent: &str,
+    runs: &[record::RunRecord],
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> UsageReport {
+    let before = since - (until - since);
+    let mut report = UsageReport {
+        agent: agent.to_string(),
+        since: since.to_rfc3339(),
+        until: until.to_rfc3339(),
+        total: Usage::default(),
+        previous: Usage::default(),
+        days: BTreeMap::new(),
+        start: since,
+    };
+    for run in runs {
+        let Some(started) = run.started() else {
+            continue;
+        };
+        if started >= since && started <= until {
+            report.total.add(run);
+            report
+                .days
+                .entry(started.date_naive().to_string())
+                .or_default()
+                .add(run);
+        } else if started >= before && started < since {
+            report.previous.add(run);
+        }
+    }
+    report
+}
+
+fn render(report: &UsageReport) -> String {
+    let mut out = format!(
+        "Usage for {} since {}\n\n",
+        report.agent,
+        report.start.format("%Y-%m-%d %H:%M UTC")
+    );
+    if report.total.runs == 0 {
+        out.push_str("No runs in this period.\n");
+        return out;
+    }
+    out.push_str(&format!(
+        "{:<12} {:>5} {:>6} {:>8} {:>8} {:>8} {:>9} {:>9}\n",
+        "Date", "Runs", "Failed", "Input", "Cached", "Output", "Cost", "Avg time"
+    ));
+    let row = |label: &str, usage: &Usage| {
+        format!(
+            "{:<12} {:>5} {:>6} {:>8} {:>8} {:>8} {:>9} {:>9}\n",
+            label,
+            usage.runs,
+            usage.failed,
+            tokens(usage.input_tokens),
+            tokens(usage.cache_tokens),
+            tokens(usage.output_tokens),
+            format!("${:.2}", usage.cost_usd),
+            duration(usage.avg_duration_ms),
+        )
+    };
+    for (day, usage) in &report.days {
+        out.push_str(&row(day, usage));
+    }
+    out.push_str(&row("Total", &report.total));
+    if report.previous.runs > 0 {
+        out.push_str(&row("Previous", &report.previous));
+        let change = |now: f64, before: f64| {
+            if before == 0.0 {
+                "n/a".to_string()
+            } else {
+                format!("{:+.0}%", (now - before) / before * 100.0)
+            }
+        };
+        out.push_str(&format!(
+            "\nAgainst the previous period: runs {}, cost {}, failures {}.\n",
+            change(report.total.runs as f64, report.previous.runs as f64),
+            change(report.total.cost_usd, report.previous.cost_usd),
+            change(report.total.failed as f64, report.previous.failed as f64),
+        ));
+    }
+    out
+}
+
+/// When a memory entry was created: `created` is `YYYYMMDD-HHMMSS` or a
+/// bare date.
+fn created(entry: &broca::Entry) -> Option<DateTime<Utc>> {
+    NaiveDateTime::parse_from_str(&entry.created, "%Y%m%d-%H%M%S")
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(&entry.created, "%Y%m%d")
+                .ok()?
+                .and_hms_opt(0, 0, 0)
+        })
+        .map(|t| t.and_utc())
+}
+
+/// How the goals and state files changed since `since`, from the last
+/// commit before it to the working tree. `None` outside a git repository.
+fn goals_diff(root: &Path, cfg: &crate::config::Config, since: DateTime<Utc>) -> Option<String> {
+    if !git::is_repo(root) {
+        return None;
+    }
+    let state = Path::new(&cfg.memory.dir).join(&cfg.memory.state_file);
+    let state = state.to_string_lossy();
+    let mut paths = match cfg.loop_config.goals_file.as_deref() {
+        Some(goals) => vec![goals],
+        None => vec!["GOALS.md", "goals"],
+    };
+    paths.push(&state);
+    let base = git::commit_before(root, &since.to_rfc3339()).ok()?;
+    git::diff_paths(root, base.as_deref(), &paths).ok()
+}
+
+fn render_digest(
+    report: &UsageReport,
+    runs: &[record::RunRecord],
+    added: &[broca::Entry],
+    goals: Option<&str>,
+) -> String {
+    let period: Vec<&record::RunRecord> = runs
+        .iter()
+        .filter(|run| {
+            run.started()
+                .is_some_and(|t| t >= report.start && t.to_rfc3339() <= report.until)
+        })
+        .collect();
+    let mut out = format!("# Digest for {}\n\n", report.agent);
+    let until = DateTime::parse_from_rfc3339(&report.until)
+        .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default();
+    let _ = writeln!(
+        out,
+        "{} to {until} UTC\n",
+        report.start.format("%Y-%m-%d %H:%M")
+    );
+
+    let usage = &report.total;
+    let commits = period.iter().filter(|run| run.commit.is_some()).count();
+    let _ = writeln!(out, "## Iterations\n");
+    let _ = writeln!(
+        out,
+        "- {} runs, {} failed, {commits} committed",
+        usage.runs, usage.failed
+    );
+    let _ = writeln!(
+        out,
+        "- {} input ({} cached) and {} output tokens",
+        tokens(usage.input_tokens + usage.cache_tokens),
+        tokens(usage.cache_tokens),
+        tokens(usage.output_tokens)
+    );
+    let previous = if report.previous.runs > 0 {
+        format!(" (${:.2} the period before)", report.previous.cost_usd)
+    } else {
+        String::new()
+    };
+    let _ = writeln!(out, "- Cost: ${:.2}{previous}", usage.cost_usd);
+    if usage.runs > 0 {
+        let _ = writeln!(out, "- Average run: {}", duration(usage.avg_duration_ms));
+    }
+
+    let _ = writeln!(out, "\n## Failures\n");
+    let failed: Vec<_> = period.iter().filter(|run| run.failed()).collect();
+    if failed.is_empty() {
+        let _ = writeln!(out, "None.");
+    }
+    for run in failed.iter().take(DIGEST_ITEMS) {
+        let when = run
+            .started()
+            .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_default();
+        let _ = writeln!(out, "- {when}: {}", failure_reason(run));
+    }
+    if failed.len() > DIGEST_ITEMS {
+        let _ = writeln!(out, "- and {} more", failed.len() - DIGEST_ITEMS);
+    }
+
+    let _ = writeln!(out, "\n## Memory\n");
+    if added.is_empty() {
+        let _ = writeln!(out, "No entries added.");
+    }
+    for entry in added.iter().take(DIGEST_ITEMS) {
+        let _ = writeln!(out, "- **{}** ({})", entry.title, entry.entry_type);
+    }
+    if added.len() > DIGEST_ITEMS {
+        let _ = writeln!(out, "- and {} more", added.len() - DIGEST_ITEMS);
+    }
+
+    let _ = writeln!(out, "\n## Goals and state\n");
+    match goals {
+        None => {
+            let _ = writeln!(out, "Not tracked: the agent is not in a git repository.");
+        }
+        Some(diff) if diff.trim().is_empty() => {
+            let _ = writeln!(out, "No changes.");
+        }
+        Some(diff) => {
+            let lines: Vec<&str> = diff.lines().collect();
+            let _ = writeln!(out, "```diff");
+            for line in lines.iter().take(DIGEST_DIFF_LINES) {
+                let _ = writeln!(out, "{line}");
+            }
+            if lines.len() > DIGEST_DIFF_LINES {
+                let _ = writeln!(out, "... {} more lines", lines.len() - DIGEST_DIFF_LINES);
+            }
+            let _ = writeln!(out, "```");
+        }
+    }
+    out
+}
+
+/// Why a run failed, in a line: a timeout, or the exit code and the first
+/// line of the response.
+fn failure_reason(run: &record::RunRecord) -> String {
+    if run.timed_out {
+        return "timed out".to_string();
+    }
+    let mut reason = format!("exit {}", run.exit_code.unwrap_or(-1));
+    let first_line = run
+        .result
+        .as_ref()
+        .and_then(|result| result.text.lines().find(|l| !l.trim().is_empty()));
+    if let Some(line) = first_line {
+        let line: String = line.trim().chars().take(120).collect();
+        reason.push_str(&format!(" ({line})"));
+    }
+    reason
+}
+
+/// `950`, `12.3k`, `4.1M`.
+pub(super) fn tokens(n: u64) -> String {
+    match n {
+        0..=9_999 => n.to_string(),
+        10_000..=999_999 => format!("{:.1}k", n as f64 / 1e3),
+        _ => format!("{:.1}M", n as f64 / 1e6),
+    }
+}
+
+/// `45s`, `2m05s`, `1h02m`.
+pub(super) fn duration(ms: u64) -> String {
+    let secs = ms / 1000;
+    match secs {
+        0..=59 => format!("{secs}s"),
+        60..=3599 => format!("{}m{:02}s", secs / 60, secs % 60),
+        _ => format!("{}h{:02}m", secs / 3600, secs % 3600 / 60),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runner::record::{LlmResult, RunRecord};
+
+    fn run(timestamp: &str, exit_code: i32, cost: f64) -> RunRecord {
+        RunRecord {
+            timestamp: timestamp.to_string(),
+            exit_code: Some(exit_code),
+            duration_ms: 60_000,
+            result: Some(LlmResult {
+                total_cost_usd: Some(cost),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_usage_report_by_day_and_previous_period() {
+        let until = parse_since("2026-10-08", Utc::now()).unwrap();
+        let since = parse_since("7d", until).unwrap();
+        let runs = [
+            run("2026-09-30_12-00-00", 0, 1.0),
+            run("2026-10-02_09-00-00", 0, 0.5),
+            run("2026-10-02_10-00-00", 1, 0.25),
+            run("2026-10-05_23-59-59", 0, 0.25),
+            run("2026-10-09_00-00-00", 0, 9.0),
+        ];
+        let report = usage_report("tri", &runs, since, until);
+        assert_eq!(report.total.runs, 3);
+        assert_eq!(report.total.failed, 1);
+        assert_eq!(report.total.cost_usd, 1.0);
+        assert_eq!(report.total.avg_duration_ms, 60_000);
+        assert_eq!(report.previous.runs, 1);
+        assert_eq!(report.days.len(), 2);
+
+        let text = render(&report);
+        assert!(text.contains("Usage for tri since 2026-10-01 00:00 UTC"));
+        assert!(text.contains("2026-10-02"));
+        assert!(text.contains("Against the previous period: runs +200%, cost +0%"));
+    }
+
+    #[test]
+    fn test_render_digest() {
+        let until = parse_since("2026-10-08", Utc::now()).unwrap();
+        let since = parse_since("7d", until).unwrap();
+        let mut failed = run("2026-10-03_08-30-00", 1, 0.25);
+        failed.result.as_mut().unwrap().text = "\nAPI Error: overloaded\nmore".to_string();
+        let committed = RunRecord {
+            commit: Some("abc123".to_string()),
+            ..run("2026-10-02_09-00-00", 0, 0.5)
+        };
+        let runs = [committed, failed, run("2026-09-20_09-00-00", 0, 9.0)];
+        let report = usage_report("tri", &runs, since, until);
+        let entry = broca::Entry::parse(
+            "20261004-120000-api-limit.md",
+            "---\ntype: fact\ntitle: \"API limit\"\ncreated: 20261004-120000\n---\n\nBody.",
+        )
+        .unwrap();
+        assert_eq!(
+            created(&entry).unwrap().to_rfc3339(),
+            "2026-10-04T12:00:00+00:00"
+        );
+        let diff = "--- a/GOALS.md\n+++ b/GOALS.md\n-- [ ] Ship v1\n+- [x] Ship v1\n";
+
+        let text = render_digest(&report, &runs, &[entry], Some(diff));
+        assert!(text.starts_with("# Digest for tri\n\n2026-10-01 00:00 to 2026-10-08 00:00 UTC\n"));
+        assert!(text.contains("- 2 runs, 1 failed, 1 committed\n"), "{text}");
+        assert!(text.contains("- Cost: $0.75\n"), "{text}");
+        assert!(text.contains("- 2026-10-03 08:30: exit 1 (API Error: overloaded)\n"));
+        assert!(text.contains("- **API limit** (fact)\n"));
+        assert!(text.contains("```diff\n--- a/GOALS.md\n"));
+
+        let quiet = render_digest(&report, &[], &[], None);
+        assert!(quiet.contains("## Failures\n\nNone.\n"));
+        assert!(quiet.contains("No entries added."));
+        assert!(quiet.contains("not in a git repository"));
+    }
+
+    #[test]
+    fn test_parse_since() {
+        let now = parse_since("2026-10-08", Utc::now()).unwrap();
+        assert_eq!(
+            parse_since("36h", now).unwrap().to_rfc3339(),
+            "2026-10-06T12:00:00+00:00"
+        );
+        assert!(parse_since("last week", now).is_err());
+        assert_eq!(tokens(12_345), "12.3k");
+        assert_eq!(duration(125_000), "2m05s");
+    }
+}
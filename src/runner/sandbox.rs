@@ -0,0 +1,206 @@
+//! Opt-in sandbox for hook, context, and command plugin scripts.
+//!
+//! These scripts normally inherit the agent's whole environment, tokens
+//! included, and can write anywhere it can. A `sandbox` table clears the
+//! environment down to an allow-list and, on Linux (`unshare`) or macOS
+//! (`sandbox-exec`), mounts the agent root read-only except for the memory
+//! directory and can cut network access. When those restrictions are asked
+//! for but cannot be enforced, the script does not run.
+
+use crate::config::{self, SandboxConfig};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+/// Host variables every sandboxed script keeps.
+const BASE_ENV: &[&str] = &[
+    "PATH", "HOME", "USER", "LOGNAME", "LANG", "LC_*", "TERM", "TMPDIR", "TZ",
+];
+
+/// `cmd` rebuilt to run inside the sandbox. Variables set on `cmd` are kept;
+/// stdio settings are not carried over, so configure them afterwards.
+pub fn wrap(
+    cmd: Command,
+    sandbox: &SandboxConfig,
+    root: &Path,
+    memory_dir: &Path,
+) -> Result<Command, String> {
+    let mut wrapped = if sandbox.read_only || !sandbox.network {
+        let root = fs::canonicalize(root).unwrap_or_else(|_| root.to_path_buf());
+        let memory_dir = fs::canonicalize(memory_dir).unwrap_or_else(|_| memory_dir.to_path_buf());
+        confine(&cmd, sandbox, &root, &memory_dir)?
+    } else {
+        let mut wrapped = Command::new(cmd.get_program());
+        wrapped.args(cmd.get_args());
+        wrapped
+    };
+    if let Some(dir) = cmd.get_current_dir() {
+        wrapped.current_dir(dir);
+    }
+
+    wrapped.env_clear();
+    let allowed: Vec<String> = BASE_ENV
+        .iter()
+        .map(|name| name.to_string())
+        .chain(sandbox.env.iter().cloned())
+        .collect();
+    for (name, value) in std::env::vars_os() {
+        if name
+            .to_str()
+            .is_some_and(|n| config::env_matches(n, &allowed))
+        {
+            wrapped.env(name, value);
+        }
+    }
+    for (name, value) in cmd.get_envs() {
+        match value {
+            Some(value) => wrapped.env(name, value),
+            None => wrapped.env_remove(name),
+        };
+    }
+    Ok(wrapped)
+}
+
+/// Run under `sh` in fresh user, mount, and (without network) network
+/// namespaces. With `read_only`, the root (`$1`) is bound over itself
+/// read-only with the memory directory (`$2`) bound writable on top, and
+/// the working directory is entered again through the new mounts.
+#[cfg(target_os = "linux")]
+fn confine(
+    cmd: &Command,
+    sandbox: &SandboxConfig,
+    root: &Path,
+    memory_dir: &Path,
+) -> Result<Command, String> {
+    const READ_ONLY: &str = r#"mount --bind "$1" "$1"
+if [ -d "$2" ]; then mount --bind "$2" "$2"; fi
+mount -o remount,bind,ro "$1"
+cd "$(pwd -P)"
+"#;
+    if super::find_on_path("unshare").is_none() {
+        return Err("needs unshare on PATH".to_string());
+    }
+    let setup = if sandbox.read_only { READ_ONLY } else { "" };
+    let mut wrapped = Command::new("unshare");
+    wrapped.args(["--user", "--map-root-user"]);
+    if sandbox.read_only {
+        wrapped.arg("--mount");
+    }
+    if !sandbox.network {
+        wrapped.arg("--net");
+    }
+    wrapped
+        .args(["--", "sh", "-c"])
+        .arg(format!("set -e\n{setup}shift 2\nexec \"$@\"\n"))
+        .arg("sh")
+        .arg(root)
+        .arg(memory_dir)
+        .arg(cmd.get_program())
+        .args(cmd.get_args());
+    Ok(wrapped)
+}
+
+/// Run under `sandbox-exec` with a profile allowing everything except
+/// writes under the root outside the memory directory and, without
+/// network, network access.
+#[cfg(target_os = "macos")]
+fn confine(
+    cmd: &Command,
+    sandbox: &SandboxConfig,
+    root: &Path,
+    memory_dir: &Path,
+) -> Result<Command, String> {
+    let quote = |path: &Path| {
+        let path = path.display().to_string();
+        format!("\"{}\"", path.replace('\\', "\\\\").replace('"', "\\\""))
+    };
+    let mut profile = String::from("(version 1)\n(allow default)\n");
+    if !sandbox.network {
+        profile.push_str("(deny network*)\n");
+    }
+    if sandbox.read_only {
+        profile.push_str(&format!(
+            "(deny file-write* (subpath {}))\n(allow file-write* (subpath {}))\n",
+            quote(root),
+            quote(memory_dir)
+        ));
+    }
+    let mut wrapped = Command::new("/usr/bin/sandbox-exec");
+    wrapped
+        .arg("-p")
+        .arg(profile)
+        .arg(cmd.get_program())
+        .args(cmd.get_args());
+    Ok(wrapped)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn confine(
+    _cmd: &Command,
+    _sandbox: &SandboxConfig,
+    _root: &Path,
+    _memory_dir: &Path,
+) -> Result<Command, String> {
+    Err(
+        "read_only and network = false are only enforced on Linux and macOS; \
+         set read_only = false to sandbox just the environment"
+            .to_string(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sandbox(text: &str) -> SandboxConfig {
+        toml::from_str(text).unwrap()
+    }
+
+    fn run(mut cmd: Command) -> (bool, String) {
+        let output = cmd.output().unwrap();
+        let text =
+            String::from_utf8_lossy(&output.stdout) + String::from_utf8_lossy(&output.stderr);
+        (output.status.success(), text.trim().to_string())
+    }
+
+    #[test]
+    fn test_environment_is_an_allow_list() {
+        let dir = tempfile::tempdir().unwrap();
+        let script = |sandbox: &SandboxConfig| {
+            let mut cmd = Command::new("sh");
+            cmd.args(["-c", "echo ${CARGO_PKG_NAME:-unset} $BOUCLE_ROOT"])
+                .env("BOUCLE_ROOT", "/agent");
+            wrap(cmd, sandbox, dir.path(), &dir.path().join("memory")).unwrap()
+        };
+
+        let closed = sandbox("read_only = false");
+        assert_eq!(run(script(&closed)), (true, "unset /agent".to_string()));
+        let open = sandbox("read_only = false\nenv = [\"CARGO_*\"]");
+        assert_eq!(run(script(&open)), (true, "boucle /agent".to_string()));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_root_is_read_only_except_memory() {
+        let probe = Command::new("unshare")
+            .args(["--user", "--map-root-user", "--mount", "true"])
+            .status();
+        if !probe.is_ok_and(|status| status.success()) {
+            eprintln!("skipping: user namespaces are not available");
+            return;
+        }
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("memory")).unwrap();
+        let mut cmd = Command::new("sh");
+        cmd.args([
+            "-c",
+            "touch memory/note && echo wrote; touch escape 2>/dev/null || echo blocked",
+        ])
+        .current_dir(dir.path());
+        let cmd = wrap(cmd, &sandbox(""), dir.path(), &dir.path().join("memory")).unwrap();
+
+        assert_eq!(run(cmd), (true, "wrote\nblocked".to_string()));
+        assert!(dir.path().join("memory/note").exists());
+        assert!(!dir.path().join("escape").exists());
+    }
+}
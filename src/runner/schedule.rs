@@ -0,0 +1,2261 @@
+//! `boucle schedule`: run the agent periodically with the platform's own
+//! scheduler — launchd on macOS, a systemd user timer on Linux (or cron
+//! with `--cron`), Task Scheduler on Windows.
+//!
+//! [`print`] shows what to install; [`install`] writes it in place and
+//! activates it, and [`uninstall`] reverses that. With `[schedule.<name>]`
+//! tables there is one job per named schedule. Everything installed
+//! carries a `boucle <root>` comment so it can be found again. [`pause`]
+//! stands the agent down without touching the scheduler: `boucle run`
+//! skips while the pause file exists.
+
+use super::{boucle_bin, load_config, RunnerError};
+use crate::config;
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::{fs, io};
+
+/// Written by `boucle schedule pause`; any other content, such as an empty
+/// file made by hand, pauses indefinitely.
+pub(super) const PAUSE_FILE: &str = ".boucle-paused.json";
+
+/// When each schedule last started a run, for `schedule.catch_up`.
+pub(super) const LAST_RUNS_FILE: &str = ".boucle-schedule.json";
+
+/// Written by `boucle run --scheduled`, and where launchd and cron send the
+/// run's stderr; in the log directory.
+pub(super) const SCHEDULER_LOG: &str = "scheduler.log";
+
+/// How late a run may start, on top of the jitter, and still be on time.
+const GRACE_SECS: u64 = 300;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Pause {
+    pub since: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub until: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl Pause {
+    /// `until <time>` or `indefinitely`, with the reason if there is one.
+    pub fn describe(&self) -> String {
+        let mut text = match self.until {
+            Some(ref until) => format!("until {until}"),
+            None => "indefinitely".to_string(),
+        };
+        if let Some(ref reason) = self.reason {
+            text.push_str(&format!(" ({reason})"));
+        }
+        text
+    }
+}
+
+/// The pause in effect for the agent at `root`, if any.
+pub(super) fn active_pause(root: &Path) -> Option<Pause> {
+    let content = fs::read_to_string(root.join(PAUSE_FILE)).ok()?;
+    let Ok(pause) = serde_json::from_str::<Pause>(&content) else {
+        return Some(Pause {
+            since: String::new(),
+            until: None,
+            reason: None,
+        });
+    };
+    let ended = pause
+        .until
+        .as_deref()
+        .and_then(|until| DateTime::parse_from_rfc3339(until).ok())
+        .is_some_and(|until| until <= Utc::now());
+    (!ended).then_some(pause)
+}
+
+/// Pause the agent: `boucle run` exits without doing anything until
+/// [`resume`], or until `until` (a timestamp, a date, or a duration from
+/// now such as `2h`) has passed.
+pub fn pause(root: &Path, until: Option<&str>, reason: Option<&str>) -> Result<Pause, RunnerError> {
+    let until = until
+        .map(|until| {
+            parse_until(until, Utc::now())
+                .map_err(|e| RunnerError::Io(io::Error::new(io::ErrorKind::InvalidInput, e)))
+        })
+        .transpose()?;
+    let timestamp = |time: DateTime<Utc>| time.to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+    let pause = Pause {
+        since: timestamp(Utc::now()),
+        until: until.map(timestamp),
+        reason: reason.map(str::to_string),
+    };
+    fs::write(root.join(PAUSE_FILE), serde_json::to_string_pretty(&pause)?)?;
+    Ok(pause)
+}
+
+/// Lift a pause; returns whether there was one.
+pub fn resume(root: &Path) -> Result<bool, RunnerError> {
+    let path = root.join(PAUSE_FILE);
+    if !path.exists() {
+        return Ok(false);
+    }
+    let was_active = active_pause(root).is_some();
+    fs::remove_file(path)?;
+    Ok(was_active)
+}
+
+/// `schedule.active_hours` and `active_days` as written, e.g.
+/// `08:00-22:00, mon-fri`, when `boucle run` falls outside them.
+pub(super) fn outside_active_hours(cfg: &config::Config) -> Result<Option<String>, RunnerError> {
+    use chrono::{Datelike, Local, Timelike};
+    let Some(window) = cfg.schedule.active_window().map_err(invalid_input)? else {
+        return Ok(None);
+    };
+    let now = Local::now();
+    let minute = now.hour() * 60 + now.minute();
+    if window.contains(minute, now.weekday().num_days_from_sunday()) {
+        return Ok(None);
+    }
+    let written: Vec<&str> = [&cfg.schedule.active_hours, &cfg.schedule.active_days]
+        .into_iter()
+        .flatten()
+        .map(String::as_str)
+        .collect();
+    Ok(Some(written.join(", ")))
+}
+
+fn invalid_input(e: String) -> RunnerError {
+    RunnerError::Io(io::Error::new(io::ErrorKind::InvalidInput, e))
+}
+
+/// `--until` for [`pause`]: an RFC 3339 timestamp, a local date or date and
+/// time (`2026-03-01`, `2026-03-01 09:00`), or a duration from `now`.
+fn parse_until(until: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>, String> {
+    use chrono::{Local, NaiveDate, NaiveDateTime, TimeZone};
+    let until = until.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(until) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    let local = NaiveDateTime::parse_from_str(until, "%Y-%m-%d %H:%M")
+        .or_else(|_| NaiveDateTime::parse_from_str(until, "%Y-%m-%dT%H:%M"))
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(until, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        });
+    if let Some(local) = local {
+        return Local
+            .from_local_datetime(&local)
+            .earliest()
+            .map(|time| time.with_timezone(&Utc))
+            .ok_or_else(|| format!("'{until}' does not exist in the local time zone"));
+    }
+    let seconds = config::parse_interval(until).map_err(|_| {
+        format!("'{until}' is not a timestamp, a date (2026-03-01 09:00), or a duration (2h)")
+    })?;
+    Ok(now + chrono::Duration::seconds(seconds as i64))
+}
+
+/// What `boucle schedule` sets up for one agent on this platform.
+enum Plan {
+    Launchd {
+        label: String,
+        plist: String,
+    },
+    TaskScheduler {
+        label: String,
+        xml: String,
+    },
+    Systemd {
+        unit: String,
+        service: String,
+        timer: String,
+    },
+    Cron {
+        line: String,
+    },
+}
+
+/// `<agent>` or `<agent>.<profile>`, then `.<schedule>` for a named
+/// schedule: names the launchd job, the systemd units, and the scheduled
+/// task.
+fn label(cfg: &config::Config, profile: Option<&str>, schedule: Option<&str>) -> String {
+    let mut label = cfg.agent.name.clone();
+    for part in [profile, schedule].into_iter().flatten() {
+        label.push('.');
+        label.push_str(part);
+    }
+    label
+}
+
+/// `boucle <root>[ --profile <name>]`: written in a comment into every
+/// crontab line, unit file, and plist installed for this agent and
+/// profile, so [`uninstall`] finds them all, including schedules since
+/// removed from boucle.toml.
+fn owner(root: &Path, profile: Option<&str>) -> String {
+    match profile {
+        Some(name) => format!("boucle {} --profile {name}", root.display()),
+        None => format!("boucle {}", root.display()),
+    }
+}
+
+/// The comment marking what is installed for one schedule.
+fn marker(root: &Path, profile: Option<&str>, schedule: Option<&str>) -> String {
+    match schedule {
+        Some(name) => format!("{} --schedule {name}", owner(root, profile)),
+        None => owner(root, profile),
+    }
+}
+
+/// Whether a line of `text` ends in a `#` or `<!--` comment marking it as
+/// installed for `owner`.
+fn is_owned(text: &str, owner: &str) -> bool {
+    text.lines().any(|line| {
+        ["# ", "<!-- "].iter().any(|open| {
+            let Some((_, rest)) = line.rsplit_once(&format!("{open}{owner}")) else {
+                return false;
+            };
+            let rest = rest.trim_end().trim_end_matches("-->").trim_end();
+            match rest.strip_prefix(" --schedule ") {
+                Some(name) => !name.is_empty() && !name.contains(' '),
+                None => rest.is_empty(),
+            }
+        })
+    })
+}
+
+/// What to set up: one plan per `[schedule.<name>]`, or one for
+/// `schedule.interval` when there are none or `interval` overrides it.
+fn plans(
+    root: &Path,
+    interval: Option<&str>,
+    profile: Option<&str>,
+    cron: bool,
+) -> Result<Vec<Plan>, RunnerError> {
+    let cfg = load_config(root, profile)?;
+    if interval.is_some() || cfg.schedule.named.is_empty() {
+        let interval = interval.unwrap_or(&cfg.schedule.interval);
+        return Ok(vec![plan(root, &cfg, profile, None, interval, cron)?]);
+    }
+    cfg.schedule
+        .named
+        .iter()
+        .map(|(name, schedule)| plan(root, &cfg, profile, Some(name), &schedule.interval, cron))
+        .collect()
+}
+
+fn plan(
+    root: &Path,
+    cfg: &config::Config,
+    profile: Option<&str>,
+    schedule: Option<&str>,
+    interval: &str,
+    cron: bool,
+) -> Result<Plan, RunnerError> {
+    let spec = schedule_spec(cfg, interval)?;
+    let boucle_path = boucle_bin();
+
+    let mut run_args: Vec<&str> = vec!["--scheduled"];
+    if let Some(name) = profile {
+        run_args.extend(["--profile", name]);
+    }
+    if let Some(name) = schedule {
+        run_args.extend(["--schedule", name]);
+    }
+    let jitter = jitter_secs(cfg, schedule)?;
+    let label = label(cfg, profile, schedule);
+    let marker = marker(root, profile, schedule);
+    let log = scheduler_log(root, cfg);
+
+    if cfg!(target_os = "macos") {
+        if jitter.is_some() {
+            run_args.push("--jitter");
+        }
+        let log_dir = log.parent().unwrap_or(root);
+        let plist = generate_launchd_plist(&label, &boucle_path, root, log_dir, &spec, &run_args);
+        let plist = plist.replacen("?>\n", &format!("?>\n<!-- {marker} -->\n"), 1);
+        Ok(Plan::Launchd { label, plist })
+    } else if cfg!(windows) {
+        let config::ScheduleSpec::Every(seconds) = spec else {
+            return Err(RunnerError::Io(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Task Scheduler has no cron-style triggers; use a duration such as 1h30m instead of '{interval}'"),
+            )));
+        };
+        let start = chrono::Local::now().format("%Y-%m-%dT%H:%M:%S").to_string();
+        let xml = generate_task_scheduler_xml(
+            &label,
+            &boucle_path,
+            root,
+            seconds,
+            &run_args,
+            &start,
+            jitter,
+        );
+        Ok(Plan::TaskScheduler { label, xml })
+    } else if cfg!(target_os = "linux") && !cron {
+        let path = std::env::var("PATH").ok();
+        let (service, timer) = generate_systemd_units(
+            &label,
+            &boucle_path,
+            root,
+            &spec,
+            &run_args,
+            path.as_deref(),
+            jitter,
+        );
+        Ok(Plan::Systemd {
+            unit: systemd_unit_name(&label),
+            service: format!("# {marker}\n{service}"),
+            timer,
+        })
+    } else {
+        if let config::ScheduleSpec::Every(seconds) = spec {
+            let minutes = seconds / 60;
+            if minutes >= 60 && minutes % 60 != 0 {
+                eprintln!(
+                    "Note: cron can't repeat every {interval}; this runs every {}h. Use a cron expression for exact times.",
+                    minutes / 60
+                );
+            }
+        }
+        if jitter.is_some() {
+            run_args.push("--jitter");
+        }
+        let entry = generate_cron_entry(&boucle_path, root, &spec, &run_args);
+        Ok(Plan::Cron {
+            line: format!("{entry} 2>> {} # {marker}", log.display()),
+        })
+    }
+}
+
+/// `interval` as this platform's scheduler gets it: confined to the active
+/// hours, except on Windows, where Task Scheduler takes durations only and
+/// `boucle run` skips the runs outside them.
+fn schedule_spec(
+    cfg: &config::Config,
+    interval: &str,
+) -> Result<config::ScheduleSpec, RunnerError> {
+    let spec = config::parse_schedule(interval).map_err(invalid_input)?;
+    match cfg.schedule.active_window().map_err(invalid_input)? {
+        Some(window) if !cfg!(windows) => confine(&spec, &window, interval).map_err(invalid_input),
+        _ => Ok(spec),
+    }
+}
+
+/// For `boucle run --scheduled`: how many iterations to run now, from the
+/// runs that came due since this schedule's last one and
+/// `schedule.catch_up`. Records this run's time.
+pub fn scheduled_iterations(
+    root: &Path,
+    profile: Option<&str>,
+    schedule: Option<&str>,
+) -> Result<u32, RunnerError> {
+    let cfg = load_config(root, profile)?;
+    log_event(root, &cfg, schedule, "started");
+    // Not recorded, so the runs skipped count as missed once they are over.
+    if let Some(reason) = super::skip_reason(root, &cfg)? {
+        println!("{reason}");
+        log_event(root, &cfg, schedule, &format!("skipped: {reason}"));
+        return Ok(0);
+    }
+    let interval = match schedule {
+        None => &cfg.schedule.interval,
+        Some(name) => match cfg.schedule.named.get(name) {
+            Some(named) => &named.interval,
+            // `boucle run` reports the unknown name.
+            None => return Ok(1),
+        },
+    };
+    let spec = schedule_spec(&cfg, interval)?;
+
+    let mut last_runs = read_last_runs(root);
+    let key = label_key(profile, schedule);
+    let now = Utc::now();
+    let last = last_scheduled(&last_runs, &key);
+    last_runs.insert(key, now.to_rfc3339_opts(chrono::SecondsFormat::Secs, true));
+    fs::write(
+        root.join(LAST_RUNS_FILE),
+        serde_json::to_string_pretty(&last_runs)?,
+    )?;
+    let Some(last) = last else {
+        return Ok(1);
+    };
+
+    let grace = jitter_secs(&cfg, schedule)?.unwrap_or(0) + GRACE_SECS;
+    let (due, on_time) = due_runs(&spec, last, now.with_timezone(&Local), grace);
+    let missed = if on_time { due.saturating_sub(1) } else { due };
+    if missed == 0 {
+        return Ok(1);
+    }
+    let runs = match cfg.schedule.catch_up {
+        config::CatchUp::Policy(config::CatchUpPolicy::Once) => 1,
+        config::CatchUp::Policy(config::CatchUpPolicy::Skip) => u32::from(on_time),
+        config::CatchUp::Iterations(max) => due.clamp(1, max),
+    };
+    let since = last.format("%Y-%m-%d %H:%M");
+    let message = match runs {
+        0 => format!("Missed {missed} scheduled run(s) since {since} — skipping until the next one (schedule.catch_up = \"skip\")."),
+        _ => format!("Missed {missed} scheduled run(s) since {since}; running {runs} iteration(s) now (schedule.catch_up)."),
+    };
+    println!("{message}");
+    let event = if runs == 0 { "skipped" } else { "catching up" };
+    log_event(root, &cfg, schedule, &format!("{event}: {message}"));
+    Ok(runs)
+}
+
+fn scheduler_log(root: &Path, cfg: &config::Config) -> PathBuf {
+    root.join(
+        cfg.loop_config
+            .log_dir
+            .as_deref()
+            .unwrap_or(super::LOG_DIR_DEFAULT),
+    )
+    .join(SCHEDULER_LOG)
+}
+
+/// Append a timestamped line to the scheduler log. Best effort: the log
+/// must never be why a run fails.
+fn log_event(root: &Path, cfg: &config::Config, schedule: Option<&str>, message: &str) {
+    use std::io::Write;
+    let path = scheduler_log(root, cfg);
+    let time = Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+    let prefix = schedule
+        .map(|name| format!("[{name}] "))
+        .unwrap_or_default();
+    let message = message.trim().replace('\n', "; ");
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    let _ = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| writeln!(file, "{time} {prefix}{message}"));
+}
+
+/// Record how a `boucle run --scheduled` ended in the scheduler log.
+pub fn log_outcome(
+    root: &Path,
+    profile: Option<&str>,
+    schedule: Option<&str>,
+    result: &Result<(), RunnerError>,
+) {
+    let Ok(cfg) = load_config(root, profile) else {
+        return;
+    };
+    let message = match result {
+        Ok(()) => "finished".to_string(),
+        Err(e) => format!("failed: {e}"),
+    };
+    log_event(root, &cfg, schedule, &message);
+}
+
+/// `boucle schedule logs`: the last `lines` lines of the scheduler log.
+pub fn show_logs(root: &Path, profile: Option<&str>, lines: usize) -> Result<(), RunnerError> {
+    let cfg = load_config(root, profile)?;
+    let path = scheduler_log(root, &cfg);
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            println!(
+                "No scheduled runs logged yet ({} is written by `boucle run --scheduled`).",
+                path.display()
+            );
+            return Ok(());
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let all: Vec<&str> = content.lines().collect();
+    for line in &all[all.len().saturating_sub(lines)..] {
+        println!("{line}");
+    }
+    Ok(())
+}
+
+fn read_last_runs(root: &Path) -> std::collections::BTreeMap<String, String> {
+    fs::read_to_string(root.join(LAST_RUNS_FILE))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn last_scheduled(
+    last_runs: &std::collections::BTreeMap<String, String>,
+    key: &str,
+) -> Option<DateTime<Local>> {
+    last_runs
+        .get(key)
+        .and_then(|last| DateTime::parse_from_rfc3339(last).ok())
+        .map(|last| last.with_timezone(&Local))
+}
+
+/// `[<profile>][:<schedule>]`, or `default`: a schedule's key in
+/// [`LAST_RUNS_FILE`].
+fn label_key(profile: Option<&str>, schedule: Option<&str>) -> String {
+    match (profile, schedule) {
+        (None, None) => "default".to_string(),
+        (Some(profile), None) => profile.to_string(),
+        (profile, Some(schedule)) => format!("{}:{schedule}", profile.unwrap_or_default()),
+    }
+}
+
+/// How many runs of `spec` came due after `last` up to `now`, and whether
+/// the latest of them was within `grace` seconds of `now`, so a run now is
+/// on time. A duration comes due every interval after `last`.
+fn due_runs(
+    spec: &config::ScheduleSpec,
+    last: DateTime<Local>,
+    now: DateTime<Local>,
+    grace: u64,
+) -> (u32, bool) {
+    let grace = chrono::Duration::seconds(grace as i64);
+    match spec {
+        config::ScheduleSpec::Every(seconds) => {
+            let elapsed = (now - last + grace).num_seconds().max(0) as u64;
+            let due = (elapsed / (*seconds).max(1)).min(u64::from(u32::MAX)) as u32;
+            (due, due <= 1)
+        }
+        config::ScheduleSpec::Cron(cron) => {
+            let (mut due, mut latest) = (0, None);
+            let mut after = last;
+            // Ten thousand is plenty to tell; a minutely schedule off for a
+            // week would otherwise take a while to count.
+            while due < 10_000 {
+                match next_fire(cron, after) {
+                    Some(fire) if fire <= now => {
+                        due += 1;
+                        latest = Some(fire);
+                        after = fire;
+                    }
+                    _ => break,
+                }
+            }
+            (due, latest.is_some_and(|fire| now - fire <= grace))
+        }
+    }
+}
+
+/// `spec` as a cron expression that only fires inside `window`. A
+/// duration repeats from the window's start: `2h` in `08:00-22:00` runs at
+/// 8:00, 10:00, and so on, and a day or more becomes once a day at the
+/// start. Windows starting mid-hour fire from the top of that hour; the
+/// runs before the start skip themselves, as do any the expression can't
+/// leave out.
+pub(super) fn confine(
+    spec: &config::ScheduleSpec,
+    window: &config::ActiveWindow,
+    interval: &str,
+) -> Result<config::ScheduleSpec, String> {
+    let hours = window.hours();
+    let intersect = |field: &Option<Vec<u32>>, allowed: &[u32]| -> Vec<u32> {
+        match field {
+            Some(values) => values
+                .iter()
+                .copied()
+                .filter(|v| allowed.contains(v))
+                .collect(),
+            None => allowed.to_vec(),
+        }
+    };
+    let mut cron = match spec {
+        config::ScheduleSpec::Cron(cron) => {
+            let mut cron = cron.clone();
+            cron.hours = Some(intersect(&cron.hours, &hours));
+            cron
+        }
+        config::ScheduleSpec::Every(seconds) => {
+            let minutes = seconds / 60;
+            let start_hour = window.start / 60;
+            // Hours in the order the window runs through them.
+            let in_order: Vec<u32> = (0..24)
+                .map(|h| (start_hour + h) % 24)
+                .filter(|h| hours.contains(h))
+                .collect();
+            let (minute_values, hour_values) = if minutes == 0 {
+                (None, in_order)
+            } else if minutes < 60 {
+                let minute_values = (0..60).step_by(minutes as usize).collect();
+                (Some(minute_values), in_order)
+            } else if minutes < 24 * 60 {
+                let every = (minutes / 60) as usize;
+                let mut hour_values: Vec<u32> = in_order.into_iter().step_by(every).collect();
+                hour_values.sort_unstable();
+                (Some(vec![window.start % 60]), hour_values)
+            } else {
+                (Some(vec![window.start % 60]), vec![start_hour])
+            };
+            config::CronExpr {
+                expr: String::new(),
+                minutes: minute_values,
+                hours: Some(hour_values),
+                days: None,
+                months: None,
+                weekdays: None,
+            }
+        }
+    };
+    // Cron runs when either the day of month or the weekday matches, so
+    // adding weekdays to a day of month would widen it, not narrow it.
+    if let Some(ref allowed) = window.weekdays {
+        if cron.days.is_none() || cron.weekdays.is_some() {
+            cron.weekdays = Some(intersect(&cron.weekdays, allowed));
+        }
+    }
+    if cron.hours.as_ref().is_some_and(|h| h.is_empty())
+        || cron.weekdays.as_ref().is_some_and(|d| d.is_empty())
+    {
+        return Err(format!(
+            "schedule '{interval}' never fires inside schedule.active_hours/active_days"
+        ));
+    }
+    let field = |values: &Option<Vec<u32>>| match values {
+        Some(values) if !values.is_empty() => values
+            .iter()
+            .map(u32::to_string)
+            .collect::<Vec<_>>()
+            .join(","),
+        _ => "*".to_string(),
+    };
+    cron.expr = [
+        &cron.minutes,
+        &cron.hours,
+        &cron.days,
+        &cron.months,
+        &cron.weekdays,
+    ]
+    .map(field)
+    .join(" ");
+    Ok(config::ScheduleSpec::Cron(cron))
+}
+
+/// The jitter of `schedule`, or `schedule.jitter`, in seconds, if set.
+fn jitter_secs(cfg: &config::Config, schedule: Option<&str>) -> Result<Option<u64>, RunnerError> {
+    let named = schedule
+        .and_then(|name| cfg.schedule.named.get(name))
+        .and_then(|named| named.jitter.as_deref());
+    let Some(jitter) = named.or(cfg.schedule.jitter.as_deref()) else {
+        return Ok(None);
+    };
+    let secs = config::parse_interval(jitter).map_err(|e| {
+        RunnerError::Io(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("schedule.jitter '{jitter}': {e}"),
+        ))
+    })?;
+    Ok((secs > 0).then_some(secs))
+}
+
+/// For `boucle run --jitter`, which cron and launchd schedules use since
+/// they can't add a random delay themselves: sleep for a random time up to
+/// the schedule's jitter.
+pub fn wait_jitter(
+    root: &Path,
+    profile: Option<&str>,
+    schedule: Option<&str>,
+) -> Result<(), RunnerError> {
+    let cfg = load_config(root, profile)?;
+    let Some(jitter) = jitter_secs(&cfg, schedule)? else {
+        return Ok(());
+    };
+    let delay = random_below(jitter + 1);
+    if delay > 0 {
+        println!("Waiting {delay}s before starting (schedule.jitter).");
+        log_event(
+            root,
+            &cfg,
+            schedule,
+            &format!("waiting {delay}s (schedule.jitter)"),
+        );
+        std::thread::sleep(std::time::Duration::from_secs(delay));
+    }
+    Ok(())
+}
+
+/// A random number in `0..bound`, from the standard library's randomly
+/// seeded hasher.
+fn random_below(bound: u64) -> u64 {
+    use std::hash::BuildHasher;
+    std::collections::hash_map::RandomState::new().hash_one(std::process::id()) % bound
+}
+
+/// Print the scheduler configuration and how to install it by hand.
+pub fn print(
+    root: &Path,
+    interval: Option<&str>,
+    profile: Option<&str>,
+    cron: bool,
+) -> Result<(), RunnerError> {
+    let root = std::path::absolute(root)?;
+    let plans = plans(&root, interval, profile, cron)?;
+    let mut crontab = Vec::new();
+    for (i, plan) in plans.into_iter().enumerate() {
+        if i > 0 && !matches!(plan, Plan::Cron { .. }) {
+            println!();
+        }
+        match plan {
+            Plan::Launchd { label, plist } => {
+                println!("# Save this as ~/Library/LaunchAgents/com.boucle.{label}.plist");
+                println!("{plist}");
+                println!("\n# Then run:");
+                println!("# launchctl load ~/Library/LaunchAgents/com.boucle.{label}.plist");
+            }
+            Plan::TaskScheduler { label, xml } => {
+                println!("<!-- Save this as boucle-{label}.xml -->");
+                println!("{xml}");
+                println!("\n<!-- Then run:");
+                println!(
+                    "     schtasks /Create /TN \"{}\" /XML boucle-{label}.xml -->",
+                    task_name(&label)
+                );
+            }
+            Plan::Systemd {
+                unit,
+                service,
+                timer,
+            } => {
+                println!("# Save this as ~/.config/systemd/user/{unit}.service");
+                println!("{service}");
+                println!("# Save this as ~/.config/systemd/user/{unit}.timer");
+                println!("{timer}");
+                println!("# Then run:");
+                println!("# systemctl --user daemon-reload");
+                println!("# systemctl --user enable --now {unit}.timer");
+                println!("# loginctl enable-linger \"$USER\"  # keep running while logged out");
+                println!("# Logs: journalctl --user -u {unit}");
+            }
+            Plan::Cron { line } => crontab.push(line),
+        }
+    }
+    if !crontab.is_empty() {
+        println!("# Add this to your crontab (crontab -e):");
+        for line in crontab {
+            println!("{line}");
+        }
+    }
+    Ok(())
+}
+
+/// Install and activate the schedules, replacing those installed before
+/// for the same agent and profile.
+pub fn install(
+    root: &Path,
+    interval: Option<&str>,
+    profile: Option<&str>,
+    cron: bool,
+) -> Result<(), RunnerError> {
+    let root = std::path::absolute(root)?;
+    let plans = plans(&root, interval, profile, cron)?;
+    remove(&root, profile)?;
+    let mut crontab = Vec::new();
+    for plan in plans {
+        match plan {
+            Plan::Launchd { label, plist } => {
+                let path = launch_agents_dir()?.join(format!("com.boucle.{label}.plist"));
+                write_file(&path, &plist)?;
+                run("launchctl", &["load", "-w", &path.display().to_string()])?;
+                println!("Installed {}", path.display());
+                println!("Loaded com.boucle.{label} with launchctl.");
+            }
+            Plan::TaskScheduler { label, xml } => {
+                let task = task_name(&label);
+                let path = std::env::temp_dir().join(format!("boucle-{label}.xml"));
+                fs::write(&path, xml)?;
+                let created = run(
+                    "schtasks",
+                    &[
+                        "/Create",
+                        "/TN",
+                        &task,
+                        "/XML",
+                        &path.display().to_string(),
+                        "/F",
+                    ],
+                );
+                let _ = fs::remove_file(&path);
+                created?;
+                println!("Registered scheduled task {task}.");
+            }
+            Plan::Systemd {
+                unit,
+                service,
+                timer,
+            } => {
+                let dir = systemd_user_dir()?;
+                write_file(&dir.join(format!("{unit}.service")), &service)?;
+                write_file(&dir.join(format!("{unit}.timer")), &timer)?;
+                run("systemctl", &["--user", "daemon-reload"])?;
+                run(
+                    "systemctl",
+                    &["--user", "enable", "--now", &format!("{unit}.timer")],
+                )?;
+                println!(
+                    "Installed {unit}.service and {unit}.timer in {}",
+                    dir.display()
+                );
+                println!("Enabled {unit}.timer. Logs: journalctl --user -u {unit}");
+                println!("To keep it running while logged out: loginctl enable-linger \"$USER\"");
+            }
+            Plan::Cron { line } => crontab.push(line),
+        }
+    }
+    if !crontab.is_empty() {
+        let owner = owner(&root, profile);
+        write_crontab(&crontab_with(&read_crontab()?, &owner, &crontab))?;
+        println!("Installed crontab entries:");
+        for line in crontab {
+            println!("{line}");
+        }
+    }
+    Ok(())
+}
+
+/// Remove what [`install`] set up for this agent and profile. On Linux
+/// that is the systemd units and any crontab entries.
+pub fn uninstall(root: &Path, profile: Option<&str>) -> Result<(), RunnerError> {
+    let root = std::path::absolute(root)?;
+    let removed = remove(&root, profile)?;
+    if removed.is_empty() {
+        let cfg = load_config(&root, profile)?;
+        println!("No schedule installed for {}.", label(&cfg, profile, None));
+    } else {
+        for item in removed {
+            println!("Removed {item}");
+        }
+    }
+    Ok(())
+}
+
+/// What is installed for one agent and profile: everything carrying its
+/// [`owner`] marker, plus the unmarked files of the default schedule
+/// written by older versions.
+#[derive(Default)]
+struct Installed {
+    /// launchd plists or systemd services.
+    files: Vec<PathBuf>,
+    /// Windows scheduled tasks.
+    tasks: Vec<String>,
+    /// Crontab lines.
+    crontab: Vec<String>,
+}
+
+fn installed(
+    root: &Path,
+    cfg: &config::Config,
+    profile: Option<&str>,
+) -> Result<Installed, RunnerError> {
+    let owner = owner(root, profile);
+    let default_label = label(cfg, profile, None);
+    let mut found = Installed::default();
+    if cfg!(target_os = "macos") {
+        let default = format!("com.boucle.{default_label}.plist");
+        found.files = owned_files(
+            &launch_agents_dir()?,
+            "com.boucle.",
+            ".plist",
+            &owner,
+            &default,
+        )?;
+    } else if cfg!(windows) {
+        // Task Scheduler has nowhere to keep a marker; look for the tasks
+        // of the schedules boucle.toml has now.
+        let labels = std::iter::once(default_label).chain(
+            cfg.schedule
+                .named
+                .keys()
+                .map(|name| label(cfg, profile, Some(name))),
+        );
+        found.tasks = labels
+            .map(|label| task_name(&label))
+            .filter(|task| run("schtasks", &["/Query", "/TN", task]).is_ok())
+            .collect();
+    } else {
+        if cfg!(target_os = "linux") {
+            let default = format!("{}.service", systemd_unit_name(&default_label));
+            found.files = owned_files(
+                &systemd_user_dir()?,
+                "boucle-",
+                ".service",
+                &owner,
+                &default,
+            )?;
+        }
+        found.crontab = read_crontab()?
+            .lines()
+            .filter(|line| is_owned(line, &owner))
+            .map(str::to_string)
+            .collect();
+    }
+    Ok(found)
+}
+
+/// Deactivate and delete what is [`installed`] for this agent and profile.
+/// Returns what was removed.
+fn remove(root: &Path, profile: Option<&str>) -> Result<Vec<String>, RunnerError> {
+    let cfg = load_config(root, profile)?;
+    let found = installed(root, &cfg, profile)?;
+    let mut removed = Vec::new();
+
+    if cfg!(target_os = "macos") {
+        for path in &found.files {
+            let _ = run("launchctl", &["unload", "-w", &path.display().to_string()]);
+            fs::remove_file(path)?;
+            removed.push(path.display().to_string());
+        }
+    }
+    for task in &found.tasks {
+        run("schtasks", &["/Delete", "/TN", task, "/F"])?;
+        removed.push(format!("scheduled task {task}"));
+    }
+    if cfg!(target_os = "linux") {
+        for service in &found.files {
+            let timer = service.with_extension("timer");
+            let unit = timer.file_name().unwrap_or_default().to_string_lossy();
+            let _ = run("systemctl", &["--user", "disable", "--now", &unit]);
+            for file in [service, &timer] {
+                if file.exists() {
+                    fs::remove_file(file)?;
+                    removed.push(file.display().to_string());
+                }
+            }
+        }
+        if !found.files.is_empty() {
+            run("systemctl", &["--user", "daemon-reload"])?;
+        }
+    }
+    if !found.crontab.is_empty() {
+        let owner = owner(root, profile);
+        write_crontab(&crontab_with(&read_crontab()?, &owner, &[]))?;
+        removed.push("crontab entries".to_string());
+    }
+    Ok(removed)
+}
+
+/// `boucle schedule status`: for each schedule, whether and how it is
+/// installed, the last run, and when the next is due.
+pub fn status(root: &Path, profile: Option<&str>) -> Result<(), RunnerError> {
+    let root = std::path::absolute(root)?;
+    let cfg = load_config(&root, profile)?;
+    let found = installed(&root, &cfg, profile)?;
+    let log_dir = root.join(
+        cfg.loop_config
+            .log_dir
+            .as_deref()
+            .unwrap_or(super::LOG_DIR_DEFAULT),
+    );
+    let schedules: Vec<(Option<&str>, &str)> = if cfg.schedule.named.is_empty() {
+        vec![(None, cfg.schedule.interval.as_str())]
+    } else {
+        cfg.schedule
+            .named
+            .iter()
+            .map(|(name, named)| (Some(name.as_str()), named.interval.as_str()))
+            .collect()
+    };
+    let now = Local::now();
+    let mut seen = Vec::new();
+    let last_runs = read_last_runs(&root);
+
+    if let Some(pause) = active_pause(&root) {
+        println!(
+            "Paused {} — scheduled runs skip until resumed.",
+            pause.describe()
+        );
+        println!();
+    }
+    for (i, (name, interval)) in schedules.into_iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        let mut window: Vec<&str> = [&cfg.schedule.active_hours, &cfg.schedule.active_days]
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+            .collect();
+        window.insert(0, interval);
+        let label = label(&cfg, profile, name);
+        println!("Schedule: {label} ({})", window.join(", "));
+
+        let marker = marker(&root, profile, name);
+        let entry = installed_entry(&found, &marker, &task_name(&label), name.is_none());
+        seen.push(marker);
+        if let Some(Entry::File(ref path)) = entry {
+            seen.push(path.display().to_string());
+        }
+        match entry {
+            Some(ref entry) => println!("Installed: {}", entry.describe()),
+            None => println!("Installed: no (`boucle schedule install` sets it up)"),
+        }
+        let last = last_run(&log_dir, name);
+        match last {
+            Some(last) => println!(
+                "Last run: {} ({} ago)",
+                last.format("%Y-%m-%d %H:%M"),
+                span(now - last)
+            ),
+            None => println!("Last run: never"),
+        }
+        let scheduled = last_scheduled(&last_runs, &label_key(profile, name));
+        if let Some(scheduled) = scheduled {
+            println!(
+                "Last scheduled run: {} ({} ago)",
+                scheduled.format("%Y-%m-%d %H:%M"),
+                span(now - scheduled)
+            );
+        }
+        let last = scheduled.or(last);
+        if entry.is_none() {
+            continue;
+        }
+        let mut spec = schedule_spec(&cfg, interval)?;
+        if matches!(entry, Some(Entry::Crontab)) {
+            spec = config::parse_schedule(&cron_expression(&spec)).map_err(invalid_input)?;
+        }
+        let next = match spec {
+            config::ScheduleSpec::Cron(ref cron) => next_fire(cron, now),
+            config::ScheduleSpec::Every(seconds) => {
+                last.map(|last| (last + chrono::Duration::seconds(seconds as i64)).max(now))
+            }
+        };
+        match next {
+            Some(next) if next <= now => println!("Next run: due now"),
+            Some(next) => println!(
+                "Next run: {} (in {})",
+                next.format("%Y-%m-%d %H:%M"),
+                span(next - now)
+            ),
+            None => println!("Next run: within {interval} of installing"),
+        }
+    }
+
+    // Jobs of schedules since removed from boucle.toml, or of the default
+    // schedule after named ones were added.
+    let mut stale: Vec<String> = found
+        .files
+        .iter()
+        .map(|path| path.display().to_string())
+        .filter(|path| !seen.contains(path))
+        .collect();
+    stale.extend(
+        found
+            .crontab
+            .iter()
+            .filter(|line| {
+                !seen
+                    .iter()
+                    .any(|m| line.trim_end().ends_with(&format!("# {m}")))
+            })
+            .map(|line| format!("crontab: {line}")),
+    );
+    if !stale.is_empty() {
+        println!();
+        println!("Left over from an earlier install (`boucle schedule install` removes them):");
+        for item in stale {
+            println!("  {item}");
+        }
+    }
+    Ok(())
+}
+
+/// Where one schedule is installed.
+enum Entry {
+    File(PathBuf),
+    Task(String),
+    Crontab,
+}
+
+impl Entry {
+    fn describe(&self) -> String {
+        match self {
+            Entry::File(path) if cfg!(target_os = "linux") => {
+                let timer = path.with_extension("timer");
+                let unit = timer.file_name().unwrap_or_default().to_string_lossy();
+                let state = Command::new("systemctl")
+                    .args(["--user", "is-active", &unit])
+                    .output()
+                    .map(|out| String::from_utf8_lossy(&out.stdout).trim().to_string())
+                    .unwrap_or_default();
+                match state.as_str() {
+                    "" => format!("systemd timer {unit}"),
+                    state => format!("systemd timer {unit} ({state})"),
+                }
+            }
+            Entry::File(path) => {
+                let label = path.file_stem().unwrap_or_default().to_string_lossy();
+                let loaded = run("launchctl", &["list", &label]).is_ok();
+                let state = if loaded { "loaded" } else { "not loaded" };
+                format!("launchd job {label} ({state})")
+            }
+            Entry::Task(task) => format!("scheduled task {task}"),
+            Entry::Crontab => "crontab entry".to_string(),
+        }
+    }
+}
+
+/// The entry in `found` marked for one schedule, or its scheduled `task`.
+/// `default` also accepts an unmarked file of the default schedule.
+fn installed_entry(found: &Installed, marker: &str, task: &str, default: bool) -> Option<Entry> {
+    let marked = |text: &str| {
+        text.lines().any(|line| {
+            let line = line.trim_end();
+            line.ends_with(&format!("# {marker}")) || line.ends_with(&format!("<!-- {marker} -->"))
+        })
+    };
+    if let Some(file) = found.files.iter().find(|file| {
+        let content = fs::read_to_string(file).unwrap_or_default();
+        marked(&content)
+            || (default && !content.contains("# boucle ") && !content.contains("<!-- boucle "))
+    }) {
+        return Some(Entry::File(file.clone()));
+    }
+    if found.crontab.iter().any(|line| marked(line)) {
+        return Some(Entry::Crontab);
+    }
+    found
+        .tasks
+        .iter()
+        .any(|t| t == task)
+        .then(|| Entry::Task(task.to_string()))
+}
+
+/// When the newest run in `log_dir` started; for a named schedule, the
+/// newest run it started.
+fn last_run(log_dir: &Path, schedule: Option<&str>) -> Option<DateTime<Local>> {
+    let mut logs: Vec<PathBuf> = fs::read_dir(log_dir)
+        .ok()?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "log"))
+        .collect();
+    logs.sort();
+    let header = schedule.map(|name| format!("Schedule: {name} ("));
+    let log = logs.iter().rev().find(|log| match header {
+        Some(ref header) => fs::read_to_string(log)
+            .is_ok_and(|content| content.lines().take(10).any(|l| l.starts_with(header))),
+        None => true,
+    })?;
+    let stem = log.file_stem()?.to_string_lossy();
+    chrono::NaiveDateTime::parse_from_str(&stem, "%Y-%m-%d_%H-%M-%S")
+        .ok()
+        .map(|time| time.and_utc().with_timezone(&Local))
+}
+
+/// The first time after `after` that `cron` fires, looking up to five
+/// years ahead.
+fn next_fire(cron: &config::CronExpr, after: DateTime<Local>) -> Option<DateTime<Local>> {
+    use chrono::{Datelike, TimeZone};
+    let allows = |field: &Option<Vec<u32>>, value: u32| {
+        field.as_ref().is_none_or(|values| values.contains(&value))
+    };
+    let every = |field: &Option<Vec<u32>>, max: u32| match field {
+        Some(values) => values.clone(),
+        None => (0..=max).collect::<Vec<_>>(),
+    };
+    let (hours, minutes) = (every(&cron.hours, 23), every(&cron.minutes, 59));
+    let start = after.naive_local();
+    for date in start.date().iter_days().take(5 * 366) {
+        let day = allows(&cron.days, date.day());
+        let weekday = allows(&cron.weekdays, date.weekday().num_days_from_sunday());
+        // Cron's rule: either field matches when both are restricted.
+        let day_matches = if cron.days.is_some() && cron.weekdays.is_some() {
+            day || weekday
+        } else {
+            day && weekday
+        };
+        if !allows(&cron.months, date.month()) || !day_matches {
+            continue;
+        }
+        for &hour in &hours {
+            for &minute in &minutes {
+                let Some(time) = date.and_hms_opt(hour, minute, 0) else {
+                    continue;
+                };
+                if time <= start {
+                    continue;
+                }
+                if let Some(time) = Local.from_local_datetime(&time).earliest() {
+                    return Some(time);
+                }
+            }
+        }
+    }
+    None
+}
+
+/// `duration` to the minute, e.g. `2h 5min`.
+fn span(duration: chrono::Duration) -> String {
+    let minutes = duration.num_minutes().max(0) as u64;
+    if minutes == 0 {
+        return "under a minute".to_string();
+    }
+    systemd_timespan(minutes * 60)
+}
+
+/// The files in `dir` named `<prefix>…<suffix>` that are marked as
+/// installed for `owner`, and `default` if it exists.
+fn owned_files(
+    dir: &Path,
+    prefix: &str,
+    suffix: &str,
+    owner: &str,
+    default: &str,
+) -> Result<Vec<PathBuf>, RunnerError> {
+    let mut files = Vec::new();
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(files),
+        Err(e) => return Err(e.into()),
+    };
+    for entry in entries {
+        let path = entry?.path();
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        if !name.starts_with(prefix) || !name.ends_with(suffix) {
+            continue;
+        }
+        let owned = fs::read_to_string(&path).is_ok_and(|content| is_owned(&content, owner));
+        if owned || name == default {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// The scheduled task's path in Task Scheduler, under a `Boucle` folder.
+fn task_name(label: &str) -> String {
+    format!("Boucle\\{label}")
+}
+
+fn home_dir() -> Result<PathBuf, RunnerError> {
+    std::env::var_os("HOME")
+        .filter(|home| !home.is_empty())
+        .map(PathBuf::from)
+        .ok_or_else(|| RunnerError::Io(io::Error::new(io::ErrorKind::NotFound, "HOME is not set")))
+}
+
+fn launch_agents_dir() -> Result<PathBuf, RunnerError> {
+    Ok(home_dir()?.join("Library/LaunchAgents"))
+}
+
+/// `$XDG_CONFIG_HOME/systemd/user`, or `~/.config/systemd/user`.
+fn systemd_user_dir() -> Result<PathBuf, RunnerError> {
+    let config_home = match std::env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => home_dir()?.join(".config"),
+    };
+    Ok(config_home.join("systemd/user"))
+}
+
+fn write_file(path: &Path, content: &str) -> Result<(), RunnerError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, content)?;
+    Ok(())
+}
+
+/// Run `program`, failing with its stderr when it exits non-zero.
+fn run(program: &str, args: &[&str]) -> Result<(), RunnerError> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| io::Error::new(e.kind(), format!("{program}: {e}")))?;
+    if output.status.success() {
+        return Ok(());
+    }
+    Err(RunnerError::Io(io::Error::other(format!(
+        "{program} {} failed: {}",
+        args.join(" "),
+        String::from_utf8_lossy(&output.stderr).trim()
+    ))))
+}
+
+/// The current user's crontab; empty when they have none, or there is no
+/// `crontab` to ask.
+fn read_crontab() -> Result<String, RunnerError> {
+    let output = match Command::new("crontab").arg("-l").output() {
+        Ok(output) => output,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(String::new()),
+        Err(e) => return Err(io::Error::new(e.kind(), format!("crontab: {e}")).into()),
+    };
+    if output.status.success() {
+        return Ok(String::from_utf8_lossy(&output.stdout).into_owned());
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if stderr.contains("no crontab") {
+        return Ok(String::new());
+    }
+    Err(RunnerError::Io(io::Error::other(format!(
+        "crontab -l failed: {}",
+        stderr.trim()
+    ))))
+}
+
+fn write_crontab(content: &str) -> Result<(), RunnerError> {
+    use std::io::Write;
+    let mut child = Command::new("crontab")
+        .arg("-")
+        .stdin(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .map_err(|e| io::Error::new(e.kind(), format!("crontab: {e}")))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(content.as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(RunnerError::Io(io::Error::other(format!(
+            "crontab - failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))));
+    }
+    Ok(())
+}
+
+/// `crontab` without the lines installed for `owner`, plus `entries`.
+fn crontab_with(crontab: &str, owner: &str, entries: &[String]) -> String {
+    let mut lines: Vec<&str> = crontab
+        .lines()
+        .filter(|line| !is_owned(line, owner))
+        .collect();
+    lines.extend(entries.iter().map(String::as_str));
+    let mut updated = lines.join("\n");
+    if !updated.is_empty() {
+        updated.push('\n');
+    }
+    updated
+}
+
+fn generate_launchd_plist(
+    name: &str,
+    binary: &Path,
+    root: &Path,
+    log_dir: &Path,
+    schedule: &config::ScheduleSpec,
+    run_args: &[&str],
+) -> String {
+    let extra_args: String = run_args
+        .iter()
+        .map(|arg| format!("\n        <string>{arg}</string>"))
+        .collect();
+    let trigger = match schedule {
+        config::ScheduleSpec::Every(interval_secs) => {
+            format!("<key>StartInterval</key>\n    <integer>{interval_secs}</integer>")
+        }
+        config::ScheduleSpec::Cron(cron) => launchd_calendar_interval(cron),
+    };
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>com.boucle.{name}</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>{binary}</string>
+        <string>--root</string>
+        <string>{root}</string>
+        <string>run</string>{extra_args}
+    </array>
+    {trigger}
+    <key>WorkingDirectory</key>
+    <string>{root}</string>
+    <key>StandardOutPath</key>
+    <string>{log_dir}/launchd-stdout.log</string>
+    <key>StandardErrorPath</key>
+    <string>{log_dir}/{SCHEDULER_LOG}</string>
+</dict>
+</plist>"#,
+        binary = binary.display(),
+        root = root.display(),
+        log_dir = log_dir.display(),
+    )
+}
+
+/// Task Scheduler definition for `schtasks /Create /XML`.
+///
+/// Task Scheduler can't repeat more often than once a minute, so shorter
+/// intervals are rounded up. `IgnoreNew` keeps a slow iteration from
+/// stacking up; the runner's own lock would refuse it anyway.
+/// `StartWhenAvailable` catches up on a run missed while the machine was
+/// asleep or off.
+fn generate_task_scheduler_xml(
+    name: &str,
+    binary: &Path,
+    root: &Path,
+    interval_secs: u64,
+    run_args: &[&str],
+    start_boundary: &str,
+    jitter_secs: Option<u64>,
+) -> String {
+    let minutes = (interval_secs / 60).max(1);
+    let interval = if minutes.is_multiple_of(60) {
+        format!("PT{}H", minutes / 60)
+    } else {
+        format!("PT{minutes}M")
+    };
+    let random_delay = jitter_secs
+        .map(|secs| format!("\n      <RandomDelay>{}</RandomDelay>", iso_duration(secs)))
+        .unwrap_or_default();
+    let mut arguments = format!("--root \"{}\" run", root.display());
+    for arg in run_args {
+        arguments.push(' ');
+        arguments.push_str(arg);
+    }
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<Task version="1.2" xmlns="http://schemas.microsoft.com/windows/2004/02/mit/task">
+  <RegistrationInfo>
+    <Description>Boucle agent loop: {name}</Description>
+  </RegistrationInfo>
+  <Triggers>
+    <TimeTrigger>
+      <Repetition>
+        <Interval>{interval}</Interval>
+        <StopAtDurationEnd>false</StopAtDurationEnd>
+      </Repetition>
+      <StartBoundary>{start_boundary}</StartBoundary>{random_delay}
+      <Enabled>true</Enabled>
+    </TimeTrigger>
+  </Triggers>
+  <Settings>
+    <MultipleInstancesPolicy>IgnoreNew</MultipleInstancesPolicy>
+    <StartWhenAvailable>true</StartWhenAvailable>
+    <DisallowStartIfOnBatteries>false</DisallowStartIfOnBatteries>
+    <StopIfGoingOnBatteries>false</StopIfGoingOnBatteries>
+    <ExecutionTimeLimit>PT0S</ExecutionTimeLimit>
+    <Enabled>true</Enabled>
+  </Settings>
+  <Actions Context="Author">
+    <Exec>
+      <Command>{binary}</Command>
+      <Arguments>{arguments}</Arguments>
+      <WorkingDirectory>{root}</WorkingDirectory>
+    </Exec>
+  </Actions>
+</Task>"#,
+        name = xml_escape(name),
+        binary = xml_escape(&binary.display().to_string()),
+        arguments = xml_escape(&arguments),
+        root = xml_escape(&root.display().to_string()),
+    )
+}
+
+/// launchd's form of a cron expression: one `StartCalendarInterval` entry
+/// per combination of the restricted fields. Every minute when none are.
+fn launchd_calendar_interval(cron: &config::CronExpr) -> String {
+    let fields = [
+        ("Minute", &cron.minutes),
+        ("Hour", &cron.hours),
+        ("Day", &cron.days),
+        ("Month", &cron.months),
+        ("Weekday", &cron.weekdays),
+    ];
+    let mut entries: Vec<Vec<(&str, u32)>> = vec![Vec::new()];
+    for (key, values) in fields {
+        let Some(values) = values else { continue };
+        entries = entries
+            .iter()
+            .flat_map(|entry| {
+                values.iter().map(move |value| {
+                    let mut entry = entry.clone();
+                    entry.push((key, *value));
+                    entry
+                })
+            })
+            .collect();
+    }
+    if entries == [Vec::new()] {
+        return "<key>StartInterval</key>\n    <integer>60</integer>".to_string();
+    }
+    let mut out = "<key>StartCalendarInterval</key>\n    <array>".to_string();
+    for entry in entries {
+        out.push_str("\n        <dict>");
+        for (key, value) in entry {
+            out.push_str(&format!(
+                "\n            <key>{key}</key>\n            <integer>{value}</integer>"
+            ));
+        }
+        out.push_str("\n        </dict>");
+    }
+    out.push_str("\n    </array>");
+    out
+}
+
+/// A systemd user service running one iteration, and the timer that
+/// starts it: `OnUnitActiveSec` for a duration (first run a minute after
+/// the timer starts), `OnCalendar` for a cron expression. Output goes to
+/// the journal under the unit's name. `path` is baked in as `PATH` because
+/// the user manager's own is too short to find the LLM CLI.
+fn generate_systemd_units(
+    label: &str,
+    binary: &Path,
+    root: &Path,
+    schedule: &config::ScheduleSpec,
+    run_args: &[&str],
+    path: Option<&str>,
+    jitter_secs: Option<u64>,
+) -> (String, String) {
+    let unit = systemd_unit_name(label);
+    let root_arg = root.display().to_string();
+    let mut exec = vec![
+        binary.display().to_string(),
+        "--root".to_string(),
+        root_arg,
+        "run".to_string(),
+    ];
+    exec.extend(run_args.iter().map(|arg| arg.to_string()));
+    let exec: Vec<String> = exec
+        .iter()
+        .map(|arg| systemd_quote(&arg.replace('$', "$$")))
+        .collect();
+    let environment = path
+        .map(|path| format!("Environment={}\n", systemd_quote(&format!("PATH={path}"))))
+        .unwrap_or_default();
+    let service = format!(
+        r#"[Unit]
+Description=Boucle agent loop: {label}
+
+[Service]
+Type=oneshot
+WorkingDirectory={root}
+{environment}ExecStart={exec}
+SyslogIdentifier={unit}
+StandardOutput=journal
+StandardError=journal
+"#,
+        root = root.display().to_string().replace('%', "%%"),
+        exec = exec.join(" "),
+    );
+
+    let mut trigger = match schedule {
+        config::ScheduleSpec::Every(interval_secs) => format!(
+            "OnActiveSec=1min\nOnUnitActiveSec={}",
+            systemd_timespan(*interval_secs)
+        ),
+        config::ScheduleSpec::Cron(cron) => {
+            let mut lines: Vec<String> = systemd_calendar(cron)
+                .into_iter()
+                .map(|calendar| format!("OnCalendar={calendar}"))
+                .collect();
+            lines.push("Persistent=true".to_string());
+            lines.join("\n")
+        }
+    };
+    if let Some(secs) = jitter_secs {
+        trigger.push_str(&format!("\nRandomizedDelaySec={}", systemd_timespan(secs)));
+    }
+    let timer = format!(
+        r#"[Unit]
+Description=Schedule for {unit}.service
+
+[Timer]
+{trigger}
+
+[Install]
+WantedBy=timers.target
+"#
+    );
+    (service, timer)
+}
+
+/// `boucle-<label>`, with characters systemd doesn't allow in unit names
+/// replaced by `-`.
+fn systemd_unit_name(label: &str) -> String {
+    let label: String = label
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || "-_.:".contains(c) {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    format!("boucle-{label}")
+}
+
+/// One value in a unit file, quoted when it has to be and with `%`
+/// escaped from specifier expansion.
+fn systemd_quote(arg: &str) -> String {
+    let arg = arg.replace('%', "%%");
+    if arg.is_empty() || arg.contains(|c: char| c.is_whitespace() || "\"'\\;".contains(c)) {
+        format!("\"{}\"", arg.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        arg
+    }
+}
+
+/// `seconds` as a systemd time span, e.g. `1h 30min`.
+fn systemd_timespan(seconds: u64) -> String {
+    let parts = [
+        (seconds / 86400, "d"),
+        (seconds % 86400 / 3600, "h"),
+        (seconds % 3600 / 60, "min"),
+        (seconds % 60, "s"),
+    ];
+    let span: Vec<String> = parts
+        .iter()
+        .filter(|(value, _)| *value > 0)
+        .map(|(value, unit)| format!("{value}{unit}"))
+        .collect();
+    if span.is_empty() {
+        "1s".to_string()
+    } else {
+        span.join(" ")
+    }
+}
+
+/// `OnCalendar` values for a cron expression. Cron runs when either the
+/// day of month or the weekday matches if both are restricted, while
+/// systemd wants both, so that case becomes two entries.
+fn systemd_calendar(cron: &config::CronExpr) -> Vec<String> {
+    const WEEKDAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+    let list = |values: &Option<Vec<u32>>| match values {
+        Some(values) => values
+            .iter()
+            .map(|v| format!("{v:02}"))
+            .collect::<Vec<_>>()
+            .join(","),
+        None => "*".to_string(),
+    };
+    let time = format!("{}:{}:00", list(&cron.hours), list(&cron.minutes));
+    let months = list(&cron.months);
+    let weekdays = cron.weekdays.as_ref().map(|days| {
+        days.iter()
+            .map(|d| WEEKDAYS[*d as usize % 7])
+            .collect::<Vec<_>>()
+            .join(",")
+    });
+    match weekdays {
+        Some(weekdays) if cron.days.is_some() => vec![
+            format!("*-{months}-{} {time}", list(&cron.days)),
+            format!("{weekdays} *-{months}-* {time}"),
+        ],
+        Some(weekdays) => vec![format!("{weekdays} *-{months}-* {time}")],
+        None => vec![format!("*-{months}-{} {time}", list(&cron.days))],
+    }
+}
+
+/// `seconds` as an ISO 8601 duration for Task Scheduler, e.g. `PT5M`.
+fn iso_duration(seconds: u64) -> String {
+    if seconds.is_multiple_of(60) {
+        format!("PT{}M", seconds / 60)
+    } else {
+        format!("PT{seconds}S")
+    }
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// The crontab schedule field for `schedule`.
+fn cron_expression(schedule: &config::ScheduleSpec) -> String {
+    match schedule {
+        config::ScheduleSpec::Cron(cron) => cron.expr.clone(),
+        config::ScheduleSpec::Every(interval_secs) => {
+            let minutes = interval_secs / 60;
+            if minutes == 0 {
+                "* * * * *".to_string() // Every minute
+            } else if minutes < 60 {
+                format!("*/{minutes} * * * *")
+            } else {
+                let hours = minutes / 60;
+                format!("0 */{hours} * * *")
+            }
+        }
+    }
+}
+
+fn generate_cron_entry(
+    binary: &Path,
+    root: &Path,
+    schedule: &config::ScheduleSpec,
+    run_args: &[&str],
+) -> String {
+    let cron_expr = cron_expression(schedule);
+    let mut command = format!(
+        "{cron_expr} cd {} && {} run",
+        root.display(),
+        binary.display()
+    );
+    for arg in run_args {
+        command.push(' ');
+        command.push_str(arg);
+    }
+    command
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_cron_hourly() {
+        let entry = generate_cron_entry(
+            Path::new("/usr/local/bin/boucle"),
+            Path::new("/home/agent"),
+            &config::ScheduleSpec::Every(3600),
+            &[],
+        );
+        assert!(entry.contains("0 */1 * * *"));
+        assert!(entry.contains("/usr/local/bin/boucle"));
+    }
+
+    #[test]
+    fn test_generate_cron_every_5_min() {
+        let entry = generate_cron_entry(
+            Path::new("/usr/local/bin/boucle"),
+            Path::new("/home/agent"),
+            &config::ScheduleSpec::Every(300),
+            &[],
+        );
+        assert!(entry.contains("*/5 * * * *"));
+    }
+
+    #[test]
+    fn test_generate_schedule_from_cron_expression() {
+        let spec = config::parse_schedule("30 9-10 * * mon-fri").unwrap();
+        let entry = generate_cron_entry(
+            Path::new("/usr/local/bin/boucle"),
+            Path::new("/home/agent"),
+            &spec,
+            &[],
+        );
+        assert!(entry.starts_with("30 9-10 * * mon-fri cd /home/agent"));
+
+        let plist = generate_launchd_plist(
+            "test",
+            Path::new("/usr/local/bin/boucle"),
+            Path::new("/home/agent"),
+            Path::new("/home/agent/logs"),
+            &spec,
+            &[],
+        );
+        assert!(plist.contains("<key>StartCalendarInterval</key>"));
+        assert!(!plist.contains("<key>StartInterval</key>"));
+        assert_eq!(
+            plist
+                .matches("<dict>\n            <key>Minute</key>")
+                .count(),
+            10
+        );
+        assert!(plist.contains(
+            "<key>Hour</key>\n            <integer>10</integer>\n            <key>Weekday</key>\n            <integer>5</integer>"
+        ));
+    }
+
+    #[test]
+    fn test_generate_task_scheduler_xml() {
+        let xml = generate_task_scheduler_xml(
+            "my-agent.nightly",
+            Path::new("C:\\tools\\boucle.exe"),
+            Path::new("C:\\agents\\R&D"),
+            1800,
+            &["--profile", "nightly"],
+            "2026-03-01T10:00:00",
+            Some(300),
+        );
+        assert!(xml.contains("<Interval>PT30M</Interval>"));
+        assert!(xml.contains("<Command>C:\\tools\\boucle.exe</Command>"));
+        assert!(xml.contains(
+            "<Arguments>--root &quot;C:\\agents\\R&amp;D&quot; run --profile nightly</Arguments>"
+        ));
+        assert!(xml.contains("<StartBoundary>2026-03-01T10:00:00</StartBoundary>"));
+        assert!(xml.contains("<StartWhenAvailable>true</StartWhenAvailable>"));
+        assert!(xml.contains("<RandomDelay>PT5M</RandomDelay>"));
+        assert_eq!(task_name("my-agent.nightly"), "Boucle\\my-agent.nightly");
+
+        let hourly =
+            generate_task_scheduler_xml("a", Path::new("b"), Path::new("c"), 7200, &[], "x", None);
+        assert!(hourly.contains("<Interval>PT2H</Interval>"));
+        let fast =
+            generate_task_scheduler_xml("a", Path::new("b"), Path::new("c"), 10, &[], "x", None);
+        assert!(fast.contains("<Interval>PT1M</Interval>"));
+    }
+
+    #[test]
+    fn test_generate_systemd_units() {
+        let (service, timer) = generate_systemd_units(
+            "my agent.nightly",
+            Path::new("/usr/local/bin/boucle"),
+            Path::new("/home/me/100% agents"),
+            &config::ScheduleSpec::Every(5400),
+            &["--profile", "nightly"],
+            Some("/home/me/.local/bin:/usr/bin"),
+            Some(90),
+        );
+        assert_eq!(
+            systemd_unit_name("my agent.nightly"),
+            "boucle-my-agent.nightly"
+        );
+        assert!(service.contains("Type=oneshot\nWorkingDirectory=/home/me/100%% agents\n"));
+        assert!(service.contains("Environment=PATH=/home/me/.local/bin:/usr/bin\n"));
+        assert!(service.contains(
+            "ExecStart=/usr/local/bin/boucle --root \"/home/me/100%% agents\" run --profile nightly\n"
+        ));
+        assert!(
+            service.contains("SyslogIdentifier=boucle-my-agent.nightly\nStandardOutput=journal")
+        );
+        assert!(timer
+            .contains("OnActiveSec=1min\nOnUnitActiveSec=1h 30min\nRandomizedDelaySec=1min 30s\n"));
+        assert!(timer.contains("WantedBy=timers.target"));
+
+        let spec = config::parse_schedule("30 9 1 * mon-fri").unwrap();
+        let (service, timer) =
+            generate_systemd_units("a", Path::new("b"), Path::new("c"), &spec, &[], None, None);
+        assert!(!service.contains("Environment="));
+        assert!(timer.contains(
+            "OnCalendar=*-*-01 09:30:00\nOnCalendar=Mon,Tue,Wed,Thu,Fri *-*-* 09:30:00\nPersistent=true"
+        ));
+        assert!(!timer.contains("OnUnitActiveSec") && !timer.contains("RandomizedDelaySec"));
+        let spec = config::parse_schedule("*/20 * * * *").unwrap();
+        let (_, timer) =
+            generate_systemd_units("a", Path::new("b"), Path::new("c"), &spec, &[], None, None);
+        assert!(timer.contains("OnCalendar=*-*-* *:00,20,40:00\n"));
+        assert_eq!(systemd_timespan(86400 + 45), "1d 45s");
+    }
+
+    #[test]
+    fn test_generate_launchd_plist() {
+        let plist = generate_launchd_plist(
+            "test",
+            Path::new("/usr/local/bin/boucle"),
+            Path::new("/home/agent"),
+            Path::new("/home/agent/logs"),
+            &config::ScheduleSpec::Every(3600),
+            &[],
+        );
+        assert!(plist.contains("com.boucle.test"));
+        assert!(plist.contains("<string>/home/agent/logs/scheduler.log</string>"));
+        assert!(plist.contains("<integer>3600</integer>"));
+        assert!(plist.contains("/usr/local/bin/boucle"));
+    }
+
+    #[test]
+    fn test_generate_schedule_with_profile_args() {
+        let entry = generate_cron_entry(
+            Path::new("/usr/local/bin/boucle"),
+            Path::new("/home/agent"),
+            &config::ScheduleSpec::Every(86400),
+            &["--profile", "nightly"],
+        );
+        assert!(entry.ends_with("run --profile nightly"));
+
+        let plist = generate_launchd_plist(
+            "test.nightly",
+            Path::new("/usr/local/bin/boucle"),
+            Path::new("/home/agent"),
+            Path::new("/home/agent/logs"),
+            &config::ScheduleSpec::Every(86400),
+            &["--profile", "nightly"],
+        );
+        assert!(plist.contains("com.boucle.test.nightly"));
+        assert!(plist.contains("<string>run</string>\n        <string>--profile</string>\n        <string>nightly</string>"));
+    }
+
+    #[test]
+    fn test_schedule_jitter() {
+        let dir = tempfile::tempdir().unwrap();
+        crate::runner::init(dir.path(), "jittery").unwrap();
+        let cfg = load_config(dir.path(), None).unwrap();
+        assert_eq!(jitter_secs(&cfg, None).unwrap(), None);
+        wait_jitter(dir.path(), None, None).unwrap();
+
+        let toml = dir.path().join("boucle.toml");
+        let content = fs::read_to_string(&toml).unwrap();
+        for (jitter, expected) in [("2m", Some(Some(120))), ("0s", Some(None)), ("soon", None)] {
+            fs::write(
+                &toml,
+                content.replace(
+                    "interval = \"1h\"",
+                    &format!("interval = \"1h\"\njitter = \"{jitter}\""),
+                ),
+            )
+            .unwrap();
+            let cfg = load_config(dir.path(), None).unwrap();
+            assert_eq!(jitter_secs(&cfg, None).ok(), expected, "{jitter}");
+        }
+        assert!((0..50).all(|_| random_below(3) < 3));
+    }
+
+    #[test]
+    fn test_pause_and_resume() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        assert!(active_pause(root).is_none());
+        assert!(!resume(root).unwrap());
+
+        let paused = pause(root, None, Some("maintenance")).unwrap();
+        assert_eq!(active_pause(root), Some(paused));
+        assert_eq!(
+            active_pause(root).unwrap().describe(),
+            "indefinitely (maintenance)"
+        );
+        assert!(resume(root).unwrap());
+        assert!(!root.join(PAUSE_FILE).exists());
+
+        pause(root, Some("2h"), None).unwrap();
+        assert!(active_pause(root).unwrap().describe().starts_with("until "));
+        fs::write(
+            root.join(PAUSE_FILE),
+            r#"{"since":"2026-01-01T00:00:00Z","until":"2026-01-01T01:00:00Z"}"#,
+        )
+        .unwrap();
+        assert!(active_pause(root).is_none());
+        fs::write(root.join(PAUSE_FILE), "").unwrap();
+        assert!(active_pause(root).is_some());
+        assert!(pause(root, Some("whenever"), None).is_err());
+
+        let now = DateTime::parse_from_rfc3339("2026-03-01T10:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(
+            parse_until("90m", now).unwrap().to_rfc3339(),
+            "2026-03-01T11:30:00+00:00"
+        );
+        assert_eq!(
+            parse_until("2026-03-02T00:00:00+01:00", now)
+                .unwrap()
+                .to_rfc3339(),
+            "2026-03-01T23:00:00+00:00"
+        );
+        assert!(parse_until("2026-03-05", now).is_ok());
+        assert!(parse_until("2026-03-05 08:30", now).is_ok());
+    }
+
+    #[test]
+    fn test_confine_to_active_hours() {
+        let window = config::ActiveWindow {
+            start: 8 * 60,
+            end: 22 * 60,
+            weekdays: Some(vec![1, 2, 3, 4, 5]),
+        };
+        let expr = |interval: &str| match confine(
+            &config::parse_schedule(interval).unwrap(),
+            &window,
+            interval,
+        ) {
+            Ok(config::ScheduleSpec::Cron(cron)) => cron.expr,
+            other => panic!("{other:?}"),
+        };
+        assert_eq!(
+            expr("15m"),
+            "0,15,30,45 8,9,10,11,12,13,14,15,16,17,18,19,20,21 * * 1,2,3,4,5"
+        );
+        assert_eq!(expr("4h"), "0 8,12,16,20 * * 1,2,3,4,5");
+        assert_eq!(expr("1d"), "0 8 * * 1,2,3,4,5");
+        assert_eq!(expr("0 7,9 * * 1-6"), "0 9 * * 1,2,3,4,5");
+        // A day of month keeps its weekdays open; `boucle run` skips instead.
+        assert_eq!(expr("0 9 1 * *"), "0 9 1 * *");
+        assert!(confine(
+            &config::parse_schedule("0 3 * * *").unwrap(),
+            &window,
+            "0 3 * * *"
+        )
+        .is_err());
+
+        let night = config::ActiveWindow {
+            start: 22 * 60 + 30,
+            end: 2 * 60,
+            weekdays: None,
+        };
+        let spec = confine(&config::ScheduleSpec::Every(7200), &night, "2h").unwrap();
+        let config::ScheduleSpec::Cron(cron) = spec else {
+            panic!()
+        };
+        assert_eq!(cron.expr, "30 0,22 * * *");
+    }
+
+    #[test]
+    fn test_schedule_status_parts() {
+        use chrono::TimeZone;
+        let at = |text: &str| {
+            let time = chrono::NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M").unwrap();
+            Local.from_local_datetime(&time).earliest().unwrap()
+        };
+        let cron = |expr: &str| match config::parse_schedule(expr).unwrap() {
+            config::ScheduleSpec::Cron(cron) => cron,
+            other => panic!("{other:?}"),
+        };
+        // 2026-03-06 is a Friday.
+        let now = at("2026-03-06 21:10");
+        assert_eq!(
+            next_fire(&cron("*/15 * * * *"), now),
+            Some(at("2026-03-06 21:15"))
+        );
+        assert_eq!(
+            next_fire(&cron("0 9 * * mon-fri"), now),
+            Some(at("2026-03-09 09:00"))
+        );
+        assert_eq!(
+            next_fire(&cron("0 9 1 * sat"), now),
+            Some(at("2026-03-07 09:00"))
+        );
+        assert_eq!(
+            next_fire(&cron("0 0 29 2 *"), now),
+            Some(at("2028-02-29 00:00"))
+        );
+        assert_eq!(span(at("2026-03-06 23:15") - now), "2h 5min");
+
+        let dir = tempfile::tempdir().unwrap();
+        let logs = dir.path();
+        assert_eq!(last_run(logs, None), None);
+        fs::write(
+            logs.join("2026-03-01_08-00-00.log"),
+            "=== Boucle loop ===\nAgent: a\nSchedule: nightly (task: nightly)\n",
+        )
+        .unwrap();
+        fs::write(
+            logs.join("2026-03-02_08-00-00.log"),
+            "=== Boucle loop ===\nAgent: a\n",
+        )
+        .unwrap();
+        let utc = |text: &str| {
+            chrono::NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M")
+                .unwrap()
+                .and_utc()
+                .with_timezone(&Local)
+        };
+        assert_eq!(last_run(logs, None), Some(utc("2026-03-02 08:00")));
+        assert_eq!(
+            last_run(logs, Some("nightly")),
+            Some(utc("2026-03-01 08:00"))
+        );
+        assert_eq!(last_run(logs, Some("hourly")), None);
+
+        let root = Path::new("/home/agent");
+        let found = Installed {
+            crontab: vec![format!(
+                "0 3 * * * cd /home/agent && boucle run --schedule nightly # {}",
+                marker(root, None, Some("nightly"))
+            )],
+            tasks: vec!["Boucle\\agent".to_string()],
+            ..Installed::default()
+        };
+        let entry = |schedule, task| {
+            installed_entry(
+                &found,
+                &marker(root, None, schedule),
+                task,
+                schedule.is_none(),
+            )
+        };
+        assert!(matches!(
+            entry(Some("nightly"), "Boucle\\agent.nightly"),
+            Some(Entry::Crontab)
+        ));
+        assert!(entry(Some("hourly"), "Boucle\\agent.hourly").is_none());
+        assert!(matches!(entry(None, "Boucle\\agent"), Some(Entry::Task(_))));
+    }
+
+    #[test]
+    fn test_catch_up_after_missed_runs() {
+        use chrono::TimeZone;
+        let at = |text: &str| {
+            let time = chrono::NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M").unwrap();
+            Local.from_local_datetime(&time).earliest().unwrap()
+        };
+        let hourly = config::ScheduleSpec::Every(3600);
+        let last = at("2026-03-06 08:00");
+        assert_eq!(
+            due_runs(&hourly, last, at("2026-03-06 09:01"), 300),
+            (1, true)
+        );
+        assert_eq!(
+            due_runs(&hourly, last, at("2026-03-06 08:58"), 300),
+            (1, true)
+        );
+        assert_eq!(
+            due_runs(&hourly, last, at("2026-03-06 11:30"), 300),
+            (3, false)
+        );
+
+        let config::ScheduleSpec::Cron(daily) = config::parse_schedule("0 9 * * *").unwrap() else {
+            panic!()
+        };
+        let daily = config::ScheduleSpec::Cron(daily);
+        let last = at("2026-03-06 09:00");
+        assert_eq!(
+            due_runs(&daily, last, at("2026-03-07 09:02"), 300),
+            (1, true)
+        );
+        // Woken at noon after three missed mornings.
+        assert_eq!(
+            due_runs(&daily, last, at("2026-03-09 12:00"), 300),
+            (3, false)
+        );
+        // Paused for two days, resumed in time for today's run.
+        assert_eq!(
+            due_runs(&daily, last, at("2026-03-09 09:01"), 300),
+            (3, true)
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        crate::runner::init(root, "sleepy").unwrap();
+        let toml = root.join("boucle.toml");
+        let content = fs::read_to_string(&toml).unwrap();
+        let late = |catch_up: &str| {
+            fs::write(
+                &toml,
+                content.replace(
+                    "interval = \"1h\"",
+                    &format!("interval = \"1h\"\ncatch_up = {catch_up}"),
+                ),
+            )
+            .unwrap();
+            let five_hours_ago = (Utc::now() - chrono::Duration::hours(5))
+                .to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+            fs::write(
+                root.join(LAST_RUNS_FILE),
+                format!("{{\"default\": \"{five_hours_ago}\"}}"),
+            )
+            .unwrap();
+            scheduled_iterations(root, None, None).unwrap()
+        };
+        assert_eq!(late("\"once\""), 1);
+        assert_eq!(late("\"skip\""), 0);
+        assert_eq!(late("3"), 3);
+        assert_eq!(late("10"), 5);
+        // Recorded: the next run is on time.
+        assert_eq!(scheduled_iterations(root, None, None).unwrap(), 1);
+        fs::write(
+            &toml,
+            content.replace(
+                "interval = \"1h\"",
+                "interval = \"1h\"\ncatch_up = \"always\"",
+            ),
+        )
+        .unwrap();
+        let err = load_config(root, None).unwrap_err().to_string();
+        assert!(err.contains("schedule.catch_up must be"), "{err}");
+    }
+
+    #[test]
+    fn test_scheduler_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        crate::runner::init(root, "logged").unwrap();
+        assert_eq!(scheduled_iterations(root, None, None).unwrap(), 1);
+        log_outcome(root, None, None, &Ok(()));
+        pause(root, None, Some("deploy")).unwrap();
+        assert_eq!(
+            scheduled_iterations(root, None, Some("nightly")).unwrap(),
+            0
+        );
+        log_outcome(
+            root,
+            None,
+            Some("nightly"),
+            &Err(RunnerError::Lock("held\nby 42".into())),
+        );
+
+        let log = fs::read_to_string(root.join("logs").join(SCHEDULER_LOG)).unwrap();
+        let lines: Vec<&str> = log.lines().collect();
+        assert_eq!(lines.len(), 5, "{log}");
+        assert!(lines[0].ends_with("Z started"), "{log}");
+        assert!(lines[1].ends_with(" finished"), "{log}");
+        assert!(lines[2].contains(" [nightly] started"), "{log}");
+        assert!(
+            lines[3].contains(" [nightly] skipped: Paused by operator"),
+            "{log}"
+        );
+        assert!(lines[4].contains(" [nightly] failed: "), "{log}");
+        assert!(lines[4].contains("held; by 42"), "{log}");
+        show_logs(root, None, 2).unwrap();
+    }
+
+    #[test]
+    fn test_crontab_entries_are_replaced_by_marker() {
+        let root = Path::new("/home/agent");
+        let agent = owner(root, None);
+        let nightly = owner(root, Some("nightly"));
+        let existing = format!(
+            "MAILTO=me\n0 * * * * backup.sh\n*/5 * * * * cd /home/agent && boucle run # {agent}\n0 3 * * * cd /home/agent && boucle run --profile nightly # {nightly}\n0 4 * * * cd /home/agent/sub && boucle run # boucle /home/agent/sub\n"
+        );
+        let kept = "MAILTO=me\n0 * * * * backup.sh\n";
+        let line = format!("*/10 * * * * cd /home/agent && boucle run # {agent}");
+        let updated = crontab_with(&existing, &agent, std::slice::from_ref(&line));
+        assert_eq!(
+            updated,
+            format!(
+                "{kept}0 3 * * * cd /home/agent && boucle run --profile nightly # {nightly}\n0 4 * * * cd /home/agent/sub && boucle run # boucle /home/agent/sub\n{line}\n"
+            )
+        );
+        assert_eq!(
+            crontab_with(&updated, &agent, std::slice::from_ref(&line)),
+            updated
+        );
+
+        let removed = crontab_with(&updated, &nightly, &[]);
+        assert!(!removed.contains("nightly") && removed.ends_with(&format!("{line}\n")));
+        assert_eq!(crontab_with(&line, &agent, &[]), "");
+    }
+
+    #[test]
+    fn test_named_schedules() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        crate::runner::init(root, "triager").unwrap();
+        let toml = root.join("boucle.toml");
+        let content = fs::read_to_string(&toml).unwrap();
+        fs::write(
+            &toml,
+            format!(
+                "{content}\n[schedule.hourly]\ninterval = \"1h\"\ntask = \"triage\"\n\n[schedule.nightly]\ninterval = \"0 3 * * *\"\njitter = \"10m\"\n"
+            ),
+        )
+        .unwrap();
+
+        let mut cfg = load_config(root, None).unwrap();
+        assert_eq!(cfg.schedule.task(), None);
+        cfg.schedule.current = Some("hourly".to_string());
+        assert_eq!(cfg.schedule.task(), Some("triage"));
+        cfg.schedule.current = Some("nightly".to_string());
+        assert_eq!(cfg.schedule.task(), Some("nightly"));
+        assert_eq!(
+            label(&cfg, Some("fast"), Some("nightly")),
+            "triager.fast.nightly"
+        );
+        assert_eq!(jitter_secs(&cfg, Some("nightly")).unwrap(), Some(600));
+        assert_eq!(jitter_secs(&cfg, Some("hourly")).unwrap(), None);
+
+        let planned = plans(root, None, None, true).unwrap();
+        let lines: Vec<&str> = planned
+            .iter()
+            .filter_map(|plan| match plan {
+                Plan::Cron { line } => Some(line.as_str()),
+                _ => None,
+            })
+            .collect();
+        if !cfg!(any(target_os = "macos", windows)) {
+            assert_eq!(lines.len(), 2);
+            let log = root.join("logs").join(SCHEDULER_LOG);
+            assert!(lines[0].contains(&format!(
+                "run --scheduled --schedule hourly 2>> {} # boucle ",
+                log.display()
+            )));
+            assert!(lines[1].starts_with("0 3 * * * "));
+            assert!(lines[1].contains("run --scheduled --schedule nightly --jitter 2>> "));
+            assert!(lines.iter().all(|line| is_owned(line, &owner(root, None))));
+            assert!(!lines
+                .iter()
+                .any(|line| is_owned(line, &owner(root, Some("x")))));
+        }
+        assert_eq!(plans(root, Some("5m"), None, true).unwrap().len(), 1);
+
+        // Anything in [schedule] that isn't a setting must be a table.
+        fs::write(
+            &toml,
+            content.replace("interval = \"1h\"", "interval = \"1h\"\nintervall = \"2h\""),
+        )
+        .unwrap();
+        assert!(load_config(root, None).is_err());
+    }
+}
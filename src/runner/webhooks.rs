@@ -0,0 +1,323 @@
+//! Webhook notifications for run events.
+//!
+//! Each `[hooks.webhooks.<name>]` target is POSTed to when one of its
+//! `events` happens. Delivery is best effort: network errors, 429 and
+//! 5xx responses are retried, then the outcome is logged. A dead endpoint
+//! never stops the loop.
+
+use crate::config::{self, SecretSource, WebhookConfig};
+use crate::runner;
+use crate::runner::hooks::HookPayload;
+use crate::runner::remote;
+use reqwest::header::CONTENT_TYPE;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+/// Events a webhook can subscribe to.
+pub const EVENTS: &[&str] = &[
+    "run-started",
+    "run-skipped",
+    "run-failed",
+    "commit-blocked",
+    "committed",
+    "run-completed",
+];
+
+/// Wait before the first retry; each further retry waits one step longer.
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Send `event` to every webhook subscribed to it and log the outcome.
+pub fn notify(
+    root: &Path,
+    webhooks: &BTreeMap<String, WebhookConfig>,
+    secrets: &BTreeMap<String, SecretSource>,
+    log_file: &Path,
+    event: &str,
+    payload: &HookPayload,
+    reason: Option<&str>,
+) {
+    for (name, hook) in webhooks {
+        if !hook.events.is_empty() && !hook.events.iter().any(|e| e == event) {
+            continue;
+        }
+        let body = render_body(hook.body.as_deref(), event, payload, reason);
+        let message = match deliver(root, secrets, name, hook, &body) {
+            Ok(status) => format!("Webhook {name} ({event}): HTTP {status}"),
+            Err(e) => format!("Webhook {name} ({event}) failed: {e}"),
+        };
+        let _ = runner::log(log_file, &message);
+    }
+}
+
+/// The request body: `template` with its placeholders filled in, or the
+/// whole event payload as JSON.
+///
+/// Text values are JSON-escaped without quotes, so they can sit inside a
+/// string in the template. `{iteration}` and `{exit_code}` are bare numbers
+/// (`null` when there is no exit code yet) and `{payload}` is the full
+/// payload object.
+fn render_body(
+    template: Option<&str>,
+    event: &str,
+    payload: &HookPayload,
+    reason: Option<&str>,
+) -> String {
+    let mut full = serde_json::to_value(payload).unwrap_or_default();
+    if let Some(object) = full.as_object_mut() {
+        object.remove("phase");
+        object.insert("event".to_string(), event.into());
+        object.insert("reason".to_string(), reason.into());
+    }
+    let Some(template) = template else {
+        return full.to_string();
+    };
+
+    let text = |value: &str| {
+        let quoted = serde_json::Value::from(value).to_string();
+        quoted[1..quoted.len() - 1].to_string()
+    };
+    let exit_code = payload
+        .exit_code
+        .map_or_else(|| "null".to_string(), |code| code.to_string());
+    [
+        ("event", text(event)),
+        ("agent", text(&payload.agent)),
+        ("iteration", payload.iteration.to_string()),
+        ("timestamp", text(&payload.timestamp)),
+        ("log_file", text(&payload.log_file)),
+        ("exit_code", exit_code),
+        ("reason", text(reason.unwrap_or_default())),
+        ("payload", full.to_string()),
+    ]
+    .iter()
+    .fold(template.to_string(), |body, (key, value)| {
+        body.replace(&format!("{{{key}}}"), value)
+    })
+}
+
+/// POST `body` to the webhook, retrying transient failures. Returns the
+/// final HTTP status.
+fn deliver(
+    root: &Path,
+    secrets: &BTreeMap<String, SecretSource>,
+    name: &str,
+    hook: &WebhookConfig,
+    body: &str,
+) -> Result<u16, String> {
+    let timeout = config::parse_interval(&hook.timeout).map_err(|e| format!("timeout: {e}"))?;
+    let headers = hook
+        .headers
+        .iter()
+        .map(|(key, value)| {
+            let setting = format!("hooks.webhooks.{name}.headers.{key}");
+            config::resolve_env_value(root, secrets, &setting, value)
+                .map(|value| format!("{key}: {value}"))
+                .map_err(|e| e.to_string())
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    send(
+        &hook.url,
+        &headers,
+        body,
+        Duration::from_secs(timeout),
+        hook.retries,
+    )
+}
+
+/// POST the JSON `body` to `url` with `retries` extra attempts after a
+/// network error, 429, or 5xx response. Returns the final HTTP status.
+pub(super) fn send(
+    url: &str,
+    headers: &[String],
+    body: &str,
+    timeout: Duration,
+    retries: u32,
+) -> Result<u16, String> {
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        let result = post(url, headers, body, timeout);
+        let transient = match result {
+            Ok(status) => status == 429 || status >= 500,
+            Err(_) => true,
+        };
+        if !transient || attempts > retries {
+            let suffix = if attempts > 1 {
+                format!(" after {attempts} attempts")
+            } else {
+                String::new()
+            };
+            return match result {
+                Ok(status) if (200..300).contains(&status) => Ok(status),
+                Ok(status) => Err(format!("HTTP {status}{suffix}")),
+                Err(e) => Err(format!("{e}{suffix}")),
+            };
+        }
+        thread::sleep(RETRY_DELAY * attempts);
+    }
+}
+
+/// One POST of the JSON `body`.
+fn post(url: &str, headers: &[String], body: &str, timeout: Duration) -> Result<u16, String> {
+    let response = remote::client(timeout)?
+        .post(url)
+        .header(CONTENT_TYPE, "application/json")
+        .headers(remote::header_map(headers)?)
+        .body(body.to_string())
+        .send()
+        .map_err(|e| remote::describe(&e, timeout))?;
+    Ok(response.status().as_u16())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+
+    fn payload() -> HookPayload {
+        HookPayload {
+            phase: "post-commit".to_string(),
+            agent: "bot".to_string(),
+            iteration: 7,
+            timestamp: "2026-03-01_10-00-00".to_string(),
+            exit_code: Some(0),
+            ..HookPayload::default()
+        }
+    }
+
+    /// Serve `responses` in order, sending each request (headers and body)
+    /// back.
+    fn serve(responses: Vec<&'static str>) -> (String, mpsc::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = String::new();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut length = 0;
+                loop {
+                    let mut line = String::new();
+                    if reader.read_line(&mut line).unwrap() == 0 || line == "\r\n" {
+                        break;
+                    }
+                    if let Some(value) = line.to_lowercase().strip_prefix("content-length:") {
+                        length = value.trim().parse().unwrap();
+                    }
+                    request.push_str(&line);
+                }
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+                request.push_str(&String::from_utf8_lossy(&body));
+                stream.write_all(response.as_bytes()).unwrap();
+                tx.send(request).unwrap();
+            }
+        });
+        (url, rx)
+    }
+
+    fn webhook(url: String, events: &[&str], body: Option<&str>) -> WebhookConfig {
+        WebhookConfig {
+            url,
+            events: events.iter().map(|e| e.to_string()).collect(),
+            body: body.map(String::from),
+            headers: BTreeMap::from([("X-Token".to_string(), "secret".to_string())]),
+            retries: 1,
+            timeout: "10s".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_render_body() {
+        let full: serde_json::Value =
+            serde_json::from_str(&render_body(None, "committed", &payload(), None)).unwrap();
+        assert_eq!(full["event"], "committed");
+        assert_eq!(full["iteration"], 7);
+        assert!(full.get("phase").is_none());
+
+        let body = render_body(
+            Some(r#"{"text": "{agent} #{iteration}: {event} ({reason})", "code": {exit_code}}"#),
+            "run-failed",
+            &HookPayload {
+                exit_code: None,
+                ..payload()
+            },
+            Some("hook said \"no\""),
+        );
+        let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body["text"], "bot #7: run-failed (hook said \"no\")");
+        assert!(body["code"].is_null());
+    }
+
+    #[test]
+    fn test_notify_retries_and_filters_events() {
+        let dir = tempfile::tempdir().unwrap();
+        let log_file = dir.path().join("run.log");
+        let (url, requests) = serve(vec![
+            "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            "HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n",
+        ]);
+        let webhooks = BTreeMap::from([(
+            "chat".to_string(),
+            webhook(url, &["committed"], Some(r#"{"text": "{agent}: {event}"}"#)),
+        )]);
+
+        notify(
+            dir.path(),
+            &webhooks,
+            &BTreeMap::new(),
+            &log_file,
+            "run-started",
+            &payload(),
+            None,
+        );
+        notify(
+            dir.path(),
+            &webhooks,
+            &BTreeMap::new(),
+            &log_file,
+            "committed",
+            &payload(),
+            None,
+        );
+
+        let first = requests.recv().unwrap();
+        assert!(first.starts_with("POST /hook"));
+        assert!(first.contains("X-Token: secret"));
+        assert!(first.ends_with(r#"{"text": "bot: committed"}"#), "{first}");
+        requests.recv().unwrap();
+        assert!(requests.try_recv().is_err());
+        let log = std::fs::read_to_string(&log_file).unwrap();
+        assert_eq!(log.trim(), "Webhook chat (committed): HTTP 204");
+    }
+
+    #[test]
+    fn test_notify_logs_client_errors_without_retrying() {
+        let dir = tempfile::tempdir().unwrap();
+        let log_file = dir.path().join("run.log");
+        let (url, requests) = serve(vec![
+            "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        ]);
+        let webhooks = BTreeMap::from([("gone".to_string(), webhook(url, &[], None))]);
+
+        notify(
+            dir.path(),
+            &webhooks,
+            &BTreeMap::new(),
+            &log_file,
+            "run-failed",
+            &payload(),
+            Some("x"),
+        );
+
+        let request = requests.recv().unwrap();
+        assert!(request.contains(r#""event":"run-failed""#), "{request}");
+        let log = std::fs::read_to_string(&log_file).unwrap();
+        assert_eq!(log.trim(), "Webhook gone (run-failed) failed: HTTP 404");
+    }
+}
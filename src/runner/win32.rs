@@ -0,0 +1,84 @@
+//! Minimal Win32 bindings for lock liveness checks.
+//!
+//! Declared by hand against kernel32 so Windows support doesn't pull in a
+//! bindings crate for a handful of functions.
+
+use std::ffi::c_void;
+
+type Handle = *mut c_void;
+
+const PROCESS_QUERY_LIMITED_INFORMATION: u32 = 0x1000;
+const STILL_ACTIVE: u32 = 259;
+const ERROR_ACCESS_DENIED: u32 = 5;
+
+#[repr(C)]
+#[derive(Default)]
+struct FileTime {
+    low: u32,
+    high: u32,
+}
+
+#[link(name = "kernel32")]
+extern "system" {
+    fn OpenProcess(desired_access: u32, inherit_handle: i32, process_id: u32) -> Handle;
+    fn GetExitCodeProcess(process: Handle, exit_code: *mut u32) -> i32;
+    fn GetProcessTimes(
+        process: Handle,
+        creation: *mut FileTime,
+        exit: *mut FileTime,
+        kernel: *mut FileTime,
+        user: *mut FileTime,
+    ) -> i32;
+    fn CloseHandle(handle: Handle) -> i32;
+    fn GetLastError() -> u32;
+}
+
+/// An open process handle, closed on drop.
+struct Process(Handle);
+
+impl Process {
+    fn open(pid: u32) -> Option<Self> {
+        let handle = unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid) };
+        if handle.is_null() {
+            None
+        } else {
+            Some(Process(handle))
+        }
+    }
+}
+
+impl Drop for Process {
+    fn drop(&mut self) {
+        unsafe {
+            CloseHandle(self.0);
+        }
+    }
+}
+
+/// Whether `pid` names a process that has not exited yet. A process that
+/// can't be opened for lack of rights (another user's, or one at a higher
+/// integrity level) exists, so it counts as running.
+pub fn is_process_running(pid: u32) -> bool {
+    let Some(process) = Process::open(pid) else {
+        return unsafe { GetLastError() } == ERROR_ACCESS_DENIED;
+    };
+    let mut code = 0u32;
+    let ok = unsafe { GetExitCodeProcess(process.0, &mut code) };
+    ok != 0 && code == STILL_ACTIVE
+}
+
+/// Creation time of `pid` in 100ns ticks since 1601, used to tell a live
+/// lock owner from a recycled PID.
+pub fn process_start_time(pid: u32) -> Option<u64> {
+    let process = Process::open(pid)?;
+    let mut creation = FileTime::default();
+    let mut exit = FileTime::default();
+    let mut kernel = FileTime::default();
+    let mut user = FileTime::default();
+    let ok =
+        unsafe { GetProcessTimes(process.0, &mut creation, &mut exit, &mut kernel, &mut user) };
+    if ok == 0 {
+        return None;
+    }
+    Some((u64::from(creation.high) << 32) | u64::from(creation.low))
+}
@@ -0,0 +1,160 @@
+//! `--all`: commands over every agent in a boucle-workspace.toml.
+//!
+//! A workspace file lists member agent roots and `[defaults]` they share
+//! (merged under each member's boucle.toml by `config::load_profile`).
+//! `boucle run --all`, `status --all` (also as JSON), and `schedule --all`
+//! visit the members in order; `memory recall --all` searches all their memories at once.
+
+use crate::broca::{self, ScoredEntry};
+use crate::config::{self, Workspace};
+use crate::runner::{self, RunnerError};
+use std::path::Path;
+
+/// Run `f` for each member, after a `== <member> ==` header. A member that
+/// fails is reported and the rest still run; returns whether all succeeded.
+pub fn for_each_member(
+    workspace: &Workspace,
+    mut f: impl FnMut(&Path) -> Result<(), RunnerError>,
+) -> Result<bool, RunnerError> {
+    let members = workspace.members()?;
+    let mut ok = true;
+    for (i, member) in members.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        let label = workspace.member_label(member);
+        println!("== {label} ==");
+        if let Err(e) = f(member) {
+            eprintln!("Error: {label}: {e}");
+            ok = false;
+        }
+    }
+    Ok(ok)
+}
+
+/// Print `f` for every member as one JSON array of `{"member", "status"}`
+/// objects, or `{"member", "error"}` for a member that fails; returns
+/// whether all succeeded.
+pub fn statuses_json(
+    workspace: &Workspace,
+    mut f: impl FnMut(&Path) -> Result<runner::StatusReport, RunnerError>,
+) -> Result<bool, RunnerError> {
+    let mut ok = true;
+    let mut members = Vec::new();
+    for member in workspace.members()? {
+        let label = workspace.member_label(&member);
+        members.push(match f(&member) {
+            Ok(status) => serde_json::json!({ "member": label, "status": status }),
+            Err(e) => {
+                ok = false;
+                serde_json::json!({ "member": label, "error": e.to_string() })
+            }
+        });
+    }
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&members).map_err(|e| RunnerError::Io(e.into()))?
+    );
+    Ok(ok)
+}
+
+/// Recall `query` from every member's memory: the best `limit` entries
+/// across all of them, each with the member it came from. Members whose
+/// config does not load are skipped with a warning.
+pub fn recall(
+    workspace: &Workspace,
+    query: &str,
+    limit: usize,
+) -> Result<Vec<(String, ScoredEntry)>, RunnerError> {
+    let mut results = Vec::new();
+    for member in workspace.members()? {
+        let label = workspace.member_label(&member);
+        let found = config::load(&member)
+            .map_err(|e| e.to_string())
+            .and_then(|cfg| {
+                broca::recall(&member.join(&cfg.memory.dir), query, limit)
+                    .map_err(|e| e.to_string())
+            });
+        match found {
+            Ok(entries) => results.extend(entries.into_iter().map(|e| (label.clone(), e))),
+            Err(e) => eprintln!("Warning: {label}: {e}"),
+        }
+    }
+    results.sort_by(|(_, a), (_, b)| b.relevance_score.total_cmp(&a.relevance_score));
+    results.truncate(limit);
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runner;
+    use std::fs;
+
+    #[test]
+    fn test_workspace_members_share_defaults_and_memory() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        for name in ["agents/research", "agents/ops", "solo"] {
+            fs::create_dir_all(root.join(name)).unwrap();
+            runner::init(&root.join(name), name.rsplit('/').next().unwrap()).unwrap();
+        }
+        fs::write(
+            root.join(config::WORKSPACE_FILE),
+            "members = [\"solo\", \"agents/*\"]\n\n[defaults.git]\ncommit_name = \"Fleet\"\n\n[defaults.agent]\nmodel = \"shared-model\"\n",
+        )
+        .unwrap();
+        let research_toml = root.join("agents/research/boucle.toml");
+        let toml = fs::read_to_string(&research_toml).unwrap();
+        fs::write(&research_toml, toml.replace("model = \"gpt-5.4\"\n", "")).unwrap();
+
+        let workspace = config::find_workspace(&root.join("agents/ops"))
+            .unwrap()
+            .unwrap();
+        let members = workspace.members().unwrap();
+        let labels: Vec<String> = members.iter().map(|m| workspace.member_label(m)).collect();
+        assert_eq!(labels, ["solo", "agents/ops", "agents/research"]);
+
+        let ops = config::load(&root.join("agents/ops")).unwrap();
+        assert_eq!(ops.agent.model, "gpt-5.4");
+        assert_eq!(ops.git.commit_name, "Fleet");
+        let research = config::load(&root.join("agents/research")).unwrap();
+        assert_eq!(research.agent.model, "shared-model");
+
+        let memory = |member: &str| root.join(member).join("memory");
+        broca::remember(
+            &memory("agents/ops"),
+            "fact",
+            "Deploy window",
+            "Deploys happen on Tuesdays",
+            &[],
+            None,
+        )
+        .unwrap();
+        broca::remember(
+            &memory("solo"),
+            "fact",
+            "Deploy freeze",
+            "No deploys in December",
+            &[],
+            None,
+        )
+        .unwrap();
+        let found = recall(&workspace, "deploy", 5).unwrap();
+        let mut from: Vec<&str> = found.iter().map(|(label, _)| label.as_str()).collect();
+        from.sort();
+        assert_eq!(from, ["agents/ops", "solo"]);
+
+        let mut visited = Vec::new();
+        let ok = for_each_member(&workspace, |member| {
+            visited.push(workspace.member_label(member));
+            if member.ends_with("ops") {
+                return Err(RunnerError::Lock("busy".to_string()));
+            }
+            Ok(())
+        })
+        .unwrap();
+        assert!(!ok);
+        assert_eq!(visited, labels);
+    }
+}
@@ -0,0 +1,77 @@
+//! OpenTelemetry export of iterations.
+//!
+//! With an OTLP endpoint, the `tracing` spans of `boucle run` are sent to a
+//! collector over OTLP/HTTP: one trace per iteration, with spans for the
+//! lock, context assembly and each plugin, the LLM call, each hook, and each
+//! git command.
+
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::trace::{SdkTracerProvider, Tracer};
+use opentelemetry_sdk::Resource;
+use std::path::Path;
+
+/// Standard variables for the collector, most specific first.
+const TRACES_ENDPOINT: &str = "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT";
+const ENDPOINT: &str = "OTEL_EXPORTER_OTLP_ENDPOINT";
+
+/// URL traces are posted to, if any: the `OTEL_EXPORTER_OTLP_*` variables,
+/// else `[telemetry] otlp_endpoint` of the agent at `root` (none for a
+/// workspace run, whose members may differ).
+pub fn traces_url(root: Option<&Path>, profile: Option<&str>) -> Option<String> {
+    let configured = root
+        .and_then(|root| crate::config::load_profile(root, profile).ok())
+        .and_then(|cfg| cfg.telemetry.otlp_endpoint);
+    resolve(|name| std::env::var(name).ok(), configured.as_deref())
+}
+
+fn resolve(var: impl Fn(&str) -> Option<String>, configured: Option<&str>) -> Option<String> {
+    let set = |name: &str| var(name).filter(|value| !value.trim().is_empty());
+    if let Some(url) = set(TRACES_ENDPOINT) {
+        return Some(url);
+    }
+    let base = set(ENDPOINT).or_else(|| configured.map(str::to_string))?;
+    Some(format!("{}/v1/traces", base.trim_end_matches('/')))
+}
+
+/// A tracer that batches spans to `url`. The provider must be shut down
+/// before exiting to send the last batch.
+pub fn tracer(url: &str) -> Result<(Tracer, SdkTracerProvider), String> {
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_http()
+        .with_endpoint(url)
+        .build()
+        .map_err(|e| format!("OTLP exporter for {url}: {e}"))?;
+    let mut resource = Resource::builder();
+    if std::env::var_os("OTEL_SERVICE_NAME").is_none() {
+        resource = resource.with_service_name("boucle");
+    }
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(resource.build())
+        .build();
+    Ok((provider.tracer("boucle"), provider))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_traces_url() {
+        let none = |_: &str| None;
+        assert_eq!(resolve(none, None), None);
+        assert_eq!(
+            resolve(none, Some("http://collector:4318/")).as_deref(),
+            Some("http://collector:4318/v1/traces")
+        );
+        let base = |name: &str| (name == ENDPOINT).then(|| "http://env:4318".to_string());
+        assert_eq!(
+            resolve(base, Some("http://collector:4318")).as_deref(),
+            Some("http://env:4318/v1/traces")
+        );
+        let traces =
+            |name: &str| (name == TRACES_ENDPOINT).then(|| "http://env/traces".to_string());
+        assert_eq!(resolve(traces, None).as_deref(), Some("http://env/traces"));
+    }
+}